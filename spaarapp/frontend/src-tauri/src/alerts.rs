@@ -0,0 +1,359 @@
+use crate::audit;
+use crate::commands::settings::load_settings;
+use crate::database::get_decimal;
+use crate::error::AppResult;
+use crate::models::{Settings, Transaction};
+use crate::rate_limit::RateLimited;
+use crate::AppState;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+/// Security config the alert thresholds are read from, as `audit.alert_threshold`
+pub const SECURITY_CONFIG_FILE: &str = "security.json";
+
+/// Days of transactions a new transaction is compared with
+const UNUSUAL_TRANSACTION_DAYS: i64 = 90;
+
+/// Limits that raise a security alert when exceeded, mirroring
+/// `AlertThreshold` in the security configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertThreshold {
+    /// Failed login attempts
+    pub failed_login_attempts: u32,
+
+    /// Data export attempts per hour
+    pub data_export_attempts_per_hour: u32,
+
+    /// Unusual transaction amounts
+    pub unusual_transaction_multiplier: Decimal,
+
+    /// API calls per minute
+    pub api_calls_per_minute: u32,
+}
+
+impl Default for AlertThreshold {
+    fn default() -> Self {
+        Self {
+            failed_login_attempts: 5,
+            data_export_attempts_per_hour: 10,
+            unusual_transaction_multiplier: Decimal::new(500, 2), // 5x normal
+            api_calls_per_minute: 100,
+        }
+    }
+}
+
+/// The parts of the security config file the monitor reads; everything else
+/// in it is ignored.
+#[derive(Debug, Default, Deserialize)]
+struct SecurityConfigFile {
+    #[serde(default)]
+    audit: AuditSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AuditSection {
+    #[serde(default)]
+    alert_threshold: AlertThreshold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityAlertKind {
+    DataExportAttempts,
+    ApiCalls,
+    UnusualTransaction,
+}
+
+impl SecurityAlertKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecurityAlertKind::DataExportAttempts => "data_export_attempts",
+            SecurityAlertKind::ApiCalls => "api_calls",
+            SecurityAlertKind::UnusualTransaction => "unusual_transaction",
+        }
+    }
+}
+
+/// Payload of the `security_alert` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecurityAlert {
+    pub kind: SecurityAlertKind,
+    pub message: String,
+    /// The transaction that was flagged, for unusual transactions
+    pub transaction_id: Option<String>,
+}
+
+/// Counts security-relevant events in rolling windows and reports when one
+/// goes over its [`AlertThreshold`]. The thresholds are re-read whenever the
+/// security config file changes, so edits apply without a restart.
+#[derive(Debug)]
+pub struct AlertMonitor {
+    config_path: PathBuf,
+    thresholds: AlertThreshold,
+    /// Modification time of the config the thresholds were read from
+    config_modified: Option<SystemTime>,
+    events: HashMap<SecurityAlertKind, VecDeque<Instant>>,
+    /// A breach is reported once per window, not for every event over the limit
+    reported_until: HashMap<SecurityAlertKind, Instant>,
+}
+
+impl AlertMonitor {
+    pub fn new(config_path: impl Into<PathBuf>) -> Self {
+        let mut monitor = Self {
+            config_path: config_path.into(),
+            thresholds: AlertThreshold::default(),
+            config_modified: None,
+            events: HashMap::new(),
+            reported_until: HashMap::new(),
+        };
+        monitor.reload_if_changed();
+        monitor
+    }
+
+    /// The current thresholds, re-read first if the config file changed.
+    pub fn thresholds(&mut self) -> &AlertThreshold {
+        self.reload_if_changed();
+        &self.thresholds
+    }
+
+    fn reload_if_changed(&mut self) {
+        let modified = std::fs::metadata(&self.config_path).and_then(|m| m.modified()).ok();
+        if modified == self.config_modified {
+            return;
+        }
+        self.config_modified = modified;
+
+        // A missing file means the defaults; a broken one keeps what was loaded
+        if modified.is_none() {
+            self.thresholds = AlertThreshold::default();
+            return;
+        }
+        match std::fs::read_to_string(&self.config_path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<SecurityConfigFile>(&content).map_err(|e| e.to_string()))
+        {
+            Ok(config) => {
+                tracing::info!("Loaded alert thresholds from {}", self.config_path.display());
+                self.thresholds = config.audit.alert_threshold;
+            }
+            Err(e) => tracing::warn!("Ignoring invalid security config {}: {}", self.config_path.display(), e),
+        }
+    }
+
+    /// Count a data export attempt made at `now`.
+    pub fn record_export_attempt(&mut self, now: Instant) -> Option<SecurityAlert> {
+        let limit = self.thresholds().data_export_attempts_per_hour;
+        self.record(SecurityAlertKind::DataExportAttempts, now, Duration::from_secs(60 * 60), limit)
+            .map(|count| SecurityAlert {
+                kind: SecurityAlertKind::DataExportAttempts,
+                message: format!("{} exportpogingen in het afgelopen uur (limiet {})", count, limit),
+                transaction_id: None,
+            })
+    }
+
+    /// Count a call to a rate-limited command made at `now`.
+    pub fn record_api_call(&mut self, now: Instant) -> Option<SecurityAlert> {
+        let limit = self.thresholds().api_calls_per_minute;
+        self.record(SecurityAlertKind::ApiCalls, now, Duration::from_secs(60), limit)
+            .map(|count| SecurityAlert {
+                kind: SecurityAlertKind::ApiCalls,
+                message: format!("{} aanroepen in de afgelopen minuut (limiet {})", count, limit),
+                transaction_id: None,
+            })
+    }
+
+    /// Add an event to its window; the number of events in the window when
+    /// that is over `limit` and the breach was not reported yet.
+    fn record(&mut self, kind: SecurityAlertKind, now: Instant, window: Duration, limit: u32) -> Option<usize> {
+        let events = self.events.entry(kind).or_default();
+        while events.front().is_some_and(|&at| now.saturating_duration_since(at) >= window) {
+            events.pop_front();
+        }
+        events.push_back(now);
+
+        let count = events.len();
+        if count <= limit as usize || self.reported_until.get(&kind).is_some_and(|&until| now < until) {
+            return None;
+        }
+        self.reported_until.insert(kind, now + window);
+        Some(count)
+    }
+
+    /// Flag `transaction` when its amount is more than the configured multiple
+    /// of `average`, the trailing average of transactions of its type. Amounts
+    /// in the message are formatted with `settings`.
+    pub fn check_transaction(
+        &mut self,
+        transaction: &Transaction,
+        average: Decimal,
+        settings: &Settings,
+    ) -> Option<SecurityAlert> {
+        let multiplier = self.thresholds().unusual_transaction_multiplier;
+        let amount = transaction.amount.abs();
+        if average <= Decimal::ZERO || multiplier <= Decimal::ZERO || amount <= average * multiplier {
+            return None;
+        }
+
+        Some(SecurityAlert {
+            kind: SecurityAlertKind::UnusualTransaction,
+            message: format!(
+                "Transactie van {} is meer dan {} keer het gemiddelde van {} over de afgelopen {} dagen",
+                settings.format_money(amount),
+                multiplier.normalize(),
+                settings.format_money(average),
+                UNUSUAL_TRANSACTION_DAYS
+            ),
+            transaction_id: Some(transaction.id.clone()),
+        })
+    }
+}
+
+/// Emit `alert` as a `security_alert` event and record it in the audit log,
+/// when the database is unlocked.
+pub(crate) async fn raise(app: &AppHandle, pool: Option<&SqlitePool>, alert: SecurityAlert) -> AppResult<()> {
+    tracing::warn!("Security alert ({}): {}", alert.kind.as_str(), alert.message);
+    if let Some(pool) = pool {
+        audit::record(pool, audit::SECURITY_ALERT, alert.kind.as_str(), Some(&alert.message)).await?;
+    }
+    let _ = app.emit("security_alert", alert);
+    Ok(())
+}
+
+/// Count a call to a rate-limited command (and for exports, the export
+/// attempt), raise an alert when that goes over a threshold, then apply the
+/// rate limit. Refused calls are counted too.
+pub(crate) async fn guard_command(app: &AppHandle, state: &AppState, group: RateLimited) -> AppResult<()> {
+    let now = Instant::now();
+    let alerts: Vec<SecurityAlert> = {
+        let mut monitor = state.alert_monitor.lock().await;
+        let export = (group == RateLimited::Export).then(|| monitor.record_export_attempt(now)).flatten();
+        monitor.record_api_call(now).into_iter().chain(export).collect()
+    };
+
+    if !alerts.is_empty() {
+        let pool = state.db.lock().await.get_pool().await.ok();
+        for alert in alerts {
+            if let Err(e) = raise(app, pool.as_ref(), alert).await {
+                tracing::warn!("Recording security alert failed: {}", e);
+            }
+        }
+    }
+
+    state.rate_limiter.lock().await.check(group, now)
+}
+
+/// Average absolute amount of the transactions of the same type as
+/// `transaction` in the [`UNUSUAL_TRANSACTION_DAYS`] before `now`, leaving out
+/// `transaction` itself; `None` without such transactions.
+pub(crate) async fn trailing_average(
+    pool: &SqlitePool,
+    transaction: &Transaction,
+    now: DateTime<Utc>
+) -> AppResult<Option<Decimal>> {
+    let rows = sqlx::query("SELECT amount FROM transactions WHERE transaction_type = ? AND date >= ? AND id != ?")
+        .bind(&transaction.transaction_type)
+        .bind(now - ChronoDuration::days(UNUSUAL_TRANSACTION_DAYS))
+        .bind(&transaction.id)
+        .fetch_all(pool)
+        .await?;
+
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    let total: Decimal = rows.iter().map(|r| get_decimal(r, "amount").abs()).sum();
+    Ok(Some(total / Decimal::from(rows.len())))
+}
+
+/// Raise an alert when a newly added `transaction` is far above the trailing
+/// average; call after it was stored.
+pub(crate) async fn check_new_transaction(
+    app: &AppHandle,
+    state: &AppState,
+    pool: &SqlitePool,
+    transaction: &Transaction
+) -> AppResult<()> {
+    let Some(average) = trailing_average(pool, transaction, Utc::now()).await? else {
+        return Ok(());
+    };
+    let settings = load_settings(pool).await?;
+    let alert = state.alert_monitor.lock().await.check_transaction(transaction, average, &settings);
+    if let Some(alert) = alert {
+        raise(app, Some(pool), alert).await?;
+    }
+    Ok(())
+}
+
+/// [`check_new_transaction`] for every transaction of an import; call after
+/// the import was committed. A failed check is logged and the rest still run.
+pub(crate) async fn check_imported_transactions(
+    app: &AppHandle,
+    state: &AppState,
+    pool: &SqlitePool,
+    transactions: &[Transaction]
+) {
+    for transaction in transactions {
+        if let Err(e) = check_new_transaction(app, state, pool, transaction).await {
+            tracing::warn!("Unusual transaction check failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_breach_once_per_window() {
+        let mut monitor = AlertMonitor::new(std::env::temp_dir().join("spaarapp-no-such-security.json"));
+        let start = Instant::now();
+
+        for i in 0..10 {
+            assert!(monitor.record_export_attempt(start + Duration::from_secs(i)).is_none());
+        }
+        let alert = monitor.record_export_attempt(start + Duration::from_secs(10)).unwrap();
+        assert_eq!(alert.kind, SecurityAlertKind::DataExportAttempts);
+        assert!(monitor.record_export_attempt(start + Duration::from_secs(11)).is_none());
+
+        // After an hour the early attempts have left the window
+        assert!(monitor.record_export_attempt(start + Duration::from_secs(60 * 60 + 20)).is_none());
+    }
+
+    #[test]
+    fn test_thresholds_reload_when_config_changes() {
+        let path = std::env::temp_dir().join(format!("spaarapp-security-{}.json", uuid::Uuid::new_v4()));
+        let mut monitor = AlertMonitor::new(&path);
+        assert_eq!(monitor.thresholds().api_calls_per_minute, 100);
+
+        std::fs::write(&path, r#"{"audit": {"alert_threshold": {"api_calls_per_minute": 2}}}"#).unwrap();
+        assert_eq!(monitor.thresholds().api_calls_per_minute, 2);
+        assert_eq!(monitor.thresholds().data_export_attempts_per_hour, 10);
+
+        let now = Instant::now();
+        assert!(monitor.record_api_call(now).is_none());
+        assert!(monitor.record_api_call(now).is_none());
+        assert!(monitor.record_api_call(now).is_some());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_flags_transaction_above_multiple_of_average() {
+        let mut monitor = AlertMonitor::new(std::env::temp_dir().join("spaarapp-no-such-security.json"));
+        let now = Utc::now();
+        let mut transaction = Transaction::test("debit", Decimal::new(-45000, 2), now);
+
+        let settings = Settings::default();
+        assert!(monitor.check_transaction(&transaction, Decimal::new(100, 0), &settings).is_none());
+        transaction.amount = Decimal::new(-50001, 2);
+        let alert = monitor.check_transaction(&transaction, Decimal::new(100, 0), &settings).unwrap();
+        assert_eq!(alert.transaction_id.as_deref(), Some(transaction.id.as_str()));
+        assert!(alert.message.starts_with("Transactie van € 500,01 is meer dan 5 keer het gemiddelde van € 100,00"));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::error::AppResult;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+/// Action name for exports of all user data
+pub const DATA_EXPORT: &str = "data_export";
+/// Action name for rotations of the encryption key
+pub const KEY_ROTATION: &str = "key_rotation";
+/// Action name for the startup warning that the encryption key is overdue for rotation
+pub const KEY_ROTATION_DUE: &str = "key_rotation_due";
+/// Action name for setting the passphrase on first run
+pub const PASSPHRASE_SET: &str = "passphrase_set";
+/// Action name for resetting all settings to their defaults
+pub const SETTINGS_RESET: &str = "settings_reset";
+/// Action name for deleting a budget
+pub const BUDGET_DELETE: &str = "budget_delete";
+/// Action name for a breached alert threshold; the outcome is the alert kind
+pub const SECURITY_ALERT: &str = "security_alert";
+
+/// One entry of the audit log, as shown to the user.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: String,
+    pub action: String,
+    pub outcome: String,
+    pub detail: Option<String>,
+    /// Whether the outcome was "success"
+    pub success: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Record a security-relevant action and how it ended ("success", "failed",
+/// "denied", ...) in the audit log.
+pub async fn record(pool: &SqlitePool, action: &str, outcome: &str, detail: Option<&str>) -> AppResult<()> {
+    sqlx::query("INSERT INTO audit_log (id, action, outcome, detail, created_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(Uuid::new_v4().to_string())
+        .bind(action)
+        .bind(outcome)
+        .bind(detail)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+
+    tracing::info!("audit: {} {} {}", action, outcome, detail.unwrap_or(""));
+    Ok(())
+}
+
+/// Number of times `action` was recorded since `since`, whatever the outcome.
+pub async fn count_since(pool: &SqlitePool, action: &str, since: DateTime<Utc>) -> AppResult<i64> {
+    Ok(sqlx::query_scalar("SELECT COUNT(*) FROM audit_log WHERE action = ? AND created_at >= ?")
+        .bind(action)
+        .bind(since)
+        .fetch_one(pool)
+        .await?)
+}
+
+/// A page of the audit log, newest first. All entries from `offset` on when
+/// `limit` is `None`.
+pub async fn entries(pool: &SqlitePool, limit: Option<u32>, offset: u32) -> AppResult<Vec<AuditEntry>> {
+    let rows = sqlx::query(
+        "SELECT id, action, outcome, detail, created_at FROM audit_log ORDER BY created_at DESC, id LIMIT ? OFFSET ?"
+    )
+    // SQLite treats a negative limit as no limit
+    .bind(limit.map_or(-1, i64::from))
+    .bind(i64::from(offset))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let outcome: String = r.get("outcome");
+            AuditEntry {
+                id: r.get("id"),
+                action: r.get("action"),
+                success: outcome == "success",
+                outcome,
+                detail: r.get("detail"),
+                created_at: r.get("created_at"),
+            }
+        })
+        .collect())
+}
+
+/// Delete entries recorded before `cutoff`; returns how many were removed.
+pub async fn purge_before(pool: &SqlitePool, cutoff: DateTime<Utc>) -> AppResult<u64> {
+    let result = sqlx::query("DELETE FROM audit_log WHERE created_at < ?")
+        .bind(cutoff)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
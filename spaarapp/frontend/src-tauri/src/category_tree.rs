@@ -0,0 +1,228 @@
+use crate::models::Category;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Bucket id used for spending without a category.
+pub const UNCATEGORIZED_ID: &str = "uncategorized";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategorySpendingNode {
+    pub category_id: String,
+    pub category_name: String,
+    pub parent_id: Option<String>,
+    /// Spending booked directly on this category
+    pub own_amount: Decimal,
+    /// Own spending plus that of all descendants
+    pub total_amount: Decimal,
+    pub children: Vec<CategorySpendingNode>,
+}
+
+/// Resolve each category's parent, dropping links to unknown categories and
+/// breaking any cycle (a category parented to its own descendant) so the
+/// result is always a forest.
+pub fn resolve_parents(categories: &[Category]) -> HashMap<String, Option<String>> {
+    let known: HashSet<&str> = categories.iter().map(|c| c.id.as_str()).collect();
+
+    let mut parents: HashMap<String, Option<String>> = categories
+        .iter()
+        .map(|c| {
+            let parent = c
+                .parent_id
+                .clone()
+                .filter(|p| p != &c.id && known.contains(p.as_str()));
+            (c.id.clone(), parent)
+        })
+        .collect();
+
+    for category in categories {
+        let mut path = vec![category.id.clone()];
+        let mut current = category.id.clone();
+
+        while let Some(Some(parent)) = parents.get(&current).cloned() {
+            if path.contains(&parent) {
+                tracing::warn!(
+                    "Category hierarchy cycle detected at {}; treating it as a top-level category",
+                    current
+                );
+                parents.insert(current.clone(), None);
+                break;
+            }
+            path.push(parent.clone());
+            current = parent;
+        }
+    }
+
+    parents
+}
+
+/// Whether `candidate` is `id` itself or one of its descendants, following the
+/// given child-to-parent links. Such a category cannot become the parent of `id`.
+pub fn is_descendant_or_self(parents: &HashMap<String, Option<String>>, id: &str, candidate: &str) -> bool {
+    let mut seen = HashSet::new();
+    let mut current = Some(candidate.to_string());
+
+    while let Some(category) = current {
+        if category == id {
+            return true;
+        }
+        if !seen.insert(category.clone()) {
+            // Existing cycle that does not pass through `id`
+            return false;
+        }
+        current = parents.get(&category).cloned().flatten();
+    }
+
+    false
+}
+
+/// Top-level ancestor of `id`, or `id` itself for a top-level or unknown
+/// category. Stops at the first repeat in case the links were not resolved.
+pub fn top_level_ancestor(parents: &HashMap<String, Option<String>>, id: &str) -> String {
+    let mut seen = HashSet::new();
+    let mut current = id.to_string();
+
+    while let Some(Some(parent)) = parents.get(&current) {
+        if !seen.insert(current.clone()) {
+            break;
+        }
+        current = parent.clone();
+    }
+
+    current
+}
+
+/// Build the category tree and roll each category's spending up into its ancestors.
+///
+/// Spending keyed by [`UNCATEGORIZED_ID`] becomes its own top-level node.
+pub fn build_spending_tree(
+    categories: &[Category],
+    own_spending: &HashMap<String, Decimal>,
+) -> Vec<CategorySpendingNode> {
+    let parents = resolve_parents(categories);
+
+    let mut children_of: HashMap<Option<String>, Vec<&Category>> = HashMap::new();
+    for category in categories {
+        let parent = parents.get(&category.id).cloned().flatten();
+        children_of.entry(parent).or_default().push(category);
+    }
+
+    fn build(
+        category: &Category,
+        parent_id: Option<String>,
+        children_of: &HashMap<Option<String>, Vec<&Category>>,
+        own_spending: &HashMap<String, Decimal>,
+    ) -> CategorySpendingNode {
+        let mut children: Vec<CategorySpendingNode> = children_of
+            .get(&Some(category.id.clone()))
+            .map(|kids| {
+                kids.iter()
+                    .map(|kid| build(kid, Some(category.id.clone()), children_of, own_spending))
+                    .collect()
+            })
+            .unwrap_or_default();
+        children.sort_by_key(|c| std::cmp::Reverse(c.total_amount));
+
+        let own_amount = own_spending.get(&category.id).copied().unwrap_or(Decimal::ZERO);
+        let total_amount = own_amount + children.iter().map(|c| c.total_amount).sum::<Decimal>();
+
+        CategorySpendingNode {
+            category_id: category.id.clone(),
+            category_name: category.name.clone(),
+            parent_id,
+            own_amount,
+            total_amount,
+            children,
+        }
+    }
+
+    let mut roots: Vec<CategorySpendingNode> = children_of
+        .get(&None)
+        .map(|tops| {
+            tops.iter()
+                .map(|top| build(top, None, &children_of, own_spending))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Some(&amount) = own_spending.get(UNCATEGORIZED_ID) {
+        roots.push(CategorySpendingNode {
+            category_id: UNCATEGORIZED_ID.to_string(),
+            category_name: "Ongecategoriseerd".to_string(),
+            parent_id: None,
+            own_amount: amount,
+            total_amount: amount,
+            children: Vec::new(),
+        });
+    }
+
+    roots.sort_by_key(|c| std::cmp::Reverse(c.total_amount));
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn category(id: &str, parent_id: Option<&str>) -> Category {
+        Category {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: None,
+            color: "#2196F3".to_string(),
+            icon: "category".to_string(),
+            parent_id: parent_id.map(|p| p.to_string()),
+            is_system: false,
+            budget_percentage: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_child_spending_rolls_up() {
+        let categories = vec![
+            category("vervoer", None),
+            category("trein", Some("vervoer")),
+            category("brandstof", Some("vervoer")),
+        ];
+        let spending = HashMap::from([
+            ("trein".to_string(), Decimal::new(4000, 2)),
+            ("brandstof".to_string(), Decimal::new(6050, 2)),
+        ]);
+
+        let tree = build_spending_tree(&categories, &spending);
+
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].own_amount, Decimal::ZERO);
+        assert_eq!(tree[0].total_amount, Decimal::new(10050, 2));
+        assert_eq!(tree[0].children[0].category_id, "brandstof");
+    }
+
+    #[test]
+    fn test_descendant_cannot_become_parent() {
+        let parents = HashMap::from([
+            ("vervoer".to_string(), None),
+            ("auto".to_string(), Some("vervoer".to_string())),
+            ("brandstof".to_string(), Some("auto".to_string())),
+        ]);
+
+        assert!(is_descendant_or_self(&parents, "vervoer", "brandstof"));
+        assert!(is_descendant_or_self(&parents, "auto", "auto"));
+        assert!(!is_descendant_or_self(&parents, "brandstof", "vervoer"));
+        assert_eq!(top_level_ancestor(&parents, "brandstof"), "vervoer");
+        assert_eq!(top_level_ancestor(&parents, "onbekend"), "onbekend");
+    }
+
+    #[test]
+    fn test_cycle_is_broken() {
+        let categories = vec![category("a", Some("b")), category("b", Some("a"))];
+        let spending = HashMap::from([("a".to_string(), Decimal::ONE)]);
+
+        let tree = build_spending_tree(&categories, &spending);
+
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].total_amount, Decimal::ONE);
+    }
+}
@@ -0,0 +1,575 @@
+use crate::ai_insights::monthly_spending;
+use crate::commands::budgets::month_bounds;
+use crate::database::get_decimal;
+use crate::error::{AppError, AppResult};
+use crate::models::{Category, FinancialInsight, Transaction};
+use crate::pii::{redact_pii, redact_pii_with_report, NAME_MASK};
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+
+/// Environment variable with the Claude API key; without it insights stay local
+pub const API_KEY_VAR: &str = "ANTHROPIC_API_KEY";
+/// Environment variable overriding [`DEFAULT_MODEL`]
+pub const MODEL_VAR: &str = "CLAUDE_MODEL";
+
+const API_URL: &str = "https://api.anthropic.com/v1/messages";
+const API_VERSION: &str = "2023-06-01";
+const DEFAULT_MODEL: &str = "claude-3-5-sonnet-20241022";
+
+/// Operation name of insight generation in `allowed_operations`
+const GENERATE_INSIGHTS: &str = "generate_insights";
+
+/// Days of transactions summarized for the API
+pub const SUMMARY_DAYS: i64 = 90;
+/// Largest expenses listed individually in the summary
+const SUMMARY_LARGEST_EXPENSES: usize = 10;
+
+/// Prices in EUR per million tokens, used for the monthly cost cap
+const INPUT_EUR_PER_MTOK: Decimal = Decimal::from_parts(280, 0, 0, false, 2);
+const OUTPUT_EUR_PER_MTOK: Decimal = Decimal::from_parts(1400, 0, 0, false, 2);
+
+/// Confidence given to insights from the API, which reports none itself
+const CLAUDE_CONFIDENCE: f64 = 0.7;
+
+const SYSTEM_PROMPT: &str = "Je bent een financieel assistent voor een Nederlandse huishoudapp. \
+Je krijgt een geanonimiseerde samenvatting van iemands inkomsten en uitgaven. \
+Geef maximaal vijf concrete, persoonlijke inzichten in het Nederlands. \
+Antwoord uitsluitend met een JSON-array van objecten met de velden \
+\"type\" (spending_pattern, budget_optimization, savings_opportunity of unusual_activity), \
+\"title\", \"description\", \"impact\" (high, medium of low) en \"suggestions\" (lijst van korte tips).";
+
+/// Limits on use of the Claude API, mirroring `ClaudeApiSecurity` in the
+/// security configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudeApiSecurity {
+    /// Maximum request size in characters
+    pub max_request_chars: usize,
+
+    /// Maximum tokens per request
+    pub max_tokens_per_request: usize,
+
+    /// Cost limit per month in EUR
+    pub monthly_cost_limit_eur: Decimal,
+
+    /// Enable content filtering
+    pub enable_content_filtering: bool,
+
+    /// Allowed operations
+    pub allowed_operations: Vec<String>,
+
+    /// PII detection enabled
+    pub pii_detection_enabled: bool,
+}
+
+impl Default for ClaudeApiSecurity {
+    fn default() -> Self {
+        Self {
+            max_request_chars: 100_000,
+            max_tokens_per_request: 4096,
+            monthly_cost_limit_eur: Decimal::new(1000, 2), // €10.00
+            enable_content_filtering: true,
+            allowed_operations: vec![
+                "analyze_transactions".to_string(),
+                "categorize_expenses".to_string(),
+                "generate_insights".to_string(),
+                "budget_optimization".to_string(),
+            ],
+            pii_detection_enabled: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    max_tokens: usize,
+    system: &'a str,
+    messages: Vec<Message<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+    usage: Usage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    text: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// One insight as the model is asked to return it.
+#[derive(Debug, Deserialize)]
+struct ClaudeInsight {
+    #[serde(rename = "type", default)]
+    insight_type: String,
+    title: String,
+    description: String,
+    #[serde(default)]
+    impact: String,
+    #[serde(default)]
+    suggestions: Vec<String>,
+}
+
+/// Cost in EUR of a request with the given token counts.
+pub fn estimate_cost(input_tokens: u64, output_tokens: u64) -> Decimal {
+    let million = Decimal::from(1_000_000);
+    (Decimal::from(input_tokens) * INPUT_EUR_PER_MTOK + Decimal::from(output_tokens) * OUTPUT_EUR_PER_MTOK) / million
+}
+
+/// Rough token count of `text`, at four characters a token.
+fn estimated_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+/// One Claude API request as stored in `claude_usage`.
+#[derive(Debug, Clone, Copy)]
+struct UsageRecord {
+    input_tokens: i64,
+    output_tokens: i64,
+    cost_eur: Decimal,
+}
+
+/// Claude API use in the calendar month of `now`, for the usage overview.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeUsage {
+    /// "YYYY-MM"
+    pub month: String,
+    pub request_count: usize,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub spent_eur: Decimal,
+    pub limit_eur: Decimal,
+    pub remaining_eur: Decimal,
+}
+
+/// Requests made in the calendar month (UTC) of `now`. Months are told apart
+/// by request time, so there is no counter to reset or drift.
+async fn usage_in_month(pool: &SqlitePool, now: DateTime<Utc>) -> AppResult<Vec<UsageRecord>> {
+    let (start, end) = month_bounds(now);
+    let rows = sqlx::query(
+        "SELECT input_tokens, output_tokens, cost_eur FROM claude_usage WHERE requested_at >= ? AND requested_at < ?"
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| UsageRecord {
+            input_tokens: row.get("input_tokens"),
+            output_tokens: row.get("output_tokens"),
+            cost_eur: get_decimal(row, "cost_eur"),
+        })
+        .collect())
+}
+
+/// Spend and remaining budget for the month of `now`.
+pub async fn monthly_usage(pool: &SqlitePool, now: DateTime<Utc>, limit_eur: Decimal) -> AppResult<ClaudeUsage> {
+    let records = usage_in_month(pool, now).await?;
+    let spent_eur: Decimal = records.iter().map(|record| record.cost_eur).sum();
+
+    Ok(ClaudeUsage {
+        month: now.format("%Y-%m").to_string(),
+        request_count: records.len(),
+        input_tokens: records.iter().map(|record| record.input_tokens).sum(),
+        output_tokens: records.iter().map(|record| record.output_tokens).sum(),
+        spent_eur: spent_eur.round_dp(4),
+        limit_eur,
+        remaining_eur: (limit_eur - spent_eur).max(Decimal::ZERO).round_dp(4),
+    })
+}
+
+/// Store the tokens and estimated cost of a request made at `requested_at`.
+pub async fn record_usage(pool: &SqlitePool, requested_at: DateTime<Utc>, usage: Usage) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO claude_usage (id, requested_at, input_tokens, output_tokens, cost_eur)
+        VALUES (?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(requested_at)
+    .bind(usage.input_tokens as i64)
+    .bind(usage.output_tokens as i64)
+    .bind(estimate_cost(usage.input_tokens, usage.output_tokens).round_dp(6).to_string())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Keeps Claude API spend within the monthly limit.
+pub struct MonthlyBudgetGuard {
+    limit_eur: Decimal,
+}
+
+impl MonthlyBudgetGuard {
+    pub fn new(limit_eur: Decimal) -> Self {
+        Self { limit_eur }
+    }
+
+    /// Refuse a request made at `now` that could cost up to `estimated_eur`
+    /// when it would take this month's spend over the limit.
+    pub async fn check(&self, pool: &SqlitePool, now: DateTime<Utc>, estimated_eur: Decimal) -> AppResult<()> {
+        let spent: Decimal = usage_in_month(pool, now).await?.iter().map(|record| record.cost_eur).sum();
+
+        if spent + estimated_eur > self.limit_eur {
+            return Err(AppError::PermissionDenied(format!(
+                "Maandbudget van € {} voor de Claude API is bereikt",
+                self.limit_eur
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Removes account numbers and the names of account holders from text sent
+/// to the API, on top of what [`redact_pii`] masks.
+pub struct PiiFilter {
+    account_number: Regex,
+    names: Option<Regex>,
+}
+
+impl PiiFilter {
+    /// Filter for long digit runs (account and card numbers) and the account
+    /// holders that appear in `transactions`.
+    pub fn for_transactions(transactions: &[Transaction]) -> Self {
+        let mut names: Vec<&str> = transactions
+            .iter()
+            .filter_map(|t| t.account_holder.as_deref())
+            .map(str::trim)
+            .filter(|name| name.chars().count() >= 3)
+            .collect();
+        // Longest first, so "J. de Vries" goes before "de Vries"
+        names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        names.dedup();
+
+        let names = (!names.is_empty()).then(|| {
+            let alternatives: Vec<String> = names.iter().map(|name| regex::escape(name)).collect();
+            Regex::new(&format!("(?i){}", alternatives.join("|"))).expect("escaped names form a valid regex")
+        });
+
+        Self {
+            account_number: Regex::new(r"\b\d{7,}\b").unwrap(),
+            names,
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let text = redact_pii(text);
+        let text = self.account_number.replace_all(&text, "[nummer]");
+        match &self.names {
+            Some(names) => names.replace_all(&text, NAME_MASK).into_owned(),
+            None => text.into_owned(),
+        }
+    }
+}
+
+/// Summary of the last [`SUMMARY_DAYS`] days to send to the API: totals per
+/// month and per category and the largest expenses. Account numbers and
+/// holders are never included; with `pii_filter` descriptions are scrubbed of
+/// them as well.
+pub fn spending_summary(
+    transactions: &[Transaction],
+    categories: &[Category],
+    now: DateTime<Utc>,
+    pii_filter: Option<&PiiFilter>,
+) -> String {
+    let since = now - Duration::days(SUMMARY_DAYS);
+    let recent: Vec<&Transaction> = transactions.iter().filter(|t| t.date > since && t.date <= now).collect();
+    let names: HashMap<&str, &str> = categories.iter().map(|c| (c.id.as_str(), c.name.as_str())).collect();
+    let category_name = |t: &Transaction| {
+        t.category_id
+            .as_deref()
+            .and_then(|id| names.get(id).copied())
+            .unwrap_or("Ongecategoriseerd")
+    };
+
+    let mut lines = vec![format!("Samenvatting van de afgelopen {} dagen, bedragen in euro.", SUMMARY_DAYS)];
+
+    lines.push("Per maand:".to_string());
+    let owned: Vec<Transaction> = recent.iter().map(|t| (*t).clone()).collect();
+    for month in monthly_spending(&owned, 3, now, 1) {
+        lines.push(format!(
+            "- {}: inkomsten {}, uitgaven {}, {} transacties",
+            month.month, month.total_income, month.total_expenses, month.transaction_count
+        ));
+    }
+
+    let mut per_category: HashMap<&str, (Decimal, usize)> = HashMap::new();
+    for transaction in recent.iter().filter(|t| t.transaction_type == "debit") {
+        let entry = per_category.entry(category_name(transaction)).or_default();
+        entry.0 += transaction.amount.abs();
+        entry.1 += 1;
+    }
+    let mut per_category: Vec<_> = per_category.into_iter().collect();
+    per_category.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(b.0)));
+
+    lines.push("Uitgaven per categorie:".to_string());
+    for (name, (amount, count)) in per_category {
+        lines.push(format!("- {}: {} ({} transacties)", name, amount, count));
+    }
+
+    let mut expenses: Vec<&&Transaction> = recent.iter().filter(|t| t.transaction_type == "debit").collect();
+    expenses.sort_by_key(|t| std::cmp::Reverse(t.amount.abs()));
+
+    lines.push("Grootste uitgaven:".to_string());
+    for transaction in expenses.into_iter().take(SUMMARY_LARGEST_EXPENSES) {
+        let description = match pii_filter {
+            Some(filter) => filter.apply(&transaction.description),
+            None => transaction.description.clone(),
+        };
+        lines.push(format!(
+            "- {} {}: {} ({})",
+            transaction.date.format("%Y-%m-%d"),
+            category_name(transaction),
+            transaction.amount.abs(),
+            description
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/// Insights from the model's answer: the JSON array in it, ignoring any text
+/// around the array.
+fn parse_insights(text: &str) -> AppResult<Vec<FinancialInsight>> {
+    let (start, end) = match (text.find('['), text.rfind(']')) {
+        (Some(start), Some(end)) if start < end => (start, end),
+        _ => return Err(AppError::AiService("Antwoord bevat geen inzichten".to_string())),
+    };
+    let parsed: Vec<ClaudeInsight> = serde_json::from_str(&text[start..=end])?;
+
+    Ok(parsed
+        .into_iter()
+        .filter(|insight| !insight.title.trim().is_empty())
+        .map(|insight| FinancialInsight {
+            id: uuid::Uuid::new_v4().to_string(),
+            insight_type: match insight.insight_type.as_str() {
+                kind @ ("spending_pattern" | "budget_optimization" | "savings_opportunity" | "unusual_activity") => {
+                    kind.to_string()
+                }
+                _ => "spending_pattern".to_string(),
+            },
+            title: insight.title,
+            description: insight.description,
+            impact: match insight.impact.as_str() {
+                impact @ ("high" | "medium" | "low") => impact.to_string(),
+                _ => "medium".to_string(),
+            },
+            actionable: !insight.suggestions.is_empty(),
+            action_suggestions: serde_json::to_string(&insight.suggestions).unwrap_or_default(),
+            confidence_score: CLAUDE_CONFIDENCE,
+            is_read: false,
+            is_dismissed: false,
+            created_at: Utc::now(),
+        })
+        .collect())
+}
+
+/// Client for natural-language insights from the Claude API, within the
+/// limits of [`ClaudeApiSecurity`].
+pub struct ClaudeClient {
+    http: reqwest::Client,
+    api_key: String,
+    model: String,
+    limits: ClaudeApiSecurity,
+}
+
+impl ClaudeClient {
+    pub fn new(api_key: String, limits: ClaudeApiSecurity) -> AppResult<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()?;
+
+        let model = std::env::var(MODEL_VAR)
+            .ok()
+            .filter(|model| !model.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+
+        Ok(Self { http, api_key, model, limits })
+    }
+
+    /// Client for the key in [`API_KEY_VAR`], `None` when no key is configured.
+    pub fn from_env() -> Option<Self> {
+        let api_key = std::env::var(API_KEY_VAR).ok().filter(|key| !key.trim().is_empty())?;
+
+        match Self::new(api_key, ClaudeApiSecurity::default()) {
+            Ok(client) => Some(client),
+            Err(e) => {
+                tracing::warn!("Failed to create Claude client: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn limits(&self) -> &ClaudeApiSecurity {
+        &self.limits
+    }
+
+    /// Ask for insights on `summary`. Fails without calling the API when the
+    /// operation is not allowed, the summary is too long or the request could
+    /// push this month's cost over the cap.
+    pub async fn generate_insights(&self, pool: &SqlitePool, summary: &str) -> AppResult<Vec<FinancialInsight>> {
+        if !self.limits.allowed_operations.iter().any(|op| op == GENERATE_INSIGHTS) {
+            return Err(AppError::PermissionDenied("Inzichten via de Claude API zijn niet toegestaan".to_string()));
+        }
+        // Whatever the caller prepared, nothing personal leaves the device
+        let summary = if self.limits.pii_detection_enabled {
+            let (redacted, report) = redact_pii_with_report(summary);
+            if !report.is_empty() {
+                tracing::info!("Redacted before sending to the Claude API: {}", report);
+            }
+            redacted
+        } else {
+            summary.to_string()
+        };
+
+        if summary.chars().count() + SYSTEM_PROMPT.chars().count() > self.limits.max_request_chars {
+            return Err(AppError::AiService("Verzoek aan de Claude API is te groot".to_string()));
+        }
+
+        let now = Utc::now();
+        let worst_case = estimate_cost(
+            estimated_tokens(&summary) + estimated_tokens(SYSTEM_PROMPT),
+            self.limits.max_tokens_per_request as u64,
+        );
+        MonthlyBudgetGuard::new(self.limits.monthly_cost_limit_eur)
+            .check(pool, now, worst_case)
+            .await?;
+
+        let request = MessagesRequest {
+            model: &self.model,
+            max_tokens: self.limits.max_tokens_per_request,
+            system: SYSTEM_PROMPT,
+            messages: vec![Message { role: "user", content: &summary }],
+        };
+
+        let response = self
+            .http
+            .post(API_URL)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .json(&request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::AiService(format!("Claude API antwoordde met status {}", response.status())));
+        }
+
+        let body: MessagesResponse = response.json().await?;
+        record_usage(pool, now, body.usage).await?;
+
+        let text: String = body.content.iter().filter_map(|block| block.text.as_deref()).collect();
+        parse_insights(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+    use chrono::TimeZone;
+
+    fn transaction(description: &str, amount: i64, holder: Option<&str>, date: DateTime<Utc>) -> Transaction {
+        Transaction {
+            description: description.to_string(),
+            account_number: Some("NL91ABNA0417164300".to_string()),
+            account_holder: holder.map(str::to_string),
+            ..Transaction::test("debit", amount, date)
+        }
+    }
+
+    #[test]
+    fn test_summary_strips_account_numbers_and_names() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let transactions = vec![
+            transaction("Tikkie van J. de Vries NL91 ABNA 0417 1643 00", 45, Some("J. de Vries"), now - Duration::days(3)),
+            transaction("Albert Heijn 1403 Utrecht pas 12345678", 80, None, now - Duration::days(5)),
+        ];
+
+        let filter = PiiFilter::for_transactions(&transactions);
+        let summary = spending_summary(&transactions, &[], now, Some(&filter));
+
+        assert!(!summary.contains("de Vries"));
+        assert!(!summary.contains("NL91"));
+        assert!(!summary.contains("12345678"));
+        assert!(summary.contains("Albert Heijn 1403 Utrecht"));
+        assert!(summary.contains("- Ongecategoriseerd: 125 (2 transacties)"));
+
+        let unfiltered = spending_summary(&transactions, &[], now, None);
+        assert!(unfiltered.contains("de Vries"));
+        assert!(!unfiltered.contains("NL91ABNA0417164300"));
+    }
+
+    #[test]
+    fn test_parse_insights_and_cost() {
+        let text = r#"Hier zijn de inzichten:
+            [{"type": "savings_opportunity", "title": "Minder uit eten", "description": "U gaf 300 euro uit aan restaurants.",
+              "impact": "high", "suggestions": ["Kook vaker zelf"]},
+             {"type": "iets anders", "title": "Vaste lasten", "description": "Stabiel.", "impact": "?"}]"#;
+
+        let insights = parse_insights(text).unwrap();
+        assert_eq!(insights.len(), 2);
+        assert_eq!(insights[0].insight_type, "savings_opportunity");
+        assert!(insights[0].actionable);
+        assert_eq!(insights[1].insight_type, "spending_pattern");
+        assert_eq!(insights[1].impact, "medium");
+        assert!(parse_insights("Geen idee").is_err());
+
+        // 1M input and 1M output tokens
+        assert_eq!(estimate_cost(1_000_000, 1_000_000), Decimal::new(1680, 2));
+    }
+
+    #[tokio::test]
+    async fn test_budget_guard_counts_only_the_current_month() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+
+        // €4.20 each: 1M input tokens and 100k output tokens
+        let usage = Usage { input_tokens: 1_000_000, output_tokens: 100_000 };
+        record_usage(&pool, Utc.with_ymd_and_hms(2024, 2, 29, 23, 0, 0).unwrap(), usage).await.unwrap();
+        record_usage(&pool, Utc.with_ymd_and_hms(2024, 3, 1, 8, 0, 0).unwrap(), usage).await.unwrap();
+        record_usage(&pool, Utc.with_ymd_and_hms(2024, 3, 20, 8, 0, 0).unwrap(), usage).await.unwrap();
+
+        let now = Utc.with_ymd_and_hms(2024, 3, 31, 12, 0, 0).unwrap();
+        let guard = MonthlyBudgetGuard::new(Decimal::from(10));
+        guard.check(&pool, now, Decimal::new(160, 2)).await.unwrap();
+        assert!(matches!(
+            guard.check(&pool, now, Decimal::new(161, 2)).await,
+            Err(AppError::PermissionDenied(_))
+        ));
+
+        let usage = monthly_usage(&pool, now, Decimal::from(10)).await.unwrap();
+        assert_eq!(usage.month, "2024-03");
+        assert_eq!(usage.request_count, 2);
+        assert_eq!(usage.spent_eur, Decimal::new(840, 2));
+        assert_eq!(usage.remaining_eur, Decimal::new(160, 2));
+
+        // A new month starts from nothing
+        let april = Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap();
+        guard.check(&pool, april, Decimal::from(9)).await.unwrap();
+        assert_eq!(monthly_usage(&pool, april, Decimal::from(10)).await.unwrap().request_count, 0);
+
+        pool.close().await;
+    }
+}
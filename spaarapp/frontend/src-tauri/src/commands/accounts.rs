@@ -0,0 +1,513 @@
+use crate::commands::settings::load_settings;
+use crate::commands::transactions::{transaction_from_row, TRANSACTION_COLUMNS};
+use crate::error::{AppError, AppResult};
+use crate::forecast::signed_amount;
+use crate::models::Transaction;
+use crate::AppState;
+use tauri::State;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::{self, sqlite::SqliteRow, Row, SqliteConnection};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Account {
+    pub id: String,
+    pub iban: String,
+    pub name: Option<String>,
+    /// Category for imported transactions that auto-categorization leaves empty
+    pub default_category_id: Option<String>,
+    /// Account assumed for imported transactions without an account number
+    pub is_default: bool,
+    /// Bank of a Dutch IBAN, when it is one of the supported banks
+    pub bank: Option<String>,
+    /// ISO 4217 code of the account's currency
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub(crate) fn account_from_row(row: &SqliteRow) -> Account {
+    Account {
+        id: row.get("id"),
+        iban: row.get("iban"),
+        name: row.get("name"),
+        default_category_id: row.get("default_category_id"),
+        is_default: row.get("is_default"),
+        bank: row.get("bank"),
+        currency: row.get("currency"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// IBAN without spaces, in upper case.
+pub(crate) fn normalize_iban(iban: &str) -> String {
+    iban.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase()
+}
+
+/// Look up the account for an IBAN, creating it when it is not known yet.
+pub(crate) async fn ensure_account(conn: &mut SqliteConnection, iban: &str) -> AppResult<Account> {
+    let iban = normalize_iban(iban);
+    if iban.is_empty() {
+        return Err(AppError::Validation("Rekeningnummer is verplicht".to_string()));
+    }
+
+    let now = Utc::now();
+    sqlx::query(
+        "INSERT OR IGNORE INTO accounts (id, iban, bank, is_default, created_at, updated_at) VALUES (?, ?, ?, FALSE, ?, ?)"
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&iban)
+    .bind(spaarapp_shared::bank_name_for_dutch_iban(&iban))
+    .bind(now)
+    .bind(now)
+    .execute(&mut *conn)
+    .await?;
+
+    let row = sqlx::query("SELECT * FROM accounts WHERE iban = ?")
+        .bind(&iban)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(account_from_row(&row))
+}
+
+/// IBAN of the account with `account_id`, to filter transactions on.
+pub(crate) async fn account_iban(conn: &mut SqliteConnection, account_id: &str) -> AppResult<String> {
+    sqlx::query_scalar("SELECT iban FROM accounts WHERE id = ?")
+        .bind(account_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Rekening {} niet gevonden", account_id)))
+}
+
+/// Whether `transaction` was booked on the account with `iban` (normalized).
+pub(crate) fn in_account(transaction: &Transaction, iban: &str) -> bool {
+    transaction.account_number.as_deref().is_some_and(|number| normalize_iban(number) == iban)
+}
+
+#[tauri::command]
+pub async fn get_accounts(state: State<'_, AppState>) -> AppResult<Vec<Account>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query("SELECT * FROM accounts ORDER BY is_default DESC, COALESCE(name, iban)")
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(rows.iter().map(account_from_row).collect())
+}
+
+/// Add an own account, e.g. a shared account before its first import.
+/// Accounts are also added automatically for account numbers in imports.
+#[tauri::command]
+pub async fn add_account(
+    iban: String,
+    name: Option<String>,
+    currency: Option<String>,
+    state: State<'_, AppState>
+) -> AppResult<Account> {
+    let normalized = normalize_iban(&iban);
+    if normalized.is_empty() {
+        return Err(AppError::Validation("Rekeningnummer is verplicht".to_string()));
+    }
+    // Older Dutch account numbers are digits only; anything else must be a valid IBAN
+    if !normalized.chars().all(|c| c.is_ascii_digit()) && !spaarapp_shared::validate_iban(&normalized).is_valid() {
+        return Err(AppError::Validation(format!("Ongeldig IBAN: {}", iban)));
+    }
+    let currency = currency.map(|c| c.trim().to_uppercase()).unwrap_or_else(|| "EUR".to_string());
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(AppError::Validation(format!("Onbekende valuta: {}", currency)));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    let exists: Option<String> = sqlx::query_scalar("SELECT id FROM accounts WHERE iban = ?")
+        .bind(&normalized)
+        .fetch_optional(&mut *tx)
+        .await?;
+    if exists.is_some() {
+        return Err(AppError::Validation(format!("Rekening {} bestaat al", normalized)));
+    }
+
+    let mut account = ensure_account(&mut tx, &normalized).await?;
+    account.name = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    account.currency = currency;
+
+    sqlx::query("UPDATE accounts SET name = ?, currency = ? WHERE id = ?")
+        .bind(&account.name)
+        .bind(&account.currency)
+        .bind(&account.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(account)
+}
+
+/// Default category for an imported transaction, based on the account it
+/// belongs to. Transactions without an account number use the default account.
+pub(crate) async fn default_category_for_account(
+    conn: &mut SqliteConnection,
+    account_number: Option<&str>,
+) -> AppResult<Option<String>> {
+    let category_id: Option<Option<String>> = match account_number.map(normalize_iban).filter(|a| !a.is_empty()) {
+        Some(iban) => {
+            sqlx::query_scalar("SELECT default_category_id FROM accounts WHERE iban = ?")
+                .bind(iban)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        None => {
+            sqlx::query_scalar("SELECT default_category_id FROM accounts WHERE is_default = TRUE LIMIT 1")
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+    };
+
+    Ok(category_id.flatten())
+}
+
+/// Set (or clear, with `None`) the category imports from this account fall back to.
+#[tauri::command]
+pub async fn set_account_default_category(
+    iban: String,
+    category_id: Option<String>,
+    state: State<'_, AppState>
+) -> AppResult<Account> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    if let Some(category_id) = &category_id {
+        let exists: Option<String> = sqlx::query_scalar("SELECT id FROM categories WHERE id = ?")
+            .bind(category_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        if exists.is_none() {
+            return Err(AppError::NotFound(format!("Categorie {} niet gevonden", category_id)));
+        }
+    }
+
+    let mut account = ensure_account(&mut tx, &iban).await?;
+    account.default_category_id = category_id;
+    account.updated_at = Utc::now();
+
+    sqlx::query("UPDATE accounts SET default_category_id = ?, updated_at = ? WHERE id = ?")
+        .bind(&account.default_category_id)
+        .bind(account.updated_at)
+        .bind(&account.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(account)
+}
+
+/// Mark one account as the default; any previous default is cleared.
+#[tauri::command]
+pub async fn set_default_account(iban: String, state: State<'_, AppState>) -> AppResult<Account> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    let mut account = ensure_account(&mut tx, &iban).await?;
+    account.is_default = true;
+    account.updated_at = Utc::now();
+
+    sqlx::query("UPDATE accounts SET is_default = (id = ?), updated_at = ? WHERE is_default = TRUE OR id = ?")
+        .bind(&account.id)
+        .bind(account.updated_at)
+        .bind(&account.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(account)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceSource {
+    /// Reported by the bank on the last transaction up to the date
+    Authoritative,
+    /// Computed from an earlier reported balance or the starting balance plus later transactions
+    Derived,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AccountBalance {
+    pub account_number: String,
+    pub date: NaiveDate,
+    pub balance: Decimal,
+    pub source: BalanceSource,
+}
+
+/// Balance after the given transactions (oldest first). Starts from the most
+/// recent reported `balance_after`, or from `starting_balance` when there is none.
+fn balance_after_transactions(transactions: &[Transaction], starting_balance: Decimal) -> (Decimal, BalanceSource) {
+    let anchor = transactions.iter().rposition(|t| t.balance_after.is_some());
+
+    let (mut balance, later) = match anchor {
+        Some(index) => (transactions[index].balance_after.unwrap_or_default(), &transactions[index + 1..]),
+        None => (starting_balance, transactions),
+    };
+    balance += later.iter().map(signed_amount).sum::<Decimal>();
+
+    let source = if anchor.is_some() && later.is_empty() {
+        BalanceSource::Authoritative
+    } else {
+        BalanceSource::Derived
+    };
+
+    (balance, source)
+}
+
+/// Balance of an account at the end of `date`. Uses the balance the bank
+/// reported when available; otherwise sums the transactions on top of
+/// `starting_balance` (zero when omitted).
+#[tauri::command]
+pub async fn get_balance_at_date(
+    account_number: String,
+    date: NaiveDate,
+    starting_balance: Option<Decimal>,
+    state: State<'_, AppState>
+) -> AppResult<AccountBalance> {
+    let account_number = normalize_iban(&account_number);
+    if account_number.is_empty() {
+        return Err(AppError::Validation("Rekeningnummer is verplicht".to_string()));
+    }
+    let end_of_day = (date + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap().and_utc();
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE UPPER(REPLACE(account_number, ' ', '')) = ? AND date < ?
+        ORDER BY date ASC, created_at ASC
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(&account_number)
+    .bind(end_of_day)
+    .fetch_all(&pool)
+    .await?;
+
+    let transactions: Vec<Transaction> = rows.iter().map(transaction_from_row).collect();
+    let (balance, source) = balance_after_transactions(&transactions, starting_balance.unwrap_or_default());
+
+    Ok(AccountBalance { account_number, date, balance, source })
+}
+
+/// One point of a reconstructed balance series: the balance right after a transaction.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct BalancePoint {
+    pub transaction_id: String,
+    pub date: DateTime<Utc>,
+    pub balance: Decimal,
+    /// Balance the bank reported for this transaction, when it did
+    pub reported_balance: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BalanceReconstruction {
+    pub account_number: String,
+    pub points: Vec<BalancePoint>,
+    /// Transactions whose missing `balance_after` was filled in
+    pub filled_in: usize,
+    /// Reported balances that disagree with the computed ones
+    pub warnings: Vec<String>,
+}
+
+/// Running balance after each of `transactions` (oldest first), starting
+/// from `starting_balance`. Where the bank reported a balance that differs
+/// from the computed one, `on_discrepancy` gets the transaction, the reported
+/// and the computed balance, and the series continues from the reported one.
+fn running_balances(
+    transactions: &[Transaction],
+    starting_balance: Decimal,
+    mut on_discrepancy: impl FnMut(&Transaction, Decimal, Decimal),
+) -> Vec<BalancePoint> {
+    let mut balance = starting_balance;
+
+    transactions
+        .iter()
+        .map(|transaction| {
+            balance += signed_amount(transaction);
+            if let Some(reported) = transaction.balance_after {
+                if reported != balance {
+                    on_discrepancy(transaction, reported, balance);
+                }
+                balance = reported;
+            }
+
+            BalancePoint {
+                transaction_id: transaction.id.clone(),
+                date: transaction.date,
+                balance,
+                reported_balance: transaction.balance_after,
+            }
+        })
+        .collect()
+}
+
+/// Rebuild the balance of an account over time from `starting_balance` and
+/// its transactions, store the computed balance on transactions the import
+/// left without one, and warn where a reported balance disagrees, which
+/// usually means transactions are missing before it.
+#[tauri::command]
+pub async fn reconstruct_balances(
+    starting_balance: Decimal,
+    account_number: String,
+    state: State<'_, AppState>
+) -> AppResult<BalanceReconstruction> {
+    let account_number = normalize_iban(&account_number);
+    if account_number.is_empty() {
+        return Err(AppError::Validation("Rekeningnummer is verplicht".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+    let settings = load_settings(&pool).await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE UPPER(REPLACE(account_number, ' ', '')) = ?
+        ORDER BY date ASC, created_at ASC
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(&account_number)
+    .fetch_all(&pool)
+    .await?;
+    let transactions: Vec<Transaction> = rows.iter().map(transaction_from_row).collect();
+
+    let mut warnings = Vec::new();
+    let points = running_balances(&transactions, starting_balance, |transaction, reported, computed| {
+        warnings.push(format!(
+            "Saldo na \"{}\" op {} is volgens de bank {}, berekend {} (verschil {})",
+            transaction.description,
+            transaction.date.format("%d-%m-%Y"),
+            settings.format_money(reported),
+            settings.format_money(computed),
+            settings.format_money(reported - computed)
+        ));
+    });
+
+    let mut tx = pool.begin().await?;
+    let mut filled_in = 0;
+    for (transaction, point) in transactions.iter().zip(&points) {
+        if transaction.balance_after.is_none() {
+            sqlx::query("UPDATE transactions SET balance_after = ? WHERE id = ? AND balance_after IS NULL")
+                .bind(point.balance.to_string())
+                .bind(&transaction.id)
+                .execute(&mut *tx)
+                .await?;
+            filled_in += 1;
+        }
+    }
+    tx.commit().await?;
+
+    Ok(BalanceReconstruction { account_number, points, filled_in, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+
+    #[test]
+    fn test_normalize_iban() {
+        assert_eq!(normalize_iban(" nl91 abna 0417 1643 00 "), "NL91ABNA0417164300");
+    }
+
+    #[test]
+    fn test_balance_prefers_reported_balance() {
+        let transaction = |transaction_type: &str, amount: i64, balance_after: Option<i64>| Transaction {
+            account_number: Some("NL01RABO0123456789".to_string()),
+            balance_after: balance_after.map(Decimal::from),
+            ..Transaction::test(transaction_type, amount, Utc::now())
+        };
+
+        let unreported = vec![transaction("credit", 100, None), transaction("debit", 30, None)];
+        assert_eq!(
+            balance_after_transactions(&unreported, Decimal::from(50)),
+            (Decimal::from(120), BalanceSource::Derived)
+        );
+
+        let reported = vec![transaction("credit", 100, None), transaction("debit", 30, Some(1000))];
+        assert_eq!(
+            balance_after_transactions(&reported, Decimal::ZERO),
+            (Decimal::from(1000), BalanceSource::Authoritative)
+        );
+
+        let after_report = vec![transaction("debit", 30, Some(1000)), transaction("debit", 25, None)];
+        assert_eq!(
+            balance_after_transactions(&after_report, Decimal::ZERO),
+            (Decimal::from(975), BalanceSource::Derived)
+        );
+    }
+
+    #[test]
+    fn test_running_balances_flag_disagreeing_report() {
+        let transaction = |transaction_type: &str, amount: i64, balance_after: Option<i64>| Transaction {
+            account_number: Some("NL01RABO0123456789".to_string()),
+            balance_after: balance_after.map(Decimal::from),
+            ..Transaction::test(transaction_type, amount, Utc::now())
+        };
+
+        let transactions = vec![
+            transaction("credit", 100, None),
+            transaction("debit", 30, Some(120)),
+            // A missing 40 debit before this one
+            transaction("debit", 10, Some(70)),
+            transaction("credit", 5, None),
+        ];
+        let mut discrepancies = Vec::new();
+        let points = running_balances(&transactions, Decimal::from(50), |_, reported, computed| {
+            discrepancies.push((reported, computed))
+        });
+
+        let balances: Vec<Decimal> = points.iter().map(|p| p.balance).collect();
+        assert_eq!(balances, [150, 120, 70, 75].map(Decimal::from));
+        assert_eq!(discrepancies, vec![(Decimal::from(70), Decimal::from(110))]);
+    }
+
+    #[tokio::test]
+    async fn test_default_category_follows_account() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let savings: String = sqlx::query_scalar("SELECT id FROM categories WHERE name = 'Sparen'")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        let account = ensure_account(&mut conn, "nl01 rabo 0123 4567 89").await.unwrap();
+        sqlx::query("UPDATE accounts SET default_category_id = ?, is_default = TRUE WHERE id = ?")
+            .bind(&savings)
+            .bind(&account.id)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let by_iban = default_category_for_account(&mut conn, Some("NL01RABO0123456789")).await.unwrap();
+        let without_account = default_category_for_account(&mut conn, None).await.unwrap();
+        let unknown = default_category_for_account(&mut conn, Some("NL02INGB0001234567")).await.unwrap();
+
+        assert_eq!(by_iban.as_deref(), Some(savings.as_str()));
+        assert_eq!(without_account.as_deref(), Some(savings.as_str()));
+        assert!(unknown.is_none());
+
+        drop(conn);
+        pool.close().await;
+    }
+}
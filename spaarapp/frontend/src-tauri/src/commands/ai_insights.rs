@@ -1,21 +1,876 @@
-use crate::error::AppResult;
-use crate::models::FinancialInsight;
-use crate::models::SpendingAnalysis;
+use crate::ai_insights::{
+    describe_recommendation, AIInsightEngine, find_unusual_transactions, monthly_spending, recommend_budgets,
+    weekday_spending_matrix, weekly_summary, BudgetRecommendation, MonthSummary, UnusualTransaction, WeekSummary,
+    WeekdaySpendingMonth, SEASONAL_HISTORY_MONTHS,
+};
+use crate::alerts;
+use crate::claude::{monthly_usage, spending_summary, ClaudeApiSecurity, ClaudeClient, ClaudeUsage, PiiFilter};
+use crate::commands::accounts::{account_iban, in_account};
+use crate::commands::budgets::{budget_from_row, budget_month_bounds, budget_month_first_day, BUDGET_COLUMNS};
+use crate::commands::categories::category_from_row;
+use crate::commands::settings::load_settings;
+use crate::commands::transactions::{decrypt_notes, transaction_from_row, TRANSACTION_COLUMNS};
+use crate::forecast::{self, CashflowForecast, SavingsProjection};
+use crate::health::{self, HealthScore, HEALTH_MONTHS};
+use crate::recurring::{
+    self, detect_recurring, detect_recurring_series, find_price_increases, price_increase_insight,
+    subscription_insights, upcoming_occurrences, PriceIncrease, RecurringStream, SubscriptionReview,
+    UpcomingRecurring, PRICE_INCREASE_THRESHOLD,
+};
+use crate::error::{AppError, AppResult};
+use crate::models::{Category, FinancialInsight, SpendingAnalysis, Transaction};
+use crate::rate_limit::RateLimited;
+use crate::AppState;
+use tauri::{AppHandle, State};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use chrono::{DateTime, Datelike, Local, TimeZone, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
 
+pub(crate) fn insight_from_row(row: &SqliteRow) -> FinancialInsight {
+    FinancialInsight {
+        id: row.get("id"),
+        insight_type: row.get("insight_type"),
+        title: row.get("title"),
+        description: row.get("description"),
+        impact: row.get("impact"),
+        actionable: row.get("actionable"),
+        action_suggestions: row.get("action_suggestions"),
+        confidence_score: row.get("confidence_score"),
+        is_read: row.get("is_read"),
+        is_dismissed: row.get("is_dismissed"),
+        created_at: row.get("created_at"),
+    }
+}
+
+pub(crate) async fn load_transactions_since(pool: &SqlitePool, since: DateTime<Utc>) -> AppResult<Vec<Transaction>> {
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(transaction_from_row).collect())
+}
+
+/// Like [`load_transactions_since`], but with split transactions as one
+/// transaction per split, for spending per category.
+pub(crate) async fn load_transaction_portions_since(pool: &SqlitePool, since: DateTime<Utc>) -> AppResult<Vec<Transaction>> {
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transaction_portions
+        WHERE date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(transaction_from_row).collect())
+}
+
+pub(crate) async fn load_categories(pool: &SqlitePool) -> AppResult<Vec<Category>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, name, description, color, icon, parent_id, is_system,
+               budget_percentage, created_at, updated_at
+        FROM categories
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(category_from_row).collect())
+}
+
+/// Insights from the Claude API when a key is configured, `None` when there is
+/// none or the API can't be used (unreachable, over the cost cap, ...), in
+/// which case the rule-based engine takes over.
+async fn claude_insights(
+    pool: &SqlitePool,
+    transactions: &[Transaction],
+    categories: &[Category],
+    now: DateTime<Utc>,
+) -> Option<Vec<FinancialInsight>> {
+    let client = ClaudeClient::from_env()?;
+
+    let pii_filter = client
+        .limits()
+        .pii_detection_enabled
+        .then(|| PiiFilter::for_transactions(transactions));
+    let summary = spending_summary(transactions, categories, now, pii_filter.as_ref());
+
+    match client.generate_insights(pool, &summary).await {
+        Ok(insights) if !insights.is_empty() => Some(insights),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!("Claude insights unavailable, using local insights: {}", e);
+            None
+        }
+    }
+}
+
+/// Claude API spend this calendar month against the monthly limit.
 #[tauri::command]
-pub async fn get_financial_insights() -> AppResult<Vec<FinancialInsight>> {
-    // TODO: Implement AI-powered financial insights
-    Ok(vec![])
+pub async fn get_claude_usage(state: State<'_, AppState>) -> AppResult<ClaudeUsage> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    monthly_usage(&pool, Utc::now(), ClaudeApiSecurity::default().monthly_cost_limit_eur).await
 }
 
+/// Generate insights from the past year of transactions and the active budgets,
+/// store the new ones, and return every insight that has not been dismissed.
 #[tauri::command]
-pub async fn analyze_spending_patterns() -> AppResult<SpendingAnalysis> {
-    // TODO: Implement spending pattern analysis
-    todo!("Implement spending analysis")
+pub async fn get_financial_insights(app: AppHandle, state: State<'_, AppState>) -> AppResult<Vec<FinancialInsight>> {
+    alerts::guard_command(&app, &state, RateLimited::AiInsights).await?;
+    let pool = state.db.lock().await.get_pool().await?;
+    let settings = load_settings(&pool).await?;
+
+    if settings.ai_insights_enabled {
+        let now = Utc::now();
+        let transactions = load_transactions_since(&pool, now - chrono::Duration::days(365)).await?;
+        let categories = load_categories(&pool).await?;
+
+        let budget_rows = sqlx::query(&format!(
+            r#"
+            SELECT
+                {}
+            FROM budgets
+            WHERE is_active = TRUE
+            "#,
+            BUDGET_COLUMNS
+        ))
+        .fetch_all(&pool)
+        .await?;
+        let budgets: Vec<_> = budget_rows.iter().map(budget_from_row).collect();
+
+        let insights = match claude_insights(&pool, &transactions, &categories, now).await {
+            Some(insights) => insights,
+            None => {
+                let engine = AIInsightEngine::with_settings(&settings);
+                let mut insights = engine.generate_spending_insights(&transactions, &categories, &budgets).await?;
+
+                // Seasonal baselines need more history than the other insights
+                let history_start = now - chrono::Duration::days(31 * SEASONAL_HISTORY_MONTHS as i64);
+                let history = load_transaction_portions_since(&pool, history_start).await?;
+                insights.extend(engine.detect_seasonal_anomalies(&history, &categories, now));
+                insights
+            }
+        };
+
+        store_new_insights(&pool, &insights, now).await?;
+    }
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            id, insight_type, title, description, impact, actionable,
+            action_suggestions, confidence_score, is_read, is_dismissed, created_at
+        FROM financial_insights
+        WHERE is_dismissed = FALSE
+        ORDER BY created_at DESC
+        "#
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(rows.iter().map(insight_from_row).collect())
+}
+
+/// Income, spending and top categories over the last `period_days` days
+/// (30 by default), with the trend against the period before. With
+/// `account_id` only that account's transactions are analyzed; with
+/// `roll_up_to_parent` subcategory spending counts toward its top-level category.
+#[tauri::command]
+pub async fn analyze_spending_patterns(
+    period_days: Option<u32>,
+    account_id: Option<String>,
+    roll_up_to_parent: Option<bool>,
+    state: State<'_, AppState>
+) -> AppResult<SpendingAnalysis> {
+    let period_days = period_days.unwrap_or(30);
+    if !(1..=366).contains(&period_days) {
+        return Err(AppError::Validation("Periode moet tussen 1 en 366 dagen liggen".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    // Twice the period, so the trend can compare with the period before
+    let since = Utc::now() - chrono::Duration::days(2 * period_days as i64);
+    let mut transactions = load_transaction_portions_since(&pool, since).await?;
+    if let Some(account_id) = account_id {
+        let iban = account_iban(&mut *pool.acquire().await?, &account_id).await?;
+        transactions.retain(|transaction| in_account(transaction, &iban));
+    }
+    let categories = load_categories(&pool).await?;
+    let settings = load_settings(&pool).await?;
+
+    AIInsightEngine::with_settings(&settings)
+        .analyze_spending_trends(&transactions, &categories, period_days, roll_up_to_parent.unwrap_or(false))
+        .await
+}
+
+/// Per month, how spending spreads over the days of the week, so shifts
+/// such as growing weekend spending show up over time.
+#[tauri::command]
+pub async fn get_weekday_spending_matrix(
+    months: u32,
+    state: State<'_, AppState>
+) -> AppResult<Vec<WeekdaySpendingMonth>> {
+    if !(1..=36).contains(&months) {
+        return Err(AppError::Validation("Aantal maanden moet tussen 1 en 36 liggen".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let now = Utc::now();
+    let first_month = now.year() * 12 + now.month0() as i32 - (months as i32 - 1);
+    let since = Utc
+        .with_ymd_and_hms(first_month.div_euclid(12), first_month.rem_euclid(12) as u32 + 1, 1, 0, 0, 0)
+        .unwrap();
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE transaction_type = 'debit' AND date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(since)
+    .fetch_all(&pool)
+    .await?;
+
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+
+    Ok(weekday_spending_matrix(&transactions, months, now))
 }
 
+/// Income, expenses and net per ISO week for the last `weeks` weeks, for
+/// users who budget and review per week.
 #[tauri::command]
-pub async fn get_budget_recommendations() -> AppResult<Vec<String>> {
-    // TODO: Implement AI budget recommendations
-    Ok(vec![])
-}
\ No newline at end of file
+pub async fn get_weekly_summary(weeks: u32, state: State<'_, AppState>) -> AppResult<Vec<WeekSummary>> {
+    if !(1..=156).contains(&weeks) {
+        return Err(AppError::Validation("Aantal weken moet tussen 1 en 156 liggen".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let now = Utc::now();
+    let since = now - chrono::Duration::weeks(weeks as i64 + 1);
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(since)
+    .fetch_all(&pool)
+    .await?;
+
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+
+    Ok(weekly_summary(&transactions, weeks, now))
+}
+
+/// Income, expenses and net savings per budget month for the last
+/// `months_back` months, for the spending trend chart.
+#[tauri::command]
+pub async fn get_monthly_spending(months_back: u32, state: State<'_, AppState>) -> AppResult<Vec<MonthSummary>> {
+    if !(1..=120).contains(&months_back) {
+        return Err(AppError::Validation("Aantal maanden moet tussen 1 en 120 liggen".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let start_day = load_settings(&pool).await?.budget_month_start_day;
+    let now = Local::now();
+    // A day of margin so transactions near the start of the month in any
+    // timezone are fetched; `monthly_spending` drops what falls outside
+    let current = budget_month_bounds(now.naive_local().and_utc(), start_day).0;
+    let first_month = current.year() * 12 + current.month0() as i32 - (months_back as i32 - 1);
+    let since = Utc.from_utc_datetime(
+        &budget_month_first_day(first_month.div_euclid(12), first_month.rem_euclid(12) as u32 + 1, start_day)
+            .and_hms_opt(0, 0, 0)
+            .unwrap(),
+    ) - chrono::Duration::days(1);
+
+    let transactions = load_transactions_since(&pool, since).await?;
+
+    Ok(monthly_spending(&transactions, months_back, now, start_day))
+}
+
+pub(crate) async fn load_budget_recommendations(pool: &SqlitePool) -> AppResult<Vec<BudgetRecommendation>> {
+    let now = Utc::now();
+    let since = Utc.with_ymd_and_hms(now.year() - 2, now.month(), 1, 0, 0, 0).unwrap();
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE transaction_type = 'debit' AND category_id IS NOT NULL AND date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+
+    let categories = load_categories(pool).await?;
+
+    Ok(recommend_budgets(&transactions, &categories, now))
+}
+
+#[tauri::command]
+pub async fn get_budget_recommendations(state: State<'_, AppState>) -> AppResult<Vec<String>> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let recommendations = load_budget_recommendations(&pool).await?;
+    let settings = load_settings(&pool).await?;
+
+    Ok(recommendations.iter().map(|r| describe_recommendation(r, &settings)).collect())
+}
+
+/// Structured budget suggestions per category, with month-specific amounts
+/// for categories whose spending depends on the season.
+#[tauri::command]
+pub async fn get_category_budget_recommendations(
+    state: State<'_, AppState>
+) -> AppResult<Vec<BudgetRecommendation>> {
+    let pool = state.db.lock().await.get_pool().await?;
+    load_budget_recommendations(&pool).await
+}
+
+/// Insights for one category's detail page, based on its transactions of the
+/// past year and the budgets set for it. Not stored with the global insights.
+#[tauri::command]
+pub async fn get_category_insights(
+    category_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<Vec<FinancialInsight>> {
+    alerts::guard_command(&app, &state, RateLimited::AiInsights).await?;
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let category_row = sqlx::query(
+        r#"
+        SELECT id, name, description, color, icon, parent_id, is_system,
+               budget_percentage, created_at, updated_at
+        FROM categories
+        WHERE id = ?
+        "#
+    )
+    .bind(&category_id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Categorie {} niet gevonden", category_id)))?;
+    let category = category_from_row(&category_row);
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transaction_portions
+        WHERE category_id = ? AND date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(&category_id)
+    .bind(Utc::now() - chrono::Duration::days(365))
+    .fetch_all(&pool)
+    .await?;
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+
+    let budget_rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        WHERE category_id = ? AND is_active = TRUE
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .bind(&category_id)
+    .fetch_all(&pool)
+    .await?;
+    let budgets: Vec<_> = budget_rows.iter().map(budget_from_row).collect();
+
+    let settings = load_settings(&pool).await?;
+
+    AIInsightEngine::with_settings(&settings)
+        .generate_category_insights(&category, &transactions, &budgets)
+        .await
+}
+
+#[tauri::command]
+pub async fn mark_all_insights_read(state: State<'_, AppState>) -> AppResult<u64> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let result = sqlx::query(
+        "UPDATE financial_insights SET is_read = TRUE WHERE is_read = FALSE AND is_dismissed = FALSE"
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[tauri::command]
+pub async fn dismiss_all_insights(
+    impact: Option<String>,
+    state: State<'_, AppState>
+) -> AppResult<u64> {
+    if let Some(impact) = &impact {
+        if !["high", "medium", "low"].contains(&impact.as_str()) {
+            return Err(AppError::Validation(format!("Onbekend impactniveau: {}", impact)));
+        }
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    // Dismissing also marks the insight as read so it no longer counts as unread
+    let result = sqlx::query(
+        r#"
+        UPDATE financial_insights SET is_dismissed = TRUE, is_read = TRUE
+        WHERE is_dismissed = FALSE AND (? IS NULL OR impact = ?)
+        "#
+    )
+    .bind(&impact)
+    .bind(&impact)
+    .execute(&pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Save a generated insight so it shows up alongside the others.
+/// Store generated insights, skipping any whose title was already stored in the
+/// last 24 hours and any the user dismissed before.
+async fn store_new_insights(pool: &SqlitePool, insights: &[FinancialInsight], now: DateTime<Utc>) -> AppResult<()> {
+    for insight in insights {
+        let recent: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM financial_insights WHERE title = ? AND created_at >= ?"
+        )
+        .bind(&insight.title)
+        .bind(now - chrono::Duration::hours(24))
+        .fetch_one(pool)
+        .await?;
+
+        let dismissed: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM financial_insights WHERE insight_type = ? AND title = ? AND is_dismissed = TRUE"
+        )
+        .bind(&insight.insight_type)
+        .bind(&insight.title)
+        .fetch_one(pool)
+        .await?;
+
+        if recent == 0 && dismissed == 0 {
+            store_insight(pool, insight).await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn store_insight(pool: &SqlitePool, insight: &FinancialInsight) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO financial_insights (
+            id, insight_type, title, description, impact, actionable,
+            action_suggestions, confidence_score, is_read, is_dismissed, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&insight.id)
+    .bind(&insight.insight_type)
+    .bind(&insight.title)
+    .bind(&insight.description)
+    .bind(&insight.impact)
+    .bind(insight.actionable)
+    .bind(&insight.action_suggestions)
+    .bind(insight.confidence_score)
+    .bind(insight.is_read)
+    .bind(insight.is_dismissed)
+    .bind(insight.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct YearEndSavings {
+    pub projection: SavingsProjection,
+    /// Comparison with last year; only present when there is last-year data
+    pub insight: Option<FinancialInsight>,
+}
+
+#[tauri::command]
+pub async fn project_year_end_savings(state: State<'_, AppState>) -> AppResult<YearEndSavings> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let now = Utc::now();
+    let last_year_start = Utc.with_ymd_and_hms(now.year() - 1, 1, 1, 0, 0, 0).unwrap();
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(last_year_start)
+    .fetch_all(&pool)
+    .await?;
+
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+    let projection = forecast::project_year_end_savings(&transactions, now);
+    let settings = load_settings(&pool).await?;
+    let insight = forecast::savings_projection_insight(&projection, &settings);
+
+    // One projection insight per week is enough of a nudge
+    if let Some(insight) = &insight {
+        let recent: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM financial_insights WHERE title LIKE 'Op dit tempo spaar je%' AND created_at >= ?"
+        )
+        .bind(now - chrono::Duration::days(7))
+        .fetch_one(&pool)
+        .await?;
+
+        if recent == 0 {
+            store_insight(&pool, insight).await?;
+        }
+    }
+
+    Ok(YearEndSavings { projection, insight })
+}
+
+/// Overall financial health from 0 to 100 with the contribution of each
+/// component and a tip for the weakest one. See [`health::calculate_health_score`].
+#[tauri::command]
+pub async fn calculate_health_score(state: State<'_, AppState>) -> AppResult<HealthScore> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let now = Utc::now();
+    let first_month = now.year() * 12 + now.month0() as i32 - HEALTH_MONTHS as i32;
+    let since = Utc
+        .with_ymd_and_hms(first_month.div_euclid(12), first_month.rem_euclid(12) as u32 + 1, 1, 0, 0, 0)
+        .unwrap();
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE date >= ?
+        ORDER BY date ASC, created_at ASC
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(since)
+    .fetch_all(&pool)
+    .await?;
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+
+    // Latest reported balance of each account, summed
+    let mut balances: HashMap<Option<String>, Decimal> = HashMap::new();
+    for transaction in &transactions {
+        if let Some(balance) = transaction.balance_after {
+            balances.insert(transaction.account_number.clone(), balance);
+        }
+    }
+    let balance: Decimal = balances.values().sum();
+
+    let budget_rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        WHERE is_active = TRUE
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .fetch_all(&pool)
+    .await?;
+    let budgets: Vec<_> = budget_rows.iter().map(budget_from_row).collect();
+
+    Ok(health::calculate_health_score(&transactions, &budgets, balance, now))
+}
+
+/// The debits behind the "unusual spending" insight, with their z-scores,
+/// for a list the user can check one by one.
+#[tauri::command]
+pub async fn get_unusual_transactions(
+    sigma: f64,
+    date_from: Option<DateTime<Utc>>,
+    date_to: Option<DateTime<Utc>>,
+    state: State<'_, AppState>
+) -> AppResult<Vec<UnusualTransaction>> {
+    if !(sigma.is_finite() && sigma > 0.0) {
+        return Err(AppError::Validation("Drempel (sigma) moet groter dan 0 zijn".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE transaction_type = 'debit'
+          AND (? IS NULL OR date >= ?)
+          AND (? IS NULL OR date <= ?)
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(date_from)
+    .bind(date_from)
+    .bind(date_to)
+    .bind(date_to)
+    .fetch_all(&pool)
+    .await?;
+
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+
+    let mut unusual = find_unusual_transactions(&transactions, sigma);
+    let encryption = state.encryption.lock().await;
+    for found in &mut unusual {
+        decrypt_notes(&mut found.transaction, &encryption)?;
+    }
+
+    Ok(unusual)
+}
+
+/// Regular income streams (salary, benefits, regular clients) with the date the
+/// next payment is expected. Looks at the past 13 months of credits.
+#[tauri::command]
+pub async fn get_recurring_income(state: State<'_, AppState>) -> AppResult<Vec<RecurringStream>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE transaction_type = 'credit' AND date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(Utc::now() - chrono::Duration::days(400))
+    .fetch_all(&pool)
+    .await?;
+
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+
+    Ok(detect_recurring(&transactions, "credit"))
+}
+
+/// Expected recurring income and charges in the next `days_ahead` days
+/// (1 - 366), based on series found in the past three years.
+#[tauri::command]
+pub async fn get_upcoming_recurring(
+    days_ahead: u32,
+    state: State<'_, AppState>
+) -> AppResult<Vec<UpcomingRecurring>> {
+    if !(1..=366).contains(&days_ahead) {
+        return Err(AppError::Validation("Aantal dagen moet tussen 1 en 366 liggen".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+    let now = Utc::now();
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(now - chrono::Duration::days(3 * 366))
+    .fetch_all(&pool)
+    .await?;
+
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+    let series = detect_recurring_series(&transactions);
+
+    Ok(upcoming_occurrences(&series, now, days_ahead))
+}
+
+/// Expected balance for each of the next `days_ahead` days (1 - 366) from
+/// `starting_balance`: recurring income and charges on their expected dates,
+/// and the usual day-to-day spending every day. Includes the lowest point and
+/// a warning when the balance is expected to go below zero.
+#[tauri::command]
+pub async fn forecast_cashflow(
+    days_ahead: u32,
+    starting_balance: Decimal,
+    state: State<'_, AppState>
+) -> AppResult<CashflowForecast> {
+    if !(1..=366).contains(&days_ahead) {
+        return Err(AppError::Validation("Aantal dagen moet tussen 1 en 366 liggen".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+    let now = Utc::now();
+    let settings = load_settings(&pool).await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(now - chrono::Duration::days(3 * 366))
+    .fetch_all(&pool)
+    .await?;
+
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+
+    Ok(forecast::forecast_cashflow(&transactions, starting_balance, days_ahead, &settings, now))
+}
+
+/// Subscriptions whose latest charge rose more than `threshold` (default 5%)
+/// above their earlier price. Each increase is also saved once as an insight.
+#[tauri::command]
+pub async fn detect_subscription_price_increases(
+    threshold: Option<f64>,
+    state: State<'_, AppState>
+) -> AppResult<Vec<PriceIncrease>> {
+    let threshold = threshold.unwrap_or(0.05);
+    if !(threshold.is_finite() && threshold > 0.0) {
+        return Err(AppError::Validation("Drempel moet groter dan 0 zijn".to_string()));
+    }
+    let threshold = Decimal::from_f64(threshold).unwrap_or(PRICE_INCREASE_THRESHOLD);
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE transaction_type = 'debit' AND date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(Utc::now() - chrono::Duration::days(400))
+    .fetch_all(&pool)
+    .await?;
+
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+    let increases = find_price_increases(&detect_recurring(&transactions, "debit"), threshold);
+
+    let settings = load_settings(&pool).await?;
+    for increase in &increases {
+        let insight = price_increase_insight(increase, &settings);
+
+        // The title names both prices, so a later increase is reported again
+        let known: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM financial_insights WHERE title = ?")
+            .bind(&insight.title)
+            .fetch_one(&pool)
+            .await?;
+
+        if known == 0 {
+            store_insight(&pool, &insight).await?;
+        }
+    }
+
+    Ok(increases)
+}
+
+/// Monthly subscriptions from the past 13 months, with price increases and
+/// long gaps between charges flagged. Each finding is also saved once as a
+/// `recurring_expense` insight.
+#[tauri::command]
+pub async fn analyze_subscriptions(state: State<'_, AppState>) -> AppResult<Vec<SubscriptionReview>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let now = Utc::now();
+    let transactions = load_transactions_since(&pool, now - chrono::Duration::days(400)).await?;
+    let settings = load_settings(&pool).await?;
+    let reviews = recurring::analyze_subscriptions(&detect_recurring(&transactions, "debit"), now, &settings);
+
+    for insight in reviews.iter().flat_map(|review| subscription_insights(review, &settings)) {
+        // Titles name the merchant and, for increases, both prices
+        let known: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM financial_insights WHERE title = ?")
+            .bind(&insight.title)
+            .fetch_one(&pool)
+            .await?;
+
+        if known == 0 {
+            store_insight(&pool, &insight).await?;
+        }
+    }
+
+    Ok(reviews)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+
+    fn insight(id: &str, title: &str, created_at: DateTime<Utc>) -> FinancialInsight {
+        FinancialInsight {
+            id: id.to_string(),
+            insight_type: "spending_pattern".to_string(),
+            title: title.to_string(),
+            description: String::new(),
+            impact: "low".to_string(),
+            actionable: false,
+            action_suggestions: "[]".to_string(),
+            confidence_score: 0.5,
+            is_read: false,
+            is_dismissed: false,
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dismissed_insight_is_not_stored_again() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let now = Utc::now();
+
+        let mut dismissed = insight("old", "Netflix duurder", now - chrono::Duration::days(3));
+        dismissed.is_dismissed = true;
+        store_insight(&pool, &dismissed).await.unwrap();
+
+        let generated = [insight("again", "Netflix duurder", now), insight("new", "Hoge uitgaven", now)];
+        store_new_insights(&pool, &generated, now).await.unwrap();
+
+        let mut ids: Vec<String> = sqlx::query_scalar("SELECT id FROM financial_insights")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        ids.sort();
+        assert_eq!(ids, vec!["new", "old"]);
+
+        pool.close().await;
+    }
+}
@@ -1,6 +1,13 @@
+use crate::audit::{self, AuditEntry};
+use crate::commands::budgets::roll_budget_periods;
+use crate::commands::settings::load_settings;
 use crate::error::AppResult;
-use crate::AppState;
+use crate::paths;
+use crate::{AppDatabase, AppState};
+use chrono::{DateTime, Duration, Utc};
 use serde::Serialize;
+use sqlx::SqlitePool;
+use std::path::Path;
 use tauri::State;
 
 #[derive(Debug, Serialize)]
@@ -48,4 +55,229 @@ pub async fn test_database(state: State<'_, AppState>) -> AppResult<serde_json::
         "database_stats": stats,
         "timestamp": chrono::Utc::now().to_rfc3339()
     }))
-}
\ No newline at end of file
+}
+#[derive(Debug, Serialize)]
+pub struct StorageUsage {
+    pub database_bytes: u64,
+    pub wal_bytes: u64,
+    pub shm_bytes: u64,
+    pub attachments_bytes: u64,
+    pub backups_bytes: u64,
+    pub logs_bytes: u64,
+    pub total_bytes: u64,
+}
+
+#[tauri::command]
+pub async fn get_storage_usage(state: State<'_, AppState>) -> AppResult<StorageUsage> {
+    let db_path = state.db.lock().await.path().to_string();
+
+    let database_bytes = paths::file_size(Path::new(&db_path));
+    let wal_bytes = paths::file_size(Path::new(&format!("{}-wal", db_path)));
+    let shm_bytes = paths::file_size(Path::new(&format!("{}-shm", db_path)));
+    let attachments_bytes = paths::dir_size(&paths::data_subdir(&db_path, paths::ATTACHMENTS_DIR));
+    let backups_bytes = paths::dir_size(&paths::data_subdir(&db_path, paths::BACKUPS_DIR));
+    let logs_bytes = paths::dir_size(&paths::data_subdir(&db_path, paths::LOGS_DIR));
+
+    Ok(StorageUsage {
+        database_bytes,
+        wal_bytes,
+        shm_bytes,
+        attachments_bytes,
+        backups_bytes,
+        logs_bytes,
+        total_bytes: database_bytes + wal_bytes + shm_bytes + attachments_bytes + backups_bytes + logs_bytes,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct CleanupReport {
+    pub insights_deleted: u64,
+    pub alerts_deleted: u64,
+    pub audit_entries_deleted: u64,
+}
+
+/// Remove derived data that only grows: insights older than the insight
+/// retention, and resolved alert records and audit log entries older than the
+/// audit retention.
+///
+/// The latest dismissed copy of each insight is kept, so an insight the user
+/// dismissed is still recognized as dismissed when it is generated again.
+pub(crate) async fn purge_derived_data(pool: &SqlitePool, now: DateTime<Utc>) -> AppResult<CleanupReport> {
+    let settings = load_settings(pool).await?;
+    let insight_cutoff = now - Duration::days(settings.insight_retention_days as i64);
+    let audit_cutoff = now - Duration::days(settings.audit_retention_days as i64);
+
+    let insights = sqlx::query(
+        r#"
+        DELETE FROM financial_insights
+        WHERE created_at < ?
+          AND id NOT IN (
+              SELECT f.id FROM financial_insights f
+              WHERE f.is_dismissed = TRUE
+                AND f.created_at = (
+                    SELECT MAX(g.created_at) FROM financial_insights g
+                    WHERE g.is_dismissed = TRUE
+                      AND g.insight_type = f.insight_type
+                      AND g.title = f.title
+                )
+          )
+        "#
+    )
+    .bind(insight_cutoff)
+    .execute(pool)
+    .await?;
+
+    // Alerts of the running month still debounce new ones, so only past months count as resolved
+    let alerts = sqlx::query("DELETE FROM spending_velocity_alerts WHERE triggered_at < ? AND period < ?")
+        .bind(audit_cutoff)
+        .bind(now.format("%Y-%m").to_string())
+        .execute(pool)
+        .await?;
+
+    let audit_entries = audit::purge_before(pool, audit_cutoff).await?;
+
+    Ok(CleanupReport {
+        insights_deleted: insights.rows_affected(),
+        alerts_deleted: alerts.rows_affected(),
+        audit_entries_deleted: audit_entries,
+    })
+}
+
+/// Purge derived data and roll budgets into their next period. Failures are
+/// logged, not returned, as nothing waits on the result.
+pub(crate) async fn run_cleanup(pool: &SqlitePool) {
+    match purge_derived_data(pool, Utc::now()).await {
+        Ok(report) => tracing::info!(
+            "Cleanup removed {} insights, {} alert records and {} audit entries",
+            report.insights_deleted,
+            report.alerts_deleted,
+            report.audit_entries_deleted
+        ),
+        Err(e) => tracing::warn!("Cleanup of derived data failed: {}", e),
+    }
+
+    if let Err(e) = roll_budget_periods(pool, Utc::now()).await {
+        tracing::warn!("Budget period rollover failed: {}", e);
+    }
+}
+
+/// Run [`run_cleanup`] once a day for as long as the app runs. The database
+/// starts locked, so the first run happens on unlock rather than here.
+pub(crate) async fn run_daily_cleanup(db: AppDatabase) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(24 * 60 * 60));
+    // The first tick completes immediately
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        let pool = {
+            let db = db.lock().await;
+            // Nothing to clean before the user has unlocked the database
+            if db.is_locked().await {
+                continue;
+            }
+            db.get_pool().await
+        };
+
+        match pool {
+            Ok(pool) => run_cleanup(&pool).await,
+            Err(e) => tracing::warn!("Cleanup of derived data failed: {}", e),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn cleanup_derived_data(state: State<'_, AppState>) -> AppResult<CleanupReport> {
+    let pool = state.db.lock().await.get_pool().await?;
+    purge_derived_data(&pool, Utc::now()).await
+}
+
+/// Most audit log entries returned in one page
+const AUDIT_PAGE_MAX: u32 = 500;
+
+/// A page of the audit log, newest first, so the user can review exports,
+/// resets and other sensitive actions.
+#[tauri::command]
+pub async fn get_audit_log(
+    limit: Option<u32>,
+    offset: Option<u32>,
+    state: State<'_, AppState>
+) -> AppResult<Vec<AuditEntry>> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let limit = limit.unwrap_or(100).min(AUDIT_PAGE_MAX);
+    audit::entries(&pool, Some(limit), offset.unwrap_or(0)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+
+    #[tokio::test]
+    async fn test_purge_keeps_latest_dismissal() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let now = Utc::now();
+
+        for (id, title, days_old, dismissed) in [
+            ("old", "Hoge uitgaven", 200, false),
+            ("old-dismissed", "Netflix duurder", 300, true),
+            ("newer-dismissed", "Netflix duurder", 150, true),
+            ("recent", "Hoge uitgaven", 5, false),
+        ] {
+            sqlx::query(
+                r#"
+                INSERT INTO financial_insights (id, insight_type, title, description, impact, confidence_score, is_dismissed, created_at)
+                VALUES (?, 'spending_pattern', ?, '', 'low', 0.5, ?, ?)
+                "#
+            )
+            .bind(id)
+            .bind(title)
+            .bind(dismissed)
+            .bind(now - Duration::days(days_old))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let report = purge_derived_data(&pool, now).await.unwrap();
+        let mut left: Vec<String> = sqlx::query_scalar("SELECT id FROM financial_insights")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        left.sort();
+
+        assert_eq!(report.insights_deleted, 2);
+        assert_eq!(left, vec!["newer-dismissed", "recent"]);
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_purge_drops_audit_entries_past_retention() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let now = Utc::now();
+
+        audit::record(&pool, audit::SETTINGS_RESET, "success", None).await.unwrap();
+        sqlx::query("INSERT INTO audit_log (id, action, outcome, detail, created_at) VALUES ('old', ?, 'denied', NULL, ?)")
+            .bind(audit::DATA_EXPORT)
+            .bind(now - Duration::days(400))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(audit::entries(&pool, None, 0).await.unwrap().len(), 2);
+
+        let report = purge_derived_data(&pool, now).await.unwrap();
+        let left = audit::entries(&pool, Some(10), 0).await.unwrap();
+
+        assert_eq!(report.audit_entries_deleted, 1);
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].action, audit::SETTINGS_RESET);
+        assert!(left[0].success);
+
+        pool.close().await;
+    }
+}
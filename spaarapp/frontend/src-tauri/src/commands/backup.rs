@@ -0,0 +1,314 @@
+use crate::commands::files::{check_readable, check_writable};
+use crate::commands::settings::load_settings;
+use crate::commands::key_rotation::{passphrase_keys, reencrypt_fields, PassphraseKeys};
+use crate::database::{connect_file, file_schema_version, rekey, Database, SCHEMA_VERSION};
+use crate::encryption::{load_encryption_config, EncryptionConfig, EncryptionManager, ENCRYPTION_CONFIG_FILE};
+use crate::error::{AppError, AppResult};
+use crate::paths;
+use crate::AppState;
+use chrono::{DateTime, Utc};
+use sqlx::Connection;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+/// Start of backups from before they recorded their salts, followed by the
+/// encrypted database snapshot.
+const BACKUP_HEADER_V1: &[u8] = b"SPAARAPP-BACKUP-v1\n";
+
+/// Start of every backup file, followed by a line with the field and database
+/// salts the backup was made with and then the encrypted database snapshot.
+/// The salts let a backup from before a key rotation be restored with the
+/// passphrase of that time.
+const BACKUP_HEADER: &[u8] = b"SPAARAPP-BACKUP-v2\n";
+
+/// Placeholder for a database salt that was not set when the backup was made
+const NO_SALT: &str = "-";
+
+/// Salts recorded in a backup.
+struct BackupSalts {
+    salt: String,
+    database_salt: Option<String>,
+}
+
+/// `path` with `suffix` appended to its file name.
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn seal_backup(snapshot: &[u8], encryption: &EncryptionManager, config: &EncryptionConfig) -> AppResult<Vec<u8>> {
+    let mut backup = BACKUP_HEADER.to_vec();
+    let database_salt = config.database_salt.as_deref().unwrap_or(NO_SALT);
+    backup.extend(format!("{} {}\n", config.salt, database_salt).into_bytes());
+    backup.extend(encryption.encrypt_data(snapshot)?);
+    Ok(backup)
+}
+
+/// Split a backup into its salts, `None` for v1 backups, and the encrypted
+/// snapshot.
+fn split_backup(backup: &[u8]) -> AppResult<(Option<BackupSalts>, &[u8])> {
+    let not_a_backup = || AppError::Validation("Dit bestand is geen SpaarApp-back-up".to_string());
+
+    if let Some(encrypted) = backup.strip_prefix(BACKUP_HEADER_V1) {
+        return Ok((None, encrypted));
+    }
+
+    let rest = backup.strip_prefix(BACKUP_HEADER).ok_or_else(not_a_backup)?;
+    let end = rest.iter().position(|&b| b == b'\n').ok_or_else(not_a_backup)?;
+    let line = std::str::from_utf8(&rest[..end]).map_err(|_| not_a_backup())?;
+    let (salt, database_salt) = line.split_once(' ').ok_or_else(not_a_backup)?;
+
+    let salts = BackupSalts {
+        salt: salt.to_string(),
+        database_salt: (database_salt != NO_SALT).then(|| database_salt.to_string()),
+    };
+    Ok((Some(salts), &rest[end + 1..]))
+}
+
+fn decrypt_snapshot(encrypted: &[u8], encryption: &EncryptionManager) -> AppResult<Vec<u8>> {
+    encryption.decrypt_data(encrypted).map_err(|_| {
+        AppError::Validation("Back-up kan niet ontsleuteld worden; is hij met een andere sleutel gemaakt?".to_string())
+    })
+}
+
+/// Write an encrypted snapshot of `db` to `target`, recording the salts of
+/// `config`. The file only appears at `target` once it is complete, so a
+/// failed backup never replaces a good one.
+pub(crate) async fn write_backup(
+    db: &Database,
+    encryption: &EncryptionManager<'_>,
+    config: &EncryptionConfig,
+    target: &Path,
+) -> AppResult<()> {
+    let snapshot_dir = paths::data_subdir(db.path(), paths::BACKUPS_DIR);
+    std::fs::create_dir_all(&snapshot_dir)?;
+    let snapshot = snapshot_dir.join(format!("{}.snapshot", Uuid::new_v4()));
+
+    let contents = match db.snapshot_to(&snapshot).await {
+        Ok(()) => std::fs::read(&snapshot).map_err(AppError::from),
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_file(&snapshot);
+    let backup = seal_backup(&contents?, encryption, config)?;
+
+    let partial = with_suffix(target, ".tmp");
+    std::fs::write(&partial, backup)?;
+    std::fs::rename(&partial, target)?;
+    Ok(())
+}
+
+/// Keys for a backup made with `old_passphrase` under the salts it recorded.
+fn backup_keys(salts: Option<BackupSalts>, old_passphrase: &str, config: &EncryptionConfig) -> AppResult<PassphraseKeys> {
+    let salts = salts.ok_or_else(|| {
+        AppError::Validation(
+            "Deze back-up is gemaakt voordat back-ups hun sleutelgegevens bewaarden en kan alleen met het huidige wachtwoord teruggezet worden"
+                .to_string(),
+        )
+    })?;
+    let backup_config = EncryptionConfig {
+        salt: salts.salt,
+        database_salt: salts.database_salt,
+        ..config.clone()
+    };
+    passphrase_keys(old_passphrase, &backup_config)
+}
+
+/// Replace the database of `db` with the backup at `source`. Backups from a
+/// newer schema than this version of the app understands are refused.
+///
+/// A backup from before a key rotation is restored with `old_passphrase`:
+/// its fields are re-encrypted and it is re-keyed to the current keys before
+/// it replaces the database.
+pub(crate) async fn restore_backup(
+    db: &mut Database,
+    encryption: &EncryptionManager<'_>,
+    config: &EncryptionConfig,
+    source: &Path,
+    old_passphrase: Option<&str>,
+) -> AppResult<()> {
+    let backup = std::fs::read(source)?;
+    let (salts, encrypted) = split_backup(&backup)?;
+    let old_keys = match old_passphrase {
+        Some(passphrase) => Some(backup_keys(salts, passphrase, config)?),
+        None => None,
+    };
+    let snapshot = decrypt_snapshot(encrypted, old_keys.as_ref().map_or(encryption, |keys| &keys.fields))?;
+
+    // Next to the database, so putting it in place is a rename on the same file system
+    let staged = with_suffix(Path::new(db.path()), ".restore");
+    std::fs::write(&staged, snapshot)?;
+
+    let key = db.key().to_string();
+    let checked = async {
+        let snapshot_key = old_keys.as_ref().map_or(key.as_str(), |keys| &keys.database_key);
+        let version = file_schema_version(&staged, snapshot_key).await?;
+        if version > SCHEMA_VERSION {
+            return Err(AppError::Validation(format!(
+                "Deze back-up komt uit een nieuwere versie van SpaarApp (schema {}, ondersteund t/m {})",
+                version, SCHEMA_VERSION
+            )));
+        }
+
+        if let Some(keys) = &old_keys {
+            let mut conn = connect_file(&staged, &keys.database_key).await?;
+            let mut tx = conn.begin().await?;
+            reencrypt_fields(&mut tx, &keys.fields, encryption).await?;
+            tx.commit().await?;
+            rekey(&mut conn, &key).await?;
+            conn.close().await?;
+        }
+        Ok(())
+    }
+    .await;
+    if let Err(e) = checked {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e);
+    }
+
+    db.replace_with(&staged, &key).await
+}
+
+/// Write an encrypted backup of the database to `target_path` and record the
+/// time in the settings.
+#[tauri::command]
+pub async fn backup_database(
+    target_path: String,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<DateTime<Utc>> {
+    check_writable(&app, &state, &target_path).await?;
+    let config = load_encryption_config(ENCRYPTION_CONFIG_FILE)?;
+    let db = state.db.lock().await;
+    {
+        let encryption = state.encryption.lock().await;
+        write_backup(&db, &encryption, &config, Path::new(&target_path)).await?;
+    }
+
+    let pool = db.get_pool().await?;
+    let settings = load_settings(&pool).await?;
+    let now = Utc::now();
+
+    sqlx::query("UPDATE settings SET last_backup = ?, updated_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(now)
+        .bind(&settings.id)
+        .execute(&pool)
+        .await?;
+
+    Ok(now)
+}
+
+/// Replace all data with the backup at `source_path`. Pass `old_passphrase`
+/// for a backup made before the key was rotated.
+#[tauri::command]
+pub async fn restore_database(
+    source_path: String,
+    old_passphrase: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<()> {
+    check_readable(&app, &state, &source_path).await?;
+    let config = load_encryption_config(ENCRYPTION_CONFIG_FILE)?;
+    let mut db = state.db.lock().await;
+    let encryption = state.encryption.lock().await;
+
+    restore_backup(&mut db, &encryption, &config, Path::new(&source_path), old_passphrase.as_deref()).await?;
+    tracing::info!("Database restored from {}", source_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::key_rotation::rotate_key;
+    use crate::commands::unlock::set_initial_passphrase;
+    use crate::database::TestDatabase;
+
+    #[tokio::test]
+    async fn test_backup_restores_earlier_state() {
+        let mut db = TestDatabase::new().await;
+        let dir = db.dir.clone();
+
+        let mut encryption = EncryptionManager::new();
+        encryption.set_master_key("test_password", &EncryptionManager::generate_salt().unwrap()).unwrap();
+        let config = EncryptionConfig::default();
+
+        let backup = dir.join("spaarapp.backup");
+        write_backup(&db, &encryption, &config, &backup).await.unwrap();
+
+        let pool = db.pool().await;
+        sqlx::query(
+            "INSERT INTO transactions (id, description, amount, date, transaction_type) VALUES ('t1', 'Na de back-up', '10.00', ?, 'debit')"
+        )
+        .bind(Utc::now())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // A backup from a newer schema is refused
+        let newer_db = dir.join("newer.db");
+        std::fs::write(&newer_db, decrypt_snapshot(split_backup(&std::fs::read(&backup).unwrap()).unwrap().1, &encryption).unwrap()).unwrap();
+        let mut conn = sqlx::SqliteConnection::connect(&format!("sqlite:{}", newer_db.display())).await.unwrap();
+        sqlx::query("INSERT INTO schema_migrations (version, description) VALUES (?, 'uit de toekomst')")
+            .bind(SCHEMA_VERSION + 1)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+        let newer_backup = dir.join("newer.backup");
+        std::fs::write(&newer_backup, seal_backup(&std::fs::read(&newer_db).unwrap(), &encryption, &config).unwrap()).unwrap();
+        assert!(matches!(restore_backup(&mut db, &encryption, &config, &newer_backup, None).await, Err(AppError::Validation(_))));
+
+        restore_backup(&mut db, &encryption, &config, &backup, None).await.unwrap();
+        let pool = db.pool().await;
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions").fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 0);
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_backup_from_before_rotation_restores_with_old_passphrase() {
+        let mut db = TestDatabase::locked();
+        let config_path = db.dir.join("encryption.json");
+        let old_passphrase = "het oude lange wachtwoord";
+
+        let mut encryption = EncryptionManager::new();
+        set_initial_passphrase(&mut db, &mut encryption, &config_path, old_passphrase).await.unwrap();
+
+        let pool = db.pool().await;
+        sqlx::query(
+            "INSERT INTO transactions (id, description, amount, date, transaction_type, notes) VALUES ('t1', 'Tandarts', '80.00', ?, 'debit', ?)"
+        )
+        .bind(Utc::now())
+        .bind(encryption.encrypt_field("Controle januari").unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let backup = db.dir.join("spaarapp.backup");
+        write_backup(&db, &encryption, &load_encryption_config(&config_path).unwrap(), &backup).await.unwrap();
+
+        rotate_key(&mut db, &mut encryption, &config_path, old_passphrase, "een veel langer nieuw wachtwoord")
+            .await
+            .unwrap();
+        let config = load_encryption_config(&config_path).unwrap();
+        sqlx::query("DELETE FROM transactions").execute(&db.pool().await).await.unwrap();
+
+        // The current key cannot open it, the old passphrase can
+        let refused = restore_backup(&mut db, &encryption, &config, &backup, None).await;
+        assert!(matches!(refused, Err(AppError::Validation(_))));
+        restore_backup(&mut db, &encryption, &config, &backup, Some(old_passphrase)).await.unwrap();
+
+        let pool = db.pool().await;
+        let notes: String = sqlx::query_scalar("SELECT notes FROM transactions WHERE id = 't1'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(encryption.decrypt_field(&notes).unwrap(), "Controle januari");
+
+        pool.close().await;
+    }
+}
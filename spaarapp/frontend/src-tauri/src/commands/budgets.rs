@@ -1,49 +1,66 @@
-use crate::error::AppResult;
-use crate::models::{Budget, BudgetPeriod};
+use crate::audit;
+use crate::commands::accounts::account_iban;
+use crate::commands::settings::load_settings;
+use crate::commands::transactions::{decrypt_notes, transaction_from_row, TRANSACTION_COLUMNS};
+use crate::database::{get_decimal, get_optional_decimal};
+use crate::error::{AppError, AppResult};
+use crate::models::{Budget, BudgetPeriod, Transaction};
 use crate::AppState;
-use tauri::State;
-use chrono::Utc;
+use tauri::{AppHandle, Emitter, State};
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeZone, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use uuid::Uuid;
-use sqlx::{self, Row};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{self, Row, SqliteConnection, SqlitePool};
 
 #[tauri::command]
 pub async fn get_budgets(state: State<'_, AppState>) -> AppResult<Vec<Budget>> {
     let pool = state.db.lock().await.get_pool().await?;
 
-    let rows = sqlx::query(
+    let rows = sqlx::query(&format!(
         r#"
         SELECT
-            id, name, category_id, amount, period, spent, remaining, is_active,
-            notification_threshold, start_date, end_date, created_at, updated_at
+            {}
         FROM budgets
         WHERE is_active = TRUE
         ORDER BY created_at DESC
-        "#
-    )
+        "#,
+        BUDGET_COLUMNS
+    ))
     .fetch_all(&pool)
     .await?;
 
-    let budgets = rows.into_iter().map(|row| {
-        crate::models::Budget {
-            id: row.get("id"),
-            name: row.get("name"),
-            category_id: row.get("category_id"),
-            amount: row.get::<String, _>("amount").parse().unwrap_or_default(),
-            period: row.get("period"),
-            spent: row.get::<String, _>("spent").parse().unwrap_or_default(),
-            remaining: row.get::<String, _>("remaining").parse().unwrap_or_default(),
-            is_active: row.get("is_active"),
-            notification_threshold: row.get::<Option<String>, _>("notification_threshold").and_then(|s| s.parse().ok()),
-            start_date: row.get("start_date"),
-            end_date: row.get("end_date"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-        }
-    }).collect();
+    let budgets = rows.iter().map(budget_from_row).collect();
 
     Ok(budgets)
 }
 
+/// The columns `budget_from_row` reads, for the SELECT list of queries on `budgets`.
+pub(crate) const BUDGET_COLUMNS: &str = "id, name, category_id, amount, period, spent, remaining, is_active, \
+    notification_threshold, start_date, end_date, created_at, updated_at";
+
+pub(crate) fn budget_from_row(row: &SqliteRow) -> Budget {
+    let amount = get_decimal(row, "amount");
+    let spent = get_decimal(row, "spent");
+
+    Budget {
+        id: row.get("id"),
+        name: row.get("name"),
+        category_id: row.get("category_id"),
+        amount,
+        period: row.get("period"),
+        spent,
+        remaining: amount - spent,
+        is_active: row.get("is_active"),
+        notification_threshold: get_optional_decimal(row, "notification_threshold"),
+        start_date: row.get("start_date"),
+        end_date: row.get("end_date"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
 #[tauri::command]
 pub async fn add_budget(
     mut budget: Budget,
@@ -60,7 +77,7 @@ pub async fn add_budget(
     let now = Utc::now();
     budget.created_at = now;
     budget.updated_at = now;
-    budget.spent = rust_decimal::Decimal::ZERO; // New budgets start with 0 spent
+    budget.spent = rust_decimal::Decimal::ZERO;
     budget.is_active = true;
 
     let result = sqlx::query(
@@ -92,10 +109,9 @@ pub async fn add_budget(
         ));
     }
 
-    // Calculate remaining (amount - spent)
-    budget.remaining = budget.amount - budget.spent;
-
-    Ok(budget)
+    // Transactions already in the budget's window count from the start
+    let mut conn = pool.acquire().await?;
+    recompute_budget_spending(&mut conn, &budget.id).await
 }
 
 #[tauri::command]
@@ -110,14 +126,6 @@ pub async fn update_budget(
     budget.id = id.clone();
     budget.updated_at = Utc::now();
 
-    // Don't allow changing spent amount directly - this should be calculated
-    let existing_row = sqlx::query("SELECT spent FROM budgets WHERE id = ?")
-        .bind(&id)
-        .fetch_one(&pool)
-        .await?;
-
-    budget.spent = existing_row.get::<String, _>("spent").parse().unwrap_or_default();
-
     let result = sqlx::query(
         r#"
         UPDATE budgets SET
@@ -145,10 +153,9 @@ pub async fn update_budget(
         ));
     }
 
-    // Update remaining amount
-    budget.remaining = budget.amount - budget.spent;
-
-    Ok(budget)
+    // Category or dates may have changed, so `spent` is derived again rather than kept
+    let mut conn = pool.acquire().await?;
+    recompute_budget_spending(&mut conn, &id).await
 }
 
 #[tauri::command]
@@ -160,58 +167,638 @@ pub async fn delete_budget(id: String, state: State<'_, AppState>) -> AppResult<
         .execute(&pool)
         .await?;
 
-    Ok(result.rows_affected() > 0)
+    let deleted = result.rows_affected() > 0;
+    if deleted {
+        audit::record(&pool, audit::BUDGET_DELETE, "success", Some(&id)).await?;
+    }
+
+    Ok(deleted)
 }
 
+/// Recalculate a budget's `spent` from its transactions.
 #[tauri::command]
-pub async fn update_budget_spending(
+pub async fn recompute_budget(
     id: String,
-    additional_spent: f64,
     state: State<'_, AppState>
 ) -> AppResult<Budget> {
     let pool = state.db.lock().await.get_pool().await?;
+    let mut conn = pool.acquire().await?;
+
+    recompute_budget_spending(&mut conn, &id).await
+}
+
+/// How close a budget is to its limit, as shown by the budget alerts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetAlertLevel {
+    Ok,
+    /// Spent has reached the notification threshold, or 80% of the amount without one
+    Warning,
+    Exceeded,
+}
+
+/// Spent amount at which a budget turns to warning: its notification
+/// threshold, or 80% of the amount without one.
+pub(crate) fn warning_at(budget: &Budget) -> Decimal {
+    budget
+        .notification_threshold
+        .unwrap_or(budget.amount * Decimal::new(8, 1))
+}
+
+impl BudgetAlertLevel {
+    pub fn for_budget(budget: &Budget) -> Self {
+        let warning_at = warning_at(budget);
 
-    let rows = sqlx::query(
+        if budget.spent >= budget.amount {
+            BudgetAlertLevel::Exceeded
+        } else if budget.spent >= warning_at {
+            BudgetAlertLevel::Warning
+        } else {
+            BudgetAlertLevel::Ok
+        }
+    }
+}
+
+/// Payload of the `budget_alert_level_changed` event.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BudgetAlertLevelChanged {
+    pub budget_id: String,
+    pub previous_level: BudgetAlertLevel,
+    pub level: BudgetAlertLevel,
+    pub amount: Decimal,
+    pub spent: Decimal,
+}
+
+/// Alert threshold of one budget, as reviewed in the alert settings.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BudgetAlertSetting {
+    pub budget_id: String,
+    pub name: String,
+    pub amount: Decimal,
+    /// Spent amount at which the budget turns to warning
+    pub warning_at: Decimal,
+    /// `warning_at` as a percentage of the budget amount
+    pub warning_percentage: Decimal,
+    /// No threshold of its own; the 80% default applies
+    pub uses_default: bool,
+}
+
+#[tauri::command]
+pub async fn get_budget_alert_settings(state: State<'_, AppState>) -> AppResult<Vec<BudgetAlertSetting>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(&format!(
         r#"
-        UPDATE budgets SET
-            spent = spent + ?,
-            updated_at = ?
-        WHERE id = ?
-        RETURNING id, name, category_id, amount, period, spent, remaining, is_active,
-                  notification_threshold, start_date, end_date, created_at, updated_at
-        "#
-    )
-    .bind(additional_spent.to_string())
-    .bind(Utc::now())
-    .bind(&id)
+        SELECT
+            {}
+        FROM budgets
+        WHERE is_active = TRUE
+        ORDER BY name
+        "#,
+        BUDGET_COLUMNS
+    ))
     .fetch_all(&pool)
     .await?;
 
-    if rows.is_empty() {
-        return Err(crate::error::AppError::Database(sqlx::Error::RowNotFound));
+    Ok(rows
+        .iter()
+        .map(budget_from_row)
+        .map(|budget| {
+            let warning_at = warning_at(&budget);
+            BudgetAlertSetting {
+                warning_percentage: if budget.amount.is_zero() {
+                    Decimal::ZERO
+                } else {
+                    (warning_at / budget.amount * Decimal::from(100)).round_dp(1)
+                },
+                uses_default: budget.notification_threshold.is_none(),
+                warning_at,
+                amount: budget.amount,
+                name: budget.name,
+                budget_id: budget.id,
+            }
+        })
+        .collect())
+}
+
+/// How a bulk alert threshold is expressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdType {
+    /// Percentage of each budget's amount
+    Percent,
+    /// The same spent amount for every budget
+    Amount,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SkippedBudget {
+    pub budget_id: String,
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BulkThresholdOutcome {
+    pub updated: usize,
+    pub skipped: Vec<SkippedBudget>,
+}
+
+/// Threshold for one budget, or why the value does not apply to it.
+fn threshold_for(budget: &Budget, threshold_type: ThresholdType, value: Decimal) -> Result<Decimal, String> {
+    match threshold_type {
+        ThresholdType::Percent => Ok((budget.amount * value / Decimal::from(100)).round_dp(2)),
+        ThresholdType::Amount if value > budget.amount => Err(format!(
+            "Drempel {} is hoger dan het budget van {}",
+            value, budget.amount
+        )),
+        ThresholdType::Amount => Ok(value),
+    }
+}
+
+/// Apply one alert threshold to every active budget. Budgets the value does
+/// not fit (an amount above the budget itself) keep their own threshold. A
+/// budget whose new threshold is above its spending alerts again once it
+/// gets there.
+#[tauri::command]
+pub async fn set_all_budget_thresholds(
+    threshold_type: ThresholdType,
+    value: Decimal,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<BulkThresholdOutcome> {
+    match threshold_type {
+        ThresholdType::Percent if value <= Decimal::ZERO || value > Decimal::from(100) => {
+            return Err(AppError::Validation("Percentage moet tussen 0 en 100 liggen".to_string()));
+        }
+        ThresholdType::Amount if value <= Decimal::ZERO => {
+            return Err(AppError::Validation("Drempelbedrag moet groter dan 0 zijn".to_string()));
+        }
+        _ => {}
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        WHERE is_active = TRUE
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let now = Utc::now();
+    let mut outcome = BulkThresholdOutcome { updated: 0, skipped: Vec::new() };
+
+    for budget in rows.iter().map(budget_from_row) {
+        match threshold_for(&budget, threshold_type, value) {
+            Ok(threshold) => {
+                sqlx::query(
+                    r#"
+                    UPDATE budgets
+                    SET notification_threshold = ?, threshold_notified = threshold_notified AND ?, updated_at = ?
+                    WHERE id = ?
+                    "#
+                )
+                .bind(threshold.to_string())
+                .bind(threshold <= budget.spent)
+                .bind(now)
+                .bind(&budget.id)
+                .execute(&mut *tx)
+                .await?;
+                outcome.updated += 1;
+            }
+            Err(reason) => outcome.skipped.push(SkippedBudget {
+                budget_id: budget.id,
+                name: budget.name,
+                reason,
+            }),
+        }
+    }
+
+    tx.commit().await?;
+
+    if let Err(e) = check_budget_thresholds(&app, &pool).await {
+        tracing::warn!("Budget threshold check failed: {}", e);
+    }
+
+    Ok(outcome)
+}
+
+/// Change only a budget's amount, leaving the rest of the row untouched.
+#[tauri::command]
+pub async fn set_budget_amount(
+    id: String,
+    amount: Decimal,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<Budget> {
+    if amount <= Decimal::ZERO {
+        return Err(AppError::Validation("Budgetbedrag moet groter dan 0 zijn".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    let select = format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        WHERE id = ?
+        "#,
+        BUDGET_COLUMNS
+    );
+
+    let previous = match sqlx::query(&select).bind(&id).fetch_optional(&mut *tx).await? {
+        Some(row) => budget_from_row(&row),
+        None => return Err(AppError::NotFound(format!("Budget {} niet gevonden", id))),
+    };
+
+    sqlx::query("UPDATE budgets SET amount = ?, updated_at = ? WHERE id = ?")
+        .bind(amount.to_string())
+        .bind(Utc::now())
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+
+    let row = sqlx::query(&select).bind(&id).fetch_one(&mut *tx).await?;
+    let budget = budget_from_row(&row);
+    tx.commit().await?;
+
+    if let Err(e) = check_budget_thresholds(&app, &pool).await {
+        tracing::warn!("Budget threshold check failed: {}", e);
+    }
+
+    let previous_level = BudgetAlertLevel::for_budget(&previous);
+    let level = BudgetAlertLevel::for_budget(&budget);
+    if level != previous_level {
+        let _ = app.emit("budget_alert_level_changed", BudgetAlertLevelChanged {
+            budget_id: budget.id.clone(),
+            previous_level,
+            level,
+            amount: budget.amount,
+            spent: budget.spent,
+        });
+    }
+
+    Ok(budget)
+}
+
+/// Whether a transaction counts toward a budget's `spent`: an active budget,
+/// a debit in the budget's category, dated within the budget's window.
+pub(crate) fn counts_toward_budget(budget: &Budget, transaction: &Transaction) -> bool {
+    budget.is_active
+        && transaction.transaction_type == "debit"
+        && transaction.category_id.is_some()
+        && transaction.category_id == budget.category_id
+        && budget.start_date <= transaction.date
+        && budget.end_date.is_none_or(|end| transaction.date <= end)
+}
+
+/// Set a budget's `spent` to the sum of the transactions that count toward it.
+pub(crate) async fn recompute_budget_spending(conn: &mut SqliteConnection, budget_id: &str) -> AppResult<Budget> {
+    let row = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        WHERE id = ?
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .bind(budget_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Budget {} niet gevonden", budget_id)))?;
+    let mut budget = budget_from_row(&row);
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transaction_portions
+        WHERE category_id = ? AND transaction_type = 'debit' AND date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(&budget.category_id)
+    .bind(budget.start_date)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let spent: Decimal = rows
+        .iter()
+        .map(transaction_from_row)
+        .filter(|transaction| counts_toward_budget(&budget, transaction))
+        .map(|transaction| transaction.amount.abs())
+        .sum();
+
+    if spent != budget.spent {
+        budget.updated_at = Utc::now();
+        sqlx::query("UPDATE budgets SET spent = ?, updated_at = ? WHERE id = ?")
+            .bind(spent.to_string())
+            .bind(budget.updated_at)
+            .bind(&budget.id)
+            .execute(&mut *conn)
+            .await?;
     }
 
-    let row = &rows[0];
-    let amount = row.get::<String, _>("amount").parse().unwrap_or_default();
-    let spent = row.get::<String, _>("spent").parse().unwrap_or_default();
+    budget.spent = spent;
+    budget.remaining = budget.amount - spent;
+    Ok(budget)
+}
+
+/// Recompute `spent` for every budget on `category_id`; call after any change
+/// to a transaction in that category.
+pub(crate) async fn recompute_category_budgets(
+    conn: &mut SqliteConnection,
+    category_id: Option<&str>,
+) -> AppResult<()> {
+    let Some(category_id) = category_id else {
+        return Ok(());
+    };
 
-    let budget = Budget {
+    let budget_ids: Vec<String> = sqlx::query_scalar("SELECT id FROM budgets WHERE category_id = ?")
+        .bind(category_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for budget_id in budget_ids {
+        recompute_budget_spending(conn, &budget_id).await?;
+    }
+
+    Ok(())
+}
+
+/// The window after `start`..`end`. The end is placed as far before the next
+/// period's start as it was before this one's, so a window that ends at
+/// 23:59:59 on the last day of the month keeps doing so in shorter months.
+pub(crate) fn next_period_window(
+    period: &BudgetPeriod,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let next_start = period.advance(start);
+    let gap = next_start - end;
+    (next_start, period.advance(next_start) - gap)
+}
+
+/// The monthly window after `start`..`end` for budget months starting on
+/// `start_day`, with the same gap between its end and the next start. A window
+/// not on the start day yet is followed by a shorter one that ends there.
+pub(crate) fn next_budget_month_window(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    start_day: u8,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let (_, boundary) = budget_month_bounds(start, start_day);
+    let next_start = if boundary > end { boundary } else { BudgetPeriod::Monthly.advance(start) };
+    let gap = next_start - end;
+    (next_start, budget_month_bounds(next_start, start_day).1 - gap)
+}
+
+/// Move every active budget whose `end_date` has passed into the period that
+/// contains `now`, keeping each closed period in `budget_periods`, and derive
+/// `spent` for the new window. Budgets without an end date run indefinitely
+/// and are left alone. Monthly budgets follow the configured budget month
+/// start day. Returns the number of periods closed.
+pub(crate) async fn roll_budget_periods(pool: &SqlitePool, now: DateTime<Utc>) -> AppResult<usize> {
+    let start_day = load_settings(pool).await?.budget_month_start_day;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        WHERE is_active = TRUE AND end_date IS NOT NULL AND end_date < ?
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .bind(now)
+    .fetch_all(pool)
+    .await?;
+
+    let mut closed = 0;
+    for budget in rows.iter().map(budget_from_row) {
+        let Some(period) = BudgetPeriod::parse(&budget.period) else {
+            tracing::warn!("Budget {} has unknown period '{}', not rolled over", budget.id, budget.period);
+            continue;
+        };
+        let (mut start, mut end) = (budget.start_date, budget.end_date.unwrap_or(budget.start_date));
+
+        let mut tx = pool.begin().await?;
+        // Periods the app wasn't running for are recorded too
+        while end < now {
+            sqlx::query(
+                "INSERT INTO budget_periods (id, budget_id, amount, start_date, end_date) VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&budget.id)
+            .bind(budget.amount.to_string())
+            .bind(start)
+            .bind(end)
+            .execute(&mut *tx)
+            .await?;
+            closed += 1;
+
+            (start, end) = match period {
+                // With the default start day monthly budgets keep their own anchor
+                BudgetPeriod::Monthly if start_day > 1 => next_budget_month_window(start, end, start_day),
+                _ => next_period_window(&period, start, end),
+            };
+        }
+
+        sqlx::query(
+            "UPDATE budgets SET start_date = ?, end_date = ?, threshold_notified = FALSE, updated_at = ? WHERE id = ?"
+        )
+            .bind(start)
+            .bind(end)
+            .bind(Utc::now())
+            .bind(&budget.id)
+            .execute(&mut *tx)
+            .await?;
+        recompute_budget_spending(&mut tx, &budget.id).await?;
+        tx.commit().await?;
+    }
+
+    Ok(closed)
+}
+
+/// A closed period of a budget as stored in `budget_periods`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ClosedBudgetPeriod {
+    pub id: String,
+    pub budget_id: String,
+    /// Budgeted amount during the period
+    pub amount: Decimal,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub(crate) fn closed_period_from_row(row: &SqliteRow) -> ClosedBudgetPeriod {
+    ClosedBudgetPeriod {
         id: row.get("id"),
-        name: row.get("name"),
-        category_id: row.get("category_id"),
-        amount,
-        period: row.get("period"),
-        spent,
-        remaining: amount - spent,
-        is_active: row.get("is_active"),
-        notification_threshold: row.get::<Option<String>, _>("notification_threshold").and_then(|s| s.parse().ok()),
+        budget_id: row.get("budget_id"),
+        amount: get_decimal(row, "amount"),
         start_date: row.get("start_date"),
         end_date: row.get("end_date"),
         created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
+    }
+}
+
+/// A closed period of a budget with what was spent in it.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BudgetPeriodSummary {
+    pub budget_id: String,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub amount: Decimal,
+    pub spent: Decimal,
+    pub remaining: Decimal,
+}
+
+/// Past periods of a budget, newest first. Spending is derived from the
+/// transactions, so corrections to old transactions show up here as well.
+pub(crate) async fn budget_history(pool: &SqlitePool, budget_id: &str) -> AppResult<Vec<BudgetPeriodSummary>> {
+    let row = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        WHERE id = ?
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .bind(budget_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Budget {} niet gevonden", budget_id)))?;
+    let budget = budget_from_row(&row);
+
+    let periods = sqlx::query(
+        "SELECT amount, start_date, end_date FROM budget_periods WHERE budget_id = ? ORDER BY start_date DESC"
+    )
+    .bind(budget_id)
+    .fetch_all(pool)
+    .await?;
+
+    let Some(oldest) = periods.last() else {
+        return Ok(Vec::new());
     };
+    let oldest_start: DateTime<Utc> = oldest.get("start_date");
 
-    Ok(budget)
+    let transactions: Vec<Transaction> = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transaction_portions
+        WHERE category_id = ? AND transaction_type = 'debit' AND date >= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(&budget.category_id)
+    .bind(oldest_start)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(transaction_from_row)
+    .collect();
+
+    Ok(periods
+        .iter()
+        .map(|row| {
+            let window = Budget {
+                amount: get_decimal(row, "amount"),
+                start_date: row.get("start_date"),
+                end_date: Some(row.get("end_date")),
+                is_active: true,
+                ..budget.clone()
+            };
+            let spent: Decimal = transactions
+                .iter()
+                .filter(|transaction| counts_toward_budget(&window, transaction))
+                .map(|transaction| transaction.amount.abs())
+                .sum();
+
+            BudgetPeriodSummary {
+                budget_id: budget.id.clone(),
+                start_date: window.start_date,
+                end_date: row.get("end_date"),
+                amount: window.amount,
+                spent,
+                remaining: window.amount - spent,
+            }
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_budget_history(
+    budget_id: String,
+    state: State<'_, AppState>
+) -> AppResult<Vec<BudgetPeriodSummary>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    budget_history(&pool, &budget_id).await
+}
+
+/// The transactions behind a budget's `spent`, oldest first.
+#[tauri::command]
+pub async fn get_budget_transactions(
+    budget_id: String,
+    state: State<'_, AppState>
+) -> AppResult<Vec<Transaction>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let row = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        WHERE id = ?
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .bind(&budget_id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Budget {} niet gevonden", budget_id)))?;
+    let budget = budget_from_row(&row);
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transaction_portions
+        WHERE category_id = ? AND date >= ?
+        ORDER BY date ASC
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(&budget.category_id)
+    .bind(budget.start_date)
+    .fetch_all(&pool)
+    .await?;
+
+    let mut transactions: Vec<Transaction> = rows
+        .iter()
+        .map(transaction_from_row)
+        .filter(|transaction| counts_toward_budget(&budget, transaction))
+        .collect();
+
+    let encryption = state.encryption.lock().await;
+    for transaction in &mut transactions {
+        decrypt_notes(transaction, &encryption)?;
+    }
+
+    Ok(transactions)
 }
 
 #[tauri::command]
@@ -221,78 +808,735 @@ pub async fn get_budget_by_id(
 ) -> AppResult<Option<Budget>> {
     let pool = state.db.lock().await.get_pool().await?;
 
-    let row = sqlx::query(
+    let row = sqlx::query(&format!(
         r#"
         SELECT
-            id, name, category_id, amount, period, spent, remaining, is_active,
-            notification_threshold, start_date, end_date, created_at, updated_at
+            {}
         FROM budgets
         WHERE id = ?
-        "#
-    )
+        "#,
+        BUDGET_COLUMNS
+    ))
     .bind(&id)
     .fetch_optional(&pool)
     .await?;
 
-    let budget = row.map(|r| {
-        crate::models::Budget {
-            id: r.get("id"),
-            name: r.get("name"),
-            category_id: r.get("category_id"),
-            amount: r.get::<String, _>("amount").parse().unwrap_or_default(),
-            period: r.get("period"),
-            spent: r.get::<String, _>("spent").parse().unwrap_or_default(),
-            remaining: r.get::<String, _>("remaining").parse().unwrap_or_default(),
-            is_active: r.get("is_active"),
-            notification_threshold: r.get::<Option<String>, _>("notification_threshold").and_then(|s| s.parse().ok()),
-            start_date: r.get("start_date"),
-            end_date: r.get("end_date"),
-            created_at: r.get("created_at"),
-            updated_at: r.get("updated_at"),
-        }
-    });
+    let budget = row.as_ref().map(budget_from_row);
 
     Ok(budget)
 }
 
+/// Budget summary with `spent` counting only the debits booked on the
+/// account with IBAN `iban`, for households that budget per account.
+async fn account_budget_summary(pool: &SqlitePool, iban: &str) -> AppResult<BudgetSummary> {
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        WHERE is_active = TRUE
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+    let budgets: Vec<Budget> = rows.iter().map(budget_from_row).collect();
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transaction_portions
+        WHERE transaction_type = 'debit' AND UPPER(REPLACE(account_number, ' ', '')) = ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(iban)
+    .fetch_all(pool)
+    .await?;
+    let transactions: Vec<Transaction> = rows.iter().map(transaction_from_row).collect();
+
+    let mut total_budgeted = Decimal::ZERO;
+    let mut total_spent = Decimal::ZERO;
+    for budget in &budgets {
+        total_budgeted += budget.amount;
+        total_spent += transactions
+            .iter()
+            .filter(|transaction| counts_toward_budget(budget, transaction))
+            .map(|transaction| transaction.amount.abs())
+            .sum::<Decimal>();
+    }
+
+    Ok(BudgetSummary {
+        total_budgets: budgets.len() as i64,
+        active_budgets: budgets.len() as i64,
+        total_budgeted,
+        total_spent,
+        total_remaining: total_budgeted - total_spent,
+    })
+}
+
+/// Totals over the active budgets. The amounts are summed as `Decimal` in
+/// Rust: a SQL SUM over the DECIMAL columns adds floats and can be a cent off.
+pub(crate) async fn budget_summary(pool: &SqlitePool) -> AppResult<BudgetSummary> {
+    let rows = sqlx::query("SELECT amount, spent, remaining FROM budgets WHERE is_active = TRUE")
+        .fetch_all(pool)
+        .await?;
+
+    let sum = |column: &str| -> Decimal { rows.iter().map(|row| get_decimal(row, column)).sum() };
+
+    Ok(BudgetSummary {
+        total_budgets: rows.len() as i64,
+        active_budgets: rows.len() as i64,
+        total_budgeted: sum("amount"),
+        total_spent: sum("spent"),
+        total_remaining: sum("remaining"),
+    })
+}
+
+/// Totals over the active budgets; with `account_id` only that account's
+/// transactions count as spent.
 #[tauri::command]
-pub async fn get_budget_summary(state: State<'_, AppState>) -> AppResult<BudgetSummary> {
+pub async fn get_budget_summary(account_id: Option<String>, state: State<'_, AppState>) -> AppResult<BudgetSummary> {
     let pool = state.db.lock().await.get_pool().await?;
 
-    let summary = sqlx::query(
+    if let Some(account_id) = account_id {
+        let iban = account_iban(&mut *pool.acquire().await?, &account_id).await?;
+        return account_budget_summary(&pool, &iban).await;
+    }
+
+    budget_summary(&pool).await
+}
+
+#[derive(serde::Serialize)]
+pub struct BudgetSummary {
+    pub total_budgets: i64,
+    pub active_budgets: i64,
+    pub total_budgeted: Decimal,
+    pub total_spent: Decimal,
+    pub total_remaining: Decimal,
+}
+
+/// Month-to-date spending measured against the combined monthly budget.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SpendingPace {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// Active budgets scaled to their monthly equivalent
+    pub budget_total: Decimal,
+    pub spent_to_date: Decimal,
+    /// Share of the combined budget already spent
+    pub spent_fraction: f64,
+    /// Share of the month that has passed
+    pub elapsed_fraction: f64,
+}
+
+impl SpendingPace {
+    /// Whether `threshold` of the budget is spent before `threshold` of the month has passed.
+    pub fn is_ahead_of(&self, threshold: f64) -> bool {
+        self.budget_total > Decimal::ZERO
+            && self.spent_fraction >= threshold
+            && self.elapsed_fraction < threshold
+    }
+}
+
+/// Payload of the `budget_threshold_crossed` event.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct BudgetThresholdCrossed {
+    pub budget_id: String,
+    /// Spent as a percentage of the budget amount
+    pub percentage: Decimal,
+    /// Spent amount the alert is set at, see [`warning_at`]
+    pub threshold: Decimal,
+}
+
+/// Budgets whose spending reached their alert threshold for the first time
+/// this period. They are marked as notified, so a crossing is reported once
+/// per period however many transactions follow. Nothing is reported, or
+/// marked, while budget alerts are turned off.
+pub(crate) async fn take_threshold_crossings(pool: &SqlitePool) -> AppResult<Vec<BudgetThresholdCrossed>> {
+    if !load_settings(pool).await?.budget_alerts_enabled {
+        return Ok(Vec::new());
+    }
+
+    let rows = sqlx::query(&format!(
         r#"
         SELECT
-            COUNT(*) as total_budgets,
-            COUNT(CASE WHEN is_active THEN 1 END) as active_budgets,
-            SUM(amount) as total_budgeted,
-            SUM(spent) as total_spent,
-            SUM(remaining) as total_remaining
+            {}
         FROM budgets
+        WHERE is_active = TRUE AND threshold_notified = FALSE
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    let mut crossings = Vec::new();
+    for budget in rows.iter().map(budget_from_row) {
+        let threshold = warning_at(&budget);
+        if budget.amount <= Decimal::ZERO || budget.spent < threshold {
+            continue;
+        }
+
+        let result = sqlx::query("UPDATE budgets SET threshold_notified = TRUE WHERE id = ? AND threshold_notified = FALSE")
+            .bind(&budget.id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() > 0 {
+            crossings.push(BudgetThresholdCrossed {
+                percentage: (budget.spent / budget.amount * Decimal::from(100)).round_dp(1),
+                budget_id: budget.id,
+                threshold,
+            });
+        }
+    }
+
+    Ok(crossings)
+}
+
+/// Emit `budget_threshold_crossed` for every budget that just reached its
+/// alert threshold; call after budget spending was recomputed.
+pub(crate) async fn check_budget_thresholds(app: &AppHandle, pool: &SqlitePool) -> AppResult<()> {
+    for crossing in take_threshold_crossings(pool).await? {
+        let _ = app.emit("budget_threshold_crossed", crossing);
+    }
+    Ok(())
+}
+
+/// Payload of the `spending_velocity_alert` event.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SpendingVelocityAlert {
+    pub threshold: f64,
+    #[serde(flatten)]
+    pub pace: SpendingPace,
+}
+
+pub(crate) fn month_bounds(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    budget_month_bounds(now, 1)
+}
+
+/// First day of the budget month that starts in `year`-`month`: `start_day`,
+/// or the last day of the month when it is too short for that.
+pub(crate) fn budget_month_first_day(year: i32, month: u32, start_day: u8) -> NaiveDate {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    let last_day = (first + Months::new(1)).pred_opt().unwrap().day();
+    first.with_day(u32::from(start_day).clamp(1, last_day)).unwrap()
+}
+
+/// Start of the budget month containing `now` and of the one after it, for
+/// months starting on `start_day` (25: from the 25th to the 24th). With 1
+/// these are calendar months.
+pub(crate) fn budget_month_bounds(now: DateTime<Utc>, start_day: u8) -> (DateTime<Utc>, DateTime<Utc>) {
+    let this_month = NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap();
+    let month = if now.date_naive() >= budget_month_first_day(now.year(), now.month(), start_day) {
+        this_month
+    } else {
+        this_month - Months::new(1)
+    };
+    let next = month + Months::new(1);
+
+    let start_of = |month: NaiveDate| {
+        Utc.from_utc_datetime(&budget_month_first_day(month.year(), month.month(), start_day).and_hms_opt(0, 0, 0).unwrap())
+    };
+    (start_of(month), start_of(next))
+}
+
+pub(crate) fn monthly_equivalent(amount: Decimal, period: &str) -> Decimal {
+    match period {
+        "weekly" => amount * Decimal::from(52) / Decimal::from(12),
+        "quarterly" => amount / Decimal::from(3),
+        "yearly" => amount / Decimal::from(12),
+        _ => amount,
+    }
+}
+
+pub(crate) async fn compute_spending_pace(
+    conn: &mut SqliteConnection,
+    now: DateTime<Utc>,
+    start_day: u8,
+) -> AppResult<SpendingPace> {
+    let (period_start, period_end) = budget_month_bounds(now, start_day);
+
+    let budget_rows = sqlx::query(
+        r#"
+        SELECT amount, period FROM budgets
         WHERE is_active = TRUE
+          AND start_date < ?
+          AND (end_date IS NULL OR end_date >= ?)
         "#
     )
-    .fetch_one(&pool)
+    .bind(period_end)
+    .bind(period_start)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let budget_total: Decimal = budget_rows
+        .iter()
+        .map(|row| monthly_equivalent(get_decimal(row, "amount"), &row.get::<String, _>("period")))
+        .sum();
+
+    let spent_rows = sqlx::query(
+        "SELECT amount FROM transactions WHERE transaction_type = 'debit' AND date >= ? AND date < ?"
+    )
+    .bind(period_start)
+    .bind(period_end)
+    .fetch_all(&mut *conn)
     .await?;
 
-    let budget_summary = BudgetSummary {
-        total_budgets: summary.get::<i64, _>("total_budgets"),
-        active_budgets: summary.get::<i64, _>("active_budgets"),
-        total_budgeted: summary.get::<Option<String>, _>("total_budgeted")
-            .and_then(|s| s.parse().ok()).unwrap_or(0.0),
-        total_spent: summary.get::<Option<String>, _>("total_spent")
-            .and_then(|s| s.parse().ok()).unwrap_or(0.0),
-        total_remaining: summary.get::<Option<String>, _>("total_remaining")
-            .and_then(|s| s.parse().ok()).unwrap_or(0.0),
+    let spent_to_date: Decimal = spent_rows
+        .iter()
+        .map(|row| get_decimal(row, "amount").abs())
+        .sum();
+
+    let spent_fraction = if budget_total > Decimal::ZERO {
+        (spent_to_date / budget_total).to_f64().unwrap_or(0.0)
+    } else {
+        0.0
     };
+    let elapsed_fraction = (now - period_start).num_seconds() as f64
+        / (period_end - period_start).num_seconds() as f64;
 
-    Ok(budget_summary)
+    Ok(SpendingPace {
+        period_start,
+        period_end,
+        budget_total,
+        spent_to_date,
+        spent_fraction,
+        elapsed_fraction,
+    })
 }
 
-#[derive(serde::Serialize)]
-pub struct BudgetSummary {
-    pub total_budgets: i64,
-    pub active_budgets: i64,
-    pub total_budgeted: f64,
-    pub total_spent: f64,
-    pub total_remaining: f64,
-}
\ No newline at end of file
+#[tauri::command]
+pub async fn get_spending_pace(state: State<'_, AppState>) -> AppResult<SpendingPace> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let start_day = load_settings(&pool).await?.budget_month_start_day;
+    let mut conn = pool.acquire().await?;
+
+    compute_spending_pace(&mut conn, Utc::now(), start_day).await
+}
+
+/// Emit `spending_velocity_alert` when this month's spending runs ahead of the
+/// configured fraction of the month. Fires at most once per month per threshold.
+pub(crate) async fn check_spending_velocity(app: &AppHandle, pool: &SqlitePool) -> AppResult<()> {
+    let settings = load_settings(pool).await?;
+    let threshold = settings.spending_velocity_alert_fraction;
+
+    if !(settings.budget_alerts_enabled && threshold > 0.0 && threshold < 1.0) {
+        return Ok(());
+    }
+
+    let mut conn = pool.acquire().await?;
+    let pace = compute_spending_pace(&mut conn, Utc::now(), settings.budget_month_start_day).await?;
+
+    if !pace.is_ahead_of(threshold) {
+        return Ok(());
+    }
+
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO spending_velocity_alerts (period, threshold, triggered_at) VALUES (?, ?, ?)"
+    )
+    .bind(pace.period_start.format("%Y-%m").to_string())
+    .bind(format!("{:.4}", threshold))
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        let _ = app.emit("spending_velocity_alert", SpendingVelocityAlert { threshold, pace });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+
+    fn pace(spent_fraction: f64, elapsed_fraction: f64) -> SpendingPace {
+        let (period_start, period_end) = month_bounds(Utc::now());
+        SpendingPace {
+            period_start,
+            period_end,
+            budget_total: Decimal::from(1000),
+            spent_to_date: Decimal::ZERO,
+            spent_fraction,
+            elapsed_fraction,
+        }
+    }
+
+    #[test]
+    fn test_alert_only_when_spending_outpaces_month() {
+        assert!(pace(0.6, 0.3).is_ahead_of(0.5));
+        assert!(!pace(0.6, 0.55).is_ahead_of(0.5));
+        assert!(!pace(0.4, 0.3).is_ahead_of(0.5));
+    }
+
+    #[test]
+    fn test_alert_level_follows_amount() {
+        let now = Utc::now();
+        let mut budget = Budget {
+            id: "b".to_string(),
+            name: "Boodschappen".to_string(),
+            category_id: None,
+            amount: Decimal::from(300),
+            period: "monthly".to_string(),
+            spent: Decimal::from(250),
+            remaining: Decimal::from(50),
+            is_active: true,
+            notification_threshold: None,
+            start_date: now,
+            end_date: None,
+            created_at: now,
+            updated_at: now,
+        };
+        assert_eq!(BudgetAlertLevel::for_budget(&budget), BudgetAlertLevel::Warning);
+
+        budget.amount = Decimal::from(400);
+        assert_eq!(BudgetAlertLevel::for_budget(&budget), BudgetAlertLevel::Ok);
+
+        budget.amount = Decimal::from(200);
+        assert_eq!(BudgetAlertLevel::for_budget(&budget), BudgetAlertLevel::Exceeded);
+
+        assert_eq!(threshold_for(&budget, ThresholdType::Percent, Decimal::from(75)), Ok(Decimal::from(150)));
+        assert!(threshold_for(&budget, ThresholdType::Amount, Decimal::from(250)).is_err());
+    }
+
+    #[test]
+    fn test_month_bounds_wrap_year() {
+        let now = Utc.with_ymd_and_hms(2024, 12, 15, 10, 0, 0).unwrap();
+        let (start, end) = month_bounds(now);
+
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 12, 1, 0, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_budget_month_bounds_cross_month_boundary() {
+        let at = |month, day| Utc.with_ymd_and_hms(2024, month, day, 0, 0, 0).unwrap();
+
+        assert_eq!(budget_month_bounds(at(3, 10), 25), (at(2, 25), at(3, 25)));
+        assert_eq!(budget_month_bounds(at(3, 25), 25), (at(3, 25), at(4, 25)));
+        assert_eq!(
+            budget_month_bounds(Utc.with_ymd_and_hms(2024, 1, 5, 0, 0, 0).unwrap(), 25),
+            (Utc.with_ymd_and_hms(2023, 12, 25, 0, 0, 0).unwrap(), at(1, 25))
+        );
+        // February is too short for the 31st and starts on its last day
+        assert_eq!(budget_month_bounds(at(3, 15), 31), (at(2, 29), at(3, 31)));
+        assert_eq!(budget_month_bounds(at(2, 29), 31), (at(2, 29), at(3, 31)));
+    }
+
+    #[test]
+    fn test_next_budget_month_window_moves_to_start_day() {
+        let at = |month, day| Utc.with_ymd_and_hms(2024, month, day, 0, 0, 0).unwrap();
+        let second = chrono::Duration::seconds(1);
+
+        // A calendar month window is followed by a short one up to the 25th
+        let (start, end) = next_budget_month_window(at(1, 1), at(2, 1) - second, 25);
+        assert_eq!((start, end), (at(2, 1), at(2, 25) - second));
+        let (start, end) = next_budget_month_window(start, end, 25);
+        assert_eq!((start, end), (at(2, 25), at(3, 25) - second));
+
+        let (start, end) = next_budget_month_window(at(1, 31), at(2, 29) - second, 31);
+        assert_eq!((start, end), (at(2, 29), at(3, 31) - second));
+        let (start, _) = next_budget_month_window(start, end, 31);
+        assert_eq!(start, at(3, 31));
+    }
+
+    #[test]
+    fn test_counts_toward_budget_window() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let budget = Budget {
+            id: "b".to_string(),
+            name: "Boodschappen".to_string(),
+            category_id: Some("food".to_string()),
+            amount: Decimal::from(300),
+            period: "monthly".to_string(),
+            spent: Decimal::ZERO,
+            remaining: Decimal::from(300),
+            is_active: true,
+            notification_threshold: None,
+            start_date: start,
+            end_date: Some(Utc.with_ymd_and_hms(2024, 3, 31, 23, 59, 59).unwrap()),
+            created_at: start,
+            updated_at: start,
+        };
+        let mut transaction = Transaction {
+            description: "Jumbo".to_string(),
+            category_id: Some("food".to_string()),
+            ..Transaction::test("debit", 20, Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap())
+        };
+        assert!(counts_toward_budget(&budget, &transaction));
+
+        transaction.date = Utc.with_ymd_and_hms(2024, 4, 1, 12, 0, 0).unwrap();
+        assert!(!counts_toward_budget(&budget, &transaction));
+
+        transaction.date = start;
+        transaction.transaction_type = "credit".to_string();
+        assert!(!counts_toward_budget(&budget, &transaction));
+    }
+
+    #[tokio::test]
+    async fn test_spent_follows_transactions() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let category_id: String = sqlx::query_scalar("SELECT id FROM categories WHERE name = 'Boodschappen'")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        sqlx::query(
+            "INSERT INTO budgets (id, name, category_id, amount, period, spent, start_date) VALUES ('b1', 'Boodschappen', ?, '300', 'monthly', '999', ?)"
+        )
+        .bind(&category_id)
+        .bind(start)
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        for (id, amount, transaction_type, day) in [("t1", "40.00", "debit", 5), ("t2", "25.50", "debit", 6), ("t3", "100.00", "credit", 7)] {
+            sqlx::query(
+                "INSERT INTO transactions (id, description, amount, date, category_id, transaction_type) VALUES (?, 'Jumbo', ?, ?, ?, ?)"
+            )
+            .bind(id)
+            .bind(amount)
+            .bind(Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap())
+            .bind(&category_id)
+            .bind(transaction_type)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        }
+
+        recompute_category_budgets(&mut conn, Some(&category_id)).await.unwrap();
+        let budget = recompute_budget_spending(&mut conn, "b1").await.unwrap();
+        assert_eq!(budget.spent, Decimal::new(6550, 2));
+
+        sqlx::query("DELETE FROM transactions WHERE id = 't1'").execute(&mut *conn).await.unwrap();
+        recompute_category_budgets(&mut conn, Some(&category_id)).await.unwrap();
+
+        let row = sqlx::query("SELECT spent FROM budgets WHERE id = 'b1'").fetch_one(&mut *conn).await.unwrap();
+        assert_eq!(get_decimal(&row, "spent"), Decimal::new(2550, 2));
+
+        drop(conn);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_budget_summary_sums_exact_cents() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+
+        // Ten budgets of 0.10 with 0.07 spent; as floats they add up to
+        // 0.9999999999999999 and 0.7000000000000002
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        for i in 0..11 {
+            sqlx::query(
+                "INSERT INTO budgets (id, name, amount, period, spent, is_active, start_date) VALUES (?, 'Klein', '0.10', 'monthly', '0.07', ?, ?)"
+            )
+            .bind(format!("b{}", i))
+            .bind(i < 10)
+            .bind(start)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        assert_ne!((0..10).map(|_| 0.1_f64).sum::<f64>(), 1.0);
+        assert_ne!((0..10).map(|_| 0.07_f64).sum::<f64>(), 0.7);
+
+        let summary = budget_summary(&pool).await.unwrap();
+        assert_eq!(summary.active_budgets, 10);
+        assert_eq!(summary.total_budgeted, Decimal::new(100, 2));
+        assert_eq!(summary.total_spent, Decimal::new(70, 2));
+        assert_eq!(summary.total_remaining, Decimal::new(30, 2));
+
+        pool.close().await;
+    }
+
+    #[test]
+    fn test_next_period_window_keeps_month_ends() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 1, 31, 23, 59, 59).unwrap();
+
+        let (start, end) = next_period_window(&BudgetPeriod::Monthly, start, end);
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2024, 2, 29, 23, 59, 59).unwrap());
+        let (start, end) = next_period_window(&BudgetPeriod::Monthly, start, end);
+        assert_eq!(end, Utc.with_ymd_and_hms(2024, 3, 31, 23, 59, 59).unwrap());
+
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap());
+
+        let quarter_start = Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap();
+        let quarter_end = Utc.with_ymd_and_hms(2024, 6, 30, 23, 59, 59).unwrap();
+        assert_eq!(next_period_window(&BudgetPeriod::Quarterly, quarter_start, quarter_end), (
+            Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 9, 30, 23, 59, 59).unwrap(),
+        ));
+
+        let week_start = Utc.with_ymd_and_hms(2024, 12, 30, 0, 0, 0).unwrap();
+        let (start, end) = next_period_window(&BudgetPeriod::Weekly, week_start, week_start + chrono::Duration::days(7));
+        assert_eq!((start, end), (week_start + chrono::Duration::days(7), week_start + chrono::Duration::days(14)));
+
+        let (start, _) = next_period_window(&BudgetPeriod::Yearly, week_start, week_start);
+        assert_eq!(start, Utc.with_ymd_and_hms(2025, 12, 30, 0, 0, 0).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_roll_budget_periods_keeps_history() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+
+        let category_id: String = sqlx::query_scalar("SELECT id FROM categories WHERE name = 'Boodschappen'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO budgets (id, name, category_id, amount, period, spent, start_date, end_date) VALUES ('b1', 'Boodschappen', ?, '300', 'monthly', '0', ?, ?)"
+        )
+        .bind(&category_id)
+        .bind(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap())
+        .bind(Utc.with_ymd_and_hms(2024, 1, 31, 23, 59, 59).unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        for (id, amount, month) in [("t1", "120.00", 1), ("t2", "80.00", 2), ("t3", "15.00", 3)] {
+            sqlx::query(
+                "INSERT INTO transactions (id, description, amount, date, category_id, transaction_type) VALUES (?, 'Jumbo', ?, ?, ?, 'debit')"
+            )
+            .bind(id)
+            .bind(amount)
+            .bind(Utc.with_ymd_and_hms(2024, month, 10, 12, 0, 0).unwrap())
+            .bind(&category_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let now = Utc.with_ymd_and_hms(2024, 3, 15, 9, 0, 0).unwrap();
+        assert_eq!(roll_budget_periods(&pool, now).await.unwrap(), 2);
+        assert_eq!(roll_budget_periods(&pool, now).await.unwrap(), 0);
+
+        let mut conn = pool.acquire().await.unwrap();
+        let budget = recompute_budget_spending(&mut conn, "b1").await.unwrap();
+        drop(conn);
+        assert_eq!(budget.start_date, Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap());
+        assert_eq!(budget.end_date, Some(Utc.with_ymd_and_hms(2024, 3, 31, 23, 59, 59).unwrap()));
+        assert_eq!(budget.spent, Decimal::from(15));
+
+        let history = budget_history(&pool, "b1").await.unwrap();
+        let spent: Vec<(u32, Decimal, Decimal)> = history.iter().map(|p| (p.start_date.month(), p.spent, p.remaining)).collect();
+        assert_eq!(spent, vec![
+            (2, Decimal::from(80), Decimal::from(220)),
+            (1, Decimal::from(120), Decimal::from(180)),
+        ]);
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_roll_budget_periods_follows_budget_month_start_day() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+
+        sqlx::query("UPDATE settings SET budget_month_start_day = 25").execute(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO budgets (id, name, amount, period, spent, start_date, end_date) VALUES ('b1', 'Totaal', '1500', 'monthly', '0', ?, ?)"
+        )
+        .bind(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap())
+        .bind(Utc.with_ymd_and_hms(2024, 1, 31, 23, 59, 59).unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let now = Utc.with_ymd_and_hms(2024, 3, 15, 9, 0, 0).unwrap();
+        assert_eq!(roll_budget_periods(&pool, now).await.unwrap(), 2);
+
+        let (start, end): (DateTime<Utc>, DateTime<Utc>) =
+            sqlx::query_as("SELECT start_date, end_date FROM budgets WHERE id = 'b1'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 2, 25, 0, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2024, 3, 24, 23, 59, 59).unwrap());
+
+        let history = budget_history(&pool, "b1").await.unwrap();
+        let ends: Vec<DateTime<Utc>> = history.iter().map(|p| p.end_date).collect();
+        assert_eq!(ends, vec![
+            Utc.with_ymd_and_hms(2024, 2, 24, 23, 59, 59).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 31, 23, 59, 59).unwrap(),
+        ]);
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_threshold_crossing_reported_once_per_period() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+
+        let category_id: String = sqlx::query_scalar("SELECT id FROM categories WHERE name = 'Boodschappen'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO budgets (id, name, category_id, amount, period, spent, notification_threshold, start_date, end_date) VALUES ('b1', 'Boodschappen', ?, '400', 'monthly', '0', '300', ?, ?)"
+        )
+        .bind(&category_id)
+        .bind(Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap())
+        .bind(Utc.with_ymd_and_hms(2024, 3, 31, 23, 59, 59).unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let spend = |id: &'static str, amount: &'static str, date: DateTime<Utc>| {
+            let pool = pool.clone();
+            let category_id = category_id.clone();
+            async move {
+                sqlx::query(
+                    "INSERT INTO transactions (id, description, amount, date, category_id, transaction_type) VALUES (?, 'Jumbo', ?, ?, ?, 'debit')"
+                )
+                .bind(id)
+                .bind(amount)
+                .bind(date)
+                .bind(&category_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+                let mut conn = pool.acquire().await.unwrap();
+                recompute_category_budgets(&mut conn, Some(&category_id)).await.unwrap();
+            }
+        };
+
+        let march = |day| Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap();
+        spend("t1", "250.00", march(5)).await;
+        assert!(take_threshold_crossings(&pool).await.unwrap().is_empty());
+
+        sqlx::query("UPDATE settings SET budget_alerts_enabled = FALSE").execute(&pool).await.unwrap();
+        spend("t2", "80.00", march(6)).await;
+        assert!(take_threshold_crossings(&pool).await.unwrap().is_empty());
+
+        sqlx::query("UPDATE settings SET budget_alerts_enabled = TRUE").execute(&pool).await.unwrap();
+        assert_eq!(take_threshold_crossings(&pool).await.unwrap(), vec![BudgetThresholdCrossed {
+            budget_id: "b1".to_string(),
+            percentage: Decimal::new(825, 1),
+            threshold: Decimal::from(300),
+        }]);
+
+        spend("t3", "20.00", march(7)).await;
+        assert!(take_threshold_crossings(&pool).await.unwrap().is_empty());
+
+        // A new period can cross the threshold again
+        roll_budget_periods(&pool, Utc.with_ymd_and_hms(2024, 4, 2, 0, 0, 0).unwrap()).await.unwrap();
+        assert!(take_threshold_crossings(&pool).await.unwrap().is_empty());
+        spend("t4", "310.00", Utc.with_ymd_and_hms(2024, 4, 8, 12, 0, 0).unwrap()).await;
+        let crossings = take_threshold_crossings(&pool).await.unwrap();
+        assert_eq!(crossings.len(), 1);
+        assert_eq!(crossings[0].percentage, Decimal::new(775, 1));
+
+        pool.close().await;
+    }
+}
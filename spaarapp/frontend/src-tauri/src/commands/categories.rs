@@ -1,10 +1,17 @@
-use crate::error::AppResult;
+use crate::category_tree::{build_spending_tree, is_descendant_or_self, CategorySpendingNode, UNCATEGORIZED_ID};
+use crate::database::{get_decimal, get_optional_decimal};
+use crate::commands::budgets::{month_bounds, recompute_category_budgets};
+use crate::error::{AppError, AppResult};
 use crate::models::Category;
 use crate::AppState;
 use tauri::State;
-use chrono::Utc;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
 use uuid::Uuid;
-use sqlx::{self, Row};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{self, Row, SqliteConnection};
 
 #[tauri::command]
 pub async fn get_categories(state: State<'_, AppState>) -> AppResult<Vec<Category>> {
@@ -22,24 +29,26 @@ pub async fn get_categories(state: State<'_, AppState>) -> AppResult<Vec<Categor
     .fetch_all(&pool)
     .await?;
 
-    let categories = rows.into_iter().map(|row| {
-        crate::models::Category {
-            id: row.get("id"),
-            name: row.get("name"),
-            description: row.get("description"),
-            color: row.get("color"),
-            icon: row.get("icon"),
-            parent_id: row.get("parent_id"),
-            is_system: row.get("is_system"),
-            budget_percentage: row.get::<Option<String>, _>("budget_percentage").and_then(|s| s.parse().ok()),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-        }
-    }).collect();
+    let categories = rows.iter().map(category_from_row).collect();
 
     Ok(categories)
 }
 
+pub(crate) fn category_from_row(row: &SqliteRow) -> Category {
+    Category {
+        id: row.get("id"),
+        name: row.get("name"),
+        description: row.get("description"),
+        color: row.get("color"),
+        icon: row.get("icon"),
+        parent_id: row.get("parent_id"),
+        is_system: row.get("is_system"),
+        budget_percentage: get_optional_decimal(row, "budget_percentage"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
 #[tauri::command]
 pub async fn add_category(
     mut category: Category,
@@ -95,6 +104,33 @@ pub async fn add_category(
     Ok(category)
 }
 
+/// Check that `parent_id` exists and is not the category itself or one of its
+/// descendants, which would turn the hierarchy into a cycle.
+async fn validate_parent(conn: &mut SqliteConnection, id: &str, parent_id: Option<&str>) -> AppResult<()> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+
+    let rows = sqlx::query("SELECT id, parent_id FROM categories")
+        .fetch_all(&mut *conn)
+        .await?;
+    let parents: HashMap<String, Option<String>> = rows
+        .iter()
+        .map(|row| (row.get("id"), row.get("parent_id")))
+        .collect();
+
+    if !parents.contains_key(parent_id) {
+        return Err(AppError::NotFound(format!("Bovenliggende categorie {} niet gevonden", parent_id)));
+    }
+    if is_descendant_or_self(&parents, id, parent_id) {
+        return Err(AppError::Validation(
+            "Een categorie kan niet onder zichzelf of een eigen subcategorie vallen".to_string()
+        ));
+    }
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn update_category(
     id: String,
@@ -118,6 +154,8 @@ pub async fn update_category(
         category.is_system = row.get("is_system");
     }
 
+    validate_parent(&mut *pool.acquire().await?, &id, category.parent_id.as_deref()).await?;
+
     let result = sqlx::query(
         r#"
         UPDATE categories SET
@@ -146,6 +184,48 @@ pub async fn update_category(
     Ok(category)
 }
 
+/// Move a category under another one, or to the top level with `None`,
+/// leaving its other fields untouched.
+#[tauri::command]
+pub async fn set_category_parent(
+    id: String,
+    parent_id: Option<String>,
+    state: State<'_, AppState>
+) -> AppResult<Category> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    validate_parent(&mut tx, &id, parent_id.as_deref()).await?;
+
+    let result = sqlx::query("UPDATE categories SET parent_id = ?, updated_at = ? WHERE id = ?")
+        .bind(&parent_id)
+        .bind(Utc::now())
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Categorie {} niet gevonden", id)));
+    }
+
+    let row = sqlx::query(
+        r#"
+        SELECT
+            id, name, description, color, icon, parent_id, is_system,
+            budget_percentage, created_at, updated_at
+        FROM categories
+        WHERE id = ?
+        "#
+    )
+    .bind(&id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(category_from_row(&row))
+}
+
 #[tauri::command]
 pub async fn delete_category(id: String, state: State<'_, AppState>) -> AppResult<bool> {
     let pool = state.db.lock().await.get_pool().await?;
@@ -159,7 +239,7 @@ pub async fn delete_category(id: String, state: State<'_, AppState>) -> AppResul
     if let Some(row) = category_row {
         let is_system: bool = row.get("is_system");
         if is_system {
-            return Err(crate::error::AppError::InvalidInput(
+            return Err(crate::error::AppError::Validation(
                 "Cannot delete system categories".to_string()
             ));
         }
@@ -173,7 +253,7 @@ pub async fn delete_category(id: String, state: State<'_, AppState>) -> AppResul
 
     let count: i64 = transaction_count.get("count");
     if count > 0 {
-        return Err(crate::error::AppError::InvalidInput(
+        return Err(crate::error::AppError::Validation(
             "Cannot delete category with existing transactions".to_string()
         ));
     }
@@ -186,6 +266,93 @@ pub async fn delete_category(id: String, state: State<'_, AppState>) -> AppResul
     Ok(result.rows_affected() > 0)
 }
 
+/// Move everything filed under `source_id` to `target_id` and delete the
+/// source category. Returns the number of transactions moved.
+pub(crate) async fn merge_category_into(
+    conn: &mut SqliteConnection,
+    source_id: &str,
+    target_id: &str,
+    allow_system: bool,
+) -> AppResult<u64> {
+    if source_id == target_id {
+        return Err(AppError::Validation("Een categorie kan niet met zichzelf worden samengevoegd".to_string()));
+    }
+
+    let mut source_parent = None;
+    for id in [source_id, target_id] {
+        let row = sqlx::query("SELECT parent_id, is_system FROM categories WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Categorie {} niet gevonden", id)))?;
+        if row.get::<bool, _>("is_system") && !allow_system {
+            return Err(AppError::Validation(
+                "Systeemcategorieën worden alleen samengevoegd als dat expliciet is toegestaan".to_string()
+            ));
+        }
+        if id == source_id {
+            source_parent = row.get::<Option<String>, _>("parent_id");
+        }
+    }
+
+    let now = Utc::now();
+    let moved = sqlx::query("UPDATE transactions SET category_id = ?, updated_at = ? WHERE category_id = ?")
+        .bind(target_id)
+        .bind(now)
+        .bind(source_id)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    for statement in [
+        "UPDATE transaction_splits SET category_id = ? WHERE category_id = ?",
+        "UPDATE budgets SET category_id = ? WHERE category_id = ?",
+        "UPDATE category_rules SET category_id = ? WHERE category_id = ?",
+        "UPDATE savings_goals SET linked_category_id = ? WHERE linked_category_id = ?",
+        "UPDATE accounts SET default_category_id = ? WHERE default_category_id = ?",
+        "UPDATE categories SET parent_id = ? WHERE parent_id = ?",
+    ] {
+        sqlx::query(statement).bind(target_id).bind(source_id).execute(&mut *conn).await?;
+    }
+
+    // A target that was a subcategory of the source takes over its place in the tree
+    sqlx::query("UPDATE categories SET parent_id = ? WHERE id = ? AND parent_id = ?")
+        .bind(&source_parent)
+        .bind(target_id)
+        .bind(target_id)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query("DELETE FROM categories WHERE id = ?")
+        .bind(source_id)
+        .execute(&mut *conn)
+        .await?;
+
+    // Budgets on the target now cover the moved transactions as well
+    recompute_category_budgets(conn, Some(target_id)).await?;
+
+    Ok(moved)
+}
+
+/// Merge a duplicate category into another one, e.g. "supermarkt" into
+/// "Boodschappen". System categories can only be merged with `allow_system`.
+#[tauri::command]
+pub async fn merge_categories(
+    source_id: String,
+    target_id: String,
+    allow_system: Option<bool>,
+    state: State<'_, AppState>
+) -> AppResult<u64> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    let moved = merge_category_into(&mut tx, &source_id, &target_id, allow_system.unwrap_or(false)).await?;
+
+    tx.commit().await?;
+
+    Ok(moved)
+}
+
 #[tauri::command]
 pub async fn get_category_by_id(
     id: String,
@@ -206,20 +373,376 @@ pub async fn get_category_by_id(
     .fetch_optional(&pool)
     .await?;
 
-    let category = row.map(|r| {
-        crate::models::Category {
-            id: r.get("id"),
-            name: r.get("name"),
-            description: r.get("description"),
-            color: r.get("color"),
-            icon: r.get("icon"),
-            parent_id: r.get("parent_id"),
-            is_system: r.get("is_system"),
-            budget_percentage: r.get::<Option<String>, _>("budget_percentage").and_then(|s| s.parse().ok()),
-            created_at: r.get("created_at"),
-            updated_at: r.get("updated_at"),
-        }
-    });
+    let category = row.as_ref().map(category_from_row);
 
     Ok(category)
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub async fn get_category_transaction_stats(
+    date_from: Option<DateTime<Utc>>,
+    date_to: Option<DateTime<Utc>>,
+    state: State<'_, AppState>
+) -> AppResult<Vec<CategoryTransactionStats>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT t.category_id, c.name AS category_name, t.amount
+        FROM transaction_portions t
+        LEFT JOIN categories c ON c.id = t.category_id
+        WHERE t.transaction_type = 'debit'
+          AND (? IS NULL OR t.date >= ?)
+          AND (? IS NULL OR t.date <= ?)
+        "#
+    )
+    .bind(date_from)
+    .bind(date_from)
+    .bind(date_to)
+    .bind(date_to)
+    .fetch_all(&pool)
+    .await?;
+
+    // Collect all amounts per category; the median needs the full ordered list
+    let mut grouped: HashMap<Option<String>, (Option<String>, Vec<Decimal>)> = HashMap::new();
+    for row in &rows {
+        let entry = grouped
+            .entry(row.get("category_id"))
+            .or_insert_with(|| (row.get("category_name"), Vec::new()));
+        entry.1.push(get_decimal(row, "amount"));
+    }
+
+    let mut stats: Vec<CategoryTransactionStats> = grouped
+        .into_iter()
+        .map(|(category_id, (category_name, mut amounts))| {
+            amounts.sort();
+
+            let count = amounts.len();
+            let total: Decimal = amounts.iter().sum();
+            let middle = count / 2;
+            let median = if count % 2 == 0 {
+                (amounts[middle - 1] + amounts[middle]) / Decimal::from(2)
+            } else {
+                amounts[middle]
+            };
+
+            CategoryTransactionStats {
+                category_id,
+                category_name: category_name.unwrap_or_else(|| "Ongecategoriseerd".to_string()),
+                count: count as i64,
+                total,
+                average: (total / Decimal::from(count)).round_dp(2),
+                median: median.round_dp(2),
+                max: amounts[count - 1],
+            }
+        })
+        .collect();
+
+    stats.sort_by_key(|s| std::cmp::Reverse(s.total));
+
+    Ok(stats)
+}
+
+#[tauri::command]
+pub async fn get_hierarchical_spending(
+    date_from: Option<DateTime<Utc>>,
+    date_to: Option<DateTime<Utc>>,
+    state: State<'_, AppState>
+) -> AppResult<Vec<CategorySpendingNode>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let category_rows = sqlx::query(
+        r#"
+        SELECT
+            id, name, description, color, icon, parent_id, is_system,
+            budget_percentage, created_at, updated_at
+        FROM categories
+        "#
+    )
+    .fetch_all(&pool)
+    .await?;
+    let categories: Vec<Category> = category_rows.iter().map(category_from_row).collect();
+
+    let rows = sqlx::query(
+        r#"
+        SELECT category_id, amount
+        FROM transaction_portions
+        WHERE transaction_type = 'debit'
+          AND (? IS NULL OR date >= ?)
+          AND (? IS NULL OR date <= ?)
+        "#
+    )
+    .bind(date_from)
+    .bind(date_from)
+    .bind(date_to)
+    .bind(date_to)
+    .fetch_all(&pool)
+    .await?;
+
+    let mut own_spending: HashMap<String, Decimal> = HashMap::new();
+    for row in &rows {
+        let category_id = row
+            .get::<Option<String>, _>("category_id")
+            .unwrap_or_else(|| UNCATEGORIZED_ID.to_string());
+        *own_spending.entry(category_id).or_insert(Decimal::ZERO) += get_decimal(row, "amount");
+    }
+
+    Ok(build_spending_tree(&categories, &own_spending))
+}
+
+#[derive(serde::Serialize)]
+pub struct CategoryTransactionStats {
+    pub category_id: Option<String>,
+    pub category_name: String,
+    pub count: i64,
+    pub total: Decimal,
+    pub average: Decimal,
+    pub median: Decimal,
+    pub max: Decimal,
+}
+
+/// Spending in one category this month compared with last month.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CategoryChange {
+    pub category_id: Option<String>,
+    pub category_name: String,
+    pub this_month: Decimal,
+    pub last_month: Decimal,
+    /// This month minus last month; positive means more spending
+    pub change: Decimal,
+    /// Change relative to last month, `None` when there was nothing to compare with
+    pub change_percentage: Option<f64>,
+}
+
+/// Per-category change between two months, largest change (up or down) first.
+/// A category missing from one of the months counts as zero there.
+fn month_over_month(
+    this_month: &HashMap<Option<String>, Decimal>,
+    last_month: &HashMap<Option<String>, Decimal>,
+    names: &HashMap<String, String>,
+) -> Vec<CategoryChange> {
+    let mut category_ids: Vec<&Option<String>> = this_month.keys().chain(last_month.keys()).collect();
+    category_ids.sort();
+    category_ids.dedup();
+
+    let mut changes: Vec<CategoryChange> = category_ids
+        .into_iter()
+        .map(|category_id| {
+            let current = this_month.get(category_id).copied().unwrap_or(Decimal::ZERO);
+            let previous = last_month.get(category_id).copied().unwrap_or(Decimal::ZERO);
+            let change = current - previous;
+
+            CategoryChange {
+                category_id: category_id.clone(),
+                category_name: category_id
+                    .as_ref()
+                    .and_then(|id| names.get(id).cloned())
+                    .unwrap_or_else(|| "Ongecategoriseerd".to_string()),
+                this_month: current,
+                last_month: previous,
+                change,
+                change_percentage: if previous.is_zero() {
+                    None
+                } else {
+                    (change / previous * Decimal::from(100)).round_dp(1).to_f64()
+                },
+            }
+        })
+        .collect();
+
+    changes.sort_by_key(|c| std::cmp::Reverse(c.change.abs()));
+    changes
+}
+
+/// First day of a month given as `YYYY-MM`.
+fn parse_month(month: &str) -> AppResult<DateTime<Utc>> {
+    NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+        .map(|date| Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()))
+        .map_err(|_| AppError::Validation(format!("Ongeldige maand '{}', verwacht JJJJ-MM", month)))
+}
+
+async fn spending_per_category(
+    pool: &sqlx::SqlitePool,
+    month_start: DateTime<Utc>,
+) -> AppResult<HashMap<Option<String>, Decimal>> {
+    let (start, end) = month_bounds(month_start);
+
+    let rows = sqlx::query(
+        "SELECT category_id, amount FROM transaction_portions WHERE transaction_type = 'debit' AND date >= ? AND date < ?"
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await?;
+
+    let mut spending: HashMap<Option<String>, Decimal> = HashMap::new();
+    for row in &rows {
+        *spending.entry(row.get("category_id")).or_insert(Decimal::ZERO) += get_decimal(row, "amount").abs();
+    }
+
+    Ok(spending)
+}
+
+/// Which categories changed most between two months (`YYYY-MM`), for the
+/// "grootste veranderingen" card.
+#[tauri::command]
+pub async fn get_category_month_over_month(
+    this_month: String,
+    last_month: String,
+    state: State<'_, AppState>
+) -> AppResult<Vec<CategoryChange>> {
+    let this_start = parse_month(&this_month)?;
+    let last_start = parse_month(&last_month)?;
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let names: HashMap<String, String> = sqlx::query("SELECT id, name FROM categories")
+        .fetch_all(&pool)
+        .await?
+        .iter()
+        .map(|row| (row.get("id"), row.get("name")))
+        .collect();
+
+    let current = spending_per_category(&pool, this_start).await?;
+    let previous = spending_per_category(&pool, last_start).await?;
+
+    Ok(month_over_month(&current, &previous, &names))
+}
+
+/// Dutch (and a few English) words mapped to the Material icons used by the default categories
+const ICON_KEYWORDS: &[(&str, &[&str])] = &[
+    ("shopping_cart", &["boodschappen", "supermarkt", "groceries"]),
+    ("home", &["huur", "hypotheek", "woning", "wonen", "huis"]),
+    ("bolt", &["energie", "gas", "stroom", "water", "utilities", "nutsvoorzieningen"]),
+    ("directions_car", &["vervoer", "auto", "transport", "brandstof", "benzine", "trein", "ov"]),
+    ("movie", &["entertainment", "uitjes", "film", "streaming", "abonnementen"]),
+    ("local_hospital", &["gezondheid", "zorg", "apotheek", "tandarts", "huisarts"]),
+    ("checkroom", &["kleding", "schoenen", "mode"]),
+    ("restaurant", &["eten", "drinken", "restaurant", "uit eten", "horeca", "lunch"]),
+    ("savings", &["sparen", "spaar", "buffer", "beleggen"]),
+    ("account_balance", &["inkomen", "salaris", "loon", "toeslag"]),
+];
+
+/// Suggest a Material icon name for a category name, defaulting to "category".
+pub fn suggest_icon(name: &str) -> &'static str {
+    let name = name.to_lowercase();
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    ICON_KEYWORDS
+        .iter()
+        .find(|(_, keywords)| {
+            keywords.iter().any(|keyword| {
+                // Short keywords must match a whole word ("ov" should not match "overig")
+                if keyword.len() <= 3 {
+                    words.contains(keyword)
+                } else {
+                    name.contains(keyword)
+                }
+            })
+        })
+        .map(|(icon, _)| *icon)
+        .unwrap_or("category")
+}
+
+#[tauri::command]
+pub async fn suggest_category_icon(name: String) -> AppResult<String> {
+    Ok(suggest_icon(&name).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+
+    #[test]
+    fn test_suggest_icon() {
+        assert_eq!(suggest_icon("Boodschappen"), "shopping_cart");
+        assert_eq!(suggest_icon("Huur & servicekosten"), "home");
+        assert_eq!(suggest_icon("OV-chipkaart"), "directions_car");
+        assert_eq!(suggest_icon("Overig"), "category");
+    }
+
+    #[test]
+    fn test_month_over_month_treats_missing_side_as_zero() {
+        let names = HashMap::from([("huur".to_string(), "Huur".to_string()), ("uit".to_string(), "Uit eten".to_string())]);
+        let this_month = HashMap::from([
+            (Some("huur".to_string()), Decimal::from(900)),
+            (Some("uit".to_string()), Decimal::from(150)),
+        ]);
+        let last_month = HashMap::from([
+            (Some("huur".to_string()), Decimal::from(900)),
+            (None, Decimal::from(40)),
+        ]);
+
+        let changes = month_over_month(&this_month, &last_month, &names);
+
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].category_name, "Uit eten");
+        assert_eq!(changes[0].change, Decimal::from(150));
+        assert_eq!(changes[0].change_percentage, None);
+        assert_eq!(changes[1].category_name, "Ongecategoriseerd");
+        assert_eq!(changes[1].change, Decimal::from(-40));
+        assert_eq!(changes[1].change_percentage, Some(-100.0));
+        assert_eq!(changes[2].change, Decimal::ZERO);
+        assert!(parse_month("2024-13").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_merge_moves_transactions_and_deletes_source() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let now = Utc::now();
+
+        for (id, name) in [("supermarkt", "supermarkt"), ("boodschappen", "Boodschappen extra")] {
+            sqlx::query("INSERT INTO categories (id, name, is_system, created_at, updated_at) VALUES (?, ?, FALSE, ?, ?)")
+                .bind(id)
+                .bind(name)
+                .bind(now)
+                .bind(now)
+                .execute(&mut *conn)
+                .await
+                .unwrap();
+        }
+        for category_id in ["supermarkt", "supermarkt", "boodschappen"] {
+            sqlx::query(
+                "INSERT INTO transactions (id, description, amount, date, category_id, transaction_type, created_at, updated_at) VALUES (?, 'Jumbo', '12.50', ?, ?, 'debit', ?, ?)"
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(now)
+            .bind(category_id)
+            .bind(now)
+            .bind(now)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        }
+        let system: String = sqlx::query_scalar("SELECT id FROM categories WHERE is_system = TRUE LIMIT 1")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            merge_category_into(&mut conn, "supermarkt", &system, false).await,
+            Err(AppError::Validation(_))
+        ));
+        assert_eq!(merge_category_into(&mut conn, "supermarkt", "boodschappen", false).await.unwrap(), 2);
+
+        let in_target: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE category_id = 'boodschappen'")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        let source_left: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM categories WHERE id = 'supermarkt'")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(in_target, 3);
+        assert_eq!(source_left, 0);
+
+        drop(conn);
+        pool.close().await;
+    }
+}
@@ -0,0 +1,772 @@
+use crate::commands::budgets::recompute_category_budgets;
+use crate::commands::settings::load_settings;
+use crate::commands::transactions::{decrypt_notes, fetch_transaction, transaction_from_row, TRANSACTION_COLUMNS};
+use crate::error::{AppError, AppResult};
+use crate::models::Transaction;
+use crate::AppState;
+use tauri::{AppHandle, Emitter, State};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{self, Row, SqliteConnection};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use uuid::Uuid;
+
+/// Transactions re-categorized per chunk; cancellation is checked between chunks
+const RECATEGORIZE_CHUNK_SIZE: i64 = 500;
+
+/// How a rule's match text is compared with a transaction description. All
+/// comparisons ignore case.
+const MATCH_TYPES: &[&str] = &["contains", "starts_with", "regex"];
+
+/// A user-editable rule that puts transactions whose description matches
+/// `match_text` in `category_id`. Rules with a higher priority are tried first.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CategoryRule {
+    pub id: String,
+    pub match_text: String,
+    /// "contains", "starts_with" or "regex"
+    pub match_type: String,
+    pub category_id: String,
+    pub priority: i64,
+    pub created_at: DateTime<Utc>,
+    /// Compiled `match_text` of a regex rule
+    #[serde(skip)]
+    pattern: Option<Regex>,
+}
+
+impl CategoryRule {
+    /// Check the match type and compile the pattern of a regex rule.
+    fn compile(mut self) -> AppResult<Self> {
+        if !MATCH_TYPES.contains(&self.match_type.as_str()) {
+            return Err(AppError::Validation(format!(
+                "Onbekend regeltype: {} (kies contains, starts_with of regex)",
+                self.match_type
+            )));
+        }
+
+        self.pattern = if self.match_type == "regex" {
+            let pattern = Regex::new(&format!("(?i){}", self.match_text))
+                .map_err(|e| AppError::Validation(format!("Ongeldige reguliere expressie: {}", e)))?;
+            Some(pattern)
+        } else {
+            None
+        };
+
+        Ok(self)
+    }
+
+    /// How sure a match of this rule is, from 0 to 1. A regex or a match at
+    /// the start of the description is more specific than text anywhere in
+    /// it, and a short match text ("ah") also matches unrelated descriptions.
+    fn confidence(&self) -> f64 {
+        let confidence = match self.match_type.as_str() {
+            "regex" => 0.9,
+            "starts_with" => 0.85,
+            _ => 0.75,
+        };
+        if self.match_type != "regex" && self.match_text.chars().count() < 4 {
+            confidence * 0.6
+        } else {
+            confidence
+        }
+    }
+
+    fn matches(&self, description: &str) -> bool {
+        match &self.pattern {
+            Some(pattern) => pattern.is_match(description),
+            None => {
+                let description = description.to_lowercase();
+                let text = self.match_text.to_lowercase();
+                if self.match_type == "starts_with" {
+                    description.trim_start().starts_with(&text)
+                } else {
+                    description.contains(&text)
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn category_rule_from_row(row: &SqliteRow) -> CategoryRule {
+    CategoryRule {
+        id: row.get("id"),
+        match_text: row.get("match_text"),
+        match_type: row.get("match_type"),
+        category_id: row.get("category_id"),
+        priority: row.get("priority"),
+        created_at: row.get("created_at"),
+        pattern: None,
+    }
+}
+
+/// All rules in the order they are evaluated. A stored regex that no longer
+/// compiles is skipped with a warning rather than failing the import.
+pub(crate) async fn load_category_rules(conn: &mut SqliteConnection) -> AppResult<Vec<CategoryRule>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, match_text, match_type, category_id, priority, created_at
+        FROM category_rules
+        ORDER BY priority DESC, created_at, id
+        "#
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(category_rule_from_row)
+        .filter_map(|rule| {
+            let id = rule.id.clone();
+            rule.compile()
+                .map_err(|e| tracing::warn!("Skipping category rule {}: {}", id, e))
+                .ok()
+        })
+        .collect())
+}
+
+/// Category the rules pick for a description and how sure they are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RuleMatch<'a> {
+    pub category_id: &'a str,
+    /// From 0 to 1; see [`CategoryRule::confidence`]
+    pub confidence: f64,
+}
+
+impl RuleMatch<'_> {
+    /// Whether the category can be assigned without asking the user.
+    pub fn is_confident(&self, threshold: f64) -> bool {
+        self.confidence >= threshold
+    }
+}
+
+/// The first rule (in priority order) matching `description`. Another rule
+/// of the same priority pointing to a different category makes the match
+/// less certain; rules of a lower priority were ranked below it by the user.
+pub(crate) fn match_category_rules<'a>(rules: &'a [CategoryRule], description: &str) -> Option<RuleMatch<'a>> {
+    let mut matching = rules.iter().filter(|rule| rule.matches(description));
+    let first = matching.next()?;
+
+    let mut confidence = first.confidence();
+    if matching.any(|rule| rule.priority == first.priority && rule.category_id != first.category_id) {
+        confidence *= 0.7;
+    }
+
+    Some(RuleMatch { category_id: &first.category_id, confidence })
+}
+
+#[tauri::command]
+pub async fn get_category_rules(state: State<'_, AppState>) -> AppResult<Vec<CategoryRule>> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut conn = pool.acquire().await?;
+
+    load_category_rules(&mut conn).await
+}
+
+/// Store a new rule, e.g. `{ match_text: "tinq", match_type: "contains", category_id, priority: 10 }`
+/// to file fuel purchases under transport.
+#[tauri::command]
+pub async fn add_category_rule(
+    mut rule: CategoryRule,
+    state: State<'_, AppState>
+) -> AppResult<CategoryRule> {
+    rule.match_text = rule.match_text.trim().to_string();
+    if rule.match_text.is_empty() {
+        return Err(AppError::Validation("Vul een tekst in om op te zoeken".to_string()));
+    }
+    let mut rule = rule.compile()?;
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let exists: Option<String> = sqlx::query_scalar("SELECT id FROM categories WHERE id = ?")
+        .bind(&rule.category_id)
+        .fetch_optional(&pool)
+        .await?;
+    if exists.is_none() {
+        return Err(AppError::NotFound(format!("Categorie {} niet gevonden", rule.category_id)));
+    }
+
+    if rule.id.is_empty() {
+        rule.id = Uuid::new_v4().to_string();
+    }
+    rule.created_at = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO category_rules (id, match_text, match_type, category_id, priority, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&rule.id)
+    .bind(&rule.match_text)
+    .bind(&rule.match_type)
+    .bind(&rule.category_id)
+    .bind(rule.priority)
+    .bind(rule.created_at)
+    .execute(&pool)
+    .await?;
+
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn delete_category_rule(id: String, state: State<'_, AppState>) -> AppResult<bool> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM category_rules WHERE id = ?")
+        .bind(&id)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Categorieregel {} niet gevonden", id)));
+    }
+
+    Ok(true)
+}
+
+/// Transactions a re-categorization moved into one category.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecategorizedCategory {
+    pub category_id: String,
+    pub category_name: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecategorizeOutcome {
+    /// Transactions the rules were run on
+    pub examined: usize,
+    /// Transactions whose category changed
+    pub changed: usize,
+    /// Transactions a rule matched too uncertainly; they are left for review
+    pub flagged_for_review: usize,
+    pub by_category: Vec<RecategorizedCategory>,
+    /// The run was cancelled and nothing was changed
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct RecategorizeProgress {
+    examined: usize,
+    changed: usize,
+}
+
+/// Run the category rules over stored transactions, all of them or only the
+/// uncategorized ones, in chunks. Transactions no rule matches keep their
+/// category, as do those matched with less than `review_threshold`
+/// confidence; those are flagged for review instead. Returns early with
+/// `cancelled` set when `cancel` is raised; the caller then rolls back.
+pub(crate) async fn recategorize(
+    conn: &mut SqliteConnection,
+    only_uncategorized: bool,
+    review_threshold: f64,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(usize, usize),
+) -> AppResult<RecategorizeOutcome> {
+    let rules = load_category_rules(conn).await?;
+    let mut outcome = RecategorizeOutcome {
+        examined: 0,
+        changed: 0,
+        flagged_for_review: 0,
+        by_category: Vec::new(),
+        cancelled: false,
+    };
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut touched_categories = HashSet::new();
+    let mut last_id = String::new();
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            outcome.cancelled = true;
+            return Ok(outcome);
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, description, category_id
+            FROM transactions
+            WHERE id > ? AND (category_id IS NULL OR ? = FALSE)
+            ORDER BY id
+            LIMIT ?
+            "#
+        )
+        .bind(&last_id)
+        .bind(only_uncategorized)
+        .bind(RECATEGORIZE_CHUNK_SIZE)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let Some(last) = rows.last() else { break };
+        last_id = last.get("id");
+
+        let now = Utc::now();
+        for row in &rows {
+            outcome.examined += 1;
+            let description: String = row.get("description");
+            let current: Option<String> = row.get("category_id");
+
+            let Some(found) = match_category_rules(&rules, &description) else { continue };
+            let category_id = found.category_id;
+            if current.as_deref() == Some(category_id) {
+                continue;
+            }
+
+            if !found.is_confident(review_threshold) {
+                sqlx::query(
+                    "UPDATE transactions SET needs_review = TRUE, suggested_category_id = ?, category_confidence = ? WHERE id = ?"
+                )
+                .bind(category_id)
+                .bind(found.confidence)
+                .bind(row.get::<String, _>("id"))
+                .execute(&mut *conn)
+                .await?;
+                outcome.flagged_for_review += 1;
+                continue;
+            }
+
+            sqlx::query(
+                r#"
+                UPDATE transactions
+                SET category_id = ?, needs_review = FALSE, suggested_category_id = NULL,
+                    category_confidence = ?, updated_at = ?
+                WHERE id = ?
+                "#
+            )
+            .bind(category_id)
+            .bind(found.confidence)
+            .bind(now)
+            .bind(row.get::<String, _>("id"))
+            .execute(&mut *conn)
+            .await?;
+
+            outcome.changed += 1;
+            *counts.entry(category_id.to_string()).or_default() += 1;
+            touched_categories.insert(category_id.to_string());
+            touched_categories.extend(current);
+        }
+
+        on_progress(outcome.examined, outcome.changed);
+    }
+
+    for category_id in &touched_categories {
+        recompute_category_budgets(conn, Some(category_id)).await?;
+    }
+
+    for (category_id, count) in counts {
+        let category_name: String = sqlx::query_scalar("SELECT name FROM categories WHERE id = ?")
+            .bind(&category_id)
+            .fetch_one(&mut *conn)
+            .await?;
+        outcome.by_category.push(RecategorizedCategory { category_id, category_name, count });
+    }
+    outcome.by_category.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.category_name.cmp(&b.category_name)));
+
+    Ok(outcome)
+}
+
+/// Apply the category rules to transactions that are already stored, e.g.
+/// after adding a rule. With `only_uncategorized` manually chosen categories
+/// are left alone. All changes are made in one database transaction;
+/// [`cancel_recategorization`] stops the run and undoes them.
+#[tauri::command]
+pub async fn recategorize_transactions(
+    only_uncategorized: bool,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<RecategorizeOutcome> {
+    state.recategorize_cancel.store(false, Ordering::Relaxed);
+
+    let pool = state.db.lock().await.get_pool().await?;
+    let review_threshold = load_settings(&pool).await?.category_review_threshold;
+    let mut tx = pool.begin().await?;
+
+    let outcome = recategorize(&mut tx, only_uncategorized, review_threshold, &state.recategorize_cancel, |examined, changed| {
+        let _ = app.emit("recategorize_progress", RecategorizeProgress { examined, changed });
+    })
+    .await?;
+
+    if outcome.cancelled {
+        tx.rollback().await?;
+        return Ok(RecategorizeOutcome { changed: 0, flagged_for_review: 0, by_category: Vec::new(), ..outcome });
+    }
+
+    tx.commit().await?;
+    Ok(outcome)
+}
+
+/// Stop a running [`recategorize_transactions`]; it rolls back after the current chunk.
+#[tauri::command]
+pub async fn cancel_recategorization(state: State<'_, AppState>) -> AppResult<()> {
+    state.recategorize_cancel.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// A transaction a category rule matched too uncertainly to categorize.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TransactionForReview {
+    pub transaction: Transaction,
+    /// Category of the rule that matched
+    pub suggested_category_id: Option<String>,
+    pub confidence: Option<f64>,
+}
+
+/// The review queue: transactions whose category the rules were unsure
+/// about, newest first.
+#[tauri::command]
+pub async fn get_transactions_needing_review(state: State<'_, AppState>) -> AppResult<Vec<TransactionForReview>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {},
+            suggested_category_id, category_confidence
+        FROM transactions
+        WHERE needs_review = TRUE
+        ORDER BY date DESC, created_at DESC
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .fetch_all(&pool)
+    .await?;
+
+    let encryption = state.encryption.lock().await;
+    rows.iter()
+        .map(|row| {
+            let mut transaction = transaction_from_row(row);
+            decrypt_notes(&mut transaction, &encryption)?;
+            Ok(TransactionForReview {
+                transaction,
+                suggested_category_id: row.get("suggested_category_id"),
+                confidence: row.get("category_confidence"),
+            })
+        })
+        .collect()
+}
+
+/// Match text and type of the rule learned from an approved category: the
+/// leading words of the description up to the first one with a digit, so
+/// "Albert Heijn 1403 Utrecht" gives a rule for every Albert Heijn. When that
+/// leaves too little, the part before " - " must appear anywhere instead.
+fn learned_rule_text(description: &str) -> (String, &'static str) {
+    let counterparty = description.split(" - ").next().unwrap_or(description).trim().to_lowercase();
+    let prefix = counterparty
+        .split_whitespace()
+        .take_while(|word| !word.chars().any(|c| c.is_ascii_digit()))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if prefix.chars().count() >= 4 {
+        (prefix, "starts_with")
+    } else {
+        (counterparty, "contains")
+    }
+}
+
+/// Put a transaction in `category_id`, take it out of the review queue and
+/// learn a rule from it. The rule gets the highest priority, as the user
+/// confirmed it. Returns the new rule, or `None` when an equal one existed.
+pub(crate) async fn approve(
+    conn: &mut SqliteConnection,
+    transaction_id: &str,
+    category_id: &str,
+) -> AppResult<Option<CategoryRule>> {
+    let exists: Option<String> = sqlx::query_scalar("SELECT id FROM categories WHERE id = ?")
+        .bind(category_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if exists.is_none() {
+        return Err(AppError::NotFound(format!("Categorie {} niet gevonden", category_id)));
+    }
+
+    let previous = fetch_transaction(conn, transaction_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Transactie {} niet gevonden", transaction_id)))?;
+
+    sqlx::query(
+        r#"
+        UPDATE transactions
+        SET category_id = ?, needs_review = FALSE, suggested_category_id = NULL,
+            category_confidence = NULL, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(category_id)
+    .bind(Utc::now())
+    .bind(transaction_id)
+    .execute(&mut *conn)
+    .await?;
+
+    if previous.category_id.as_deref() != Some(category_id) {
+        recompute_category_budgets(conn, previous.category_id.as_deref()).await?;
+        recompute_category_budgets(conn, Some(category_id)).await?;
+    }
+
+    let (match_text, match_type) = learned_rule_text(&previous.description);
+    if match_text.is_empty() {
+        return Ok(None);
+    }
+    let existing: Option<String> = sqlx::query_scalar(
+        "SELECT id FROM category_rules WHERE match_text = ? COLLATE NOCASE AND match_type = ? AND category_id = ?"
+    )
+    .bind(&match_text)
+    .bind(match_type)
+    .bind(category_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if existing.is_some() {
+        return Ok(None);
+    }
+
+    let priority: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(priority), 0) + 1 FROM category_rules")
+        .fetch_one(&mut *conn)
+        .await?;
+    let rule = CategoryRule {
+        id: Uuid::new_v4().to_string(),
+        match_text,
+        match_type: match_type.to_string(),
+        category_id: category_id.to_string(),
+        priority,
+        created_at: Utc::now(),
+        pattern: None,
+    }
+    .compile()?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO category_rules (id, match_text, match_type, category_id, priority, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&rule.id)
+    .bind(&rule.match_text)
+    .bind(&rule.match_type)
+    .bind(&rule.category_id)
+    .bind(rule.priority)
+    .bind(rule.created_at)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(Some(rule))
+}
+
+/// Confirm the category of a transaction from the review queue, e.g. the
+/// suggested one. Similar transactions get that category from then on.
+#[tauri::command]
+pub async fn approve_category(
+    transaction_id: String,
+    category_id: String,
+    state: State<'_, AppState>
+) -> AppResult<Option<CategoryRule>> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    let rule = approve(&mut tx, &transaction_id, &category_id).await?;
+
+    tx.commit().await?;
+    Ok(rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Database, TestDatabase};
+
+    fn apply_category_rules<'a>(rules: &'a [CategoryRule], description: &str) -> Option<&'a str> {
+        match_category_rules(rules, description).map(|m| m.category_id)
+    }
+
+    fn rule(match_text: &str, match_type: &str, category_id: &str, priority: i64) -> CategoryRule {
+        CategoryRule {
+            id: String::new(),
+            match_text: match_text.to_string(),
+            match_type: match_type.to_string(),
+            category_id: category_id.to_string(),
+            priority,
+            created_at: Utc::now(),
+            pattern: None,
+        }
+        .compile()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rules_apply_in_priority_order() {
+        let mut rules = vec![
+            rule("albert heijn", "contains", "boodschappen", 0),
+            rule(r"\bah\b", "regex", "boodschappen", 0),
+            rule("AH to go", "starts_with", "eten", 10),
+        ];
+        rules.sort_by_key(|r| std::cmp::Reverse(r.priority));
+
+        assert_eq!(apply_category_rules(&rules, "ALBERT HEIJN 1234 AMSTERDAM"), Some("boodschappen"));
+        assert_eq!(apply_category_rules(&rules, "AH to go Utrecht CS"), Some("eten"));
+        assert_eq!(apply_category_rules(&rules, "Betaling AH 1234"), Some("boodschappen"));
+        assert_eq!(apply_category_rules(&rules, "Bakkerij Ahlers"), None);
+
+        let invalid = CategoryRule { match_type: "regex".to_string(), ..rule("(", "contains", "x", 0) };
+        assert!(matches!(invalid.compile(), Err(AppError::Validation(_))));
+        let unknown = CategoryRule { match_type: "exact".to_string(), ..rule("x", "contains", "x", 0) };
+        assert!(matches!(unknown.compile(), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_match_confidence() {
+        let rules = vec![
+            rule("albert heijn", "contains", "boodschappen", 0),
+            rule("bol", "contains", "winkelen", 0),
+            rule("kruidvat", "contains", "drogist", 0),
+            rule("kruidvat", "contains", "boodschappen", 0),
+        ];
+
+        let confident = match_category_rules(&rules, "Albert Heijn 1403").unwrap();
+        assert!(confident.is_confident(0.7));
+        // Short match texts and equally ranked rules that disagree are uncertain
+        assert!(!match_category_rules(&rules, "Bol.com").unwrap().is_confident(0.7));
+        let conflicting = match_category_rules(&rules, "Kruidvat 7721").unwrap();
+        assert_eq!(conflicting.category_id, "drogist");
+        assert!(!conflicting.is_confident(0.7));
+
+        assert_eq!(learned_rule_text("Albert Heijn 1403 Utrecht"), ("albert heijn".to_string(), "starts_with"));
+        assert_eq!(learned_rule_text("BEA 12 Bakker - Utrecht"), ("bea 12 bakker".to_string(), "contains"));
+    }
+
+    #[tokio::test]
+    async fn test_default_rules_map_to_seeded_categories() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let rules = load_category_rules(&mut conn).await.unwrap();
+        assert!(!rules.is_empty());
+
+        let groceries: String = sqlx::query_scalar("SELECT id FROM categories WHERE name = 'Boodschappen'")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(apply_category_rules(&rules, "Albert Heijn 1403 Utrecht"), Some(groceries.as_str()));
+
+        // Restarting does not seed the defaults a second time
+        drop(conn);
+        pool.close().await;
+        let reopened = Database::new(db.path()).await.unwrap();
+        let pool = reopened.get_pool().await.unwrap();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM category_rules").fetch_one(&pool).await.unwrap();
+        assert_eq!(count as usize, rules.len());
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_recategorize_respects_manual_categories() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let category = |name: &'static str| {
+            sqlx::query_scalar::<_, String>("SELECT id FROM categories WHERE name = ?").bind(name)
+        };
+        let groceries = category("Boodschappen").fetch_one(&mut *conn).await.unwrap();
+        let clothing = category("Kleding").fetch_one(&mut *conn).await.unwrap();
+
+        for (id, description, category_id) in [
+            ("t1", "Albert Heijn 1403", None),
+            ("t2", "Jumbo Utrecht", Some(clothing.as_str())),
+            ("t3", "Onbekende winkel", None),
+        ] {
+            sqlx::query(
+                "INSERT INTO transactions (id, description, amount, date, category_id, transaction_type) VALUES (?, ?, '10.00', ?, ?, 'debit')"
+            )
+            .bind(id)
+            .bind(description)
+            .bind(Utc::now())
+            .bind(category_id)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        }
+
+        let cancelled = recategorize(&mut conn, false, 0.7, &AtomicBool::new(true), |_, _| {}).await.unwrap();
+        assert!(cancelled.cancelled);
+
+        let outcome = recategorize(&mut conn, true, 0.7, &AtomicBool::new(false), |_, _| {}).await.unwrap();
+        assert_eq!((outcome.examined, outcome.changed), (2, 1));
+        let stored = |id: &'static str| {
+            sqlx::query_scalar::<_, Option<String>>("SELECT category_id FROM transactions WHERE id = ?").bind(id)
+        };
+        assert_eq!(stored("t2").fetch_one(&mut *conn).await.unwrap(), Some(clothing.clone()));
+
+        let outcome = recategorize(&mut conn, false, 0.7, &AtomicBool::new(false), |_, _| {}).await.unwrap();
+        assert_eq!((outcome.examined, outcome.changed), (3, 1));
+        assert_eq!(outcome.by_category.len(), 1);
+        assert_eq!(outcome.by_category[0].category_name, "Boodschappen");
+        assert_eq!(stored("t2").fetch_one(&mut *conn).await.unwrap(), Some(groceries));
+        assert_eq!(stored("t3").fetch_one(&mut *conn).await.unwrap(), None);
+
+        drop(conn);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_uncertain_match_waits_for_review_and_approval_learns() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let category = |name: &'static str| {
+            sqlx::query_scalar::<_, String>("SELECT id FROM categories WHERE name = ?").bind(name)
+        };
+        let groceries = category("Boodschappen").fetch_one(&mut *conn).await.unwrap();
+        let clothing = category("Kleding").fetch_one(&mut *conn).await.unwrap();
+        for category_id in [&clothing, &groceries] {
+            sqlx::query(
+                "INSERT INTO category_rules (id, match_text, match_type, category_id, priority) VALUES (?, 'bakkerij bart', 'contains', ?, 0)"
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(category_id)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO transactions (id, description, amount, date, transaction_type) VALUES ('t1', 'Bakkerij Bart 12 Utrecht', '4.50', ?, 'debit')"
+        )
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let outcome = recategorize(&mut conn, true, 0.7, &AtomicBool::new(false), |_, _| {}).await.unwrap();
+        assert_eq!((outcome.changed, outcome.flagged_for_review), (0, 1));
+        let queued: (bool, Option<String>) = sqlx::query_as(
+            "SELECT needs_review, category_id FROM transactions WHERE id = 't1'"
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+        assert_eq!(queued, (true, None));
+
+        let rule = approve(&mut conn, "t1", &groceries).await.unwrap().unwrap();
+        assert_eq!((rule.match_text.as_str(), rule.match_type.as_str()), ("bakkerij bart", "starts_with"));
+        assert!(approve(&mut conn, "t1", &groceries).await.unwrap().is_none());
+
+        let reviewed: (bool, Option<String>) = sqlx::query_as(
+            "SELECT needs_review, category_id FROM transactions WHERE id = 't1'"
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+        assert_eq!(reviewed, (false, Some(groceries.clone())));
+
+        // The learned rule outranks the conflicting ones
+        let rules = load_category_rules(&mut conn).await.unwrap();
+        let found = match_category_rules(&rules, "Bakkerij Bart 7 Zeist").unwrap();
+        assert_eq!(found.category_id, groceries);
+        assert!(found.is_confident(0.7));
+
+        drop(conn);
+        pool.close().await;
+    }
+}
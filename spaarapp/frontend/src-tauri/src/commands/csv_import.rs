@@ -1,20 +1,64 @@
-use crate::error::AppResult;
-use crate::models::{Transaction, TransactionType, CsvImportConfig};
+use crate::alerts;
+use crate::commands::accounts::{default_category_for_account, ensure_account};
+use crate::commands::budgets::{check_budget_thresholds, recompute_category_budgets};
+use crate::commands::files::check_readable;
+use crate::commands::category_rules::{load_category_rules, match_category_rules, CategoryRule};
+use crate::commands::settings::{category_review_threshold, load_settings};
+use crate::commands::transactions::{stored_notes, validate_transaction_amounts};
+use crate::csv_import::CsvImporter;
+use crate::encryption::EncryptionManager;
+use crate::error::{AppError, AppResult};
+use crate::models::{Settings, Transaction, CsvImportConfig};
+use crate::rate_limit::RateLimited;
+use crate::AppState;
+use tauri::{AppHandle, Emitter, State};
 use chrono::{DateTime, NaiveDate, Utc};
 use csv::{ReaderBuilder, StringRecord};
+use regex::Regex;
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use spaarapp_shared::Currency;
+use std::collections::{HashMap, HashSet};
 use std::io::Cursor;
 use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
+use sqlx::SqliteConnection;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CsvImportResult {
     pub transactions: Vec<Transaction>,
-    pub errors: Vec<String>,
+    pub errors: Vec<ImportError>,
     pub warnings: Vec<String>,
     pub total_rows: usize,
     pub imported_rows: usize,
+    /// Summary/footer lines (totals, balances) that were skipped rather than parsed
+    #[serde(default)]
+    pub skipped_footer_rows: usize,
+}
+
+/// A row that could not be imported, with enough context to fix it in the source file.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ImportError {
+    /// Line number in the file, counting the header as line 1
+    pub line: usize,
+    /// The raw record as it appeared in the file; `None` when the error is
+    /// about the whole file, or the record itself could not be read
+    pub raw: Option<String>,
+    /// The column that failed, when the failure is specific to one field
+    pub field: Option<String>,
+    pub reason: String,
+}
+
+impl ImportError {
+    fn new(line: usize, record: &StringRecord, field: Option<&str>, reason: impl Into<String>) -> Self {
+        Self {
+            line,
+            raw: Some(record.iter().collect::<Vec<_>>().join(";")),
+            field: field.map(|f| f.to_string()),
+            reason: reason.into(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -30,34 +74,121 @@ pub struct RabobankTransaction {
     pub mededelingen: String,
 }
 
+/// Fill in the category of parsed transactions from the category rules, so a
+/// preview shows where they will end up. Uncertain matches are left empty,
+/// as the import will leave them for review.
+async fn categorize_parsed(state: &State<'_, AppState>, result: &mut CsvImportResult) -> AppResult<()> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut conn = pool.acquire().await?;
+    let rules = load_category_rules(&mut conn).await?;
+    let review_threshold = category_review_threshold(&mut conn).await?;
+
+    for transaction in &mut result.transactions {
+        if transaction.category_id.is_none() {
+            transaction.category_id = match_category_rules(&rules, &transaction.description)
+                .filter(|found| found.is_confident(review_threshold))
+                .map(|found| found.category_id.to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Parse a CSV file for import. With a config the columns are read as it maps
+/// them; without one the bank is detected from the file.
 #[tauri::command]
-pub async fn import_csv(file_path: String) -> AppResult<CsvImportResult> {
+pub async fn import_csv(
+    file_path: String,
+    config: Option<CsvImportConfig>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<CsvImportResult> {
+    alerts::guard_command(&app, &state, RateLimited::CsvParsing).await?;
+    check_readable(&app, &state, &file_path).await?;
     let content = std::fs::read_to_string(&file_path)?;
-    parse_rabobank_csv(content).await
+    let mut result = match config {
+        Some(config) => parse_mapped_csv(&content, config).await,
+        None => {
+            let currency = load_settings(&state.db.lock().await.get_pool().await?).await?.currency();
+            parse_bank_csv(content, &currency).await?
+        }
+    };
+    categorize_parsed(&state, &mut result).await?;
+    Ok(result)
 }
 
 #[tauri::command]
-pub async fn parse_csv(content: String) -> AppResult<CsvImportResult> {
-    parse_rabobank_csv(content).await
+pub async fn parse_csv(content: String, app: AppHandle, state: State<'_, AppState>) -> AppResult<CsvImportResult> {
+    alerts::guard_command(&app, &state, RateLimited::CsvParsing).await?;
+    let currency = load_settings(&state.db.lock().await.get_pool().await?).await?.currency();
+    let mut result = parse_bank_csv(content, &currency).await?;
+    categorize_parsed(&state, &mut result).await?;
+    Ok(result)
 }
 
 #[tauri::command]
-pub async fn preview_csv(content: String, limit: Option<usize>) -> AppResult<CsvImportResult> {
-    let mut result = parse_rabobank_csv(content).await?;
+pub async fn preview_csv(
+    content: String,
+    limit: Option<usize>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<CsvImportResult> {
+    alerts::guard_command(&app, &state, RateLimited::CsvParsing).await?;
+    let currency = load_settings(&state.db.lock().await.get_pool().await?).await?.currency();
+    let mut result = parse_bank_csv(content, &currency).await?;
     if let Some(limit) = limit {
         result.transactions.truncate(limit);
     }
+    categorize_parsed(&state, &mut result).await?;
+    Ok(result)
+}
+
+/// Parse an MT940 (SWIFT) bank statement, as exported by most Dutch banks,
+/// into transactions; the result has the same shape as a CSV import.
+#[tauri::command]
+pub async fn import_mt940(content: String, app: AppHandle, state: State<'_, AppState>) -> AppResult<CsvImportResult> {
+    alerts::guard_command(&app, &state, RateLimited::CsvParsing).await?;
+    let mut result = parse_mt940(&content)?;
+    categorize_parsed(&state, &mut result).await?;
+    Ok(result)
+}
+
+/// Parse a CAMT.053 XML bank statement into transactions; the result has the
+/// same shape as a CSV import.
+#[tauri::command]
+pub async fn import_camt053(content: String, app: AppHandle, state: State<'_, AppState>) -> AppResult<CsvImportResult> {
+    alerts::guard_command(&app, &state, RateLimited::CsvParsing).await?;
+    let mut result = parse_camt053(&content)?;
+    categorize_parsed(&state, &mut result).await?;
     Ok(result)
 }
 
 #[tauri::command]
 pub async fn validate_csv_structure(content: String) -> AppResult<bool> {
+    Ok(detect_bank_format(&content).is_some())
+}
+
+/// Bank export layouts the importer can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BankFormat {
+    Rabobank,
+    AbnAmro,
+}
+
+/// Recognize a bank export by its header row, or for ABN AMRO (which has no
+/// header) by the shape of its first line.
+pub(crate) fn detect_bank_format(content: &str) -> Option<BankFormat> {
+    if is_abnamro_export(content) {
+        return Some(BankFormat::AbnAmro);
+    }
+
+
     let mut rdr = ReaderBuilder::new()
         .delimiter(b';')
         .has_headers(true)
         .from_reader(Cursor::new(content));
 
-    let headers = rdr.headers()?;
+    let headers = rdr.headers().ok()?;
 
     let required_headers = vec![
         "Datum",
@@ -71,25 +202,679 @@ pub async fn validate_csv_structure(content: String) -> AppResult<bool> {
         "Mededelingen",
     ];
 
-    for header in &required_headers {
-        if !headers.iter().any(|h| h.trim() == *header) {
-            return Ok(false);
+    required_headers
+        .iter()
+        .all(|header| headers.iter().any(|h| h.trim() == *header))
+        .then_some(BankFormat::Rabobank)
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ImportOutcome {
+    pub imported: usize,
+    /// Rows already in the database from an earlier import
+    pub skipped_duplicates: usize,
+    /// Rows matching a stored transaction, whether skipped or imported anyway
+    #[serde(default)]
+    pub duplicates: Vec<ImportDuplicate>,
+    /// Transactions stored, for the checks that run once the import is committed
+    #[serde(skip)]
+    pub stored: Vec<Transaction>,
+}
+
+/// An imported row that matches a transaction already in the database.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ImportDuplicate {
+    /// Position of the row among the imported transactions, from 0
+    pub index: usize,
+    pub description: String,
+    pub date: DateTime<Utc>,
+    pub amount: Decimal,
+    /// Id of the stored transaction it matches
+    pub existing_id: String,
+}
+
+/// Stable fingerprint of a transaction's identifying content, so the same bank
+/// line imported twice maps to the same value regardless of formatting.
+pub(crate) fn import_hash(transaction: &Transaction) -> String {
+    let description = transaction
+        .description
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    let account = transaction
+        .account_number
+        .as_deref()
+        .unwrap_or("")
+        .replace(' ', "")
+        .to_uppercase();
+
+    let content = format!(
+        "{}|{}|{}|{}",
+        transaction.date.format("%Y-%m-%d"),
+        transaction.amount.round_dp(2).normalize(),
+        description,
+        account
+    );
+
+    ring::digest::digest(&ring::digest::SHA256, content.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Where an imported transaction's category came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CategorySource {
+    /// The parsed transaction came with a stored category
+    Suggested,
+    /// A category rule matched the description
+    Rule,
+    /// A category rule matched, but not confidently enough to assign its
+    /// category; the transaction goes to the review queue
+    NeedsReview,
+    /// Fallback category of the transaction's account
+    AccountDefault,
+    Uncategorized,
+}
+
+/// Category resolved for an imported transaction.
+#[derive(Debug, Clone)]
+struct ResolvedCategory {
+    category_id: Option<String>,
+    source: CategorySource,
+    /// Category of a rule that matched with too little confidence to assign
+    /// it, and that confidence
+    review: Option<(String, f64)>,
+}
+
+impl ResolvedCategory {
+    fn new(category_id: Option<String>, source: CategorySource) -> Self {
+        Self { category_id, source, review: None }
+    }
+}
+
+/// Stored category for an imported transaction: a category the transaction
+/// already names, else the first matching category rule, else the account's
+/// default category. A rule match below `review_threshold` only suggests its
+/// category, and the account default (if any) is used meanwhile.
+async fn resolve_import_category(
+    conn: &mut SqliteConnection,
+    rules: &[CategoryRule],
+    review_threshold: f64,
+    transaction: &Transaction,
+) -> AppResult<ResolvedCategory> {
+    if let Some(suggested) = &transaction.category_id {
+        let category_id: Option<String> = sqlx::query_scalar(
+            "SELECT id FROM categories WHERE id = ? OR name = ? COLLATE NOCASE LIMIT 1"
+        )
+        .bind(suggested)
+        .bind(suggested)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        if category_id.is_some() {
+            return Ok(ResolvedCategory::new(category_id, CategorySource::Suggested));
+        }
+    }
+
+    let found = match_category_rules(rules, &transaction.description);
+    if let Some(found) = found.filter(|found| found.is_confident(review_threshold)) {
+        return Ok(ResolvedCategory::new(Some(found.category_id.to_string()), CategorySource::Rule));
+    }
+
+    let default_category = default_category_for_account(conn, transaction.account_number.as_deref()).await?;
+    Ok(match found {
+        Some(found) => ResolvedCategory {
+            category_id: default_category,
+            source: CategorySource::NeedsReview,
+            review: Some((found.category_id.to_string(), found.confidence)),
+        },
+        None => match default_category {
+            Some(category_id) => ResolvedCategory::new(Some(category_id), CategorySource::AccountDefault),
+            None => ResolvedCategory::new(None, CategorySource::Uncategorized),
+        },
+    })
+}
+
+/// Candidates with the same date, amount, description and account number as a
+/// stored transaction, e.g. from an earlier statement that overlaps this one.
+/// Compared on the [`import_hash`], so transactions added by hand since the
+/// hashes were backfilled are not found.
+pub(crate) async fn find_duplicates_against_db(
+    conn: &mut SqliteConnection,
+    transactions: &[Transaction],
+) -> AppResult<Vec<ImportDuplicate>> {
+    let mut duplicates = Vec::new();
+
+    for (index, transaction) in transactions.iter().enumerate() {
+        let existing_id: Option<String> = sqlx::query_scalar("SELECT id FROM transactions WHERE import_hash = ?")
+            .bind(import_hash(transaction))
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        if let Some(existing_id) = existing_id {
+            duplicates.push(ImportDuplicate {
+                index,
+                description: transaction.description.clone(),
+                date: transaction.date,
+                amount: transaction.amount,
+                existing_id,
+            });
+        }
+    }
+
+    Ok(duplicates)
+}
+
+/// Insert parsed transactions. Rows matching a stored transaction are
+/// reported in the outcome and skipped, unless `skip_duplicates` is off: then
+/// they are stored without an import hash, so the stored original keeps
+/// catching later re-imports. Repeats within `transactions` are always skipped.
+/// Notes are stored as [`stored_notes`] does for `settings`.
+pub(crate) async fn persist_imported(
+    conn: &mut SqliteConnection,
+    transactions: Vec<Transaction>,
+    skip_duplicates: bool,
+    settings: &Settings,
+    encryption: &EncryptionManager<'_>,
+) -> AppResult<ImportOutcome> {
+    let duplicates = find_duplicates_against_db(conn, &transactions).await?;
+    let duplicate_rows: HashSet<usize> = duplicates.iter().map(|d| d.index).collect();
+
+    let mut outcome = ImportOutcome { imported: 0, skipped_duplicates: 0, duplicates, stored: Vec::new() };
+    let mut touched_categories = HashSet::new();
+    let mut account_numbers = HashSet::new();
+    let rules = load_category_rules(conn).await?;
+    let review_threshold = category_review_threshold(conn).await?;
+    let now = Utc::now();
+
+    for (index, mut transaction) in transactions.into_iter().enumerate() {
+        let is_duplicate = duplicate_rows.contains(&index);
+        if is_duplicate && skip_duplicates {
+            outcome.skipped_duplicates += 1;
+            continue;
+        }
+
+        if transaction.id.is_empty() {
+            transaction.id = Uuid::new_v4().to_string();
+        }
+        transaction.created_at = now;
+        transaction.updated_at = now;
+
+        let resolved = resolve_import_category(conn, &rules, review_threshold, &transaction).await?;
+        transaction.category_id = resolved.category_id;
+        let (suggested_category_id, category_confidence) = resolved.review.unzip();
+        let notes = stored_notes(transaction.notes.as_deref(), settings, encryption)?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO transactions (
+                id, description, amount, date, category_id, account_number,
+                account_holder, counterparty_bank, transaction_type, balance_after, notes, tags,
+                is_recurring, recurring_frequency, import_hash, needs_review, suggested_category_id,
+                category_confidence, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&transaction.id)
+        .bind(&transaction.description)
+        .bind(transaction.amount.to_string())
+        .bind(transaction.date)
+        .bind(&transaction.category_id)
+        .bind(&transaction.account_number)
+        .bind(&transaction.account_holder)
+        .bind(&transaction.counterparty_bank)
+        .bind(&transaction.transaction_type)
+        .bind(transaction.balance_after.map(|d| d.to_string()))
+        .bind(&notes)
+        .bind(serde_json::to_string(&transaction.tags)?)
+        .bind(transaction.is_recurring)
+        .bind(&transaction.recurring_frequency)
+        .bind((!is_duplicate).then(|| import_hash(&transaction)))
+        .bind(suggested_category_id.is_some())
+        .bind(&suggested_category_id)
+        .bind(category_confidence)
+        .bind(transaction.created_at)
+        .bind(transaction.updated_at)
+        .execute(&mut *conn)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            outcome.skipped_duplicates += 1;
+            continue;
+        }
+
+        touched_categories.insert(transaction.category_id.clone());
+        account_numbers.extend(transaction.account_number.clone().filter(|n| !n.trim().is_empty()));
+        outcome.imported += 1;
+        outcome.stored.push(transaction);
+    }
+
+    // Every own account seen in an import can be filtered on afterwards
+    for account_number in account_numbers {
+        ensure_account(conn, &account_number).await?;
+    }
+
+    for category_id in touched_categories {
+        recompute_category_budgets(conn, category_id.as_deref()).await?;
+    }
+
+    Ok(outcome)
+}
+
+/// Imports that started in the last few seconds, by content hash. Catches a
+/// double-clicked import button before the duplicate check in the database
+/// sees the first import's rows.
+#[derive(Debug, Default)]
+pub struct RecentImports {
+    started: HashMap<String, Instant>,
+}
+
+impl RecentImports {
+    /// Register an import of `key`, refusing it when the same import started less than `window` ago.
+    pub(crate) fn begin(&mut self, key: &str, window: Duration, now: Instant) -> AppResult<()> {
+        self.started.retain(|_, started| now.duration_since(*started) < window);
+
+        if self.started.contains_key(key) {
+            return Err(AppError::Validation("Deze import is zojuist al uitgevoerd".to_string()));
+        }
+
+        self.started.insert(key.to_string(), now);
+        Ok(())
+    }
+
+    /// Forget a failed import so the user can retry it right away.
+    pub(crate) fn forget(&mut self, key: &str) {
+        self.started.remove(key);
+    }
+}
+
+/// SHA-256 of the given parts, hex encoded.
+fn content_hash<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> String {
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    for part in parts {
+        context.update(part);
+        context.update(&[0]);
+    }
+    context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Run `import` unless the same content was imported within the configured window.
+async fn guard_repeated_import<T, F>(state: &AppState, key: String, import: F) -> AppResult<T>
+where
+    F: std::future::Future<Output = AppResult<T>>,
+{
+    let pool = state.db.lock().await.get_pool().await?;
+    let window = Duration::from_secs(load_settings(&pool).await?.import_repeat_window_seconds as u64);
+    if window.is_zero() {
+        return import.await;
+    }
+
+    state.recent_imports.lock().await.begin(&key, window, Instant::now())?;
+
+    let result = import.await;
+    if result.is_err() {
+        state.recent_imports.lock().await.forget(&key);
+    }
+    result
+}
+
+/// Store transactions from a parsed import. Rows that were imported before are
+/// reported as duplicates; with `skip_duplicates` re-importing the same file
+/// adds nothing.
+#[tauri::command]
+pub async fn import_transactions(
+    transactions: Vec<Transaction>,
+    skip_duplicates: bool,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<ImportOutcome> {
+    let rows: Vec<String> = transactions
+        .iter()
+        .map(|t| format!("{}|{}|{}|{}|{:?}", t.date.to_rfc3339(), t.amount, t.transaction_type, t.description, t.account_number))
+        .collect();
+    let key = content_hash(rows.iter().map(|r| r.as_bytes()));
+
+    guard_repeated_import(&state, key, async {
+        let pool = state.db.lock().await.get_pool().await?;
+
+        let settings = load_settings(&pool).await?;
+        for transaction in &transactions {
+            validate_transaction_amounts(transaction, &settings.currency)?;
+        }
+
+        let encryption = state.encryption.lock().await;
+        let mut tx = pool.begin().await?;
+        let outcome = persist_imported(&mut tx, transactions, skip_duplicates, &settings, &encryption).await?;
+        tx.commit().await?;
+        drop(encryption);
+
+        if let Err(e) = check_budget_thresholds(&app, &pool).await {
+            tracing::warn!("Budget threshold check failed: {}", e);
+        }
+        alerts::check_imported_transactions(&app, &state, &pool, &outcome.stored).await;
+
+        Ok(outcome)
+    })
+    .await
+}
+
+/// Result of one file in a multi-file import.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FileImportSummary {
+    pub path: String,
+    /// `None` when the file was unreadable or its format was not recognized
+    pub bank: Option<BankFormat>,
+    pub imported: usize,
+    /// Rows already in the database or in an earlier file of the same import
+    pub skipped_duplicates: usize,
+    pub errors: Vec<ImportError>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MultiImportOutcome {
+    pub files: Vec<FileImportSummary>,
+    pub imported: usize,
+    pub skipped_duplicates: usize,
+    /// Transactions stored from all files, for the checks that run once the
+    /// import is committed
+    #[serde(skip)]
+    pub stored: Vec<Transaction>,
+}
+
+/// Payload of the `import_progress` event.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ImportProgress {
+    /// "parsing" while files are read, "saving" while they are stored
+    pub phase: &'static str,
+    /// Files finished in this phase so far
+    pub files_done: usize,
+    pub total_files: usize,
+    pub path: String,
+}
+
+fn file_error_summary(path: &str, reason: String) -> FileImportSummary {
+    FileImportSummary {
+        path: path.to_string(),
+        bank: None,
+        imported: 0,
+        skipped_duplicates: 0,
+        errors: vec![ImportError { line: 0, raw: None, field: None, reason }],
+        warnings: Vec::new(),
+    }
+}
+
+/// Parse every file and store all of them on `conn` as one batch. A row that
+/// is already stored, or appeared in an earlier file, is skipped; a row with
+/// more decimals than the currency allows is reported in its file's errors.
+pub(crate) async fn import_files(
+    conn: &mut SqliteConnection,
+    files: Vec<(String, std::io::Result<String>)>,
+    settings: &Settings,
+    encryption: &EncryptionManager<'_>,
+    mut on_progress: impl FnMut(ImportProgress),
+) -> AppResult<MultiImportOutcome> {
+    let currency = &settings.currency();
+    let total_files = files.len();
+    let mut parsed = Vec::with_capacity(total_files);
+
+    for (i, (path, content)) in files.into_iter().enumerate() {
+        let file = match content {
+            Err(e) => (file_error_summary(&path, format!("Bestand kan niet gelezen worden: {}", e)), Vec::new()),
+            Ok(content) => match detect_bank_format(&content) {
+                None => (file_error_summary(&path, "Onbekend bankformaat".to_string()), Vec::new()),
+                Some(bank) => {
+                    let result = match bank {
+                        BankFormat::Rabobank => parse_rabobank_csv(content, currency).await?,
+                        BankFormat::AbnAmro => parse_abnamro_csv(content).await?,
+                    };
+                    let mut errors = result.errors;
+                    let mut transactions = Vec::with_capacity(result.transactions.len());
+                    for transaction in result.transactions {
+                        match validate_transaction_amounts(&transaction, &settings.currency) {
+                            Ok(()) => transactions.push(transaction),
+                            Err(e) => errors.push(ImportError {
+                                line: 0,
+                                raw: None,
+                                field: None,
+                                reason: format!(
+                                    "{} op {}: {}",
+                                    transaction.description,
+                                    transaction.date.format("%d-%m-%Y"),
+                                    e.user_message()
+                                ),
+                            }),
+                        }
+                    }
+                    let summary = FileImportSummary {
+                        path: path.clone(),
+                        bank: Some(bank),
+                        imported: 0,
+                        skipped_duplicates: 0,
+                        errors,
+                        warnings: result.warnings,
+                    };
+                    (summary, transactions)
+                }
+            },
+        };
+
+        parsed.push(file);
+        on_progress(ImportProgress { phase: "parsing", files_done: i + 1, total_files, path });
+    }
+
+    let mut outcome = MultiImportOutcome {
+        files: Vec::with_capacity(total_files),
+        imported: 0,
+        skipped_duplicates: 0,
+        stored: Vec::new(),
+    };
+
+    for (i, (mut summary, transactions)) in parsed.into_iter().enumerate() {
+        let stored = persist_imported(conn, transactions, true, settings, encryption).await?;
+        summary.imported = stored.imported;
+        summary.skipped_duplicates = stored.skipped_duplicates;
+        outcome.imported += stored.imported;
+        outcome.skipped_duplicates += stored.skipped_duplicates;
+        outcome.stored.extend(stored.stored);
+
+        on_progress(ImportProgress { phase: "saving", files_done: i + 1, total_files, path: summary.path.clone() });
+        outcome.files.push(summary);
+    }
+
+    Ok(outcome)
+}
+
+/// Import several bank exports at once, e.g. one per account or per year.
+/// Everything is stored in a single database transaction.
+#[tauri::command]
+pub async fn import_multiple(
+    paths: Vec<String>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<MultiImportOutcome> {
+    alerts::guard_command(&app, &state, RateLimited::CsvParsing).await?;
+    for path in &paths {
+        check_readable(&app, &state, path).await?;
+    }
+    let files: Vec<(String, std::io::Result<String>)> = paths
+        .into_iter()
+        .map(|path| {
+            let content = std::fs::read_to_string(&path);
+            (path, content)
+        })
+        .collect();
+
+    let key = content_hash(files.iter().map(|(path, content)| match content {
+        Ok(content) => content.as_bytes(),
+        Err(_) => path.as_bytes(),
+    }));
+
+    guard_repeated_import(&state, key, async {
+        let pool = state.db.lock().await.get_pool().await?;
+        let settings = load_settings(&pool).await?;
+        let encryption = state.encryption.lock().await;
+        let mut tx = pool.begin().await?;
+
+        let outcome = import_files(&mut tx, files, &settings, &encryption, |progress| {
+            let _ = app.emit("import_progress", progress);
+        })
+        .await?;
+
+        tx.commit().await?;
+        drop(encryption);
+
+        if let Err(e) = check_budget_thresholds(&app, &pool).await {
+            tracing::warn!("Budget threshold check failed: {}", e);
         }
+        alerts::check_imported_transactions(&app, &state, &pool, &outcome.stored).await;
+
+        Ok(outcome)
+    })
+    .await
+}
+
+/// How an imported row relates to data that is already there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateStatus {
+    New,
+    /// Same content as an earlier row in this file
+    InFile,
+    /// Same content as a transaction already in the database
+    AlreadyImported,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DryRunRow {
+    /// The transaction as it would be stored, with its resolved category
+    pub transaction: Transaction,
+    pub category_source: CategorySource,
+    pub duplicate: DuplicateStatus,
+    /// The row's [`import_hash`]; rows sharing one are the same transaction
+    pub import_hash: String,
+}
+
+/// Everything an import would do, without having done it.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DryRunReport {
+    pub rows: Vec<DryRunRow>,
+    pub errors: Vec<ImportError>,
+    pub warnings: Vec<String>,
+    pub total_rows: usize,
+    pub skipped_footer_rows: usize,
+    /// Rows that would be inserted
+    pub would_import: usize,
+    /// Rows that would be skipped as duplicates
+    pub would_skip: usize,
+}
+
+/// Classify parsed rows the way [`persist_imported`] would treat them. Only reads.
+pub(crate) async fn dry_run(conn: &mut SqliteConnection, parsed: CsvImportResult) -> AppResult<DryRunReport> {
+    let mut seen_hashes = HashSet::new();
+    let mut rows = Vec::with_capacity(parsed.transactions.len());
+    let rules = load_category_rules(conn).await?;
+    let review_threshold = category_review_threshold(conn).await?;
+
+    for mut transaction in parsed.transactions {
+        let hash = import_hash(&transaction);
+
+        let duplicate = if !seen_hashes.insert(hash.clone()) {
+            DuplicateStatus::InFile
+        } else {
+            let stored: Option<String> = sqlx::query_scalar("SELECT id FROM transactions WHERE import_hash = ?")
+                .bind(&hash)
+                .fetch_optional(&mut *conn)
+                .await?;
+            if stored.is_some() { DuplicateStatus::AlreadyImported } else { DuplicateStatus::New }
+        };
+
+        let resolved = resolve_import_category(conn, &rules, review_threshold, &transaction).await?;
+        transaction.category_id = resolved.category_id;
+
+        rows.push(DryRunRow { transaction, category_source: resolved.source, duplicate, import_hash: hash });
+    }
+
+    let would_import = rows.iter().filter(|r| r.duplicate == DuplicateStatus::New).count();
+
+    Ok(DryRunReport {
+        would_skip: rows.len() - would_import,
+        would_import,
+        rows,
+        errors: parsed.errors,
+        warnings: parsed.warnings,
+        total_rows: parsed.total_rows,
+        skipped_footer_rows: parsed.skipped_footer_rows,
+    })
+}
+
+/// Parse with the generic column mapping, reporting a failure as an import error.
+async fn parse_mapped_csv(content: &str, config: CsvImportConfig) -> CsvImportResult {
+    let (transactions, errors) = match CsvImporter::new(config).parse_csv_content(content).await {
+        Ok(transactions) => (transactions, Vec::new()),
+        Err(e) => (
+            Vec::new(),
+            vec![ImportError { line: 0, raw: None, field: None, reason: e.to_string() }],
+        ),
+    };
+
+    CsvImportResult {
+        total_rows: transactions.len() + errors.len(),
+        imported_rows: transactions.len(),
+        transactions,
+        errors,
+        warnings: Vec::new(),
+        skipped_footer_rows: 0,
     }
+}
+
+/// Run the whole import except the database writes and report what would
+/// happen. Without a config the file is read as a Rabobank export.
+#[tauri::command]
+pub async fn dry_run_import(
+    content: String,
+    config: Option<CsvImportConfig>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<DryRunReport> {
+    alerts::guard_command(&app, &state, RateLimited::CsvParsing).await?;
+    let pool = state.db.lock().await.get_pool().await?;
+    let parsed = match config {
+        Some(config) => parse_mapped_csv(&content, config).await,
+        None => parse_rabobank_csv(content, &load_settings(&pool).await?.currency()).await?,
+    };
+
+    let mut conn = pool.acquire().await?;
+
+    dry_run(&mut conn, parsed).await
+}
 
-    Ok(true)
+/// Parse with the parser for the detected bank; anything unrecognized is read
+/// as a Rabobank export so its errors point at the missing columns.
+pub(crate) async fn parse_bank_csv(content: String, currency: &Currency) -> AppResult<CsvImportResult> {
+    match detect_bank_format(&content) {
+        Some(BankFormat::AbnAmro) => parse_abnamro_csv(content).await,
+        Some(BankFormat::Rabobank) | None => parse_rabobank_csv(content, currency).await,
+    }
 }
 
-async fn parse_rabobank_csv(content: String) -> AppResult<CsvImportResult> {
+async fn parse_rabobank_csv(content: String, currency: &Currency) -> AppResult<CsvImportResult> {
     let mut rdr = ReaderBuilder::new()
         .delimiter(b';')
         .has_headers(true)
+        .flexible(true)
         .from_reader(Cursor::new(content));
 
     let mut transactions = Vec::new();
     let mut errors = Vec::new();
     let mut warnings = Vec::new();
     let mut total_rows = 0;
+    let mut skipped_footer_rows = 0;
+    let mut seen_hashes = HashSet::new();
 
     let headers = rdr.headers()?.clone();
     let header_map: HashMap<String, usize> = headers
@@ -98,28 +883,40 @@ async fn parse_rabobank_csv(content: String) -> AppResult<CsvImportResult> {
         .map(|(i, h)| (h.trim().to_string(), i))
         .collect();
 
-    for (line_num, result) in rdr.records().enumerate() {
+    let records: Vec<_> = rdr.records().collect();
+
+    // Rows without a valid date at the very end of the file are a summary block, not transactions.
+    // If no row has a valid date at all, the rows are reported as errors instead.
+    let footer_start = records
+        .iter()
+        .rposition(|r| !matches!(r, Ok(record) if !has_valid_date(record, &header_map)))
+        .map_or(records.len(), |last_transaction| last_transaction + 1);
+
+    for (line_num, result) in records.into_iter().enumerate() {
+        if line_num >= footer_start || matches!(&result, Ok(record) if is_labeled_footer(record)) {
+            skipped_footer_rows += 1;
+            continue;
+        }
+
         total_rows += 1;
 
         let record = match result {
             Ok(r) => r,
             Err(e) => {
-                errors.push(format!("Fout op regel {}: {}", line_num + 2, e));
+                errors.push(ImportError {
+                    line: line_num + 2,
+                    raw: None,
+                    field: None,
+                    reason: e.to_string(),
+                });
                 continue;
             }
         };
 
-        match parse_rabobank_record(&record, &header_map, line_num + 2) {
-            Ok(mut transaction) => {
-                // Auto-categorize based on description
-                transaction.category_id = auto_categorize(&transaction.description);
-
+        match parse_rabobank_record(&record, &header_map, line_num + 2, currency) {
+            Ok(transaction) => {
                 // Check for potential duplicates
-                if transactions.iter().any(|t: &crate::models::Transaction| {
-                    t.date == transaction.date
-                    && t.description == transaction.description
-                    && t.amount == transaction.amount
-                }) {
+                if !seen_hashes.insert(import_hash(&transaction)) {
                     warnings.push(format!(
                         "Mogelijke duplicaat gevonden op regel {}: {} ({}: {})",
                         line_num + 2,
@@ -132,7 +929,7 @@ async fn parse_rabobank_csv(content: String) -> AppResult<CsvImportResult> {
                 transactions.push(transaction);
             }
             Err(e) => {
-                errors.push(format!("Fout op regel {}: {}", line_num + 2, e));
+                errors.push(e);
             }
         }
     }
@@ -150,14 +947,56 @@ async fn parse_rabobank_csv(content: String) -> AppResult<CsvImportResult> {
         warnings,
         total_rows,
         imported_rows,
+        skipped_footer_rows,
+    })
+}
+
+/// Whether the record's date column holds a parseable date.
+fn has_valid_date(record: &StringRecord, header_map: &HashMap<String, usize>) -> bool {
+    ["Datum", "datum"]
+        .iter()
+        .find_map(|header| header_map.get(*header))
+        .and_then(|idx| record.get(*idx))
+        .and_then(|value| parse_dutch_date(value.trim()))
+        .is_some()
+}
+
+/// Whether the record is an explicit totals/balance line as appended by some bank exports.
+fn is_labeled_footer(record: &StringRecord) -> bool {
+    let label = record
+        .iter()
+        .map(|field| field.trim())
+        .find(|field| !field.is_empty())
+        .unwrap_or("")
+        .to_lowercase();
+
+    ["totaal", "saldo", "beginsaldo", "eindsaldo"]
+        .iter()
+        .any(|prefix| label.starts_with(prefix))
+}
+
+/// Parse a date in one of the formats used by Dutch bank exports.
+fn parse_dutch_date(value: &str) -> Option<DateTime<Utc>> {
+    let formats = ["%d-%m-%Y", "%d/%m/%Y", "%Y-%m-%d", "%d-%m-%y"];
+
+    formats.iter().find_map(|format| {
+        NaiveDate::parse_from_str(value, format).ok().map(|naive_date| {
+            DateTime::from_naive_utc_and_offset(naive_date.and_hms_opt(12, 0, 0).unwrap(), Utc)
+        })
     })
 }
 
+/// Bank of a Dutch counterparty IBAN, when it is one of the supported banks.
+pub(crate) fn counterparty_bank(iban: Option<&str>) -> Option<String> {
+    iban.and_then(spaarapp_shared::bank_name_for_dutch_iban).map(str::to_string)
+}
+
 fn parse_rabobank_record(
     record: &StringRecord,
     header_map: &HashMap<String, usize>,
     line_num: usize,
-) -> AppResult<Transaction> {
+    currency: &Currency,
+) -> Result<Transaction, ImportError> {
     // Extract fields using flexible header matching
     let get_field = |headers: &[&str]| {
         for header in headers {
@@ -176,44 +1015,30 @@ fn parse_rabobank_record(
     let bedrag_str = get_field(&["Bedrag", "bedrag"]);
     let mutatiesoort = get_field(&["MutatieSoort", "Mutatie"]);
     let mededelingen = get_field(&["Mededelingen", "Mededeling"]);
+    // Not in bank exports; added by our own Rabobank-style export
+    let categorie = get_field(&["Categorie"]);
 
     // Parse date (DD-MM-YYYY format)
     let date = if datum_str.is_empty() {
-        return Err(anyhow::anyhow!("Datum is leeg op regel {}", line_num).into());
+        return Err(ImportError::new(line_num, record, Some("Datum"), "Datum is leeg"));
     } else {
-        // Try different date formats
-        let formats = ["%d-%m-%Y", "%d/%m/%Y", "%Y-%m-%d", "%d-%m-%y"];
-        let mut parsed_date = None;
-
-        for format in &formats {
-            if let Ok(naive_date) = NaiveDate::parse_from_str(datum_str, format) {
-                parsed_date = Some(DateTime::from_naive_utc_and_offset(
-                    naive_date.and_hms_opt(12, 0, 0).unwrap(),
-                    Utc,
-                ));
-                break;
-            }
-        }
-
-        parsed_date.ok_or_else(|| {
-            anyhow::anyhow!("Ongeldige datum formaat: {} op regel {}", datum_str, line_num)
+        parse_dutch_date(datum_str).ok_or_else(|| {
+            ImportError::new(line_num, record, Some("Datum"), format!("Ongeldige datum formaat: {}", datum_str))
         })?
     };
 
-    // Parse amount (handle Dutch decimal separator and currency)
-    let amount_clean = bedrag_str
-        .replace("€", "")
-        .replace(".", "")
-        .replace(",", ".")
-        .trim()
-        .to_string();
-
-    let amount = if amount_clean.is_empty() || amount_clean == "0" {
-        return Err(anyhow::anyhow!("Bedrag is ongeldig: {} op regel {}", bedrag_str, line_num).into());
-    } else {
-        Decimal::from_str(&amount_clean).map_err(|_| {
-            anyhow::anyhow!("Kan bedrag niet parseren: {} op regel {}", bedrag_str, line_num)
-        })?
+    // Parse amount with the separators and symbol of the configured currency
+    let amount = match currency.parse_amount(bedrag_str) {
+        _ if bedrag_str.is_empty() => {
+            return Err(ImportError::new(line_num, record, Some("Bedrag"), format!("Bedrag is ongeldig: {}", bedrag_str)));
+        }
+        Some(amount) if amount.is_zero() => {
+            return Err(ImportError::new(line_num, record, Some("Bedrag"), format!("Bedrag is ongeldig: {}", bedrag_str)));
+        }
+        Some(amount) => amount,
+        None => {
+            return Err(ImportError::new(line_num, record, Some("Bedrag"), format!("Kan bedrag niet parseren: {}", bedrag_str)));
+        }
     };
 
     // Determine transaction type
@@ -255,13 +1080,15 @@ fn parse_rabobank_record(
         description,
         amount: amount.abs(),
         date,
-        category_id: None,
+        // A category name, resolved to the category when the import is stored
+        category_id: Some(categorie.to_string()).filter(|name| !name.is_empty()),
         account_number: Some(rekening.to_string()),
         account_holder: Some(tegenrekening.to_string()),
+        counterparty_bank: counterparty_bank(Some(tegenrekening)),
         transaction_type,
         balance_after: None,
         notes: if !mededelingen.is_empty() { Some(mededelingen.to_string()) } else { None },
-        tags: serde_json::to_string(&extract_tags(&naam_omschrijving, &mutatiesoort, &mededelingen)).unwrap_or_default(),
+        tags: extract_tags(naam_omschrijving, mutatiesoort, mededelingen),
         is_recurring: is_recurring_transaction(&naam_omschrijving, &mutatiesoort),
         recurring_frequency: detect_recurring_frequency(&naam_omschrijving),
         created_at: now,
@@ -269,69 +1096,685 @@ fn parse_rabobank_record(
     })
 }
 
-fn auto_categorize(description: &str) -> Option<String> {
-    let desc_lower = description.to_lowercase();
+/// Number of tab-separated fields on an ABN AMRO export line: account number,
+/// currency, transaction date, start balance, end balance, interest date,
+/// amount and description.
+const ABNAMRO_FIELDS: usize = 8;
 
-    // Common Dutch keywords for categories
-    let categories: &[(&str, &[&str])] = &[
-        ("supermarkt", &["albert heijn", "jumbo", "plus", "dirk", "c1000", "vomar", "dekamarkt", "ekoplaza"]),
-        ("boodschappen", &["ah", "picnic", "gorillas", "flinck", "crisp"]),
-        ("restaurant", &["restaurant", "cafe", "bar", "eetcafe", "lunch", "diner"]),
-        ("fastfood", &["mcdonald", "bk", "burger king", "kfc", "subway", "dominos"]),
-        ("woning", &["huur", "hypotheek", "energie", "gas", "elektra", "water", "vve"]),
-        ("verzekering", &["verzekering", "inz", "cz", "menzis", "aegon", "nn"]),
-        ("telecom", &["kpn", "vodafone", "t-mobile", "ziggo", "tele2"]),
-        ("transport", &["ns", "ov", "trein", "bus", "tram", "metro", "benzine", "shell", "bp", "total"]),
-        ("internet", &["ziggo", "kpn", "t-mobile", "online"]),
-        ("salaris", &["salaris", "loon", "inkomen"]),
-        ("belasting", &["belasting", "toeslag", "douane"]),
-        ("entertainment", &["netflix", "spotify", "videoland", "bol.com", "amazon", "coolblue"]),
-        ("sport", &["sportschool", "fitness", "gym", "basic-fit"]),
-        ("kleding", &["h&m", "zara", "c&a", "we", "bijenkorf"]),
-        ("gezondheid", &["apotheek", "huisarts", "ziekenhuis", "tandarts"]),
-        ("onderwijs", &["school", "universiteit", "studie", "les", "cursus"]),
-    ];
+/// Keys that open a segment in a `/TRTP/` description blob.
+const TRTP_KEYS: &[&str] = &["TRTP", "CSID", "NAME", "MARF", "REMI", "IBAN", "BIC", "EREF", "ORDP", "BENM", "ID"];
 
-    for (category_id, keywords) in categories {
-        for keyword in keywords.iter() {
-            if desc_lower.contains(keyword) {
-                return Some(category_id.to_string());
-            }
-        }
-    }
+/// Labels that open a field in a `SEPA Overboeking IBAN: ... Naam: ...` blob.
+const SEPA_LABELS: &[&str] = &["IBAN:", "BIC:", "Naam:", "Omschrijving:", "Kenmerk:", "Machtiging:", "Incassant:"];
+
+/// ABN AMRO exports have no header; recognize one by its first line having the
+/// eight tab-separated fields with a `YYYYMMDD` transaction date.
+fn is_abnamro_export(content: &str) -> bool {
+    let Some(first) = content.lines().find(|line| !line.trim().is_empty()) else {
+        return false;
+    };
 
-    None
+    let fields: Vec<&str> = first.split('\t').collect();
+    fields.len() == ABNAMRO_FIELDS && parse_compact_date(fields[2].trim()).is_some()
 }
 
-fn extract_tags(naam: &str, mutatiesoort: &str, mededelingen: &str) -> Vec<String> {
-    let mut tags = Vec::new();
-    let text = format!("{} {} {}", naam, mutatiesoort, mededelingen).to_lowercase();
+/// Parse a `YYYYMMDD` date as used in ABN AMRO exports.
+fn parse_compact_date(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .ok()
+        .map(|naive_date| DateTime::from_naive_utc_and_offset(naive_date.and_hms_opt(12, 0, 0).unwrap(), Utc))
+}
 
-    // Common tags
-    if text.contains("incasso") || text.contains("sepa") {
-        tags.push("automatische incasso".to_string());
-    }
-    if text.contains("ideal") {
-        tags.push("iDEAL".to_string());
-    }
-    if text.contains("pin") {
-        tags.push("pinbetaling".to_string());
-    }
-    if text.contains("online") || text.contains("webshop") {
-        tags.push("online".to_string());
-    }
-    if text.contains("cash") || text.contains("geldautomaat") {
-        tags.push("contant".to_string());
-    }
-    if text.contains("gift") || text.contains("cadeau") {
-        tags.push("geschenk".to_string());
-    }
+/// Counterparty and payment details embedded in an ABN AMRO description blob
+/// or an MT940 `:86:` information field.
+#[derive(Debug, Default, PartialEq)]
+struct PaymentDetails {
+    /// Payment kind, e.g. "SEPA OVERBOEKING" or "BEA"
+    kind: String,
+    iban: Option<String>,
+    name: Option<String>,
+    remittance: Option<String>,
+}
 
-    tags
+/// Value after `marker` up to the first of `next_markers` that follows it.
+fn segment_after(blob: &str, marker: &str, next_markers: &[String]) -> Option<String> {
+    let start = blob.find(marker)? + marker.len();
+    let rest = &blob[start..];
+    let end = next_markers
+        .iter()
+        .filter_map(|next| rest.find(next.as_str()))
+        .min()
+        .unwrap_or(rest.len());
+
+    let value = rest[..end].split_whitespace().collect::<Vec<_>>().join(" ");
+    (!value.is_empty()).then_some(value)
 }
 
-fn is_recurring_transaction(naam: &str, mutatiesoort: &str) -> bool {
-    let text = format!("{} {}", naam, mutatiesoort).to_lowercase();
+/// Details from a description in `/KEY/value` subfields, as in
+/// `/TRTP/SEPA OVERBOEKING/IBAN/.../NAME/.../REMI/...`.
+fn parse_structured_description(blob: &str) -> PaymentDetails {
+    let markers: Vec<String> = TRTP_KEYS.iter().map(|key| format!("/{}/", key)).collect();
+    let field = |key: &str| segment_after(blob, &format!("/{}/", key), &markers);
+
+    PaymentDetails {
+        kind: field("TRTP").unwrap_or_default(),
+        iban: field("IBAN"),
+        name: field("NAME"),
+        remittance: field("REMI"),
+    }
+}
+
+/// Description shown for a transaction with `details`: the counterparty and
+/// what the payment was for, whichever are known.
+fn payment_description(details: &PaymentDetails) -> String {
+    match (&details.name, &details.remittance) {
+        (Some(name), Some(remittance)) => format!("{} - {}", name, remittance),
+        (Some(name), None) => name.clone(),
+        (None, Some(remittance)) => remittance.clone(),
+        (None, None) if !details.kind.is_empty() => details.kind.clone(),
+        (None, None) => "Onbekende transactie".to_string(),
+    }
+}
+
+/// Pull the counterparty out of the three blob layouts ABN AMRO uses:
+/// `/TRTP/.../IBAN/.../NAME/.../REMI/...`, the labeled
+/// `SEPA Overboeking IBAN: ... Naam: ... Omschrijving: ...`, and card payments
+/// `BEA NR:... 01.03.24/12.34 MERCHANT,PAS123`.
+fn parse_abnamro_description(blob: &str) -> PaymentDetails {
+    let blob = blob.trim();
+
+    if blob.starts_with("/TRTP/") {
+        return parse_structured_description(blob);
+    }
+
+    if blob.to_uppercase().starts_with("SEPA") {
+        let markers: Vec<String> = SEPA_LABELS.iter().map(|label| label.to_string()).collect();
+        let field = |label: &str| segment_after(blob, label, &markers);
+        let kind_end = markers.iter().filter_map(|m| blob.find(m.as_str())).min().unwrap_or(blob.len());
+
+        return PaymentDetails {
+            kind: blob[..kind_end].split_whitespace().collect::<Vec<_>>().join(" "),
+            iban: field("IBAN:"),
+            name: field("Naam:"),
+            remittance: field("Omschrijving:"),
+        };
+    }
+
+    if blob.starts_with("BEA") || blob.starts_with("GEA") {
+        // The merchant follows the "dd.mm.yy/hh.mm" timestamp, up to the card number
+        let name = blob
+            .split_once('/')
+            .and_then(|(_, after_date)| after_date.split_once(char::is_whitespace))
+            .and_then(|(_, merchant)| merchant.split(',').next())
+            .map(|merchant| merchant.trim().to_string())
+            .filter(|merchant| !merchant.is_empty());
+
+        return PaymentDetails { kind: blob[..3].to_string(), name, ..Default::default() };
+    }
+
+    PaymentDetails {
+        remittance: Some(blob.split_whitespace().collect::<Vec<_>>().join(" ")).filter(|text| !text.is_empty()),
+        ..Default::default()
+    }
+}
+
+/// Parse an amount from an ABN AMRO export, which writes `-12,50`.
+fn parse_abnamro_amount(value: &str) -> Option<Decimal> {
+    Decimal::from_str(&value.trim().replace('.', "").replace(',', ".")).ok()
+}
+
+async fn parse_abnamro_csv(content: String) -> AppResult<CsvImportResult> {
+    let mut transactions = Vec::new();
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut total_rows = 0;
+    let mut seen_hashes = HashSet::new();
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        total_rows += 1;
+
+        match parse_abnamro_line(line, index + 1) {
+            Ok(transaction) => {
+                if !seen_hashes.insert(import_hash(&transaction)) {
+                    warnings.push(format!(
+                        "Mogelijke duplicaat gevonden op regel {}: {} ({}: {})",
+                        index + 1,
+                        transaction.description,
+                        transaction.date.format("%d-%m-%Y"),
+                        transaction.amount
+                    ));
+                }
+
+                transactions.push(transaction);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    let imported_rows = transactions.len();
+
+    if transactions.is_empty() {
+        warnings.push("Geen geldige transacties gevonden in het CSV-bestand".to_string());
+    }
+
+    Ok(CsvImportResult {
+        transactions,
+        errors,
+        warnings,
+        total_rows,
+        imported_rows,
+        skipped_footer_rows: 0,
+    })
+}
+
+fn parse_abnamro_line(line: &str, line_num: usize) -> Result<Transaction, ImportError> {
+    let error = |field: &str, reason: String| ImportError {
+        line: line_num,
+        raw: Some(line.to_string()),
+        field: Some(field.to_string()),
+        reason,
+    };
+
+    let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+    if fields.len() != ABNAMRO_FIELDS {
+        return Err(ImportError {
+            line: line_num,
+            raw: Some(line.to_string()),
+            field: None,
+            reason: format!("Verwacht {} velden, gevonden: {}", ABNAMRO_FIELDS, fields.len()),
+        });
+    }
+
+    let date = parse_compact_date(fields[2])
+        .ok_or_else(|| error("Transactiedatum", format!("Ongeldige datum formaat: {}", fields[2])))?;
+
+    let amount = parse_abnamro_amount(fields[6])
+        .filter(|amount| !amount.is_zero())
+        .ok_or_else(|| error("Bedrag", format!("Kan bedrag niet parseren: {}", fields[6])))?;
+
+    let balance_after = if fields[4].is_empty() {
+        None
+    } else {
+        Some(
+            parse_abnamro_amount(fields[4])
+                .ok_or_else(|| error("Eindsaldo", format!("Kan saldo niet parseren: {}", fields[4])))?,
+        )
+    };
+
+    let details = parse_abnamro_description(fields[7]);
+
+    let description = payment_description(&details);
+
+    let name = details.name.as_deref().unwrap_or("");
+    let remittance = details.remittance.as_deref().unwrap_or("");
+    let now = Utc::now();
+
+    Ok(Transaction {
+        id: Uuid::new_v4().to_string(),
+        description,
+        amount: amount.abs(),
+        date,
+        category_id: None,
+        account_number: Some(fields[0].to_string()),
+        account_holder: details.iban.clone(),
+        counterparty_bank: counterparty_bank(details.iban.as_deref()),
+        transaction_type: if amount < Decimal::ZERO { "debit" } else { "credit" }.to_string(),
+        balance_after,
+        notes: details.remittance.clone(),
+        tags: extract_tags(name, &details.kind, remittance),
+        is_recurring: is_recurring_transaction(name, &details.kind),
+        recurring_frequency: detect_recurring_frequency(&format!("{} {}", name, remittance)),
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+/// A `:61:` statement line with the `:86:` information that follows it.
+struct Mt940Entry {
+    line: usize,
+    raw: String,
+    info: String,
+}
+
+/// One statement in an MT940 file; a file can hold several, one per account
+/// and day.
+#[derive(Default)]
+struct Mt940Statement {
+    account: Option<String>,
+    opening_balance: Option<Decimal>,
+    entries: Vec<Mt940Entry>,
+}
+
+/// The fields of an MT940 statement as `(tag, line number, value)`. Lines
+/// that don't start a `:tag:` continue the previous field; SWIFT block
+/// markers and the `-` end-of-message line are skipped.
+fn mt940_fields(content: &str) -> Vec<(String, usize, String)> {
+    let mut fields: Vec<(String, usize, String)> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() || line == "-" || line.starts_with('{') || line.starts_with('}') {
+            continue;
+        }
+
+        let tag = line
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| !tag.is_empty() && tag.len() <= 3 && tag.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        match (tag, fields.last_mut()) {
+            (Some((tag, value)), _) => fields.push((tag.to_string(), index + 1, value.to_string())),
+            (None, Some((_, _, value))) => {
+                value.push('\n');
+                value.push_str(line);
+            }
+            (None, None) => {}
+        }
+    }
+
+    fields
+}
+
+/// Parse an MT940 amount, which uses a decimal comma and may end in it (`1500,`).
+fn parse_mt940_amount(value: &str) -> Option<Decimal> {
+    Decimal::from_str(value.replace(',', ".").trim_end_matches('.')).ok()
+}
+
+/// Signed amount of a `:60F:`/`:62F:` balance such as `C240229EUR1000,00`.
+fn parse_mt940_balance(value: &str) -> Option<Decimal> {
+    let mark = value.chars().next()?;
+    let amount = parse_mt940_amount(value.get(10..)?)?;
+    match mark {
+        'C' => Some(amount),
+        'D' => Some(-amount),
+        _ => None,
+    }
+}
+
+/// Signed amount and value date of a `:61:` statement line:
+/// `YYMMDD[MMDD](D|C|RD|RC)[funds code]amount` followed by the transaction
+/// type and references. A reversed debit (RD) is money coming in, a reversed
+/// credit (RC) money going out.
+fn parse_mt940_statement_line(value: &str) -> Result<(DateTime<Utc>, Decimal), (&'static str, String)> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let caps = PATTERN
+        .get_or_init(|| Regex::new(r"^(\d{6})(\d{4})?(RD|RC|D|C)[A-Z]?(\d+,\d{0,2})").unwrap())
+        .captures(value)
+        .ok_or_else(|| ("Transactieregel", format!("Onbekend formaat van :61: regel: {}", value)))?;
+
+    let date = NaiveDate::parse_from_str(&caps[1], "%y%m%d")
+        .ok()
+        .map(|naive_date| DateTime::from_naive_utc_and_offset(naive_date.and_hms_opt(12, 0, 0).unwrap(), Utc))
+        .ok_or_else(|| ("Valutadatum", format!("Ongeldige datum formaat: {}", &caps[1])))?;
+
+    let amount = parse_mt940_amount(&caps[4])
+        .filter(|amount| !amount.is_zero())
+        .ok_or_else(|| ("Bedrag", format!("Kan bedrag niet parseren: {}", &caps[4])))?;
+
+    Ok(match &caps[3] {
+        "D" | "RC" => (date, -amount),
+        _ => (date, amount),
+    })
+}
+
+/// Group MT940 fields into statements, each starting at a `:20:` reference.
+fn mt940_statements(fields: Vec<(String, usize, String)>) -> Vec<Mt940Statement> {
+    let mut statements = vec![Mt940Statement::default()];
+
+    for (tag, line, value) in fields {
+        if tag == "20" && !statements.last().is_some_and(|s| s.account.is_none() && s.entries.is_empty()) {
+            statements.push(Mt940Statement::default());
+        }
+        let statement = statements.last_mut().unwrap();
+
+        match tag.as_str() {
+            // Account as IBAN or bank code/number, optionally followed by the currency
+            "25" => statement.account = value.split_whitespace().next().map(str::to_string),
+            "60F" | "60M" => statement.opening_balance = parse_mt940_balance(value.trim()),
+            "61" => statement.entries.push(Mt940Entry {
+                line,
+                // The optional second line holds supplementary details only
+                raw: value.lines().next().unwrap_or("").trim().to_string(),
+                info: String::new(),
+            }),
+            "86" => {
+                if let Some(entry) = statement.entries.last_mut().filter(|entry| entry.info.is_empty()) {
+                    entry.info = value;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    statements
+}
+
+/// The transaction for a `:61:` entry of `account`; `balance` is the balance
+/// before it and is moved past it.
+fn parse_mt940_entry(
+    entry: &Mt940Entry,
+    account: Option<&str>,
+    balance: &mut Option<Decimal>,
+) -> Result<Transaction, ImportError> {
+    let (date, amount) = parse_mt940_statement_line(&entry.raw).map_err(|(field, reason)| ImportError {
+        line: entry.line,
+        raw: Some(format!(":61:{}", entry.raw)),
+        field: Some(field.to_string()),
+        reason,
+    })?;
+    *balance = balance.map(|b| b + amount);
+
+    // Information lines are wrapped at a fixed width, often mid-word
+    let info = entry.info.replace('\n', "");
+    let details = if info.trim_start().starts_with('/') {
+        parse_structured_description(info.trim())
+    } else {
+        parse_abnamro_description(&info)
+    };
+    let name = details.name.as_deref().unwrap_or("");
+    let remittance = details.remittance.as_deref().unwrap_or("");
+    let now = Utc::now();
+
+    Ok(Transaction {
+        id: Uuid::new_v4().to_string(),
+        description: payment_description(&details),
+        amount: amount.abs(),
+        date,
+        category_id: None,
+        account_number: account.map(str::to_string),
+        account_holder: details.iban.clone(),
+        counterparty_bank: counterparty_bank(details.iban.as_deref()),
+        transaction_type: if amount < Decimal::ZERO { "debit" } else { "credit" }.to_string(),
+        balance_after: *balance,
+        notes: details.remittance.clone(),
+        tags: extract_tags(name, &details.kind, remittance),
+        is_recurring: is_recurring_transaction(name, &details.kind),
+        recurring_frequency: detect_recurring_frequency(&format!("{} {}", name, remittance)),
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+/// Parse an MT940 (SWIFT) statement: every `:61:` line becomes a transaction
+/// on its value date, described by the `:86:` line after it. Structured
+/// `/TRTP/.../NAME/.../REMI/...` information is split into counterparty and
+/// remittance; free text is kept as the description. Balances are carried
+/// forward from the opening balance in `:60F:`.
+fn parse_mt940(content: &str) -> AppResult<CsvImportResult> {
+    let fields = mt940_fields(content);
+    if !fields.iter().any(|(tag, _, _)| tag == "61" || tag == "20") {
+        return Err(AppError::Validation("Bestand is geen MT940-afschrift".to_string()));
+    }
+
+    let mut transactions = Vec::new();
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut total_rows = 0;
+    let mut seen_hashes = HashSet::new();
+
+    for statement in mt940_statements(fields) {
+        let mut balance = statement.opening_balance;
+
+        for entry in &statement.entries {
+            total_rows += 1;
+
+            match parse_mt940_entry(entry, statement.account.as_deref(), &mut balance) {
+                Ok(transaction) => {
+                    if !seen_hashes.insert(import_hash(&transaction)) {
+                        warnings.push(format!(
+                            "Mogelijke duplicaat gevonden op regel {}: {} ({}: {})",
+                            entry.line,
+                            transaction.description,
+                            transaction.date.format("%d-%m-%Y"),
+                            transaction.amount
+                        ));
+                    }
+
+                    transactions.push(transaction);
+                }
+                Err(e) => {
+                    // Without this entry's amount the running balance is unknown
+                    if balance.take().is_some() {
+                        warnings.push(format!(
+                            "Geen saldo vanaf regel {}: die transactie kon niet worden gelezen",
+                            entry.line
+                        ));
+                    }
+                    errors.push(e);
+                }
+            }
+        }
+    }
+
+    let imported_rows = transactions.len();
+
+    if transactions.is_empty() {
+        warnings.push("Geen geldige transacties gevonden in het MT940-bestand".to_string());
+    }
+
+    Ok(CsvImportResult {
+        transactions,
+        errors,
+        warnings,
+        total_rows,
+        imported_rows,
+        skipped_footer_rows: 0,
+    })
+}
+
+/// Currency CAMT.053 entries are imported in; others are skipped.
+const CAMT_CURRENCY: &str = "EUR";
+
+/// The element reached from `node` through child elements named `path`.
+/// Names are matched without their namespace, which differs per CAMT version.
+fn xml_child<'a, 'input>(node: roxmltree::Node<'a, 'input>, path: &[&str]) -> Option<roxmltree::Node<'a, 'input>> {
+    path.iter()
+        .try_fold(node, |node, name| node.children().find(|child| child.has_tag_name(*name)))
+}
+
+/// Trimmed text of the element at `path` below `node`, when not empty.
+fn xml_text(node: roxmltree::Node, path: &[&str]) -> Option<String> {
+    xml_child(node, path)
+        .and_then(|node| node.text())
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+}
+
+/// Name and IBAN of the party at `party` (`Dbtr` or `Cdtr`) in `RltdPties`.
+/// Newer CAMT versions wrap the name in a `Pty` element.
+fn camt_party(related: roxmltree::Node, party: &str) -> (Option<String>, Option<String>) {
+    let name = xml_text(related, &[party, "Nm"]).or_else(|| xml_text(related, &[party, "Pty", "Nm"]));
+    let iban = xml_text(related, &[&format!("{}Acct", party), "Id", "IBAN"]);
+    (name, iban)
+}
+
+/// The transaction for one `Ntry` of a CAMT.053 statement for `account`,
+/// `Ok(None)` when it is not in euros.
+fn parse_camt_entry(
+    entry: roxmltree::Node,
+    account: Option<&str>,
+    line: usize,
+    raw: &str,
+) -> Result<Option<Transaction>, ImportError> {
+    let error = |field: &str, reason: String| ImportError {
+        line,
+        raw: Some(raw.to_string()),
+        field: Some(field.to_string()),
+        reason,
+    };
+
+    let amount_node = xml_child(entry, &["Amt"]).ok_or_else(|| error("Amt", "Bedrag ontbreekt".to_string()))?;
+    if amount_node.attribute("Ccy").is_some_and(|currency| currency != CAMT_CURRENCY) {
+        return Ok(None);
+    }
+    let amount_text = amount_node.text().unwrap_or("").trim();
+    let amount = Decimal::from_str(amount_text)
+        .ok()
+        .filter(|amount| !amount.is_zero())
+        .ok_or_else(|| error("Amt", format!("Kan bedrag niet parseren: {}", amount_text)))?;
+
+    let debit = match xml_text(entry, &["CdtDbtInd"]).as_deref() {
+        Some("DBIT") => true,
+        Some("CRDT") => false,
+        other => return Err(error("CdtDbtInd", format!("Onbekende af/bij-indicator: {}", other.unwrap_or("")))),
+    };
+
+    let date_text = xml_text(entry, &["BookgDt", "Dt"])
+        .or_else(|| xml_text(entry, &["BookgDt", "DtTm"]))
+        .unwrap_or_default();
+    let date = date_text
+        .get(..10)
+        .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+        .map(|naive_date| DateTime::from_naive_utc_and_offset(naive_date.and_hms_opt(12, 0, 0).unwrap(), Utc))
+        .ok_or_else(|| error("BookgDt", format!("Ongeldige datum formaat: {}", date_text)))?;
+
+    // The counterparty is the creditor of a payment and the debtor of a receipt
+    let details_node = xml_child(entry, &["NtryDtls", "TxDtls"]);
+    let (name, iban) = details_node
+        .and_then(|details| xml_child(details, &["RltdPties"]))
+        .map(|related| camt_party(related, if debit { "Cdtr" } else { "Dbtr" }))
+        .unwrap_or_default();
+    let remittance = details_node
+        .and_then(|details| xml_child(details, &["RmtInf"]))
+        .map(|info| {
+            info.children()
+                .filter(|child| child.has_tag_name("Ustrd"))
+                .filter_map(|child| child.text())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+        .or_else(|| details_node.and_then(|details| xml_text(details, &["RmtInf", "Strd", "CdtrRefInf", "Ref"])));
+
+    let details = PaymentDetails {
+        kind: xml_text(entry, &["AddtlNtryInf"]).unwrap_or_default(),
+        iban,
+        name,
+        remittance,
+    };
+    let name = details.name.as_deref().unwrap_or("");
+    let remittance = details.remittance.as_deref().unwrap_or("");
+    let now = Utc::now();
+
+    Ok(Some(Transaction {
+        id: Uuid::new_v4().to_string(),
+        description: payment_description(&details),
+        amount,
+        date,
+        category_id: None,
+        account_number: account.map(str::to_string),
+        account_holder: details.iban.clone(),
+        counterparty_bank: counterparty_bank(details.iban.as_deref()),
+        transaction_type: if debit { "debit" } else { "credit" }.to_string(),
+        balance_after: None,
+        notes: details.remittance.clone(),
+        tags: extract_tags(name, &details.kind, remittance),
+        is_recurring: is_recurring_transaction(name, &details.kind),
+        recurring_frequency: detect_recurring_frequency(&format!("{} {}", name, remittance)),
+        created_at: now,
+        updated_at: now,
+    }))
+}
+
+/// Parse a CAMT.053 (ISO 20022) XML statement: every `Ntry` becomes a
+/// transaction on its booking date, with the counterparty from `RltdPties`
+/// and the remittance information as notes. Entries in another currency than
+/// euros are skipped with a warning rather than imported at the wrong value.
+fn parse_camt053(content: &str) -> AppResult<CsvImportResult> {
+    let document = roxmltree::Document::parse(content)
+        .map_err(|e| AppError::Validation(format!("Ongeldig CAMT.053-bestand: {}", e)))?;
+    let Some(report) = xml_child(document.root_element(), &["BkToCstmrStmt"]) else {
+        return Err(AppError::Validation("Bestand is geen CAMT.053-afschrift".to_string()));
+    };
+
+    let mut transactions = Vec::new();
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut total_rows = 0;
+    let mut seen_hashes = HashSet::new();
+
+    for statement in report.children().filter(|node| node.has_tag_name("Stmt")) {
+        let account = xml_text(statement, &["Acct", "Id", "IBAN"]);
+
+        for entry in statement.children().filter(|node| node.has_tag_name("Ntry")) {
+            total_rows += 1;
+            let line = document.text_pos_at(entry.range().start).row as usize;
+            let raw = content[entry.range()].split_whitespace().collect::<Vec<_>>().join(" ");
+
+            match parse_camt_entry(entry, account.as_deref(), line, &raw) {
+                Ok(Some(transaction)) => {
+                    if !seen_hashes.insert(import_hash(&transaction)) {
+                        warnings.push(format!(
+                            "Mogelijke duplicaat gevonden op regel {}: {} ({}: {})",
+                            line,
+                            transaction.description,
+                            transaction.date.format("%d-%m-%Y"),
+                            transaction.amount
+                        ));
+                    }
+
+                    transactions.push(transaction);
+                }
+                Ok(None) => warnings.push(format!(
+                    "Transactie op regel {} overgeslagen: bedrag in {} in plaats van {}",
+                    line,
+                    xml_child(entry, &["Amt"]).and_then(|amount| amount.attribute("Ccy")).unwrap_or("?"),
+                    CAMT_CURRENCY
+                )),
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+
+    let imported_rows = transactions.len();
+
+    if transactions.is_empty() {
+        warnings.push("Geen geldige transacties gevonden in het CAMT.053-bestand".to_string());
+    }
+
+    Ok(CsvImportResult {
+        transactions,
+        errors,
+        warnings,
+        total_rows,
+        imported_rows,
+        skipped_footer_rows: 0,
+    })
+}
+
+fn extract_tags(naam: &str, mutatiesoort: &str, mededelingen: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let text = format!("{} {} {}", naam, mutatiesoort, mededelingen).to_lowercase();
+
+    // Common tags
+    if text.contains("incasso") || text.contains("sepa") {
+        tags.push("automatische incasso".to_string());
+    }
+    if text.contains("ideal") {
+        tags.push("iDEAL".to_string());
+    }
+    if text.contains("pin") {
+        tags.push("pinbetaling".to_string());
+    }
+    if text.contains("online") || text.contains("webshop") {
+        tags.push("online".to_string());
+    }
+    if text.contains("cash") || text.contains("geldautomaat") {
+        tags.push("contant".to_string());
+    }
+    if text.contains("gift") || text.contains("cadeau") {
+        tags.push("geschenk".to_string());
+    }
+
+    tags
+}
+
+fn is_recurring_transaction(naam: &str, mutatiesoort: &str) -> bool {
+    let text = format!("{} {}", naam, mutatiesoort).to_lowercase();
 
     text.contains("incasso")
         || text.contains("periodiek")
@@ -356,4 +1799,471 @@ fn detect_recurring_frequency(description: &str) -> Option<String> {
     } else {
         None
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+
+    /// [`persist_imported`] with the default settings; fixtures without notes need no key
+    async fn persist(conn: &mut SqliteConnection, transactions: Vec<Transaction>, skip_duplicates: bool) -> AppResult<ImportOutcome> {
+        persist_imported(conn, transactions, skip_duplicates, &Settings::default(), &EncryptionManager::new()).await
+    }
+
+    const HEADER: &str = "Datum;Naam/Omschrijving;Rekening;Tegenrekening;Code;Af/Bij;Bedrag;MutatieSoort;Mededelingen";
+
+    #[test]
+    fn test_identical_import_refused_within_window() {
+        let mut recent = RecentImports::default();
+        let window = Duration::from_secs(5);
+        let start = Instant::now();
+        let key = content_hash([b"Datum;Bedrag\n".as_slice()]);
+
+        assert!(recent.begin(&key, window, start).is_ok());
+        assert!(recent.begin(&key, window, start + Duration::from_secs(2)).is_err());
+        assert!(recent.begin("ander bestand", window, start + Duration::from_secs(2)).is_ok());
+        assert!(recent.begin(&key, window, start + Duration::from_secs(6)).is_ok());
+
+        recent.forget(&key);
+        assert!(recent.begin(&key, window, start + Duration::from_secs(7)).is_ok());
+    }
+
+    #[test]
+    fn test_mt940_statement_is_parsed() {
+        let content = [
+            ":20:940S240301",
+            ":25:NL91ABNA0417164300 EUR",
+            ":28C:00001",
+            ":60F:C240229EUR1000,00",
+            ":61:2403010301D25,50N106NONREF",
+            ":86:/TRTP/SEPA OVERBOEKING/IBAN/NL02RABO0123456789/BIC/RABONL2U/NAME/J. de",
+            " Vries/REMI/Huur fiets maart/EREF/NOTPROVIDED",
+            ":61:240302C1500,N196NONREF",
+            ":86:/TRTP/SEPA OVERBOEKING/NAME/Werkgever BV/REMI/Salaris maart",
+            ":62F:C240302EUR2474,50",
+            "-",
+        ]
+        .join("\n");
+
+        let result = parse_mt940(&content).unwrap();
+        assert_eq!(result.total_rows, 2);
+        assert!(result.errors.is_empty());
+
+        let debit = &result.transactions[0];
+        assert_eq!(debit.transaction_type, "debit");
+        assert_eq!(debit.amount, Decimal::new(2550, 2));
+        assert_eq!(debit.date.format("%Y-%m-%d").to_string(), "2024-03-01");
+        assert_eq!(debit.description, "J. de Vries - Huur fiets maart");
+        assert_eq!(debit.account_number.as_deref(), Some("NL91ABNA0417164300"));
+        assert_eq!(debit.account_holder.as_deref(), Some("NL02RABO0123456789"));
+        assert_eq!(debit.balance_after, Some(Decimal::new(97450, 2)));
+
+        let credit = &result.transactions[1];
+        assert_eq!(credit.transaction_type, "credit");
+        assert_eq!(credit.amount, Decimal::new(1500, 0));
+        assert_eq!(credit.description, "Werkgever BV - Salaris maart");
+        assert_eq!(credit.balance_after, Some(Decimal::new(247450, 2)));
+
+        assert!(parse_mt940("Datum;Bedrag\n01-03-2024;12,50").is_err());
+    }
+
+    #[test]
+    fn test_mt940_drops_balance_after_unreadable_entry() {
+        let content = [
+            ":20:940S240301",
+            ":25:NL91ABNA0417164300 EUR",
+            ":60F:C240229EUR1000,00",
+            ":61:2403010301D25,50N106NONREF",
+            ":86:Fietsenmaker",
+            ":61:2403XXD10,00N106NONREF",
+            ":86:Onleesbaar",
+            ":61:240302C1500,N196NONREF",
+            ":86:Werkgever BV",
+            "-",
+        ]
+        .join("\n");
+
+        let result = parse_mt940(&content).unwrap();
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.transactions[0].balance_after, Some(Decimal::new(97450, 2)));
+        assert_eq!(result.transactions[1].balance_after, None);
+        assert!(result.warnings.iter().any(|w| w.starts_with("Geen saldo vanaf regel 6")));
+    }
+
+    #[test]
+    fn test_camt053_statement_is_parsed() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <BkToCstmrStmt>
+    <GrpHdr><MsgId>CAMT053-20240301</MsgId><CreDtTm>2024-03-02T06:00:00</CreDtTm></GrpHdr>
+    <Stmt>
+      <Id>20240301</Id>
+      <Acct><Id><IBAN>NL91ABNA0417164300</IBAN></Id><Ccy>EUR</Ccy></Acct>
+      <Ntry>
+        <Amt Ccy="EUR">25.50</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><Dt>2024-03-01</Dt></BookgDt>
+        <AddtlNtryInf>SEPA Overboeking</AddtlNtryInf>
+        <NtryDtls><TxDtls>
+          <RltdPties>
+            <Dbtr><Nm>Eigen Naam</Nm></Dbtr>
+            <Cdtr><Nm>Fietsenmaker de Vries</Nm></Cdtr>
+            <CdtrAcct><Id><IBAN>NL02RABO0123456789</IBAN></Id></CdtrAcct>
+          </RltdPties>
+          <RmtInf><Ustrd>Reparatie fiets</Ustrd></RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">1500.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><DtTm>2024-03-02T09:15:00</DtTm></BookgDt>
+        <NtryDtls><TxDtls>
+          <RltdPties><Dbtr><Pty><Nm>Werkgever BV</Nm></Pty></Dbtr></RltdPties>
+          <RmtInf><Ustrd>Salaris maart</Ustrd></RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="USD">40.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <BookgDt><Dt>2024-03-02</Dt></BookgDt>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>"#;
+
+        let result = parse_camt053(content).unwrap();
+        assert_eq!(result.total_rows, 3);
+        assert_eq!(result.imported_rows, 2);
+        assert!(result.errors.is_empty());
+        assert!(result.warnings.iter().any(|w| w.contains("USD")));
+
+        let debit = &result.transactions[0];
+        assert_eq!(debit.transaction_type, "debit");
+        assert_eq!(debit.amount, Decimal::new(2550, 2));
+        assert_eq!(debit.description, "Fietsenmaker de Vries - Reparatie fiets");
+        assert_eq!(debit.account_number.as_deref(), Some("NL91ABNA0417164300"));
+        assert_eq!(debit.account_holder.as_deref(), Some("NL02RABO0123456789"));
+
+        let credit = &result.transactions[1];
+        assert_eq!(credit.transaction_type, "credit");
+        assert_eq!(credit.date.format("%Y-%m-%d").to_string(), "2024-03-02");
+        assert_eq!(credit.description, "Werkgever BV - Salaris maart");
+
+        assert!(parse_camt053("<Document><Other/></Document>").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_abnamro_export_is_parsed() {
+        let content = [
+            "123456789\tEUR\t20240301\t1000,00\t987,50\t20240301\t-12,50\tBEA   NR:XXZZ01   01.03.24/12.34 ALBERT HEIJN 1234,PAS123",
+            "123456789\tEUR\t20240302\t987,50\t1737,50\t20240302\t750,00\t/TRTP/SEPA OVERBOEKING/IBAN/NL91ABNA0417164300/BIC/ABNANL2A/NAME/J Jansen/REMI/Huur maart/EREF/NOTPROVIDED",
+            "123456789\tEUR\t2024-03-03\t1737,50\t1737,50\t20240303\t-1,00\tSEPA Overboeking  IBAN: NL02RABO0123456789  Naam: Test",
+        ]
+        .join("\n");
+
+        assert_eq!(detect_bank_format(&content), Some(BankFormat::AbnAmro));
+        let result = parse_abnamro_csv(content).await.unwrap();
+
+        assert_eq!(result.imported_rows, 2);
+        let card = &result.transactions[0];
+        assert_eq!(card.description, "ALBERT HEIJN 1234");
+        assert_eq!(card.amount, Decimal::new(1250, 2));
+        assert_eq!(card.transaction_type, "debit");
+        assert_eq!(card.balance_after, Some(Decimal::new(98750, 2)));
+
+        let transfer = &result.transactions[1];
+        assert_eq!(transfer.description, "J Jansen - Huur maart");
+        assert_eq!(transfer.account_holder.as_deref(), Some("NL91ABNA0417164300"));
+        assert_eq!(transfer.counterparty_bank.as_deref(), Some("ABN AMRO"));
+        assert_eq!(card.counterparty_bank, None);
+        assert_eq!(transfer.transaction_type, "credit");
+        assert_eq!(transfer.date.format("%Y-%m-%d").to_string(), "2024-03-02");
+
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].line, 3);
+        assert_eq!(result.errors[0].field.as_deref(), Some("Transactiedatum"));
+
+        let labeled = parse_abnamro_description("SEPA Overboeking  IBAN: NL02RABO0123456789  BIC: RABONL2U  Naam: Test BV  Omschrijving: Factuur 12");
+        assert_eq!(labeled.kind, "SEPA Overboeking");
+        assert_eq!(labeled.name.as_deref(), Some("Test BV"));
+        assert_eq!(labeled.remittance.as_deref(), Some("Factuur 12"));
+    }
+
+    #[tokio::test]
+    async fn test_footer_rows_are_skipped() {
+        let content = format!(
+            "{}\n{}\n{}\n{}\n",
+            HEADER,
+            "01-03-2024;Albert Heijn;NL01RABO0123456789;;BA;Af;12,50;Betaalautomaat;",
+            "Totaal;;;;;;12,50;;",
+            ";;;;;;;;Einde overzicht",
+        );
+
+        let result = parse_rabobank_csv(content, &Currency::default()).await.unwrap();
+
+        assert_eq!(result.imported_rows, 1);
+        assert_eq!(result.skipped_footer_rows, 2);
+        assert!(result.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_errors_carry_raw_line_and_field() {
+        let content = format!(
+            "{}\n{}\n{}\n",
+            HEADER,
+            "01-03-2024;Jumbo;NL01RABO0123456789;;BA;Af;abc;Betaalautomaat;",
+            "02-03-2024;Albert Heijn;NL01RABO0123456789;;BA;Af;7,25;Betaalautomaat;",
+        );
+
+        let result = parse_rabobank_csv(content, &Currency::default()).await.unwrap();
+
+        assert_eq!(result.errors.len(), 1);
+        let error = &result.errors[0];
+        assert_eq!(error.line, 2);
+        assert_eq!(error.field.as_deref(), Some("Bedrag"));
+        assert!(error.raw.as_deref().unwrap().starts_with("01-03-2024;Jumbo;"));
+    }
+
+    #[tokio::test]
+    async fn test_reimport_adds_no_rows() {
+        let content = format!(
+            "{}\n{}\n{}\n",
+            HEADER,
+            "01-03-2024;Albert Heijn;NL01RABO0123456789;;BA;Af;12,50;Betaalautomaat;",
+            "02-03-2024;Salaris;NL01RABO0123456789;NL02INGB0001234567;OV;Bij;2500,00;Overschrijving;",
+        );
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+
+        let mut outcomes = Vec::new();
+        for _ in 0..2 {
+            let parsed = parse_rabobank_csv(content.clone(), &Currency::default()).await.unwrap();
+            let mut conn = pool.acquire().await.unwrap();
+            outcomes.push(persist(&mut conn, parsed.transactions, true).await.unwrap());
+        }
+
+        assert_eq!(outcomes[0].imported, 2);
+        assert_eq!(outcomes[1].imported, 0);
+        assert_eq!(outcomes[1].skipped_duplicates, 2);
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_import_encrypts_notes() {
+        let content = format!("{}\n{}\n", HEADER, "01-03-2024;Tandarts;NL01RABO0123456789;;OV;Af;80,00;Overschrijving;Factuur 42");
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let mut encryption = EncryptionManager::new();
+        encryption.set_master_key("test_password", &EncryptionManager::generate_salt().unwrap()).unwrap();
+        let parsed = parse_rabobank_csv(content, &Currency::default()).await.unwrap();
+        persist_imported(&mut conn, parsed.transactions, true, &Settings::default(), &encryption).await.unwrap();
+
+        let notes: String = sqlx::query_scalar("SELECT notes FROM transactions").fetch_one(&mut *conn).await.unwrap();
+        assert!(notes.starts_with(crate::encryption::ENCRYPTED_FIELD_PREFIX));
+        assert_eq!(encryption.decrypt_field(&notes).unwrap(), "Factuur 42");
+
+        drop(conn);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_mapped_import_keeps_unmapped_columns_in_notes() {
+        let mut config = CsvImportConfig::default();
+        config.column_mapping.transaction_type = None;
+        config.column_mapping.balance_after = None;
+        config.unmapped_columns_to_notes = true;
+        let content = "datum,omschrijving,bedrag,rekening,naam,kenmerk\n\
+                       20241112,Bakker,-3.50,NL01RABO0123456789,Bakker BV,Factuur 42\n";
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let settings = Settings { encrypt_notes: false, ..Settings::default() };
+        let parsed = parse_mapped_csv(content, config).await;
+        persist_imported(&mut conn, parsed.transactions, true, &settings, &EncryptionManager::new()).await.unwrap();
+
+        let notes: String = sqlx::query_scalar("SELECT notes FROM transactions").fetch_one(&mut *conn).await.unwrap();
+        assert!(notes.ends_with("\nkenmerk: Factuur 42"));
+
+        drop(conn);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_second_import_reports_every_row_as_duplicate() {
+        let content = format!(
+            "{}\n{}\n{}\n",
+            HEADER,
+            "01-03-2024;Albert Heijn;NL01RABO0123456789;;BA;Af;12,50;Betaalautomaat;",
+            "02-03-2024;Salaris;NL01RABO0123456789;NL02INGB0001234567;OV;Bij;2500,00;Overschrijving;",
+        );
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let first = persist(&mut conn, parse_rabobank_csv(content.clone(), &Currency::default()).await.unwrap().transactions, true)
+            .await
+            .unwrap();
+        assert!(first.duplicates.is_empty());
+        let stored_ids: HashSet<String> = sqlx::query_scalar("SELECT id FROM transactions")
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap()
+            .into_iter()
+            .collect();
+
+        let second = persist(&mut conn, parse_rabobank_csv(content.clone(), &Currency::default()).await.unwrap().transactions, false)
+            .await
+            .unwrap();
+        assert_eq!(second.duplicates.iter().map(|d| d.index).collect::<Vec<_>>(), [0, 1]);
+        assert!(second.duplicates.iter().all(|d| stored_ids.contains(&d.existing_id)));
+        assert_eq!(second.imported, 2);
+
+        // The originals still catch a third import
+        let third = persist(&mut conn, parse_rabobank_csv(content, &Currency::default()).await.unwrap().transactions, true)
+            .await
+            .unwrap();
+        assert_eq!(third.duplicates.len(), 2);
+        assert_eq!((third.imported, third.skipped_duplicates), (0, 2));
+
+        drop(conn);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_statements_import_each_row_once() {
+        let rows = [
+            "01-03-2024;Albert Heijn;NL01RABO0123456789;;BA;Af;12,50;Betaalautomaat;",
+            "02-03-2024;Salaris;NL01RABO0123456789;NL02INGB0001234567;OV;Bij;2500,00;Overschrijving;",
+            "03-03-2024;Kapper;NL01RABO0123456789;;BA;Af;25,00;Betaalautomaat;",
+        ];
+        let statement = |lines: &[&str]| format!("{}\n{}\n", HEADER, lines.join("\n"));
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let march_first_half = parse_rabobank_csv(statement(&rows[..2]), &Currency::default()).await.unwrap();
+        assert_eq!(persist(&mut conn, march_first_half.transactions, true).await.unwrap().imported, 2);
+
+        // The preview shows the overlap by hash before anything is stored
+        let report = dry_run(&mut conn, parse_rabobank_csv(statement(&rows[1..]), &Currency::default()).await.unwrap())
+            .await
+            .unwrap();
+        let stored_hash: String = sqlx::query_scalar("SELECT import_hash FROM transactions WHERE transaction_type = 'credit'")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(report.rows[0].import_hash, stored_hash);
+        assert_eq!(report.rows[0].duplicate, DuplicateStatus::AlreadyImported);
+        assert_eq!(report.rows[1].duplicate, DuplicateStatus::New);
+
+        let march_second_half = parse_rabobank_csv(statement(&rows[1..]), &Currency::default()).await.unwrap();
+        let outcome = persist(&mut conn, march_second_half.transactions, true).await.unwrap();
+        assert_eq!((outcome.imported, outcome.skipped_duplicates), (1, 1));
+
+        let whole_month = parse_rabobank_csv(statement(&rows), &Currency::default()).await.unwrap();
+        let outcome = persist(&mut conn, whole_month.transactions, true).await.unwrap();
+        assert_eq!((outcome.imported, outcome.skipped_duplicates), (0, 3));
+
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(stored, 3);
+
+        drop(conn);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_writes_nothing() {
+        let content = format!(
+            "{}\n{}\n{}\n{}\n",
+            HEADER,
+            "01-03-2024;Albert Heijn;NL01RABO0123456789;;BA;Af;12,50;Betaalautomaat;",
+            "01-03-2024;Albert Heijn;NL01RABO0123456789;;BA;Af;12,50;Betaalautomaat;",
+            "05-03-2024;Kapper;NL01RABO0123456789;;BA;Af;25,00;Betaalautomaat;",
+        );
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let first_line = format!("{}\n{}\n", HEADER, content.lines().nth(1).unwrap());
+        persist(&mut conn, parse_rabobank_csv(first_line, &Currency::default()).await.unwrap().transactions, true).await.unwrap();
+
+        let report = dry_run(&mut conn, parse_rabobank_csv(content, &Currency::default()).await.unwrap()).await.unwrap();
+        let statuses: Vec<DuplicateStatus> = report.rows.iter().map(|r| r.duplicate).collect();
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            statuses,
+            vec![DuplicateStatus::AlreadyImported, DuplicateStatus::InFile, DuplicateStatus::New]
+        );
+        assert_eq!(report.would_import, 1);
+        assert_eq!(report.rows[2].category_source, CategorySource::Uncategorized);
+        assert_eq!(stored, 1);
+
+        drop(conn);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_import_files_dedupes_across_files() {
+        let line = |date: &str, name: &str| format!("{};{};NL01RABO0123456789;;BA;Af;10,00;Betaalautomaat;", date, name);
+        let march = format!("{}\n{}\n{}\n", HEADER, line("01-03-2024", "Jumbo"), line("31-03-2024", "Hema"));
+        let april = format!("{}\n{}\n{}\n", HEADER, line("31-03-2024", "Hema"), line("02-04-2024", "Blokker"));
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let files = vec![
+            ("maart.csv".to_string(), Ok(march)),
+            ("april.csv".to_string(), Ok(april)),
+            ("notities.txt".to_string(), Ok("geen csv".to_string())),
+        ];
+        let mut events = 0;
+        let outcome = import_files(&mut conn, files, &Settings::default(), &EncryptionManager::new(), |_| events += 1).await.unwrap();
+
+        assert_eq!(outcome.imported, 3);
+        assert_eq!(outcome.skipped_duplicates, 1);
+        assert_eq!(outcome.files[1].skipped_duplicates, 1);
+        assert_eq!(outcome.files[2].bank, None);
+        assert_eq!(events, 6);
+
+        drop(conn);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_import_files_reports_amounts_with_too_many_decimals() {
+        let content = format!(
+            "{}\n{}\n{}\n",
+            HEADER,
+            "01-03-2024;Jumbo;NL01RABO0123456789;;BA;Af;10,00;Betaalautomaat;",
+            "02-03-2024;Tankstation;NL01RABO0123456789;;BA;Af;45,129;Betaalautomaat;",
+        );
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let files = vec![("maart.csv".to_string(), Ok(content))];
+        let outcome = import_files(&mut conn, files, &Settings::default(), &EncryptionManager::new(), |_| {}).await.unwrap();
+
+        assert_eq!(outcome.imported, 1);
+        assert_eq!(outcome.files[0].errors.len(), 1);
+        assert!(outcome.files[0].errors[0].reason.starts_with("Tankstation - Betaalautomaat op 02-03-2024"));
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions").fetch_one(&mut *conn).await.unwrap();
+        assert_eq!(stored, 1);
+
+        drop(conn);
+        pool.close().await;
+    }
+}
@@ -0,0 +1,555 @@
+use crate::alerts;
+use crate::audit;
+use crate::commands::accounts::account_from_row;
+use crate::commands::ai_insights::{insight_from_row, load_categories, load_transaction_portions_since};
+use crate::commands::budgets::{budget_from_row, closed_period_from_row, BUDGET_COLUMNS};
+use crate::commands::category_rules::category_rule_from_row;
+use crate::commands::files::check_writable;
+use crate::commands::savings_goals::savings_goal_from_row;
+use crate::commands::settings::load_settings;
+use crate::commands::transactions::{decrypt_notes, transaction_from_row, TRANSACTION_COLUMNS};
+use crate::error::{AppError, AppResult};
+use crate::models::{Budget, Transaction};
+use crate::monthly_report::{report_month_bounds, MonthlyReport};
+use crate::rate_limit::RateLimited;
+use crate::user_export::{ExportFormat, UserDataExport};
+use crate::AppState;
+use tauri::{AppHandle, State};
+use chrono::{DateTime, Duration, Utc};
+use csv::WriterBuilder;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// Exportable transaction fields with their Dutch and English column headers,
+/// in the default column order.
+const EXPORT_COLUMNS: &[(&str, &str, &str)] = &[
+    ("date", "Datum", "Date"),
+    ("description", "Omschrijving", "Description"),
+    ("amount", "Bedrag", "Amount"),
+    ("transaction_type", "Af/Bij", "Debit/Credit"),
+    ("category_id", "Categorie", "Category"),
+    ("account_number", "Rekeningnummer", "Account number"),
+    ("account_holder", "Tegenrekening", "Counter account"),
+    ("counterparty_bank", "Bank tegenrekening", "Counter account bank"),
+    ("balance_after", "Saldo na mutatie", "Balance after"),
+    ("notes", "Notities", "Notes"),
+    ("tags", "Labels", "Tags"),
+    ("is_recurring", "Terugkerend", "Recurring"),
+    ("recurring_frequency", "Frequentie", "Frequency"),
+    ("id", "ID", "ID"),
+];
+
+/// Check the requested columns against the transaction fields; all columns when omitted.
+fn resolve_columns(columns: Option<Vec<String>>) -> AppResult<Vec<&'static str>> {
+    let Some(columns) = columns else {
+        return Ok(EXPORT_COLUMNS.iter().map(|(field, _, _)| *field).collect());
+    };
+
+    if columns.is_empty() {
+        return Err(AppError::Validation("Kies minstens één kolom om te exporteren".to_string()));
+    }
+
+    let mut resolved = Vec::with_capacity(columns.len());
+    for column in &columns {
+        let field = EXPORT_COLUMNS
+            .iter()
+            .map(|(field, _, _)| *field)
+            .find(|field| field == column)
+            .ok_or_else(|| AppError::Validation(format!("Onbekende kolom: {}", column)))?;
+
+        if resolved.contains(&field) {
+            return Err(AppError::Validation(format!("Kolom {} staat er meer dan één keer in", column)));
+        }
+        resolved.push(field);
+    }
+
+    Ok(resolved)
+}
+
+fn header(field: &str, language: &str) -> &'static str {
+    EXPORT_COLUMNS
+        .iter()
+        .find(|(name, _, _)| *name == field)
+        .map(|(_, nl, en)| if language == "en" { *en } else { *nl })
+        .unwrap_or_default()
+}
+
+fn field_value(transaction: &Transaction, field: &str, language: &str) -> String {
+    let decimal = |amount: rust_decimal::Decimal| {
+        let text = amount.round_dp(2).to_string();
+        if language == "en" { text } else { text.replace('.', ",") }
+    };
+
+    match field {
+        "id" => transaction.id.clone(),
+        "date" => {
+            let format = if language == "en" { "%Y-%m-%d" } else { "%d-%m-%Y" };
+            transaction.date.format(format).to_string()
+        }
+        "description" => transaction.description.clone(),
+        "amount" => decimal(transaction.amount),
+        "transaction_type" => transaction.transaction_type.clone(),
+        "category_id" => transaction.category_id.clone().unwrap_or_default(),
+        "account_number" => transaction.account_number.clone().unwrap_or_default(),
+        "account_holder" => transaction.account_holder.clone().unwrap_or_default(),
+        "counterparty_bank" => transaction.counterparty_bank.clone().unwrap_or_default(),
+        "balance_after" => transaction.balance_after.map(decimal).unwrap_or_default(),
+        "notes" => transaction.notes.clone().unwrap_or_default(),
+        "tags" => transaction.tags.join(", "),
+        "is_recurring" => match (transaction.is_recurring, language) {
+            (true, "en") => "yes".to_string(),
+            (false, "en") => "no".to_string(),
+            (true, _) => "ja".to_string(),
+            (false, _) => "nee".to_string(),
+        },
+        "recurring_frequency" => transaction.recurring_frequency.clone().unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Semicolon-separated CSV with the given columns, headers in the given language.
+fn write_csv(transactions: &[Transaction], columns: &[&str], language: &str) -> AppResult<String> {
+    let mut writer = WriterBuilder::new().delimiter(b';').from_writer(Vec::new());
+
+    writer.write_record(columns.iter().map(|field| header(field, language)))?;
+    for transaction in transactions {
+        writer.write_record(columns.iter().map(|field| field_value(transaction, field, language)))?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| AppError::Io(e.into_error()))?;
+    String::from_utf8(bytes).map_err(|e| AppError::Validation(e.to_string()))
+}
+
+/// Columns of a Rabobank CSV export in the order the bank writes them, plus
+/// the category name, which the importer reads back.
+const RABOBANK_HEADERS: &[&str] = &[
+    "Datum",
+    "Naam/Omschrijving",
+    "Rekening",
+    "Tegenrekening",
+    "Code",
+    "Af/Bij",
+    "Bedrag",
+    "MutatieSoort",
+    "Mededelingen",
+    "Categorie",
+];
+
+/// Transactions in the Rabobank CSV layout, with Dutch dates and amounts, so
+/// the file can be imported again. The description goes in Naam/Omschrijving
+/// as a whole; the bank's own code, mutation type and messages are not stored.
+fn write_rabobank_csv(transactions: &[Transaction], category_names: &HashMap<String, String>) -> AppResult<String> {
+    let mut writer = WriterBuilder::new().delimiter(b';').from_writer(Vec::new());
+
+    writer.write_record(RABOBANK_HEADERS)?;
+    for transaction in transactions {
+        let category = transaction
+            .category_id
+            .as_ref()
+            .and_then(|id| category_names.get(id))
+            .map(String::as_str)
+            .unwrap_or_default();
+
+        writer.write_record([
+            transaction.date.format("%d-%m-%Y").to_string().as_str(),
+            &transaction.description,
+            transaction.account_number.as_deref().unwrap_or_default(),
+            transaction.account_holder.as_deref().unwrap_or_default(),
+            "",
+            if transaction.transaction_type == "credit" { "Bij" } else { "Af" },
+            &format!("{:.2}", transaction.amount.abs()).replace('.', ","),
+            "",
+            "",
+            category,
+        ])?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| AppError::Io(e.into_error()))?;
+    String::from_utf8(bytes).map_err(|e| AppError::Validation(e.to_string()))
+}
+
+/// Write the transactions between `date_from` and `date_to` (both optional,
+/// inclusive) to `target_path` as a Rabobank CSV, e.g. a cleaned-up year
+/// for the accountant. Returns the number of transactions written.
+#[tauri::command]
+pub async fn export_transactions_rabobank_csv(
+    date_from: Option<DateTime<Utc>>,
+    date_to: Option<DateTime<Utc>>,
+    target_path: String,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<usize> {
+    alerts::guard_command(&app, &state, RateLimited::Export).await?;
+    check_writable(&app, &state, &target_path).await?;
+    if let (Some(from), Some(to)) = (date_from, date_to) {
+        if from > to {
+            return Err(AppError::Validation("De begindatum ligt na de einddatum".to_string()));
+        }
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+    if !load_settings(&pool).await?.allow_data_export {
+        audit::record(&pool, audit::DATA_EXPORT, "denied", Some("rabobank csv")).await?;
+        return Err(AppError::PermissionDenied("Het exporteren van gegevens staat uit in de instellingen".to_string()));
+    }
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE (? IS NULL OR date >= ?)
+          AND (? IS NULL OR date <= ?)
+        ORDER BY date ASC, created_at ASC
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(date_from)
+    .bind(date_from)
+    .bind(date_to)
+    .bind(date_to)
+    .fetch_all(&pool)
+    .await?;
+    let transactions: Vec<Transaction> = rows.iter().map(transaction_from_row).collect();
+
+    let category_names: HashMap<String, String> = load_categories(&pool)
+        .await?
+        .into_iter()
+        .map(|category| (category.id, category.name))
+        .collect();
+
+    let written = write_rabobank_csv(&transactions, &category_names)
+        .and_then(|csv| std::fs::write(&target_path, csv).map_err(AppError::from));
+
+    match &written {
+        Ok(()) => {
+            let detail = format!("rabobank csv, {} transactions", transactions.len());
+            audit::record(&pool, audit::DATA_EXPORT, "success", Some(&detail)).await?
+        }
+        Err(e) => audit::record(&pool, audit::DATA_EXPORT, "failed", Some(&e.to_string())).await?,
+    }
+
+    written.map(|()| transactions.len())
+}
+
+/// All transactions as CSV, newest first. `columns` picks the fields and their
+/// order (e.g. `["date", "amount", "description"]`) for tools that expect a
+/// fixed layout; without it every column is exported.
+#[tauri::command]
+pub async fn export_transactions_csv(
+    columns: Option<Vec<String>>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<String> {
+    alerts::guard_command(&app, &state, RateLimited::Export).await?;
+    let columns = resolve_columns(columns)?;
+
+    let pool = state.db.lock().await.get_pool().await?;
+    let settings = load_settings(&pool).await?;
+    if !settings.allow_data_export {
+        audit::record(&pool, audit::DATA_EXPORT, "denied", Some("csv")).await?;
+        return Err(AppError::PermissionDenied("Het exporteren van gegevens staat uit in de instellingen".to_string()));
+    }
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        ORDER BY date DESC, created_at DESC
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .fetch_all(&pool)
+    .await?;
+
+    let mut transactions: Vec<Transaction> = rows.iter().map(transaction_from_row).collect();
+
+    if columns.contains(&"notes") {
+        let encryption = state.encryption.lock().await;
+        for transaction in &mut transactions {
+            decrypt_notes(transaction, &encryption)?;
+        }
+    }
+
+    let written = write_csv(&transactions, &columns, &settings.language);
+
+    match &written {
+        Ok(_) => {
+            let detail = format!("csv, {} transactions", transactions.len());
+            audit::record(&pool, audit::DATA_EXPORT, "success", Some(&detail)).await?
+        }
+        Err(e) => audit::record(&pool, audit::DATA_EXPORT, "failed", Some(&e.to_string())).await?,
+    }
+
+    written
+}
+
+async fn collect_user_data(pool: &SqlitePool, state: &State<'_, AppState>) -> AppResult<UserDataExport> {
+    let settings = load_settings(pool).await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        ORDER BY date, created_at
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+    let mut transactions: Vec<Transaction> = rows.iter().map(transaction_from_row).collect();
+    {
+        let encryption = state.encryption.lock().await;
+        for transaction in &mut transactions {
+            decrypt_notes(transaction, &encryption)?;
+        }
+    }
+
+    let budgets = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        ORDER BY name
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    let insights = sqlx::query(
+        r#"
+        SELECT
+            id, insight_type, title, description, impact, actionable,
+            action_suggestions, confidence_score, is_read, is_dismissed, created_at
+        FROM financial_insights
+        ORDER BY created_at
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let accounts = sqlx::query(
+        r#"
+        SELECT id, iban, name, default_category_id, is_default, bank, currency, created_at, updated_at
+        FROM accounts
+        ORDER BY iban
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let budget_periods = sqlx::query(
+        "SELECT id, budget_id, amount, start_date, end_date, created_at FROM budget_periods ORDER BY budget_id, start_date"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let category_rules = sqlx::query(
+        r#"
+        SELECT id, match_text, match_type, category_id, priority, created_at
+        FROM category_rules
+        ORDER BY priority DESC, created_at, id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let savings_goals = sqlx::query(
+        r#"
+        SELECT id, name, target_amount, current_amount, target_date,
+               linked_category_id, created_at, updated_at
+        FROM savings_goals
+        ORDER BY target_date, name
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(UserDataExport {
+        exported_at: Utc::now(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        settings,
+        accounts: accounts.iter().map(account_from_row).collect(),
+        categories: load_categories(pool).await?,
+        budgets: budgets.iter().map(budget_from_row).collect(),
+        budget_periods: budget_periods.iter().map(closed_period_from_row).collect(),
+        category_rules: category_rules.iter().map(category_rule_from_row).collect(),
+        savings_goals: savings_goals.iter().map(savings_goal_from_row).collect(),
+        transactions,
+        insights: insights.iter().map(insight_from_row).collect(),
+        audit_log: audit::entries(pool, None, 0).await?,
+    })
+}
+
+/// Write everything stored about the user to `target_path` (GDPR art. 20):
+/// one JSON document, a zip with a CSV file per table, or a PDF summary.
+/// With `redact_pii` personal data of others (names, IBANs, phone numbers,
+/// email addresses) is masked in transactions. Every attempt is recorded in
+/// the audit log.
+#[tauri::command]
+pub async fn export_user_data(
+    format: String,
+    target_path: String,
+    redact_pii: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<()> {
+    alerts::guard_command(&app, &state, RateLimited::Export).await?;
+    check_writable(&app, &state, &target_path).await?;
+    let format = ExportFormat::parse(&format)?;
+    let pool = state.db.lock().await.get_pool().await?;
+
+    if !load_settings(&pool).await?.allow_data_export {
+        audit::record(&pool, audit::DATA_EXPORT, "denied", None).await?;
+        return Err(AppError::PermissionDenied("Het exporteren van gegevens staat uit in de instellingen".to_string()));
+    }
+
+    // Exports allowed per hour, whatever their outcome; the same limit that raises an alert
+    let limit = state.alert_monitor.lock().await.thresholds().data_export_attempts_per_hour;
+    let attempts = audit::count_since(&pool, audit::DATA_EXPORT, Utc::now() - Duration::hours(1)).await?;
+    if attempts >= i64::from(limit) {
+        audit::record(&pool, audit::DATA_EXPORT, "rate_limited", None).await?;
+        return Err(AppError::PermissionDenied(
+            "Te veel exportpogingen in het afgelopen uur; probeer het later opnieuw".to_string()
+        ));
+    }
+
+    let mut detail = format!("{:?}", format);
+    let written = match collect_user_data(&pool, &state).await {
+        Ok(mut export) => {
+            if redact_pii.unwrap_or(false) {
+                let report = export.redact_pii();
+                detail = format!("{}, redacted {}", detail, report);
+            }
+            export
+                .render(format)
+                .and_then(|bytes| std::fs::write(&target_path, bytes).map_err(AppError::from))
+        }
+        Err(e) => Err(e),
+    };
+
+    match &written {
+        Ok(()) => audit::record(&pool, audit::DATA_EXPORT, "success", Some(&detail)).await?,
+        Err(e) => audit::record(&pool, audit::DATA_EXPORT, "failed", Some(&e.to_string())).await?,
+    }
+
+    written
+}
+
+/// Write a printable PDF overview of one month to `target_path`: income,
+/// expenses, net savings, spending per category, budget status and the top
+/// insights, in the language from the settings. A month without transactions
+/// gives a report that says so.
+#[tauri::command]
+pub async fn generate_monthly_report_pdf(
+    year: i32,
+    month: u32,
+    target_path: String,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<()> {
+    alerts::guard_command(&app, &state, RateLimited::Export).await?;
+    check_writable(&app, &state, &target_path).await?;
+    let (start, _) = report_month_bounds(year, month)?;
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let settings = load_settings(&pool).await?;
+    if !settings.allow_data_export {
+        audit::record(&pool, audit::DATA_EXPORT, "denied", Some("monthly report")).await?;
+        return Err(AppError::PermissionDenied("Het exporteren van gegevens staat uit in de instellingen".to_string()));
+    }
+
+    let portions = load_transaction_portions_since(&pool, start).await?;
+    let categories = load_categories(&pool).await?;
+    let budgets: Vec<Budget> = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM budgets
+        WHERE is_active = TRUE
+        "#,
+        BUDGET_COLUMNS
+    ))
+    .fetch_all(&pool)
+    .await?
+    .iter()
+    .map(budget_from_row)
+    .collect();
+
+    let report = MonthlyReport::build(year, month, &settings, &portions, &categories, &budgets).await?;
+    let written = std::fs::write(&target_path, report.to_pdf(Utc::now())).map_err(AppError::from);
+
+    let detail = format!("monthly report {}-{:02}", year, month);
+    match &written {
+        Ok(()) => audit::record(&pool, audit::DATA_EXPORT, "success", Some(&detail)).await?,
+        Err(e) => audit::record(&pool, audit::DATA_EXPORT, "failed", Some(&e.to_string())).await?,
+    }
+
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_columns_in_requested_order() {
+        let date = Utc.with_ymd_and_hms(2024, 3, 5, 12, 0, 0).unwrap();
+        let transaction = Transaction {
+            description: "Albert Heijn; filiaal 12".to_string(),
+            tags: vec!["boodschappen".to_string()],
+            ..Transaction::test("debit", Decimal::new(-4250, 2), date)
+        };
+
+        let columns = resolve_columns(Some(vec!["amount".to_string(), "date".to_string(), "description".to_string()]))
+            .unwrap();
+        let csv = write_csv(&[transaction], &columns, "nl").unwrap();
+
+        assert_eq!(csv, "Bedrag;Datum;Omschrijving\n-42,50;05-03-2024;\"Albert Heijn; filiaal 12\"\n");
+        assert_eq!(resolve_columns(None).unwrap().len(), EXPORT_COLUMNS.len());
+        assert!(resolve_columns(Some(vec!["wachtwoord".to_string()])).is_err());
+        assert!(resolve_columns(Some(vec!["date".to_string(), "date".to_string()])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rabobank_export_round_trips_through_import() {
+        let transaction = |description: &str, amount: i64, transaction_type: &str, category_id: Option<&str>| {
+            let date = Utc.with_ymd_and_hms(2024, 3, 5, 12, 0, 0).unwrap();
+            Transaction {
+                description: description.to_string(),
+                category_id: category_id.map(str::to_string),
+                account_number: Some("NL01RABO0123456789".to_string()),
+                account_holder: Some("NL02INGB0001234567".to_string()),
+                ..Transaction::test(transaction_type, Decimal::new(amount, 2), date)
+            }
+        };
+        let exported = vec![
+            transaction("Albert Heijn; filiaal \"Centrum\"", 123456, "debit", Some("c1")),
+            transaction("Werkgever BV", 250000, "credit", None),
+        ];
+        let names = HashMap::from([("c1".to_string(), "Boodschappen".to_string())]);
+
+        let csv = write_rabobank_csv(&exported, &names).unwrap();
+        assert!(csv.contains("05-03-2024;\"Albert Heijn; filiaal \"\"Centrum\"\"\";NL01RABO0123456789;NL02INGB0001234567;;Af;1234,56;;;Boodschappen\n"));
+
+        let imported = crate::commands::csv_import::parse_bank_csv(csv, &spaarapp_shared::Currency::default())
+            .await
+            .unwrap();
+        assert!(imported.errors.is_empty());
+        assert_eq!(imported.transactions.len(), exported.len());
+        for (before, after) in exported.iter().zip(&imported.transactions) {
+            assert_eq!(after.date, before.date);
+            assert_eq!(after.description, before.description);
+            assert_eq!(after.amount, before.amount);
+            assert_eq!(after.transaction_type, before.transaction_type);
+            assert_eq!(after.account_number, before.account_number);
+            assert_eq!(after.account_holder, before.account_holder);
+            assert_eq!(after.category_id.as_deref(), before.category_id.as_ref().map(|id| names[id].as_str()));
+        }
+    }
+}
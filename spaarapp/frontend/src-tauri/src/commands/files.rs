@@ -1,20 +1,148 @@
-use crate::error::AppResult;
-// File dialog functionality will be handled by tauri-plugin-dialog
+use crate::alerts::SECURITY_CONFIG_FILE;
+use crate::commands::key_rotation::previous_config_path;
+use crate::encryption::ENCRYPTION_CONFIG_FILE;
+use crate::error::{AppError, AppResult};
+use crate::paths;
+use crate::AppState;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::DialogExt;
 
+/// Folders commands may read files from and write files to: the app's data
+/// folder and the user's Documents and Downloads folders, where bank exports
+/// usually end up.
+fn allowed_roots(app: &AppHandle) -> Vec<PathBuf> {
+    let path = app.path();
+    [path.app_data_dir(), path.document_dir(), path.download_dir()]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Files no command may overwrite, wherever they are: the database with its
+/// WAL and shared-memory files, and the encryption config (with the copy a key
+/// switch keeps) and security config.
+pub(crate) fn protected_files(database_path: &str) -> Vec<PathBuf> {
+    vec![
+        PathBuf::from(database_path),
+        PathBuf::from(format!("{}-wal", database_path)),
+        PathBuf::from(format!("{}-shm", database_path)),
+        PathBuf::from(ENCRYPTION_CONFIG_FILE),
+        previous_config_path(Path::new(ENCRYPTION_CONFIG_FILE)),
+        PathBuf::from(SECURITY_CONFIG_FILE),
+    ]
+}
+
+/// Refuse to read `path` unless it lies in one of the allowed folders or the
+/// user picked it in the file dialog.
+pub(crate) async fn check_readable(app: &AppHandle, state: &AppState, path: &str) -> AppResult<()> {
+    let picked = match Path::new(path).canonicalize() {
+        Ok(path) => state.picked_files.lock().await.contains(&path),
+        Err(_) => false,
+    };
+
+    if !picked && !paths::is_within(Path::new(path), &allowed_roots(app)) {
+        return Err(AppError::PermissionDenied(format!(
+            "{} ligt buiten de mappen die SpaarApp mag lezen",
+            path
+        )));
+    }
+    Ok(())
+}
+
+/// Refuse to write `path` unless it lies in one of the allowed folders and is
+/// none of the app's own files.
+pub(crate) async fn check_writable(app: &AppHandle, state: &AppState, path: &str) -> AppResult<()> {
+    let database_path = state.db.lock().await.path().to_string();
+
+    check_writable_in(path, &allowed_roots(app), &protected_files(&database_path))
+}
+
+fn check_writable_in(path: &str, roots: &[PathBuf], protected: &[PathBuf]) -> AppResult<()> {
+    if paths::is_one_of(Path::new(path), protected) {
+        return Err(AppError::PermissionDenied(format!(
+            "{} is een bestand van SpaarApp zelf en mag niet worden overschreven",
+            path
+        )));
+    }
+    if !paths::is_writable_within(Path::new(path), roots) {
+        return Err(AppError::PermissionDenied(format!(
+            "{} ligt buiten de mappen waarin SpaarApp mag schrijven",
+            path
+        )));
+    }
+    Ok(())
+}
+
+/// Read a UTF-8 text file from one of the allowed folders.
 #[tauri::command]
-pub async fn read_file(_path: String) -> AppResult<String> {
-    // TODO: Implement file reading
-    todo!("Implement file reading")
+pub async fn read_file(path: String, app: AppHandle, state: State<'_, AppState>) -> AppResult<String> {
+    check_readable(&app, &state, &path).await?;
+
+    Ok(std::fs::read_to_string(&path)?)
 }
 
+/// Write a text file into one of the allowed folders.
 #[tauri::command]
-pub async fn write_file(_path: String, _content: String) -> AppResult<bool> {
-    // TODO: Implement file writing
-    todo!("Implement file writing")
+pub async fn write_file(path: String, content: String, app: AppHandle, state: State<'_, AppState>) -> AppResult<bool> {
+    let database_path = state.db.lock().await.path().to_string();
+
+    write_file_in(&path, &content, &allowed_roots(&app), &protected_files(&database_path))
+}
+
+fn write_file_in(path: &str, content: &str, roots: &[PathBuf], protected: &[PathBuf]) -> AppResult<bool> {
+    check_writable_in(path, roots, protected)?;
+
+    std::fs::write(path, content)?;
+    Ok(true)
 }
 
+/// Let the user pick a CSV file; `None` when the dialog is cancelled. The
+/// picked file may then be read even outside the allowed folders.
 #[tauri::command]
-pub async fn select_file() -> AppResult<Option<String>> {
-    // TODO: Implement file selection dialog
-    Ok(None)
-}
\ No newline at end of file
+pub async fn select_file(app: AppHandle, state: State<'_, AppState>) -> AppResult<Option<String>> {
+    let (sender, picked) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("Kies een CSV-bestand")
+        .add_filter("CSV-bestanden", &["csv"])
+        .pick_file(move |file| {
+            let _ = sender.send(file);
+        });
+
+    let Some(path) = picked.await.ok().flatten().and_then(|file| file.into_path().ok()) else {
+        return Ok(None);
+    };
+
+    if let Ok(canonical) = path.canonicalize() {
+        state.picked_files.lock().await.insert(canonical);
+    }
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+
+    #[test]
+    fn test_write_file_refuses_the_apps_own_files() {
+        let db = TestDatabase::locked();
+        let current_dir = std::env::current_dir().unwrap();
+        // Even with the database folder and the working directory allowed
+        let roots = vec![db.dir.clone(), current_dir.clone()];
+        let protected = protected_files(db.path());
+
+        for path in [db.path().to_string(), format!("{}-wal", db.path()), ENCRYPTION_CONFIG_FILE.to_string()] {
+            assert!(matches!(
+                write_file_in(&path, "overschreven", &roots, &protected),
+                Err(AppError::PermissionDenied(_))
+            ));
+        }
+        let spelled_out = current_dir.join(ENCRYPTION_CONFIG_FILE);
+        assert!(write_file_in(spelled_out.to_str().unwrap(), "overschreven", &roots, &protected).is_err());
+
+        let export = db.dir.join("export.csv");
+        assert!(write_file_in(export.to_str().unwrap(), "datum;bedrag", &roots, &protected).unwrap());
+    }
+}
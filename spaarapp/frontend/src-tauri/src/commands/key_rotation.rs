@@ -0,0 +1,313 @@
+use crate::audit;
+use crate::commands::backup::with_suffix;
+use crate::database::{self, Database};
+use crate::encryption::{
+    decode_salt, load_encryption_config, save_encryption_config, EncryptionConfig, EncryptionManager,
+    ENCRYPTED_FIELD_PREFIX, ENCRYPTION_CONFIG_FILE,
+};
+use crate::error::{AppError, AppResult};
+use crate::AppState;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Utc;
+use sqlx::{Connection, Row, SqliteConnection};
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Shortest passphrase accepted
+const MIN_PASSPHRASE_LENGTH: usize = 12;
+
+pub(crate) fn validate_new_passphrase(passphrase: &str) -> AppResult<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Wachtwoord moet minstens {} tekens lang zijn",
+            MIN_PASSPHRASE_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// Database key and field encryption derived from a passphrase, with the
+/// config that records their salts.
+pub(crate) struct PassphraseKeys {
+    pub config: EncryptionConfig,
+    pub database_key: String,
+    pub fields: EncryptionManager<'static>,
+}
+
+/// Keys for `passphrase` with the salts already stored in `config`.
+pub(crate) fn passphrase_keys(passphrase: &str, config: &EncryptionConfig) -> AppResult<PassphraseKeys> {
+    let database_salt = config
+        .database_salt
+        .as_deref()
+        .ok_or_else(|| AppError::Configuration("Geen databasesalt in de versleutelingsconfiguratie".to_string()))?;
+
+    let mut fields = EncryptionManager::new();
+    fields.set_master_key(passphrase, &decode_salt(&config.salt)?)?;
+
+    Ok(PassphraseKeys {
+        database_key: fields.database_key(passphrase, &decode_salt(database_salt)?)?,
+        config: config.clone(),
+        fields,
+    })
+}
+
+/// Keys for `passphrase` with fresh salts, recorded in a copy of `config`
+/// together with a new verifier and today as the rotation date.
+pub(crate) fn new_passphrase_keys(passphrase: &str, config: &EncryptionConfig) -> AppResult<PassphraseKeys> {
+    let manager = EncryptionManager::new();
+    let config = EncryptionConfig {
+        salt: BASE64.encode(EncryptionManager::generate_salt()?),
+        database_salt: Some(BASE64.encode(EncryptionManager::generate_salt()?)),
+        passphrase_verifier: Some(manager.passphrase_verifier(passphrase)?),
+        last_key_rotation: Some(Utc::now()),
+        ..config.clone()
+    };
+    passphrase_keys(passphrase, &config)
+}
+
+/// Re-encrypt every encrypted field on `conn` from the key of `from` to the
+/// key of `to`. Returns the number of values re-encrypted.
+pub(crate) async fn reencrypt_fields(
+    conn: &mut SqliteConnection,
+    from: &EncryptionManager<'_>,
+    to: &EncryptionManager<'_>,
+) -> AppResult<usize> {
+    let rows = sqlx::query("SELECT id, notes FROM transactions WHERE notes LIKE ?")
+        .bind(format!("{}%", ENCRYPTED_FIELD_PREFIX))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for row in &rows {
+        let notes: String = row.get("notes");
+        sqlx::query("UPDATE transactions SET notes = ? WHERE id = ?")
+            .bind(to.encrypt_field(&from.decrypt_field(&notes)?)?)
+            .bind(row.get::<String, _>("id"))
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(rows.len())
+}
+
+/// Where the config in use before a key switch is kept until the switched
+/// database has replaced the old one.
+pub(crate) fn previous_config_path(config_path: &Path) -> PathBuf {
+    with_suffix(config_path, ".prev")
+}
+
+/// Store `config`, keeping the current config at [`previous_config_path`]
+/// so unlocking still works if the database is never replaced.
+pub(crate) fn stage_config(config: &EncryptionConfig, config_path: &Path) -> AppResult<EncryptionConfig> {
+    let previous_config = load_encryption_config(config_path)?;
+    save_encryption_config(&previous_config, previous_config_path(config_path))?;
+    save_encryption_config(config, config_path)?;
+    Ok(previous_config)
+}
+
+/// Move the open database and its encrypted fields over to `keys`, and
+/// store `keys.config`.
+///
+/// All work happens on a snapshot: its fields are re-encrypted, it is
+/// re-keyed and it must open with the new key before it replaces the
+/// database in a single rename. Until then the database is untouched and
+/// still opens with the old key; should the app stop in between, unlocking
+/// falls back to the previous config.
+pub(crate) async fn switch_keys(
+    db: &mut Database,
+    encryption: &mut EncryptionManager<'static>,
+    config_path: &Path,
+    keys: PassphraseKeys,
+) -> AppResult<()> {
+    let staged = with_suffix(Path::new(db.path()), ".rekey");
+    let _ = std::fs::remove_file(&staged);
+
+    let prepared = async {
+        db.snapshot_to(&staged).await?;
+
+        let mut conn = database::connect_file(&staged, db.key()).await?;
+        let mut tx = conn.begin().await?;
+        reencrypt_fields(&mut tx, encryption, &keys.fields).await?;
+        tx.commit().await?;
+        database::rekey(&mut conn, &keys.database_key).await?;
+        conn.close().await?;
+
+        database::file_schema_version(&staged, &keys.database_key).await
+    }
+    .await;
+    if let Err(e) = prepared {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e);
+    }
+
+    let previous_config = stage_config(&keys.config, config_path)?;
+
+    if let Err(e) = db.replace_with(&staged, &keys.database_key).await {
+        // The database may not have been swapped; keep the salts it needs
+        let _ = save_encryption_config(&previous_config, config_path);
+        let _ = std::fs::remove_file(previous_config_path(config_path));
+        let _ = std::fs::remove_file(&staged);
+        return Err(e);
+    }
+    let _ = std::fs::remove_file(previous_config_path(config_path));
+
+    *encryption = keys.fields;
+    Ok(())
+}
+
+/// Re-key the database and the encrypted fields from `old_passphrase` to
+/// `new_passphrase`, with fresh salts.
+pub(crate) async fn rotate_key(
+    db: &mut Database,
+    encryption: &mut EncryptionManager<'static>,
+    config_path: &Path,
+    old_passphrase: &str,
+    new_passphrase: &str,
+) -> AppResult<()> {
+    let config = load_encryption_config(config_path)?;
+    let verifier = config
+        .passphrase_verifier
+        .as_deref()
+        .ok_or_else(|| AppError::Validation("Er is nog geen wachtwoord ingesteld".to_string()))?;
+
+    if !encryption.verify_passphrase(old_passphrase, verifier)? {
+        return Err(AppError::PermissionDenied("Huidig wachtwoord is onjuist".to_string()));
+    }
+    validate_new_passphrase(new_passphrase)?;
+    if new_passphrase == old_passphrase {
+        return Err(AppError::Validation("Nieuw wachtwoord moet verschillen van het huidige".to_string()));
+    }
+
+    let keys = new_passphrase_keys(new_passphrase, &config)?;
+    switch_keys(db, encryption, config_path, keys).await
+}
+
+/// Replace the passphrase, and with it the keys of the database and of
+/// encrypted fields. Backups made before the rotation are restored with the
+/// old passphrase.
+#[tauri::command]
+pub async fn rotate_encryption_key(
+    old_password: String,
+    new_password: String,
+    state: State<'_, AppState>
+) -> AppResult<()> {
+    let mut db = state.db.lock().await;
+    let mut encryption = state.encryption.lock().await;
+
+    let result = rotate_key(
+        &mut db,
+        &mut encryption,
+        Path::new(ENCRYPTION_CONFIG_FILE),
+        &old_password,
+        &new_password,
+    )
+    .await;
+
+    let pool = db.get_pool().await?;
+    match &result {
+        Ok(()) => audit::record(&pool, audit::KEY_ROTATION, "success", None).await?,
+        Err(AppError::PermissionDenied(_)) => audit::record(&pool, audit::KEY_ROTATION, "denied", None).await?,
+        Err(e) => audit::record(&pool, audit::KEY_ROTATION, "failed", Some(&e.to_string())).await?,
+    }
+
+    result
+}
+
+/// Warn in the audit log when the encryption key is older than its
+/// configured rotation period. Run on every unlock.
+pub async fn audit_key_age(db: &Database, config: &EncryptionConfig) -> AppResult<()> {
+    if !config.rotation_due(Utc::now()) {
+        return Ok(());
+    }
+
+    let detail = match config.last_key_rotation {
+        Some(rotated) => format!(
+            "Sleutel is {} dagen oud, rotatie na {} dagen",
+            (Utc::now() - rotated).num_days(),
+            config.key_rotation_days
+        ),
+        None => "Sleutel is nooit geroteerd".to_string(),
+    };
+    tracing::warn!("Encryption key overdue for rotation: {}", detail);
+
+    audit::record(&db.get_pool().await?, audit::KEY_ROTATION_DUE, "warning", Some(&detail)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::unlock::{set_initial_passphrase, unlock_with};
+    use crate::database::TestDatabase;
+
+    #[tokio::test]
+    async fn test_rotation_reencrypts_fields_and_keeps_old_key_on_refusal() {
+        let mut db = TestDatabase::locked();
+        let config_path = db.dir.join("encryption.json");
+        let old_passphrase = "het oude lange wachtwoord";
+        let new_passphrase = "een veel langer nieuw wachtwoord";
+
+        let mut encryption = EncryptionManager::new();
+        set_initial_passphrase(&mut db, &mut encryption, &config_path, old_passphrase).await.unwrap();
+        let config = load_encryption_config(&config_path).unwrap();
+
+        let pool = db.pool().await;
+        sqlx::query(
+            "INSERT INTO transactions (id, description, amount, date, transaction_type, notes) VALUES ('t1', 'Tandarts', '80.00', ?, 'debit', ?)"
+        )
+        .bind(Utc::now())
+        .bind(encryption.encrypt_field("Controle januari").unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let refused = rotate_key(&mut db, &mut encryption, &config_path, "geraden", new_passphrase).await;
+        assert!(matches!(refused, Err(AppError::PermissionDenied(_))));
+        assert_eq!(load_encryption_config(&config_path).unwrap().salt, config.salt);
+
+        rotate_key(&mut db, &mut encryption, &config_path, old_passphrase, new_passphrase).await.unwrap();
+
+        let rotated_config = load_encryption_config(&config_path).unwrap();
+        assert_ne!(rotated_config.salt, config.salt);
+        assert_ne!(rotated_config.database_salt, config.database_salt);
+        assert_eq!(db.key(), passphrase_keys(new_passphrase, &rotated_config).unwrap().database_key);
+
+        let pool = db.pool().await;
+        let notes: String = sqlx::query_scalar("SELECT notes FROM transactions WHERE id = 't1'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(encryption.decrypt_field(&notes).unwrap(), "Controle januari");
+
+        let reloaded = passphrase_keys(new_passphrase, &rotated_config).unwrap().fields;
+        assert_eq!(reloaded.decrypt_field(&notes).unwrap(), "Controle januari");
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_unlock_after_crash_before_database_swap() {
+        let mut db = TestDatabase::locked();
+        let config_path = db.dir.join("encryption.json");
+        let old_passphrase = "het oude lange wachtwoord";
+        let new_passphrase = "een veel langer nieuw wachtwoord";
+
+        let mut encryption = EncryptionManager::new();
+        set_initial_passphrase(&mut db, &mut encryption, &config_path, old_passphrase).await.unwrap();
+        let config = load_encryption_config(&config_path).unwrap();
+        assert!(!previous_config_path(&config_path).exists());
+
+        // The new config is stored, then the app stops before the database is replaced
+        let keys = new_passphrase_keys(new_passphrase, &config).unwrap();
+        stage_config(&keys.config, &config_path).unwrap();
+        db.get_pool().await.unwrap().close().await;
+
+        assert!(previous_config_path(&config_path).exists());
+
+        let mut restarted = Database::locked(db.path());
+        unlock_with(&mut restarted, &mut encryption, &config_path, old_passphrase).await.unwrap();
+        assert_eq!(load_encryption_config(&config_path).unwrap().salt, config.salt);
+        assert!(!previous_config_path(&config_path).exists());
+
+        restarted.get_pool().await.unwrap().close().await;
+    }
+}
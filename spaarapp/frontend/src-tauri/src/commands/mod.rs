@@ -4,5 +4,14 @@ pub mod budgets;
 pub mod csv_import;
 pub mod ai_insights;
 pub mod settings;
+pub mod tags;
+pub mod accounts;
+pub mod export;
 pub mod files;
-pub mod app;
\ No newline at end of file
+pub mod app;
+pub mod backup;
+pub mod category_rules;
+pub mod savings_goals;
+pub mod key_rotation;
+pub mod unlock;
+pub mod splits;
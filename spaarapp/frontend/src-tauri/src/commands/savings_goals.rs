@@ -0,0 +1,280 @@
+use crate::ai_insights::AIInsightEngine;
+use crate::commands::ai_insights::{load_budget_recommendations, load_transactions_since, store_insight};
+use crate::commands::settings::load_settings;
+use crate::database::get_decimal;
+use crate::error::{AppError, AppResult};
+use crate::commands::transactions::{transaction_from_row, TRANSACTION_COLUMNS};
+use crate::goals::{self, GoalFundingPlan, GoalProgress, RoundupSimulation, ROUNDUP_AMOUNTS, SAVINGS_RATE_DAYS};
+use crate::models::SavingsGoal;
+use crate::AppState;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use tauri::State;
+use uuid::Uuid;
+
+pub(crate) fn savings_goal_from_row(row: &SqliteRow) -> SavingsGoal {
+    SavingsGoal {
+        id: row.get("id"),
+        name: row.get("name"),
+        target_amount: get_decimal(row, "target_amount"),
+        current_amount: get_decimal(row, "current_amount"),
+        target_date: row.get("target_date"),
+        linked_category_id: row.get("linked_category_id"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+async fn load_goal(pool: &SqlitePool, id: &str) -> AppResult<Option<SavingsGoal>> {
+    let row = sqlx::query(
+        r#"
+        SELECT id, name, target_amount, current_amount, target_date,
+               linked_category_id, created_at, updated_at
+        FROM savings_goals
+        WHERE id = ?
+        "#
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(savings_goal_from_row))
+}
+
+fn validate_goal(goal: &SavingsGoal, check_target_date: bool) -> AppResult<()> {
+    if goal.name.trim().is_empty() {
+        return Err(AppError::Validation("Naam van het spaardoel is verplicht".to_string()));
+    }
+    if goal.target_amount <= Decimal::ZERO {
+        return Err(AppError::Validation("Doelbedrag moet groter dan 0 zijn".to_string()));
+    }
+    if goal.current_amount < Decimal::ZERO {
+        return Err(AppError::Validation("Gespaard bedrag kan niet negatief zijn".to_string()));
+    }
+    if check_target_date && goal.target_date <= Utc::now() {
+        return Err(AppError::Validation("Streefdatum moet in de toekomst liggen".to_string()));
+    }
+    Ok(())
+}
+
+/// Store a goal progress insight when the goal passed a milestone it had not
+/// reached before. Each milestone is reported once, even if the saved amount
+/// later drops below it and climbs back.
+async fn record_milestone(pool: &SqlitePool, goal: &SavingsGoal) -> AppResult<()> {
+    let milestone = goals::reached_milestone(goal);
+    let previous: i64 = sqlx::query_scalar("SELECT milestone_reached FROM savings_goals WHERE id = ?")
+        .bind(&goal.id)
+        .fetch_one(pool)
+        .await?;
+
+    if i64::from(milestone) <= previous {
+        return Ok(());
+    }
+
+    let settings = load_settings(pool).await?;
+    let insight = AIInsightEngine::with_settings(&settings).goal_progress_insight(goal, milestone);
+    store_insight(pool, &insight).await?;
+
+    sqlx::query("UPDATE savings_goals SET milestone_reached = ? WHERE id = ?")
+        .bind(i64::from(milestone))
+        .bind(&goal.id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_savings_goals(state: State<'_, AppState>) -> AppResult<Vec<SavingsGoal>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT id, name, target_amount, current_amount, target_date,
+               linked_category_id, created_at, updated_at
+        FROM savings_goals
+        ORDER BY target_date
+        "#
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(rows.iter().map(savings_goal_from_row).collect())
+}
+
+#[tauri::command]
+pub async fn get_savings_goal_by_id(
+    id: String,
+    state: State<'_, AppState>
+) -> AppResult<Option<SavingsGoal>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    load_goal(&pool, &id).await
+}
+
+#[tauri::command]
+pub async fn add_savings_goal(
+    mut goal: SavingsGoal,
+    state: State<'_, AppState>
+) -> AppResult<SavingsGoal> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    validate_goal(&goal, true)?;
+
+    if goal.id.is_empty() {
+        goal.id = Uuid::new_v4().to_string();
+    }
+    let now = Utc::now();
+    goal.created_at = now;
+    goal.updated_at = now;
+
+    sqlx::query(
+        r#"
+        INSERT INTO savings_goals (
+            id, name, target_amount, current_amount, target_date,
+            linked_category_id, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&goal.id)
+    .bind(&goal.name)
+    .bind(goal.target_amount.to_string())
+    .bind(goal.current_amount.to_string())
+    .bind(goal.target_date)
+    .bind(&goal.linked_category_id)
+    .bind(goal.created_at)
+    .bind(goal.updated_at)
+    .execute(&pool)
+    .await?;
+
+    record_milestone(&pool, &goal).await?;
+
+    Ok(goal)
+}
+
+#[tauri::command]
+pub async fn update_savings_goal(
+    id: String,
+    mut goal: SavingsGoal,
+    state: State<'_, AppState>
+) -> AppResult<SavingsGoal> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let existing = load_goal(&pool, &id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Spaardoel {} niet gevonden", id)))?;
+
+    // A goal whose target date has passed can still be updated, e.g. to
+    // record the final deposit, as long as the date itself is left alone
+    validate_goal(&goal, goal.target_date != existing.target_date)?;
+
+    goal.id = id.clone();
+    goal.created_at = existing.created_at;
+    goal.updated_at = Utc::now();
+
+    sqlx::query(
+        r#"
+        UPDATE savings_goals SET
+            name = ?, target_amount = ?, current_amount = ?, target_date = ?,
+            linked_category_id = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&goal.name)
+    .bind(goal.target_amount.to_string())
+    .bind(goal.current_amount.to_string())
+    .bind(goal.target_date)
+    .bind(&goal.linked_category_id)
+    .bind(goal.updated_at)
+    .bind(&id)
+    .execute(&pool)
+    .await?;
+
+    record_milestone(&pool, &goal).await?;
+
+    Ok(goal)
+}
+
+#[tauri::command]
+pub async fn delete_savings_goal(id: String, state: State<'_, AppState>) -> AppResult<bool> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM savings_goals WHERE id = ?")
+        .bind(&id)
+        .execute(&pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Progress of a goal with its completion date projected from the savings
+/// rate of the last 90 days.
+#[tauri::command]
+pub async fn get_goal_progress(id: String, state: State<'_, AppState>) -> AppResult<GoalProgress> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let goal = load_goal(&pool, &id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Spaardoel {} niet gevonden", id)))?;
+
+    let now = Utc::now();
+    let transactions = load_transactions_since(&pool, now - Duration::days(SAVINGS_RATE_DAYS)).await?;
+
+    Ok(goals::goal_progress(&goal, &transactions, now))
+}
+
+/// Monthly contribution a goal needs to be reached on time, against the
+/// current savings rate, with overspent categories to trim when that falls short.
+#[tauri::command]
+pub async fn get_goal_funding_plan(goal_id: String, state: State<'_, AppState>) -> AppResult<GoalFundingPlan> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let goal = load_goal(&pool, &goal_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Spaardoel {} niet gevonden", goal_id)))?;
+
+    let now = Utc::now();
+    let transactions = load_transactions_since(&pool, now - Duration::days(SAVINGS_RATE_DAYS)).await?;
+    let recommendations = load_budget_recommendations(&pool).await?;
+
+    Ok(goals::goal_funding_plan(&goal, &transactions, &recommendations, now))
+}
+
+/// What rounding every purchase between `date_from` and `date_to` up to the
+/// next €1 or €5 (`round_to`) would have put into savings, per month.
+#[tauri::command]
+pub async fn simulate_roundup_savings(
+    date_from: DateTime<Utc>,
+    date_to: DateTime<Utc>,
+    round_to: Decimal,
+    state: State<'_, AppState>
+) -> AppResult<RoundupSimulation> {
+    if !ROUNDUP_AMOUNTS.iter().any(|amount| Decimal::from(*amount) == round_to) {
+        return Err(AppError::Validation("Afronden kan op € 1 of € 5".to_string()));
+    }
+    if date_from > date_to {
+        return Err(AppError::Validation("De begindatum ligt na de einddatum".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE transaction_type = 'debit' AND date >= ? AND date <= ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(date_from)
+    .bind(date_to)
+    .fetch_all(&pool)
+    .await?;
+
+    let transactions: Vec<_> = rows.iter().map(transaction_from_row).collect();
+
+    Ok(goals::simulate_roundup(&transactions, date_from, date_to, round_to))
+}
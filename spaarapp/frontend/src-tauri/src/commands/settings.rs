@@ -1,4 +1,5 @@
-use crate::error::AppResult;
+use crate::audit;
+use crate::error::{AppError, AppResult};
 use crate::models::Settings;
 use crate::AppState;
 use tauri::State;
@@ -8,20 +9,47 @@ use sqlx::{self, Row};
 #[tauri::command]
 pub async fn get_settings(state: State<'_, AppState>) -> AppResult<Settings> {
     let pool = state.db.lock().await.get_pool().await?;
+    load_settings(&pool).await
+}
+
+/// Confidence a category rule needs to assign its category, for code that
+/// runs inside a database transaction.
+pub(crate) async fn category_review_threshold(conn: &mut sqlx::SqliteConnection) -> AppResult<f64> {
+    let threshold: Option<f64> = sqlx::query_scalar(
+        "SELECT category_review_threshold FROM settings ORDER BY created_at DESC LIMIT 1"
+    )
+    .fetch_optional(conn)
+    .await?;
 
+    Ok(threshold.unwrap_or_else(|| Settings::default().category_review_threshold))
+}
+
+/// Load the current settings, creating the defaults if none exist yet.
+pub(crate) async fn load_settings(pool: &sqlx::SqlitePool) -> AppResult<Settings> {
     let row = sqlx::query(
         r#"
         SELECT
             id, currency, date_format, theme, language, notifications_enabled,
             auto_categorization_enabled, ai_insights_enabled, budget_alerts_enabled,
             data_retention_days, export_format, encryption_enabled, last_backup,
+            spending_velocity_alert_fraction,
+            restrict_tags_to_vocabulary,
+            insight_retention_days,
+            import_repeat_window_seconds,
+            allow_data_export,
+            audit_retention_days,
+            category_review_threshold,
+            seasonal_anomaly_sigma,
+            cashflow_spend_estimate,
+            budget_month_start_day,
+            encrypt_notes,
             created_at, updated_at
         FROM settings
         ORDER BY created_at DESC
         LIMIT 1
         "#
     )
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?;
 
     let settings = row.map(|r| {
@@ -39,6 +67,17 @@ pub async fn get_settings(state: State<'_, AppState>) -> AppResult<Settings> {
             export_format: r.get("export_format"),
             encryption_enabled: r.get("encryption_enabled"),
             last_backup: r.get("last_backup"),
+            spending_velocity_alert_fraction: r.get("spending_velocity_alert_fraction"),
+            restrict_tags_to_vocabulary: r.get("restrict_tags_to_vocabulary"),
+            insight_retention_days: r.get("insight_retention_days"),
+            import_repeat_window_seconds: r.get("import_repeat_window_seconds"),
+            allow_data_export: r.get("allow_data_export"),
+            audit_retention_days: r.get("audit_retention_days"),
+            category_review_threshold: r.get("category_review_threshold"),
+            seasonal_anomaly_sigma: r.get("seasonal_anomaly_sigma"),
+            cashflow_spend_estimate: r.get("cashflow_spend_estimate"),
+            budget_month_start_day: r.get("budget_month_start_day"),
+            encrypt_notes: r.get("encrypt_notes"),
             created_at: r.get("created_at"),
             updated_at: r.get("updated_at"),
         }
@@ -49,7 +88,7 @@ pub async fn get_settings(state: State<'_, AppState>) -> AppResult<Settings> {
         None => {
             // Create default settings if none exist
             let default_settings = Settings::default();
-            create_settings(default_settings.clone(), &pool).await?;
+            create_settings(default_settings.clone(), pool).await?;
             Ok(default_settings)
         }
     }
@@ -62,6 +101,69 @@ pub async fn update_settings(
 ) -> AppResult<Settings> {
     let pool = state.db.lock().await.get_pool().await?;
 
+    let fraction = settings.spending_velocity_alert_fraction;
+    if !(fraction > 0.0 && fraction < 1.0) {
+        return Err(AppError::Validation(format!(
+            "Drempel voor bestedingstempo moet tussen 0 en 1 liggen, niet {}",
+            fraction
+        )));
+    }
+
+    if settings.insight_retention_days == 0 {
+        return Err(AppError::Validation(
+            "Bewaartermijn voor inzichten moet minstens 1 dag zijn".to_string()
+        ));
+    }
+
+    let threshold = settings.category_review_threshold;
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(AppError::Validation(format!(
+            "Drempel voor automatisch categoriseren moet tussen 0 en 1 liggen, niet {}",
+            threshold
+        )));
+    }
+
+    let sigma = settings.seasonal_anomaly_sigma;
+    if !(sigma > 0.0 && sigma.is_finite()) {
+        return Err(AppError::Validation(format!(
+            "Drempel voor seizoensafwijkingen moet groter dan 0 zijn, niet {}",
+            sigma
+        )));
+    }
+
+    if !matches!(settings.language.as_str(), "nl" | "en") {
+        return Err(AppError::Validation(format!(
+            "Taal moet \"nl\" of \"en\" zijn, niet {}",
+            settings.language
+        )));
+    }
+
+    if !matches!(settings.cashflow_spend_estimate.as_str(), "mean" | "median") {
+        return Err(AppError::Validation(format!(
+            "Schatting voor dagelijkse uitgaven moet \"mean\" of \"median\" zijn, niet {}",
+            settings.cashflow_spend_estimate
+        )));
+    }
+
+    if settings.audit_retention_days == 0 {
+        return Err(AppError::Validation(
+            "Bewaartermijn voor het auditlogboek moet minstens 1 dag zijn".to_string()
+        ));
+    }
+
+    if settings.import_repeat_window_seconds > 300 {
+        return Err(AppError::Validation(
+            "Wachttijd tussen identieke imports mag hoogstens 300 seconden zijn".to_string()
+        ));
+    }
+
+    if !(1..=31).contains(&settings.budget_month_start_day) {
+        return Err(AppError::Validation(format!(
+            "Startdag van de budgetmaand moet tussen 1 en 31 liggen, niet {}",
+            settings.budget_month_start_day
+        )));
+    }
+
     // Update timestamp
     settings.updated_at = Utc::now();
 
@@ -72,7 +174,17 @@ pub async fn update_settings(
             notifications_enabled = ?, auto_categorization_enabled = ?,
             ai_insights_enabled = ?, budget_alerts_enabled = ?,
             data_retention_days = ?, export_format = ?, encryption_enabled = ?,
-            last_backup = ?, updated_at = ?
+            last_backup = ?, spending_velocity_alert_fraction = ?,
+            restrict_tags_to_vocabulary = ?,
+            insight_retention_days = ?,
+            import_repeat_window_seconds = ?,
+            allow_data_export = ?,
+            audit_retention_days = ?,
+            category_review_threshold = ?,
+            seasonal_anomaly_sigma = ?,
+            cashflow_spend_estimate = ?,
+            budget_month_start_day = ?,
+            encrypt_notes = ?, updated_at = ?
         WHERE id = ?
         "#
     )
@@ -88,6 +200,17 @@ pub async fn update_settings(
     .bind(&settings.export_format)
     .bind(settings.encryption_enabled)
     .bind(&settings.last_backup)
+    .bind(settings.spending_velocity_alert_fraction)
+    .bind(settings.restrict_tags_to_vocabulary)
+    .bind(settings.insight_retention_days)
+    .bind(settings.import_repeat_window_seconds)
+    .bind(settings.allow_data_export)
+    .bind(settings.audit_retention_days)
+    .bind(settings.category_review_threshold)
+    .bind(settings.seasonal_anomaly_sigma)
+    .bind(&settings.cashflow_spend_estimate)
+    .bind(settings.budget_month_start_day)
+    .bind(settings.encrypt_notes)
     .bind(settings.updated_at)
     .bind(&settings.id)
     .execute(&pool)
@@ -108,8 +231,19 @@ async fn create_settings(settings: Settings, pool: &sqlx::SqlitePool) -> AppResu
             id, currency, date_format, theme, language, notifications_enabled,
             auto_categorization_enabled, ai_insights_enabled, budget_alerts_enabled,
             data_retention_days, export_format, encryption_enabled, last_backup,
+            spending_velocity_alert_fraction,
+            restrict_tags_to_vocabulary,
+            insight_retention_days,
+            import_repeat_window_seconds,
+            allow_data_export,
+            audit_retention_days,
+            category_review_threshold,
+            seasonal_anomaly_sigma,
+            cashflow_spend_estimate,
+            budget_month_start_day,
+            encrypt_notes,
             created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(&settings.id)
@@ -125,6 +259,17 @@ async fn create_settings(settings: Settings, pool: &sqlx::SqlitePool) -> AppResu
     .bind(&settings.export_format)
     .bind(settings.encryption_enabled)
     .bind(&settings.last_backup)
+    .bind(settings.spending_velocity_alert_fraction)
+    .bind(settings.restrict_tags_to_vocabulary)
+    .bind(settings.insight_retention_days)
+    .bind(settings.import_repeat_window_seconds)
+    .bind(settings.allow_data_export)
+    .bind(settings.audit_retention_days)
+    .bind(settings.category_review_threshold)
+    .bind(settings.seasonal_anomaly_sigma)
+    .bind(&settings.cashflow_spend_estimate)
+    .bind(settings.budget_month_start_day)
+    .bind(settings.encrypt_notes)
     .bind(settings.created_at)
     .bind(settings.updated_at)
     .execute(pool)
@@ -146,5 +291,7 @@ pub async fn reset_settings_to_default(state: State<'_, AppState>) -> AppResult<
     let default_settings = Settings::default();
     create_settings(default_settings.clone(), &pool).await?;
 
+    audit::record(&pool, audit::SETTINGS_RESET, "success", None).await?;
+
     Ok(default_settings)
 }
\ No newline at end of file
@@ -0,0 +1,209 @@
+use crate::commands::budgets::recompute_category_budgets;
+use crate::commands::transactions::fetch_transaction;
+use crate::database::get_decimal;
+use crate::error::{AppError, AppResult};
+use crate::models::TransactionSplit;
+use crate::AppState;
+use tauri::State;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashSet;
+use uuid::Uuid;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{self, Row, SqliteConnection};
+
+/// One part of a transaction as entered by the user.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewSplit {
+    pub category_id: Option<String>,
+    pub amount: Decimal,
+    pub note: Option<String>,
+}
+
+fn split_from_row(row: &SqliteRow) -> TransactionSplit {
+    TransactionSplit {
+        id: row.get("id"),
+        transaction_id: row.get("transaction_id"),
+        category_id: row.get("category_id"),
+        amount: get_decimal(row, "amount"),
+        note: row.get("note"),
+        created_at: row.get("created_at"),
+    }
+}
+
+pub(crate) async fn load_splits(conn: &mut SqliteConnection, transaction_id: &str) -> AppResult<Vec<TransactionSplit>> {
+    let rows = sqlx::query(
+        "SELECT id, transaction_id, category_id, amount, note, created_at FROM transaction_splits WHERE transaction_id = ? ORDER BY created_at, rowid"
+    )
+    .bind(transaction_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.iter().map(split_from_row).collect())
+}
+
+/// Replace the splits of a transaction; no splits books it on its own
+/// category again. The splits must add up to the transaction's amount.
+pub(crate) async fn replace_splits(
+    conn: &mut SqliteConnection,
+    transaction_id: &str,
+    splits: &[NewSplit],
+) -> AppResult<Vec<TransactionSplit>> {
+    let transaction = fetch_transaction(conn, transaction_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Transactie {} niet gevonden", transaction_id)))?;
+
+    if !splits.is_empty() {
+        if splits.iter().any(|split| split.amount.is_zero()) {
+            return Err(AppError::Validation("Een deel van de splitsing mag niet 0 zijn".to_string()));
+        }
+        let total: Decimal = splits.iter().map(|split| split.amount).sum();
+        if total != transaction.amount {
+            return Err(AppError::Validation(format!(
+                "De delen tellen op tot {}, maar de transactie is {}",
+                total, transaction.amount
+            )));
+        }
+        for category_id in splits.iter().filter_map(|split| split.category_id.as_deref()) {
+            let exists: Option<String> = sqlx::query_scalar("SELECT id FROM categories WHERE id = ?")
+                .bind(category_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+            if exists.is_none() {
+                return Err(AppError::NotFound(format!("Categorie {} niet gevonden", category_id)));
+            }
+        }
+    }
+
+    // Budgets of the old and the new categories both change
+    let mut touched_categories: HashSet<Option<String>> = load_splits(conn, transaction_id)
+        .await?
+        .into_iter()
+        .map(|split| split.category_id)
+        .collect();
+    touched_categories.insert(transaction.category_id.clone());
+
+    sqlx::query("DELETE FROM transaction_splits WHERE transaction_id = ?")
+        .bind(transaction_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let now = Utc::now();
+    for split in splits {
+        sqlx::query(
+            "INSERT INTO transaction_splits (id, transaction_id, category_id, amount, note, created_at) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(transaction_id)
+        .bind(&split.category_id)
+        .bind(split.amount.to_string())
+        .bind(split.note.as_deref().map(str::trim).filter(|note| !note.is_empty()))
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+        touched_categories.insert(split.category_id.clone());
+    }
+
+    for category_id in touched_categories {
+        recompute_category_budgets(conn, category_id.as_deref()).await?;
+    }
+
+    load_splits(conn, transaction_id).await
+}
+
+/// Spread a transaction over several categories, e.g. groceries and
+/// household items in one supermarket purchase. Budgets and category
+/// spending then count the split amounts instead of the whole transaction.
+#[tauri::command]
+pub async fn split_transaction(
+    transaction_id: String,
+    splits: Vec<NewSplit>,
+    state: State<'_, AppState>
+) -> AppResult<Vec<TransactionSplit>> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    let splits = replace_splits(&mut tx, &transaction_id, &splits).await?;
+
+    tx.commit().await?;
+
+    Ok(splits)
+}
+
+#[tauri::command]
+pub async fn get_transaction_splits(
+    transaction_id: String,
+    state: State<'_, AppState>
+) -> AppResult<Vec<TransactionSplit>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    load_splits(&mut *pool.acquire().await?, &transaction_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+
+    #[tokio::test]
+    async fn test_splits_replace_category_in_budgets_and_cascade() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let now = Utc::now();
+
+        for id in ["boodschappen", "huishouden"] {
+            sqlx::query("INSERT INTO categories (id, name, created_at, updated_at) VALUES (?, ?, ?, ?)")
+                .bind(id)
+                .bind(format!("Test {}", id))
+                .bind(now)
+                .bind(now)
+                .execute(&mut *conn)
+                .await
+                .unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO budgets (id, name, category_id, amount, period, spent, is_active, start_date, created_at, updated_at) VALUES ('b1', 'Huishouden', 'huishouden', '100.00', 'monthly', '0', TRUE, ?, ?, ?)"
+        )
+        .bind(now - chrono::Duration::days(1))
+        .bind(now)
+        .bind(now)
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO transactions (id, description, amount, date, category_id, transaction_type, created_at, updated_at) VALUES ('t1', 'Albert Heijn', '50.00', ?, 'boodschappen', 'debit', ?, ?)"
+        )
+        .bind(now)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let split = |category_id: &str, amount: i64| NewSplit {
+            category_id: Some(category_id.to_string()),
+            amount: Decimal::from(amount),
+            note: None,
+        };
+        assert!(matches!(
+            replace_splits(&mut conn, "t1", &[split("boodschappen", 30), split("huishouden", 15)]).await,
+            Err(AppError::Validation(_))
+        ));
+
+        let splits = replace_splits(&mut conn, "t1", &[split("boodschappen", 35), split("huishouden", 15)]).await.unwrap();
+        assert_eq!(splits.len(), 2);
+        let budget = sqlx::query("SELECT spent FROM budgets WHERE id = 'b1'")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(get_decimal(&budget, "spent"), Decimal::from(15));
+
+        sqlx::query("DELETE FROM transactions WHERE id = 't1'").execute(&mut *conn).await.unwrap();
+        assert!(load_splits(&mut conn, "t1").await.unwrap().is_empty());
+
+        drop(conn);
+        pool.close().await;
+    }
+}
@@ -0,0 +1,415 @@
+use crate::commands::settings::load_settings;
+use crate::error::{AppError, AppResult};
+use crate::AppState;
+use tauri::State;
+use chrono::{DateTime, Utc};
+use sqlx::{self, Row, SqlitePool};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Tag {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A tag and the number of transactions that carry it.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TagUsage {
+    pub name: String,
+    pub usage_count: i64,
+}
+
+/// Read a transaction's `tags` column into a list.
+///
+/// Some rows hold the JSON array encoded a second time as a JSON string, so
+/// a string that itself contains an array is unwrapped too. A value that is
+/// not JSON at all is kept as a single tag rather than dropped.
+pub(crate) fn parse_tags(raw: &str) -> Vec<String> {
+    match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(serde_json::Value::Array(items)) => dedupe_tags(
+            items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|t| t.to_string())
+                .collect(),
+        ),
+        Ok(serde_json::Value::String(inner)) => parse_tags(&inner),
+        Ok(_) => Vec::new(),
+        Err(_) => dedupe_tags(vec![raw.to_string()]),
+    }
+}
+
+/// Trim tags and drop empty ones and case-insensitive repeats, keeping the first spelling.
+fn dedupe_tags(tags: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !result.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            result.push(tag);
+        }
+    }
+
+    result
+}
+
+/// Replace `source` with `target` in a tag list, without creating a duplicate.
+fn replace_tag(tags: &[String], source: &str, target: &str) -> Vec<String> {
+    dedupe_tags(
+        tags.iter()
+            .map(|t| if t.eq_ignore_ascii_case(source) { target.to_string() } else { t.clone() })
+            .collect(),
+    )
+}
+
+/// Clean up a transaction's tags and, when the vocabulary restriction is on,
+/// reject unknown tags and use the vocabulary's spelling for the known ones.
+pub(crate) async fn resolve_tags(pool: &SqlitePool, tags: Vec<String>) -> AppResult<Vec<String>> {
+    let tags = dedupe_tags(tags);
+
+    if tags.is_empty() || !load_settings(pool).await?.restrict_tags_to_vocabulary {
+        return Ok(tags);
+    }
+
+    let mut resolved = Vec::with_capacity(tags.len());
+    let mut unknown = Vec::new();
+
+    for tag in tags {
+        let known: Option<String> = sqlx::query_scalar("SELECT name FROM tags WHERE name = ?")
+            .bind(&tag)
+            .fetch_optional(pool)
+            .await?;
+
+        match known {
+            Some(name) => resolved.push(name),
+            None => unknown.push(tag),
+        }
+    }
+
+    if !unknown.is_empty() {
+        return Err(AppError::Validation(format!(
+            "Onbekende tags: {}. Voeg ze eerst toe aan de tag-lijst.",
+            unknown.join(", ")
+        )));
+    }
+
+    Ok(resolved)
+}
+
+#[tauri::command]
+pub async fn get_tags(state: State<'_, AppState>) -> AppResult<Vec<Tag>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query("SELECT name, created_at FROM tags ORDER BY name COLLATE NOCASE")
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Tag {
+            name: row.get("name"),
+            created_at: row.get("created_at"),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn add_tag(name: String, state: State<'_, AppState>) -> AppResult<Tag> {
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 50 {
+        return Err(AppError::Validation("Tagnaam moet 1 tot 50 tekens lang zijn".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let tag = Tag { name, created_at: Utc::now() };
+
+    let result = sqlx::query("INSERT OR IGNORE INTO tags (name, created_at) VALUES (?, ?)")
+        .bind(&tag.name)
+        .bind(tag.created_at)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::Validation(format!("Tag '{}' bestaat al", tag.name)));
+    }
+
+    Ok(tag)
+}
+
+/// Remove a tag from the vocabulary. Transactions keep the tag.
+#[tauri::command]
+pub async fn delete_tag(name: String, state: State<'_, AppState>) -> AppResult<bool> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM tags WHERE name = ?")
+        .bind(&name)
+        .execute(&pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Replace a transaction's tags, validated against the vocabulary when that is enabled.
+#[tauri::command]
+pub async fn set_transaction_tags(
+    id: String,
+    tags: Vec<String>,
+    state: State<'_, AppState>
+) -> AppResult<Vec<String>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let tags = resolve_tags(&pool, tags).await?;
+
+    let result = sqlx::query("UPDATE transactions SET tags = ?, updated_at = ? WHERE id = ?")
+        .bind(serde_json::to_string(&tags)?)
+        .bind(Utc::now())
+        .bind(&id)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Transactie {} niet gevonden", id)));
+    }
+
+    Ok(tags)
+}
+
+/// Every tag on a transaction with the number of transactions carrying it,
+/// most used first, followed by vocabulary tags no transaction uses yet.
+pub(crate) async fn tag_usage(pool: &SqlitePool) -> AppResult<Vec<TagUsage>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT MIN(tag) AS name, COUNT(*) AS usage_count
+        FROM transaction_tags
+        GROUP BY tag
+        ORDER BY usage_count DESC, name COLLATE NOCASE
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut usage: Vec<TagUsage> = rows
+        .iter()
+        .map(|row| TagUsage {
+            name: row.get("name"),
+            usage_count: row.get("usage_count"),
+        })
+        .collect();
+
+    let vocabulary: Vec<String> = sqlx::query_scalar("SELECT name FROM tags ORDER BY name COLLATE NOCASE")
+        .fetch_all(pool)
+        .await?;
+    for name in vocabulary {
+        if !usage.iter().any(|tag| tag.name.eq_ignore_ascii_case(&name)) {
+            usage.push(TagUsage { name, usage_count: 0 });
+        }
+    }
+
+    Ok(usage)
+}
+
+#[tauri::command]
+pub async fn get_all_tags(state: State<'_, AppState>) -> AppResult<Vec<TagUsage>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    tag_usage(&pool).await
+}
+
+/// Apply `change` to the tags of transaction `id` and store the result.
+async fn change_transaction_tags(
+    pool: &SqlitePool,
+    id: &str,
+    change: impl FnOnce(Vec<String>) -> Vec<String>,
+) -> AppResult<Vec<String>> {
+    let raw: Option<String> = sqlx::query_scalar("SELECT tags FROM transactions WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    let raw = raw.ok_or_else(|| AppError::NotFound(format!("Transactie {} niet gevonden", id)))?;
+
+    let tags = resolve_tags(pool, change(parse_tags(&raw))).await?;
+
+    sqlx::query("UPDATE transactions SET tags = ?, updated_at = ? WHERE id = ?")
+        .bind(serde_json::to_string(&tags)?)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(tags)
+}
+
+/// Add one tag to a transaction; a tag it already has is left as it is.
+#[tauri::command]
+pub async fn add_transaction_tag(
+    id: String,
+    tag: String,
+    state: State<'_, AppState>
+) -> AppResult<Vec<String>> {
+    if tag.trim().is_empty() {
+        return Err(AppError::Validation("Tagnaam is verplicht".to_string()));
+    }
+    let pool = state.db.lock().await.get_pool().await?;
+
+    change_transaction_tags(&pool, &id, |mut tags| {
+        tags.push(tag);
+        tags
+    })
+    .await
+}
+
+/// Remove one tag (case-insensitive) from a transaction.
+#[tauri::command]
+pub async fn remove_transaction_tag(
+    id: String,
+    tag: String,
+    state: State<'_, AppState>
+) -> AppResult<Vec<String>> {
+    let pool = state.db.lock().await.get_pool().await?;
+
+    change_transaction_tags(&pool, &id, |tags| {
+        tags.into_iter().filter(|t| !t.eq_ignore_ascii_case(tag.trim())).collect()
+    })
+    .await
+}
+
+/// Fold `source` into `target` on every transaction and in the vocabulary.
+/// Returns the number of transactions that changed.
+#[tauri::command]
+pub async fn merge_tags(
+    source: String,
+    target: String,
+    state: State<'_, AppState>
+) -> AppResult<u64> {
+    let source = source.trim().to_string();
+    let target = target.trim().to_string();
+
+    if source.is_empty() || target.is_empty() {
+        return Err(AppError::Validation("Bron- en doeltag zijn verplicht".to_string()));
+    }
+    if source == target {
+        return Err(AppError::Validation("Bron- en doeltag zijn gelijk".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    // LIKE is case-insensitive, so this narrows down to rows that may carry the tag
+    let rows = sqlx::query("SELECT id, tags FROM transactions WHERE tags LIKE ?")
+        .bind(format!("%{}%", source))
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let now = Utc::now();
+    let mut changed = 0;
+
+    for row in rows {
+        let tags = parse_tags(&row.get::<String, _>("tags"));
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(&source)) {
+            continue;
+        }
+
+        sqlx::query("UPDATE transactions SET tags = ?, updated_at = ? WHERE id = ?")
+            .bind(serde_json::to_string(&replace_tag(&tags, &source, &target))?)
+            .bind(now)
+            .bind(row.get::<String, _>("id"))
+            .execute(&mut *tx)
+            .await?;
+        changed += 1;
+    }
+
+    sqlx::query("DELETE FROM tags WHERE name = ?")
+        .bind(&source)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT OR IGNORE INTO tags (name, created_at) VALUES (?, ?)")
+        .bind(&target)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+
+    #[test]
+    fn test_parse_tags_handles_double_encoding() {
+        assert_eq!(parse_tags(r#"["online", "Online", " webshop "]"#), vec!["online", "webshop"]);
+        assert_eq!(parse_tags(r#""[\"iDEAL\"]""#), vec!["iDEAL"]);
+        assert_eq!(parse_tags(" niet json "), vec!["niet json"]);
+        assert_eq!(parse_tags(r#""iDEAL""#), vec!["iDEAL"]);
+        assert!(parse_tags("null").is_empty());
+    }
+
+    #[test]
+    fn test_replace_tag_avoids_duplicates() {
+        let tags = vec!["webshop".to_string(), "online".to_string()];
+
+        assert_eq!(replace_tag(&tags, "webshop", "online"), vec!["online"]);
+        assert_eq!(replace_tag(&tags, "WEBSHOP", "internet"), vec!["internet", "online"]);
+    }
+
+    #[tokio::test]
+    async fn test_tag_table_follows_transactions() {
+        use crate::commands::transactions::{query_transactions, TransactionFilter};
+
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let now = Utc::now();
+
+        for (id, tags) in [
+            ("t1", r#"["iDEAL", "online"]"#),
+            ("t2", r#""[\"pinbetaling\", \"ideal\"]""#),
+            ("t3", "niet json"),
+        ] {
+            sqlx::query(
+                "INSERT INTO transactions (id, description, amount, date, transaction_type, tags, created_at, updated_at) VALUES (?, 'Test', '10.00', ?, 'debit', ?, ?, ?)"
+            )
+            .bind(id)
+            .bind(now)
+            .bind(tags)
+            .bind(now)
+            .bind(now)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let usage = tag_usage(&pool).await.unwrap();
+        assert_eq!(usage[0], TagUsage { name: "iDEAL".to_string(), usage_count: 2 });
+        assert_eq!(usage.len(), 4);
+
+        let by_tags = |tags: &[&str]| TransactionFilter {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        };
+        assert_eq!(query_transactions(&pool, &by_tags(&["IDEAL"])).await.unwrap().total_count, 2);
+        assert_eq!(query_transactions(&pool, &by_tags(&["online", "pinbetaling"])).await.unwrap().total_count, 2);
+        assert_eq!(query_transactions(&pool, &by_tags(&["onbekend"])).await.unwrap().total_count, 0);
+
+        let tags = change_transaction_tags(&pool, "t3", |mut tags| {
+            tags.push("Online".to_string());
+            tags
+        })
+        .await
+        .unwrap();
+        assert_eq!(tags, vec!["niet json", "Online"]);
+        change_transaction_tags(&pool, "t1", |tags| tags.into_iter().filter(|t| t != "online").collect())
+            .await
+            .unwrap();
+        assert_eq!(query_transactions(&pool, &by_tags(&["online"])).await.unwrap().transactions[0].id, "t3");
+
+        sqlx::query("DELETE FROM transactions WHERE id = 't2'").execute(&pool).await.unwrap();
+        let usage = tag_usage(&pool).await.unwrap();
+        assert!(usage.iter().all(|tag| tag.usage_count == 1));
+        assert!(!usage.iter().any(|tag| tag.name == "pinbetaling"));
+
+        pool.close().await;
+    }
+}
@@ -1,57 +1,368 @@
-use crate::error::AppResult;
-use crate::models::{Transaction, TransactionType};
+use crate::alerts;
+use crate::commands::budgets::{check_budget_thresholds, check_spending_velocity, recompute_category_budgets};
+use crate::commands::csv_import::counterparty_bank;
+use crate::commands::settings::load_settings;
+use crate::commands::splits::load_splits;
+use crate::commands::tags::{parse_tags, resolve_tags};
+use crate::database::{get_decimal, get_optional_decimal};
+use crate::duplicates::{self, DoubleCharge, DuplicateCandidate};
+use crate::encryption::EncryptionManager;
+use crate::error::{AppError, AppResult};
+use crate::models::{Settings, Transaction};
 use crate::AppState;
-use tauri::State;
-use chrono::Utc;
+use tauri::{AppHandle, State};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use uuid::Uuid;
-use sqlx::{self, Row};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{self, Row, SqliteConnection, SqlitePool};
 
+/// Optional filters and paging for `get_transactions`. Every field left out
+/// (or `None`) does not filter.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TransactionFilter {
+    /// Page size; all matching transactions when omitted
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub category_id: Option<String>,
+    /// "debit" or "credit"
+    pub transaction_type: Option<String>,
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+    /// Case-insensitive text the description must contain
+    pub search: Option<String>,
+    /// Only transactions booked on this account
+    pub account_id: Option<String>,
+    /// Only transactions with at least one of these tags (case-insensitive)
+    pub tags: Vec<String>,
+}
+
+/// One page of transactions, newest first.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TransactionPage {
+    pub transactions: Vec<Transaction>,
+    /// Number of transactions matching the filter across all pages
+    pub total_count: i64,
+}
+
+/// Results of `search_transactions`, best match first.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TransactionSearch {
+    pub transactions: Vec<Transaction>,
+    /// False while `encrypt_notes` is on: notes stored encrypted can't be
+    /// searched, so only notes written before it was turned on are found
+    pub notes_searchable: bool,
+}
+
+/// Totals for the dashboard, computed in the database.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TransactionStats {
+    pub total_income: Decimal,
+    pub total_expenses: Decimal,
+    /// Income minus expenses
+    pub net: Decimal,
+    pub transaction_count: i64,
+    /// Mean amount over income and expenses alike, rounded to cents
+    pub average_amount: Decimal,
+    /// Largest single debit; `None` without any
+    pub largest_expense: Option<Decimal>,
+}
+
+const FILTER_CONDITIONS: &str = r#"
+    (? IS NULL OR category_id = ?)
+    AND (? IS NULL OR transaction_type = ?)
+    AND (? IS NULL OR date >= ?)
+    AND (? IS NULL OR date <= ?)
+    AND (? IS NULL OR description LIKE ? ESCAPE '\')
+    AND (? IS NULL OR UPPER(REPLACE(account_number, ' ', '')) = (SELECT iban FROM accounts WHERE id = ?))
+    AND (? IS NULL OR id IN (SELECT transaction_id FROM transaction_tags WHERE tag IN (SELECT value FROM json_each(?))))
+"#;
+
+/// Transactions matching `filter`, newest first, and the total number of matches.
+pub(crate) async fn query_transactions(
+    pool: &SqlitePool,
+    filter: &TransactionFilter,
+) -> AppResult<TransactionPage> {
+    if filter.limit == Some(0) {
+        return Err(AppError::Validation("Paginagrootte moet minstens 1 zijn".to_string()));
+    }
+    if let Some(transaction_type) = &filter.transaction_type {
+        if transaction_type != "debit" && transaction_type != "credit" {
+            return Err(AppError::Validation(format!("Onbekend transactietype: {}", transaction_type)));
+        }
+    }
+
+    let search = filter
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| format!("%{}%", s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")));
+    let tags = if filter.tags.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&filter.tags)?)
+    };
+
+    let count_sql = format!("SELECT COUNT(*) FROM transactions WHERE {}", FILTER_CONDITIONS);
+    let total_count: i64 = sqlx::query_scalar(&count_sql)
+        .bind(&filter.category_id)
+        .bind(&filter.category_id)
+        .bind(&filter.transaction_type)
+        .bind(&filter.transaction_type)
+        .bind(filter.date_from)
+        .bind(filter.date_from)
+        .bind(filter.date_to)
+        .bind(filter.date_to)
+        .bind(&search)
+        .bind(&search)
+        .bind(&filter.account_id)
+        .bind(&filter.account_id)
+        .bind(&tags)
+        .bind(&tags)
+        .fetch_one(pool)
+        .await?;
+
+    let page_sql = format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE {}
+        ORDER BY date DESC, created_at DESC
+        LIMIT ? OFFSET ?
+        "#,
+        TRANSACTION_COLUMNS, FILTER_CONDITIONS
+    );
+    let rows = sqlx::query(&page_sql)
+        .bind(&filter.category_id)
+        .bind(&filter.category_id)
+        .bind(&filter.transaction_type)
+        .bind(&filter.transaction_type)
+        .bind(filter.date_from)
+        .bind(filter.date_from)
+        .bind(filter.date_to)
+        .bind(filter.date_to)
+        .bind(&search)
+        .bind(&search)
+        .bind(&filter.account_id)
+        .bind(&filter.account_id)
+        .bind(&tags)
+        .bind(&tags)
+        // SQLite treats a negative limit as no limit
+        .bind(filter.limit.map_or(-1, i64::from))
+        .bind(i64::from(filter.offset.unwrap_or(0)))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(TransactionPage {
+        transactions: rows.iter().map(transaction_from_row).collect(),
+        total_count,
+    })
+}
+
+/// Transactions newest first. Without a filter every transaction is returned on one page.
 #[tauri::command]
-pub async fn get_transactions(state: State<'_, AppState>) -> AppResult<Vec<Transaction>> {
+pub async fn get_transactions(
+    filter: Option<TransactionFilter>,
+    state: State<'_, AppState>
+) -> AppResult<TransactionPage> {
     let pool = state.db.lock().await.get_pool().await?;
 
-    let rows = sqlx::query(
+    let mut page = query_transactions(&pool, &filter.unwrap_or_default()).await?;
+
+    let encryption = state.encryption.lock().await;
+    for transaction in &mut page.transactions {
+        decrypt_notes(transaction, &encryption)?;
+    }
+
+    Ok(page)
+}
+
+/// The columns `transaction_from_row` reads, for the SELECT list of queries
+/// on `transactions` or the `transaction_portions` view.
+pub(crate) const TRANSACTION_COLUMNS: &str = "id, description, amount, date, category_id, account_number, \
+    account_holder, counterparty_bank, transaction_type, balance_after, notes, tags, \
+    is_recurring, recurring_frequency, created_at, updated_at";
+
+pub(crate) fn transaction_from_row(row: &SqliteRow) -> Transaction {
+    Transaction {
+        id: row.get("id"),
+        description: row.get("description"),
+        amount: get_decimal(row, "amount"),
+        date: row.get("date"),
+        category_id: row.get("category_id"),
+        account_number: row.get("account_number"),
+        account_holder: row.get("account_holder"),
+        counterparty_bank: row.get("counterparty_bank"),
+        transaction_type: row.get("transaction_type"),
+        balance_after: get_optional_decimal(row, "balance_after"),
+        notes: row.get("notes"),
+        tags: row.get::<Option<String>, _>("tags").as_deref().map(parse_tags).unwrap_or_default(),
+        is_recurring: row.get("is_recurring"),
+        recurring_frequency: row.get("recurring_frequency"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// Load a single transaction as stored, without decrypting its notes.
+pub(crate) async fn fetch_transaction(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> AppResult<Option<Transaction>> {
+    let row = sqlx::query(&format!(
         r#"
         SELECT
-            id, description, amount, date, category_id, account_number,
-            account_holder, transaction_type, balance_after, notes, tags,
-            is_recurring, recurring_frequency, created_at, updated_at
+            {}
         FROM transactions
-        ORDER BY date DESC, created_at DESC
-        "#
-    )
-    .fetch_all(&pool)
+        WHERE id = ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
     .await?;
 
-    let transactions = rows.into_iter().map(|row| {
-        crate::models::Transaction {
-            id: row.get("id"),
-            description: row.get("description"),
-            amount: row.get::<String, _>("amount").parse().unwrap_or_default(),
-            date: row.get("date"),
-            category_id: row.get("category_id"),
-            account_number: row.get("account_number"),
-            account_holder: row.get("account_holder"),
-            transaction_type: row.get("transaction_type"),
-            balance_after: row.get::<Option<String>, _>("balance_after").map(|s| s.parse().unwrap_or_default()),
-            notes: row.get("notes"),
-            tags: row.get("tags"),
-            is_recurring: row.get("is_recurring"),
-            recurring_frequency: row.get("recurring_frequency"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-        }
-    }).collect();
+    Ok(row.as_ref().map(transaction_from_row))
+}
 
-    Ok(transactions)
+/// Most results `search_transactions` returns, whatever limit is asked for
+const MAX_SEARCH_RESULTS: usize = 500;
+
+/// Turn what the user typed into an FTS5 query: every word must match, as a
+/// prefix, so "tand" finds "Tandarts". Words are quoted so characters like
+/// `-`, `*` or `:` are searched for rather than read as query syntax. `None`
+/// when there is nothing to search for.
+pub(crate) fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+async fn search_transactions_in(pool: &SqlitePool, query: &str, limit: usize) -> AppResult<Vec<Transaction>> {
+    let Some(fts_query) = fts_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    // Ranked in a subquery, so the columns of `transactions` need no prefix
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        JOIN (
+            SELECT transaction_id, bm25(transactions_fts) AS rank
+            FROM transactions_fts
+            WHERE transactions_fts MATCH ?
+        ) matches ON matches.transaction_id = transactions.id
+        ORDER BY matches.rank, date DESC
+        LIMIT ?
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(fts_query)
+    .bind(limit.min(MAX_SEARCH_RESULTS) as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(transaction_from_row).collect())
+}
+
+/// Full-text search over description, notes and account holder, best match
+/// first and newer before older among equally good matches. Words match as
+/// prefixes and accents are ignored. Encrypted notes are not searchable, which
+/// the result reports so the search field can say so.
+#[tauri::command]
+pub async fn search_transactions(
+    query: String,
+    limit: usize,
+    state: State<'_, AppState>
+) -> AppResult<TransactionSearch> {
+    if limit == 0 {
+        return Err(AppError::Validation("Aantal resultaten moet minstens 1 zijn".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let notes_searchable = !notes_encrypted(&load_settings(&pool).await?);
+    let mut transactions = search_transactions_in(&pool, &query, limit).await?;
+
+    let encryption = state.encryption.lock().await;
+    for transaction in &mut transactions {
+        decrypt_notes(transaction, &encryption)?;
+    }
+
+    Ok(TransactionSearch { transactions, notes_searchable })
+}
+
+/// Whether new notes are stored encrypted: `encrypt_notes` is on and
+/// encryption isn't turned off altogether.
+pub(crate) fn notes_encrypted(settings: &Settings) -> bool {
+    settings.encryption_enabled && settings.encrypt_notes
+}
+
+/// Notes as they are stored: encrypted when `notes_encrypted`, otherwise as given.
+pub(crate) fn stored_notes(
+    notes: Option<&str>,
+    settings: &Settings,
+    encryption: &EncryptionManager,
+) -> AppResult<Option<String>> {
+    match notes {
+        Some(n) if notes_encrypted(settings) => Ok(Some(encryption.encrypt_field(n)?)),
+        _ => Ok(notes.map(str::to_string)),
+    }
+}
+
+/// Replace encrypted notes with their plaintext; legacy plaintext notes are left as-is.
+pub(crate) fn decrypt_notes(transaction: &mut Transaction, encryption: &EncryptionManager) -> AppResult<()> {
+    if let Some(notes) = &transaction.notes {
+        transaction.notes = Some(encryption.decrypt_field(notes)?);
+    }
+    Ok(())
+}
+
+/// Reject amounts with more decimals than the currency has (cents for EUR).
+/// The database keeps two decimals, so €10.005 would otherwise be stored rounded.
+pub(crate) fn validate_amount_precision(amount: Decimal, currency: &str) -> AppResult<()> {
+    let allowed = spaarapp_shared::minor_units(currency);
+    if amount.normalize().scale() > allowed {
+        return Err(AppError::Validation(format!(
+            "Bedrag {} heeft meer dan {} decimalen",
+            amount, allowed
+        )));
+    }
+    Ok(())
+}
+
+/// Amount and balance of a transaction must both fit the currency's precision.
+pub(crate) fn validate_transaction_amounts(transaction: &Transaction, currency: &str) -> AppResult<()> {
+    validate_amount_precision(transaction.amount, currency)?;
+    if let Some(balance) = transaction.balance_after {
+        validate_amount_precision(balance, currency)?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn add_transaction(
     mut transaction: Transaction,
+    app: AppHandle,
     state: State<'_, AppState>
 ) -> AppResult<Transaction> {
+    transaction.validate()?;
+
     let pool = state.db.lock().await.get_pool().await?;
+    let settings = load_settings(&pool).await?;
+
+    validate_transaction_amounts(&transaction, &settings.currency)?;
+    let notes = stored_notes(transaction.notes.as_deref(), &settings, &*state.encryption.lock().await)?;
 
     // Generate ID if not provided
     if transaction.id.is_empty() {
@@ -63,16 +374,18 @@ pub async fn add_transaction(
     transaction.created_at = now;
     transaction.updated_at = now;
 
-    // Parse tags from array to JSON string
-    let tags_json = serde_json::to_string(&transaction.tags)?;
+    // Normalize tags and check them against the vocabulary when it is enforced
+    transaction.tags = resolve_tags(&pool, std::mem::take(&mut transaction.tags)).await?;
+
+    let mut tx = pool.begin().await?;
 
     let result = sqlx::query(
         r#"
         INSERT INTO transactions (
             id, description, amount, date, category_id, account_number,
-            account_holder, transaction_type, balance_after, notes, tags,
+            account_holder, counterparty_bank, transaction_type, balance_after, notes, tags,
             is_recurring, recurring_frequency, created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(&transaction.id)
@@ -82,15 +395,16 @@ pub async fn add_transaction(
     .bind(&transaction.category_id)
     .bind(&transaction.account_number)
     .bind(&transaction.account_holder)
+    .bind(&transaction.counterparty_bank)
     .bind(&transaction.transaction_type)
     .bind(transaction.balance_after.map(|d| d.to_string()))
-    .bind(&transaction.notes)
-    .bind(tags_json)
+    .bind(&notes)
+    .bind(serde_json::to_string(&transaction.tags)?)
     .bind(transaction.is_recurring)
     .bind(&transaction.recurring_frequency)
     .bind(transaction.created_at)
     .bind(transaction.updated_at)
-    .execute(&pool)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -99,29 +413,37 @@ pub async fn add_transaction(
         ));
     }
 
-    Ok(transaction)
-}
+    recompute_category_budgets(&mut tx, transaction.category_id.as_deref()).await?;
+    tx.commit().await?;
 
-#[tauri::command]
-pub async fn update_transaction(
-    id: String,
-    mut transaction: Transaction,
-    state: State<'_, AppState>
-) -> AppResult<Transaction> {
-    let pool = state.db.lock().await.get_pool().await?;
+    if let Err(e) = check_spending_velocity(&app, &pool).await {
+        tracing::warn!("Spending velocity check failed: {}", e);
+    }
+    if let Err(e) = check_budget_thresholds(&app, &pool).await {
+        tracing::warn!("Budget threshold check failed: {}", e);
+    }
+    if let Err(e) = alerts::check_new_transaction(&app, &state, &pool, &transaction).await {
+        tracing::warn!("Unusual transaction check failed: {}", e);
+    }
 
-    // Ensure ID matches and update timestamp
-    transaction.id = id.clone();
-    transaction.updated_at = Utc::now();
+    Ok(transaction)
+}
 
-    // Parse tags from array to JSON string
-    let tags_json = serde_json::to_string(&transaction.tags)?;
+/// Write the edited fields of `transaction` over the stored row, with the
+/// counterparty bank derived again from the counterparty IBAN, which is kept
+/// in `account_holder`. Returns false when there is no such row.
+async fn update_stored_transaction(
+    conn: &mut SqliteConnection,
+    transaction: &mut Transaction,
+    notes: Option<&str>,
+) -> AppResult<bool> {
+    transaction.counterparty_bank = counterparty_bank(transaction.account_holder.as_deref());
 
     let result = sqlx::query(
         r#"
         UPDATE transactions SET
             description = ?, amount = ?, date = ?, category_id = ?,
-            account_number = ?, account_holder = ?, transaction_type = ?,
+            account_number = ?, account_holder = ?, counterparty_bank = ?, transaction_type = ?,
             balance_after = ?, notes = ?, tags = ?, is_recurring = ?,
             recurring_frequency = ?, updated_at = ?
         WHERE id = ?
@@ -133,23 +455,77 @@ pub async fn update_transaction(
     .bind(&transaction.category_id)
     .bind(&transaction.account_number)
     .bind(&transaction.account_holder)
+    .bind(&transaction.counterparty_bank)
     .bind(&transaction.transaction_type)
     .bind(transaction.balance_after.map(|d| d.to_string()))
-    .bind(&transaction.notes)
-    .bind(tags_json)
+    .bind(notes)
+    .bind(serde_json::to_string(&transaction.tags)?)
     .bind(transaction.is_recurring)
     .bind(&transaction.recurring_frequency)
     .bind(transaction.updated_at)
-    .bind(&id)
-    .execute(&pool)
+    .bind(&transaction.id)
+    .execute(&mut *conn)
     .await?;
 
-    if result.rows_affected() == 0 {
+    Ok(result.rows_affected() > 0)
+}
+
+#[tauri::command]
+pub async fn update_transaction(
+    id: String,
+    mut transaction: Transaction,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<Transaction> {
+    transaction.validate()?;
+
+    let pool = state.db.lock().await.get_pool().await?;
+    let settings = load_settings(&pool).await?;
+
+    validate_transaction_amounts(&transaction, &settings.currency)?;
+    let notes = stored_notes(transaction.notes.as_deref(), &settings, &*state.encryption.lock().await)?;
+
+    // Ensure ID matches and update timestamp
+    transaction.id = id.clone();
+    transaction.updated_at = Utc::now();
+
+    // Normalize tags and check them against the vocabulary when it is enforced
+    transaction.tags = resolve_tags(&pool, std::mem::take(&mut transaction.tags)).await?;
+
+    let mut tx = pool.begin().await?;
+
+    // The previous version is needed to take its amount back out of the budgets
+    let previous = fetch_transaction(&mut tx, &id).await?;
+    let splits = load_splits(&mut tx, &id).await?;
+    if !splits.is_empty() && previous.as_ref().is_some_and(|p| p.amount != transaction.amount) {
+        return Err(AppError::Validation(
+            "Het bedrag van een gesplitste transactie kan alleen samen met de splitsing worden aangepast".to_string()
+        ));
+    }
+
+    if !update_stored_transaction(&mut tx, &mut transaction, notes.as_deref()).await? {
         return Err(crate::error::AppError::Database(
             sqlx::Error::RowNotFound
         ));
     }
 
+    if let Some(previous) = previous.as_ref().filter(|p| p.category_id != transaction.category_id) {
+        recompute_category_budgets(&mut tx, previous.category_id.as_deref()).await?;
+    }
+    recompute_category_budgets(&mut tx, transaction.category_id.as_deref()).await?;
+    // A new date can move the split amounts in or out of a budget period
+    for split in &splits {
+        recompute_category_budgets(&mut tx, split.category_id.as_deref()).await?;
+    }
+    tx.commit().await?;
+
+    if let Err(e) = check_spending_velocity(&app, &pool).await {
+        tracing::warn!("Spending velocity check failed: {}", e);
+    }
+    if let Err(e) = check_budget_thresholds(&app, &pool).await {
+        tracing::warn!("Budget threshold check failed: {}", e);
+    }
+
     Ok(transaction)
 }
 
@@ -157,11 +533,25 @@ pub async fn update_transaction(
 pub async fn delete_transaction(id: String, state: State<'_, AppState>) -> AppResult<bool> {
     let pool = state.db.lock().await.get_pool().await?;
 
+    let mut tx = pool.begin().await?;
+
+    let existing = fetch_transaction(&mut tx, &id).await?;
+    // Deleted along with the transaction
+    let splits = load_splits(&mut tx, &id).await?;
+
     let result = sqlx::query("DELETE FROM transactions WHERE id = ?")
         .bind(&id)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await?;
 
+    if let Some(existing) = &existing {
+        recompute_category_budgets(&mut tx, existing.category_id.as_deref()).await?;
+    }
+    for split in &splits {
+        recompute_category_budgets(&mut tx, split.category_id.as_deref()).await?;
+    }
+    tx.commit().await?;
+
     Ok(result.rows_affected() > 0)
 }
 
@@ -172,39 +562,531 @@ pub async fn get_transaction_by_id(
 ) -> AppResult<Option<Transaction>> {
     let pool = state.db.lock().await.get_pool().await?;
 
-    let row = sqlx::query(
+    let row = sqlx::query(&format!(
         r#"
         SELECT
-            id, description, amount, date, category_id, account_number,
-            account_holder, transaction_type, balance_after, notes, tags,
-            is_recurring, recurring_frequency, created_at, updated_at
+            {}
         FROM transactions
         WHERE id = ?
-        "#
-    )
+        "#,
+        TRANSACTION_COLUMNS
+    ))
     .bind(&id)
     .fetch_optional(&pool)
     .await?;
 
-    let transaction = row.map(|r| {
-        crate::models::Transaction {
-            id: r.get("id"),
-            description: r.get("description"),
-            amount: r.get::<String, _>("amount").parse().unwrap_or_default(),
-            date: r.get("date"),
-            category_id: r.get("category_id"),
-            account_number: r.get("account_number"),
-            account_holder: r.get("account_holder"),
-            transaction_type: r.get("transaction_type"),
-            balance_after: r.get::<Option<String>, _>("balance_after").map(|s| s.parse().unwrap_or_default()),
-            notes: r.get("notes"),
-            tags: r.get("tags"),
-            is_recurring: r.get("is_recurring"),
-            recurring_frequency: r.get("recurring_frequency"),
-            created_at: r.get("created_at"),
-            updated_at: r.get("updated_at"),
+    let mut transaction = row.as_ref().map(transaction_from_row);
+
+    if let Some(transaction) = &mut transaction {
+        decrypt_notes(transaction, &*state.encryption.lock().await)?;
+    }
+
+    Ok(transaction)
+}
+
+#[tauri::command]
+pub async fn set_transaction_notes(
+    id: String,
+    notes: Option<String>,
+    state: State<'_, AppState>
+) -> AppResult<bool> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let settings = load_settings(&pool).await?;
+
+    // Blank notes clear the field rather than storing an empty string
+    let notes = notes.filter(|n| !n.trim().is_empty());
+    let notes = stored_notes(notes.as_deref(), &settings, &*state.encryption.lock().await)?;
+
+    let result = sqlx::query("UPDATE transactions SET notes = ?, updated_at = ? WHERE id = ?")
+        .bind(&notes)
+        .bind(Utc::now())
+        .bind(&id)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(crate::error::AppError::NotFound(format!("Transactie {} niet gevonden", id)));
+    }
+
+    Ok(true)
+}
+
+/// Change only a transaction's category (`None` to uncategorize), moving its
+/// amount from the old category's budgets to the new one's. Choosing a
+/// category takes the transaction out of the review queue.
+#[tauri::command]
+pub async fn set_transaction_category(
+    id: String,
+    category_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> AppResult<Transaction> {
+    let pool = state.db.lock().await.get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    let previous = fetch_transaction(&mut tx, &id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Transactie {} niet gevonden", id)))?;
+
+    if let Some(category_id) = &category_id {
+        let exists: Option<String> = sqlx::query_scalar("SELECT id FROM categories WHERE id = ?")
+            .bind(category_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        if exists.is_none() {
+            return Err(AppError::NotFound(format!("Categorie {} niet gevonden", category_id)));
         }
-    });
+    }
+
+    let mut transaction = previous.clone();
+    transaction.category_id = category_id;
+    transaction.updated_at = Utc::now();
+
+    sqlx::query(
+        r#"
+        UPDATE transactions
+        SET category_id = ?, needs_review = FALSE, suggested_category_id = NULL,
+            category_confidence = NULL, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&transaction.category_id)
+    .bind(transaction.updated_at)
+    .bind(&id)
+    .execute(&mut *tx)
+    .await?;
+
+    if previous.category_id != transaction.category_id {
+        recompute_category_budgets(&mut tx, previous.category_id.as_deref()).await?;
+        recompute_category_budgets(&mut tx, transaction.category_id.as_deref()).await?;
+    }
+
+    tx.commit().await?;
+
+    if let Err(e) = check_budget_thresholds(&app, &pool).await {
+        tracing::warn!("Budget threshold check failed: {}", e);
+    }
+
+    let encryption = state.encryption.lock().await;
+    decrypt_notes(&mut transaction, &encryption)?;
 
     Ok(transaction)
-}
\ No newline at end of file
+}
+
+/// Rank pairs of stored transactions that look like the same purchase imported
+/// twice under slightly different descriptions. Nothing is merged; the user decides.
+#[tauri::command]
+pub async fn find_near_duplicates(
+    window_days: u32,
+    amount_tolerance: Decimal,
+    state: State<'_, AppState>
+) -> AppResult<Vec<DuplicateCandidate>> {
+    if amount_tolerance < Decimal::ZERO {
+        return Err(crate::error::AppError::Validation(
+            "Bedragtolerantie mag niet negatief zijn".to_string()
+        ));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        ORDER BY date ASC
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .fetch_all(&pool)
+    .await?;
+
+    let mut transactions: Vec<Transaction> = rows.iter().map(transaction_from_row).collect();
+
+    let encryption = state.encryption.lock().await;
+    for transaction in &mut transactions {
+        decrypt_notes(transaction, &encryption)?;
+    }
+
+    Ok(duplicates::find_near_duplicates(&transactions, window_days, amount_tolerance))
+}
+
+/// Debits charged twice by the same merchant for the same amount within
+/// `window_hours` (24 by default), so the user can dispute them.
+#[tauri::command]
+pub async fn detect_double_charges(
+    window_hours: Option<u32>,
+    state: State<'_, AppState>
+) -> AppResult<Vec<DoubleCharge>> {
+    let window_hours = window_hours.unwrap_or(24);
+    if window_hours == 0 || window_hours > 24 * 7 {
+        return Err(AppError::Validation("Tijdvenster moet tussen 1 en 168 uur liggen".to_string()));
+    }
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE transaction_type = 'debit'
+        ORDER BY date ASC
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .fetch_all(&pool)
+    .await?;
+
+    let mut transactions: Vec<Transaction> = rows.iter().map(transaction_from_row).collect();
+
+    let encryption = state.encryption.lock().await;
+    for transaction in &mut transactions {
+        decrypt_notes(transaction, &encryption)?;
+    }
+
+    Ok(duplicates::find_double_charges(&transactions, window_hours))
+}
+
+/// Optional transaction fields that can be checked for missing values
+const CHECKABLE_FIELDS: &[&str] = &[
+    "account_holder",
+    "account_number",
+    "balance_after",
+    "category_id",
+    "notes",
+    "recurring_frequency",
+];
+
+/// Transactions lacking any of the given fields, for data-quality cleanup.
+/// Empty strings count as missing.
+#[tauri::command]
+pub async fn get_transactions_missing_fields(
+    fields: Vec<String>,
+    state: State<'_, AppState>
+) -> AppResult<Vec<Transaction>> {
+    if fields.is_empty() {
+        return Err(crate::error::AppError::Validation(
+            "Geef minstens één veld op om op te controleren".to_string()
+        ));
+    }
+
+    if let Some(unknown) = fields.iter().find(|f| !CHECKABLE_FIELDS.contains(&f.as_str())) {
+        return Err(crate::error::AppError::Validation(format!(
+            "Onbekend veld '{}'; kies uit: {}",
+            unknown,
+            CHECKABLE_FIELDS.join(", ")
+        )));
+    }
+
+    // Field names are checked against CHECKABLE_FIELDS above, so they are safe to inline
+    let conditions = fields
+        .iter()
+        .map(|f| format!("{0} IS NULL OR TRIM({0}) = ''", f))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+
+    let pool = state.db.lock().await.get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT
+            {}
+        FROM transactions
+        WHERE {}
+        ORDER BY date DESC, created_at DESC
+        "#,
+        TRANSACTION_COLUMNS, conditions
+    ))
+    .fetch_all(&pool)
+    .await?;
+
+    let mut transactions: Vec<Transaction> = rows.iter().map(transaction_from_row).collect();
+
+    let encryption = state.encryption.lock().await;
+    for transaction in &mut transactions {
+        decrypt_notes(transaction, &encryption)?;
+    }
+
+    Ok(transactions)
+}
+
+/// Income, expenses and counts of the transactions between `date_from` and
+/// `date_to`, both optional. Amounts are summed as whole cents: SQLite keeps
+/// DECIMAL columns as floating point, and a float SUM drifts over many rows.
+pub(crate) async fn transaction_stats(
+    pool: &SqlitePool,
+    date_from: Option<DateTime<Utc>>,
+    date_to: Option<DateTime<Utc>>,
+) -> AppResult<TransactionStats> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            COUNT(*) AS transaction_count,
+            CAST(COALESCE(SUM(CASE WHEN transaction_type = 'credit' THEN CAST(ROUND(amount * 100) AS INTEGER) END), 0) AS TEXT) AS income_cents,
+            CAST(COALESCE(SUM(CASE WHEN transaction_type = 'debit' THEN CAST(ROUND(amount * 100) AS INTEGER) END), 0) AS TEXT) AS expense_cents,
+            CAST(MAX(CASE WHEN transaction_type = 'debit' THEN CAST(ROUND(amount * 100) AS INTEGER) END) AS TEXT) AS largest_expense_cents
+        FROM transactions
+        WHERE (? IS NULL OR date >= ?)
+          AND (? IS NULL OR date <= ?)
+        "#
+    )
+    .bind(date_from)
+    .bind(date_from)
+    .bind(date_to)
+    .bind(date_to)
+    .fetch_one(pool)
+    .await?;
+
+    let cents = Decimal::from(100);
+    let total_income = get_decimal(&row, "income_cents") / cents;
+    let total_expenses = get_decimal(&row, "expense_cents") / cents;
+    let transaction_count: i64 = row.get("transaction_count");
+    let average_amount = if transaction_count > 0 {
+        ((total_income + total_expenses) / Decimal::from(transaction_count)).round_dp(2)
+    } else {
+        Decimal::ZERO
+    };
+
+    Ok(TransactionStats {
+        total_income,
+        total_expenses,
+        net: total_income - total_expenses,
+        transaction_count,
+        average_amount,
+        largest_expense: get_optional_decimal(&row, "largest_expense_cents").map(|amount| amount / cents),
+    })
+}
+
+/// Totals for the dashboard over an optional date range, all-time by default.
+#[tauri::command]
+pub async fn get_transaction_stats(
+    date_from: Option<DateTime<Utc>>,
+    date_to: Option<DateTime<Utc>>,
+    state: State<'_, AppState>
+) -> AppResult<TransactionStats> {
+    let pool = state.db.lock().await.get_pool().await?;
+    transaction_stats(&pool, date_from, date_to).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_stored_notes_follow_encrypt_notes() {
+        let mut encryption = EncryptionManager::new();
+        encryption.set_master_key("test_password", &EncryptionManager::generate_salt().unwrap()).unwrap();
+        let mut settings = Settings::default();
+
+        let stored = stored_notes(Some("Fysiotherapie"), &settings, &encryption).unwrap().unwrap();
+        assert!(stored.starts_with(crate::encryption::ENCRYPTED_FIELD_PREFIX));
+        assert_eq!(encryption.decrypt_field(&stored).unwrap(), "Fysiotherapie");
+        assert_eq!(stored_notes(None, &settings, &encryption).unwrap(), None);
+
+        settings.encrypt_notes = false;
+        assert_eq!(stored_notes(Some("Fysiotherapie"), &settings, &encryption).unwrap().as_deref(), Some("Fysiotherapie"));
+        settings.encrypt_notes = true;
+        settings.encryption_enabled = false;
+        assert!(!notes_encrypted(&settings));
+    }
+
+    #[tokio::test]
+    async fn test_filtered_page_with_total_count() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+
+        for (day, description, transaction_type) in [
+            (1, "Albert Heijn 1234", "debit"),
+            (2, "Salaris maart", "credit"),
+            (3, "albert heijn to go", "debit"),
+            (4, "Jumbo 100%", "debit"),
+        ] {
+            let date = Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap();
+            sqlx::query(
+                "INSERT INTO transactions (id, description, amount, date, transaction_type, created_at, updated_at) VALUES (?, ?, '10.00', ?, ?, ?, ?)"
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(description)
+            .bind(date)
+            .bind(transaction_type)
+            .bind(date)
+            .bind(date)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let filter = TransactionFilter {
+            limit: Some(1),
+            transaction_type: Some("debit".to_string()),
+            search: Some("Albert".to_string()),
+            ..Default::default()
+        };
+        let page = query_transactions(&pool, &filter).await.unwrap();
+
+        assert_eq!(page.total_count, 2);
+        assert_eq!(page.transactions.len(), 1);
+        assert_eq!(page.transactions[0].description, "albert heijn to go");
+
+        let literal_percent = TransactionFilter { search: Some("0%".to_string()), ..Default::default() };
+        assert_eq!(query_transactions(&pool, &literal_percent).await.unwrap().total_count, 1);
+        assert_eq!(query_transactions(&pool, &TransactionFilter::default()).await.unwrap().transactions.len(), 4);
+
+        // Account numbers are matched to the account's IBAN regardless of spacing and case
+        let date = Utc.with_ymd_and_hms(2024, 3, 5, 12, 0, 0).unwrap();
+        sqlx::query(
+            "INSERT INTO transactions (id, description, amount, date, transaction_type, account_number, created_at, updated_at) VALUES (?, 'Huur', '900.00', ?, 'debit', 'nl01 rabo 0123 4567 89', ?, ?)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(date)
+        .bind(date)
+        .bind(date)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let account = crate::commands::accounts::ensure_account(&mut pool.acquire().await.unwrap(), "NL01RABO0123456789")
+            .await
+            .unwrap();
+        let by_account = TransactionFilter { account_id: Some(account.id), ..Default::default() };
+        let page = query_transactions(&pool, &by_account).await.unwrap();
+        assert_eq!(page.total_count, 1);
+        assert_eq!(page.transactions[0].description, "Huur");
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_edited_accounts_update_counterparty_bank() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        sqlx::query(
+            r#"
+            INSERT INTO transactions (id, description, amount, date, account_number, account_holder, counterparty_bank, transaction_type)
+            VALUES ('t1', 'Huur', '950.00', ?, 'NL01RABO0123456789', 'NL91ABNA0417164300', 'ABN AMRO', 'debit')
+            "#
+        )
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let mut edited = fetch_transaction(&mut conn, "t1").await.unwrap().unwrap();
+        edited.account_number = Some("NL02INGB0001234567".to_string());
+        edited.account_holder = Some("NL20RABO0300065264".to_string());
+        assert!(update_stored_transaction(&mut conn, &mut edited, None).await.unwrap());
+
+        let stored = fetch_transaction(&mut conn, "t1").await.unwrap().unwrap();
+        assert_eq!(stored.account_number.as_deref(), Some("NL02INGB0001234567"));
+        assert_eq!(stored.counterparty_bank.as_deref(), Some("Rabobank"));
+
+        drop(conn);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_transaction_stats_sum_exact_cents() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+
+        let empty = transaction_stats(&pool, None, None).await.unwrap();
+        assert_eq!(empty.transaction_count, 0);
+        assert_eq!(empty.net, Decimal::ZERO);
+        assert_eq!(empty.largest_expense, None);
+
+        // A thousand dimes add up to a float SUM of 99.9999999999986
+        let mut rows = vec![(1, "2500.00", "credit"), (2, "1234.56", "debit")];
+        rows.extend((0..1000).map(|_| (3, "0.10", "debit")));
+        for (day, amount, transaction_type) in rows {
+            let date = Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap();
+            sqlx::query(
+                "INSERT INTO transactions (id, description, amount, date, transaction_type, created_at, updated_at) VALUES (?, 'Test', ?, ?, ?, ?, ?)"
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(amount)
+            .bind(date)
+            .bind(transaction_type)
+            .bind(date)
+            .bind(date)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let stats = transaction_stats(&pool, None, None).await.unwrap();
+        assert_eq!(stats.transaction_count, 1002);
+        assert_eq!(stats.total_income, Decimal::new(250000, 2));
+        assert_eq!(stats.total_expenses, Decimal::new(133456, 2));
+        assert_eq!(stats.net, Decimal::new(116544, 2));
+        assert_eq!(stats.average_amount, Decimal::new(383, 2));
+        assert_eq!(stats.largest_expense, Some(Decimal::new(123456, 2)));
+
+        let from = Utc.with_ymd_and_hms(2024, 3, 2, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2024, 3, 2, 23, 59, 59).unwrap();
+        let one_day = transaction_stats(&pool, Some(from), Some(to)).await.unwrap();
+        assert_eq!(one_day.transaction_count, 1);
+        assert_eq!(one_day.total_income, Decimal::ZERO);
+        assert_eq!(one_day.net, Decimal::new(-123456, 2));
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_and_follows_changes() {
+        let db = TestDatabase::new().await;
+        let pool = db.pool().await;
+
+        for (id, day, description, notes) in [
+            ("old", 1, "Betaling tandarts De Vries", None),
+            ("new", 20, "Tandarts De Vries tandartscontrole", None),
+            ("note", 10, "Overboeking", Some("voor de tandarts")),
+            ("secret", 11, "Overboeking", Some("enc:v1:tandarts")),
+            ("other", 12, "Albert Heijn", None),
+        ] {
+            let date = Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap();
+            sqlx::query(
+                "INSERT INTO transactions (id, description, amount, date, transaction_type, notes, created_at, updated_at) VALUES (?, ?, '10.00', ?, 'debit', ?, ?, ?)"
+            )
+            .bind(id)
+            .bind(description)
+            .bind(date)
+            .bind(notes)
+            .bind(date)
+            .bind(date)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let ids = |transactions: Vec<Transaction>| transactions.into_iter().map(|t| t.id).collect::<Vec<_>>();
+
+        // "tand" matches as a prefix; two hits in the description rank first
+        let found = ids(search_transactions_in(&pool, "tand", 10).await.unwrap());
+        assert_eq!(found[0], "new");
+        assert_eq!(found.len(), 3);
+        assert!(!found.contains(&"secret".to_string()));
+        assert_eq!(ids(search_transactions_in(&pool, "tand vries", 1).await.unwrap()).len(), 1);
+        assert!(search_transactions_in(&pool, "  \"\" ", 10).await.unwrap().is_empty());
+
+        sqlx::query("UPDATE transactions SET description = 'Huisarts' WHERE id = 'new'").execute(&pool).await.unwrap();
+        sqlx::query("DELETE FROM transactions WHERE id = 'note'").execute(&pool).await.unwrap();
+        assert_eq!(ids(search_transactions_in(&pool, "tandarts", 10).await.unwrap()), ["old"]);
+        assert_eq!(ids(search_transactions_in(&pool, "huis", 10).await.unwrap()), ["new"]);
+
+        pool.close().await;
+    }
+
+    #[test]
+    fn test_sub_cent_amounts_are_rejected() {
+        assert!(validate_amount_precision(Decimal::new(10005, 3), "EUR").is_err());
+        assert!(validate_amount_precision(Decimal::new(1000, 2), "EUR").is_ok());
+        // Trailing zeros do not count as extra precision
+        assert!(validate_amount_precision(Decimal::new(100000, 4), "EUR").is_ok());
+        assert!(validate_amount_precision(Decimal::new(105, 1), "JPY").is_err());
+    }
+}
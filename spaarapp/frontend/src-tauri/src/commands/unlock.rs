@@ -0,0 +1,202 @@
+use crate::audit;
+use crate::commands::app::run_cleanup;
+use crate::commands::key_rotation::{
+    audit_key_age, new_passphrase_keys, passphrase_keys, previous_config_path, switch_keys, validate_new_passphrase,
+};
+use crate::database::Database;
+use crate::encryption::{
+    legacy_database_key, legacy_field_encryption, load_encryption_config, load_or_create_encryption_config,
+    save_encryption_config, EncryptionConfig, EncryptionManager, ENCRYPTION_CONFIG_FILE,
+};
+use crate::error::{AppError, AppResult};
+use crate::AppState;
+use std::path::Path;
+use tauri::State;
+
+/// Whether the database is open and whether a passphrase was ever set, so
+/// the app knows to show the unlock screen or the first-run setup.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LockStatus {
+    pub unlocked: bool,
+    pub passphrase_set: bool,
+}
+
+/// Open the locked database with the keys `config` derives from `passphrase`.
+async fn open_with(
+    db: &mut Database,
+    encryption: &mut EncryptionManager<'static>,
+    config: &EncryptionConfig,
+    passphrase: &str,
+) -> AppResult<()> {
+    let verifier = config
+        .passphrase_verifier
+        .as_deref()
+        .ok_or_else(|| AppError::Validation("Stel eerst een wachtwoord in".to_string()))?;
+
+    if !encryption.verify_passphrase(passphrase, verifier)? {
+        return Err(AppError::PermissionDenied("Onjuist wachtwoord".to_string()));
+    }
+
+    let keys = passphrase_keys(passphrase, config)?;
+    db.unlock(keys.database_key).await?;
+    *encryption = keys.fields;
+    Ok(())
+}
+
+/// Open the locked database with the keys derived from `passphrase`. When a
+/// key switch stopped before the database was replaced, the database still
+/// needs the previous config; that config is then put back.
+pub(crate) async fn unlock_with(
+    db: &mut Database,
+    encryption: &mut EncryptionManager<'static>,
+    config_path: &Path,
+    passphrase: &str,
+) -> AppResult<()> {
+    let mut config = load_or_create_encryption_config(config_path)?;
+    let previous_path = previous_config_path(config_path);
+
+    if let Err(e) = open_with(db, encryption, &config, passphrase).await {
+        let Ok(previous) = load_encryption_config(&previous_path) else {
+            return Err(e);
+        };
+        match previous.passphrase_verifier {
+            Some(_) => open_with(db, encryption, &previous, passphrase).await.map_err(|_| e)?,
+            // The first passphrase was being set: the database is still on the legacy key
+            None => {
+                db.unlock(legacy_database_key()).await.map_err(|_| e)?;
+                *encryption = legacy_field_encryption(&previous)?;
+            }
+        }
+        save_encryption_config(&previous, config_path)?;
+        tracing::warn!("Interrupted key switch found; restored the previous encryption config");
+        config = previous;
+    }
+    let _ = std::fs::remove_file(&previous_path);
+
+    if let Err(e) = audit_key_age(db, &config).await {
+        tracing::warn!("Failed to check encryption key age: {}", e);
+    }
+    // Cleanup and budgets whose period ended while the app was closed
+    run_cleanup(&db.get_pool().await?).await;
+    Ok(())
+}
+
+/// First run: protect the database with `passphrase`. An existing database
+/// still on the legacy key is opened with it once and moved over, encrypted
+/// fields included; without a database a new one is created.
+pub(crate) async fn set_initial_passphrase(
+    db: &mut Database,
+    encryption: &mut EncryptionManager<'static>,
+    config_path: &Path,
+    passphrase: &str,
+) -> AppResult<()> {
+    let config = load_or_create_encryption_config(config_path)?;
+    if config.passphrase_verifier.is_some() {
+        return Err(AppError::Validation(
+            "Er is al een wachtwoord ingesteld; wijzig het via sleutelrotatie".to_string()
+        ));
+    }
+    validate_new_passphrase(passphrase)?;
+
+    if db.is_locked().await {
+        db.unlock(legacy_database_key()).await?;
+        *encryption = legacy_field_encryption(&config)?;
+    }
+
+    let keys = new_passphrase_keys(passphrase, &config)?;
+    switch_keys(db, encryption, config_path, keys).await
+}
+
+#[tauri::command]
+pub async fn get_lock_status(state: State<'_, AppState>) -> AppResult<LockStatus> {
+    let config = load_or_create_encryption_config(ENCRYPTION_CONFIG_FILE)?;
+
+    Ok(LockStatus {
+        unlocked: !state.db.lock().await.is_locked().await,
+        passphrase_set: config.passphrase_verifier.is_some(),
+    })
+}
+
+/// Open the database with the user's passphrase. Every command that reads or
+/// writes data fails until this succeeds.
+#[tauri::command]
+pub async fn unlock_database(passphrase: String, state: State<'_, AppState>) -> AppResult<()> {
+    let mut db = state.db.lock().await;
+    if !db.is_locked().await {
+        return Ok(());
+    }
+    let mut encryption = state.encryption.lock().await;
+
+    unlock_with(&mut db, &mut encryption, Path::new(ENCRYPTION_CONFIG_FILE), &passphrase).await
+}
+
+/// Set the passphrase on first run, after which the database is unlocked.
+#[tauri::command]
+pub async fn set_passphrase(passphrase: String, state: State<'_, AppState>) -> AppResult<()> {
+    let mut db = state.db.lock().await;
+    let mut encryption = state.encryption.lock().await;
+
+    set_initial_passphrase(&mut db, &mut encryption, Path::new(ENCRYPTION_CONFIG_FILE), &passphrase).await?;
+
+    audit::record(&db.get_pool().await?, audit::PASSPHRASE_SET, "success", None).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+    use chrono::Utc;
+
+    #[tokio::test]
+    async fn test_locked_until_unlocked_with_passphrase() {
+        // An install from before passphrases, with an encrypted note
+        let legacy = TestDatabase::new().await;
+        let config_path = legacy.dir.join("encryption.json");
+        let db_path = legacy.path().to_string();
+        let passphrase = "lange zin als wachtwoord";
+
+        let legacy_fields = legacy_field_encryption(&load_or_create_encryption_config(&config_path).unwrap()).unwrap();
+        let pool = legacy.get_pool().await.unwrap();
+        sqlx::query(
+            "INSERT INTO transactions (id, description, amount, date, transaction_type, notes) VALUES ('t1', 'Tandarts', '80.00', ?, 'debit', ?)"
+        )
+        .bind(Utc::now())
+        .bind(legacy_fields.encrypt_field("Controle januari").unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+
+        let mut db = Database::locked(&db_path);
+        let mut encryption = EncryptionManager::new();
+        assert!(matches!(db.get_pool().await, Err(AppError::PermissionDenied(_))));
+        assert!(matches!(
+            unlock_with(&mut db, &mut encryption, &config_path, passphrase).await,
+            Err(AppError::Validation(_))
+        ));
+        assert!(set_initial_passphrase(&mut db, &mut encryption, &config_path, "kort").await.is_err());
+
+        set_initial_passphrase(&mut db, &mut encryption, &config_path, passphrase).await.unwrap();
+        db.get_pool().await.unwrap().close().await;
+
+        // Next start
+        let mut db = Database::locked(&db_path);
+        let mut encryption = EncryptionManager::new();
+        assert!(matches!(
+            unlock_with(&mut db, &mut encryption, &config_path, "niet het wachtwoord").await,
+            Err(AppError::PermissionDenied(_))
+        ));
+        assert!(db.is_locked().await);
+
+        unlock_with(&mut db, &mut encryption, &config_path, passphrase).await.unwrap();
+        let pool = db.get_pool().await.unwrap();
+        let notes: String = sqlx::query_scalar("SELECT notes FROM transactions WHERE id = 't1'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(encryption.decrypt_field(&notes).unwrap(), "Controle januari");
+        assert!(legacy_fields.decrypt_field(&notes).is_err());
+
+        pool.close().await;
+    }
+}
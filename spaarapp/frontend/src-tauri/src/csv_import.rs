@@ -33,6 +33,12 @@ impl CsvImporter {
             .has_headers(self.config.has_header_row)
             .from_reader(content.as_bytes());
 
+        let headers = if self.config.has_header_row {
+            Some(rdr.headers().map_err(AppError::Csv)?.clone())
+        } else {
+            None
+        };
+
         let mut transactions = Vec::new();
         let mut record_number = 0;
 
@@ -43,9 +49,9 @@ impl CsvImporter {
                 AppError::Csv(csv::Error::from(e))
             })?;
 
-            let transaction = self.map_record_to_transaction(&record, record_number)
+            let transaction = self.map_record_to_transaction(&record, headers.as_ref(), record_number)
                 .map_err(|e| {
-                    AppError::InvalidInput(format!("Record {}: {}", record_number, e))
+                    AppError::Validation(format!("Record {}: {}", record_number, e))
                 })?;
 
             if let Some(tx) = transaction {
@@ -59,6 +65,7 @@ impl CsvImporter {
     fn map_record_to_transaction(
         &self,
         record: &csv::StringRecord,
+        headers: Option<&csv::StringRecord>,
         record_number: u32,
     ) -> AppResult<Option<Transaction>> {
         // Extract date
@@ -93,6 +100,14 @@ impl CsvImporter {
         let balance_after = balance_after_str
             .and_then(|s| Decimal::from_str_radix(&s.replace(',', "."), 10).ok());
 
+        let mut notes = format!("Imported from CSV - Record {}", record_number);
+        if self.config.unmapped_columns_to_notes {
+            for line in self.unmapped_values(record, headers) {
+                notes.push('\n');
+                notes.push_str(&line);
+            }
+        }
+
         // Create transaction
         let transaction = Transaction {
             id: uuid::Uuid::new_v4().to_string(),
@@ -102,10 +117,11 @@ impl CsvImporter {
             category_id: None, // Will be set by auto-categorization
             account_number,
             account_holder,
+            counterparty_bank: None,
             transaction_type,
             balance_after,
-            notes: Some(format!("Imported from CSV - Record {}", record_number)),
-            tags: serde_json::to_string(&vec!["imported".to_string()]).unwrap_or_default(),
+            notes: Some(notes),
+            tags: vec!["imported".to_string()],
             is_recurring: false,
             recurring_frequency: None,
             created_at: Utc::now(),
@@ -116,10 +132,33 @@ impl CsvImporter {
     }
 
     fn get_field_value(&self, record: &csv::StringRecord, index: &Option<usize>) -> Option<String> {
-        index.and_then(|i| record.get(i).map(|s| s.trim().to_string()))
+        index.filter(|i| !self.config.skip_columns.contains(i))
+            .and_then(|i| record.get(i).map(|s| s.trim().to_string()))
             .filter(|s| !s.is_empty())
     }
 
+    /// "Header: value" lines for non-empty columns that are neither mapped nor skipped.
+    fn unmapped_values(&self, record: &csv::StringRecord, headers: Option<&csv::StringRecord>) -> Vec<String> {
+        let mapped = self.config.column_mapping.mapped_columns();
+
+        record
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !mapped.contains(i) && !self.config.skip_columns.contains(i))
+            .map(|(i, value)| (i, value.trim()))
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(i, value)| {
+                let label = headers
+                    .and_then(|h| h.get(i))
+                    .map(str::trim)
+                    .filter(|h| !h.is_empty())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("Kolom {}", i + 1));
+                format!("{}: {}", label, value)
+            })
+            .collect()
+    }
+
     fn parse_date(&self, date_str: &str) -> AppResult<NaiveDate> {
         // Try different date formats commonly used by Dutch banks
         let formats = [
@@ -136,7 +175,7 @@ impl CsvImporter {
             }
         }
 
-        Err(AppError::InvalidInput(format!(
+        Err(AppError::Validation(format!(
             "Unable to parse date '{}' with any known format",
             date_str
         )))
@@ -168,4 +207,24 @@ mod tests {
         assert!(importer.parse_date("2024-11-12").is_ok());
         assert!(importer.parse_date("invalid").is_err());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_skipped_and_unmapped_columns() {
+        let mut config = CsvImportConfig::default();
+        config.column_mapping.transaction_type = None;
+        config.column_mapping.balance_after = None;
+        config.skip_columns = vec![4, 5];
+        config.unmapped_columns_to_notes = true;
+        let importer = CsvImporter::new(config);
+
+        let content = "datum,omschrijving,bedrag,rekening,naam,bankcode,kenmerk\n\
+                       20241112,Bakker,-3.50,NL01RABO0123456789,Bakker BV,X17,Factuur 42\n";
+        let transactions = importer.parse_csv_content(content).await.unwrap();
+
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].account_holder, None);
+        let notes = transactions[0].notes.as_deref().unwrap();
+        assert!(notes.ends_with("\nkenmerk: Factuur 42"));
+        assert!(!notes.contains("X17"));
+    }
+}
@@ -1,58 +1,754 @@
 use crate::error::{AppError, AppResult};
 use crate::models::*;
-use chrono::Utc;
-use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous, SqlitePoolOptions};
-use sqlx::{Pool, Sqlite, SqlitePool};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteRow, SqliteSynchronous, SqlitePoolOptions};
+use sqlx::{Connection, Row, SqliteConnection, SqlitePool};
+use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+/// Version of the newest migration in [`migrations`]; a database at this
+/// version has the schema this build of the app expects.
+pub const SCHEMA_VERSION: i64 = 21;
+
+struct Migration {
+    version: i64,
+    description: &'static str,
+    steps: Vec<MigrationStep>,
+}
+
+enum MigrationStep {
+    Sql(&'static str),
+    /// Add a column unless it is already there
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+    /// Give stored transactions without an import hash one. SQLite has no
+    /// SHA-256, so the hashes are computed here.
+    BackfillImportHashes,
+}
+
+impl MigrationStep {
+    async fn apply(&self, conn: &mut SqliteConnection) -> AppResult<()> {
+        match self {
+            MigrationStep::Sql(sql) => {
+                sqlx::query(sql).execute(conn).await?;
+            }
+            MigrationStep::AddColumn { table, column, definition } => {
+                add_column_if_missing(conn, table, column, definition).await?;
+            }
+            MigrationStep::BackfillImportHashes => {
+                backfill_import_hashes(conn).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Migration {
+    /// Run the steps and record the migration as applied.
+    async fn apply(&self, conn: &mut SqliteConnection) -> AppResult<()> {
+        for step in &self.steps {
+            step.apply(&mut *conn).await?;
+        }
+
+        sqlx::query("INSERT INTO schema_migrations (version, description, applied_at) VALUES (?, ?, ?)")
+            .bind(self.version)
+            .bind(self.description)
+            .bind(Utc::now())
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+}
+
+const CREATE_SCHEMA_MIGRATIONS: &str = r#"
+    CREATE TABLE IF NOT EXISTS schema_migrations (
+        version INTEGER PRIMARY KEY,
+        description TEXT NOT NULL,
+        applied_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )
+"#;
+
+/// Schema changes in the order they were made. Each runs once per database
+/// and is recorded in `schema_migrations`; never edit an applied migration,
+/// add a new one instead.
+fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            description: "initial schema",
+            steps: vec![
+                MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS settings (
+                    id TEXT PRIMARY KEY,
+                    currency TEXT NOT NULL DEFAULT 'EUR',
+                    date_format TEXT NOT NULL DEFAULT 'DD-MM-YYYY',
+                    theme TEXT NOT NULL DEFAULT 'light',
+                    language TEXT NOT NULL DEFAULT 'nl',
+                    notifications_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+                    auto_categorization_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+                    ai_insights_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+                    budget_alerts_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+                    data_retention_days INTEGER NOT NULL DEFAULT 365,
+                    export_format TEXT NOT NULL DEFAULT 'csv',
+                    encryption_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+                    last_backup TEXT,
+                    spending_velocity_alert_fraction REAL NOT NULL DEFAULT 0.5,
+                    restrict_tags_to_vocabulary BOOLEAN NOT NULL DEFAULT FALSE,
+                    insight_retention_days INTEGER NOT NULL DEFAULT 90,
+                    import_repeat_window_seconds INTEGER NOT NULL DEFAULT 5,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+                )
+                "#),
+                MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS categories (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE,
+                    description TEXT,
+                    color TEXT NOT NULL DEFAULT '#2196F3',
+                    icon TEXT NOT NULL DEFAULT 'category',
+                    parent_id TEXT,
+                    is_system BOOLEAN NOT NULL DEFAULT FALSE,
+                    budget_percentage REAL,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (parent_id) REFERENCES categories(id)
+                )
+                "#),
+                MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS transactions (
+                    id TEXT PRIMARY KEY,
+                    description TEXT NOT NULL,
+                    amount DECIMAL(15,2) NOT NULL,
+                    date DATETIME NOT NULL,
+                    category_id TEXT,
+                    account_number TEXT,
+                    account_holder TEXT,
+                    transaction_type TEXT NOT NULL DEFAULT 'debit',
+                    balance_after DECIMAL(15,2),
+                    notes TEXT,
+                    tags TEXT DEFAULT '[]',
+                    is_recurring BOOLEAN NOT NULL DEFAULT FALSE,
+                    recurring_frequency TEXT,
+                    import_hash TEXT,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (category_id) REFERENCES categories(id)
+                )
+                "#),
+                MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS budgets (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    category_id TEXT,
+                    amount DECIMAL(15,2) NOT NULL,
+                    period TEXT NOT NULL DEFAULT 'monthly',
+                    spent DECIMAL(15,2) NOT NULL DEFAULT 0,
+                    remaining DECIMAL(15,2) GENERATED ALWAYS AS (amount - spent) STORED,
+                    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+                    notification_threshold DECIMAL(15,2),
+                    start_date DATETIME NOT NULL,
+                    end_date DATETIME,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (category_id) REFERENCES categories(id)
+                )
+                "#),
+                MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS financial_insights (
+                    id TEXT PRIMARY KEY,
+                    insight_type TEXT NOT NULL,
+                    title TEXT NOT NULL,
+                    description TEXT NOT NULL,
+                    impact TEXT NOT NULL,
+                    actionable BOOLEAN NOT NULL DEFAULT TRUE,
+                    action_suggestions TEXT DEFAULT '[]',
+                    confidence_score REAL NOT NULL,
+                    is_read BOOLEAN NOT NULL DEFAULT FALSE,
+                    is_dismissed BOOLEAN NOT NULL DEFAULT FALSE,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+                )
+                "#),
+                // Curated tag vocabulary; enforced on transactions only when the setting is on
+                MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS tags (
+                    name TEXT PRIMARY KEY COLLATE NOCASE,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+                )
+                "#),
+                // Own bank accounts, keyed by IBAN, with per-account import defaults
+                MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS accounts (
+                    id TEXT PRIMARY KEY,
+                    iban TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                    name TEXT,
+                    default_category_id TEXT,
+                    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (default_category_id) REFERENCES categories (id) ON DELETE SET NULL
+                )
+                "#),
+                // One row per (month, threshold) a spending velocity alert already fired for
+                MigrationStep::Sql(r#"
+                CREATE TABLE IF NOT EXISTS spending_velocity_alerts (
+                    period TEXT NOT NULL,
+                    threshold TEXT NOT NULL,
+                    triggered_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (period, threshold)
+                )
+                "#),
+                // Databases created before migrations were versioned may lack columns added later
+                MigrationStep::AddColumn { table: "financial_insights", column: "is_read", definition: "BOOLEAN NOT NULL DEFAULT FALSE" },
+                MigrationStep::AddColumn { table: "financial_insights", column: "is_dismissed", definition: "BOOLEAN NOT NULL DEFAULT FALSE" },
+                MigrationStep::AddColumn { table: "settings", column: "spending_velocity_alert_fraction", definition: "REAL NOT NULL DEFAULT 0.5" },
+                MigrationStep::AddColumn { table: "settings", column: "restrict_tags_to_vocabulary", definition: "BOOLEAN NOT NULL DEFAULT FALSE" },
+                MigrationStep::AddColumn { table: "transactions", column: "import_hash", definition: "TEXT" },
+                MigrationStep::AddColumn { table: "settings", column: "insight_retention_days", definition: "INTEGER NOT NULL DEFAULT 90" },
+                MigrationStep::AddColumn { table: "settings", column: "import_repeat_window_seconds", definition: "INTEGER NOT NULL DEFAULT 5" },
+                MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_transactions_date ON transactions(date)"),
+                MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_transactions_category ON transactions(category_id)"),
+                MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_transactions_type ON transactions(transaction_type)"),
+                MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_transactions_recurring ON transactions(is_recurring)"),
+                MigrationStep::Sql("CREATE UNIQUE INDEX IF NOT EXISTS idx_transactions_import_hash ON transactions(import_hash)"),
+                MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id)"),
+                MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_budgets_active ON budgets(is_active)"),
+                MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_budgets_category ON budgets(category_id)"),
+                MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_insights_state ON financial_insights(is_dismissed, is_read)"),
+            ],
+        },
+        Migration {
+            version: 2,
+            description: "data export setting and audit log",
+            steps: vec![
+                MigrationStep::Sql("ALTER TABLE settings ADD COLUMN allow_data_export BOOLEAN NOT NULL DEFAULT TRUE"),
+                // Security-relevant actions such as data exports, with their outcome
+                MigrationStep::Sql(r#"
+                CREATE TABLE audit_log (
+                    id TEXT PRIMARY KEY,
+                    action TEXT NOT NULL,
+                    outcome TEXT NOT NULL,
+                    detail TEXT,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+                )
+                "#),
+                MigrationStep::Sql("CREATE INDEX idx_audit_log_action ON audit_log(action, created_at)"),
+            ],
+        },
+        Migration {
+            version: 3,
+            description: "transactions.counterparty_bank",
+            steps: vec![MigrationStep::Sql("ALTER TABLE transactions ADD COLUMN counterparty_bank TEXT")],
+        },
+        Migration {
+            version: 4,
+            description: "category rules",
+            steps: vec![
+                MigrationStep::Sql(r#"
+                CREATE TABLE category_rules (
+                    id TEXT PRIMARY KEY,
+                    match_text TEXT NOT NULL,
+                    match_type TEXT NOT NULL DEFAULT 'contains',
+                    category_id TEXT NOT NULL,
+                    priority INTEGER NOT NULL DEFAULT 0,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE
+                )
+                "#),
+                MigrationStep::Sql("CREATE INDEX idx_category_rules_priority ON category_rules(priority DESC, created_at)"),
+                MigrationStep::Sql(SEED_CATEGORY_RULES),
+            ],
+        },
+        Migration {
+            version: 5,
+            description: "savings goals",
+            steps: vec![MigrationStep::Sql(r#"
+                CREATE TABLE savings_goals (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    target_amount DECIMAL(15,2) NOT NULL,
+                    current_amount DECIMAL(15,2) NOT NULL DEFAULT 0,
+                    target_date DATETIME NOT NULL,
+                    linked_category_id TEXT,
+                    milestone_reached INTEGER NOT NULL DEFAULT 0,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (linked_category_id) REFERENCES categories (id) ON DELETE SET NULL
+                )
+                "#)],
+        },
+        Migration {
+            version: 6,
+            description: "transaction full-text search",
+            // Keyed on the transaction id rather than the rowid, which VACUUM
+            // INTO (used for backups) does not preserve. Encrypted notes
+            // (`enc:v1:`, see `ENCRYPTED_FIELD_PREFIX`) are not indexed.
+            steps: vec![
+                MigrationStep::Sql(r#"
+                CREATE VIRTUAL TABLE transactions_fts USING fts5(
+                    transaction_id UNINDEXED,
+                    description,
+                    notes,
+                    account_holder,
+                    tokenize = 'unicode61 remove_diacritics 2'
+                )
+                "#),
+                MigrationStep::Sql(r#"
+                INSERT INTO transactions_fts (transaction_id, description, notes, account_holder)
+                SELECT id, description,
+                       CASE WHEN notes LIKE 'enc:v1:%' THEN NULL ELSE notes END,
+                       account_holder
+                FROM transactions
+                "#),
+                MigrationStep::Sql(r#"
+                CREATE TRIGGER transactions_fts_insert AFTER INSERT ON transactions BEGIN
+                    INSERT INTO transactions_fts (transaction_id, description, notes, account_holder)
+                    VALUES (
+                        new.id, new.description,
+                        CASE WHEN new.notes LIKE 'enc:v1:%' THEN NULL ELSE new.notes END,
+                        new.account_holder
+                    );
+                END
+                "#),
+                MigrationStep::Sql(r#"
+                CREATE TRIGGER transactions_fts_update
+                AFTER UPDATE OF id, description, notes, account_holder ON transactions BEGIN
+                    DELETE FROM transactions_fts WHERE transaction_id = old.id;
+                    INSERT INTO transactions_fts (transaction_id, description, notes, account_holder)
+                    VALUES (
+                        new.id, new.description,
+                        CASE WHEN new.notes LIKE 'enc:v1:%' THEN NULL ELSE new.notes END,
+                        new.account_holder
+                    );
+                END
+                "#),
+                MigrationStep::Sql(r#"
+                CREATE TRIGGER transactions_fts_delete AFTER DELETE ON transactions BEGIN
+                    DELETE FROM transactions_fts WHERE transaction_id = old.id;
+                END
+                "#),
+            ],
+        },
+        Migration {
+            version: 7,
+            description: "budget period history",
+            // Only the window and amount of a closed period are kept; what was
+            // spent is derived from the transactions when history is queried
+            steps: vec![
+                MigrationStep::Sql(r#"
+                CREATE TABLE budget_periods (
+                    id TEXT PRIMARY KEY,
+                    budget_id TEXT NOT NULL,
+                    amount DECIMAL(15,2) NOT NULL,
+                    start_date DATETIME NOT NULL,
+                    end_date DATETIME NOT NULL,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (budget_id) REFERENCES budgets (id) ON DELETE CASCADE
+                )
+                "#),
+                MigrationStep::Sql("CREATE INDEX idx_budget_periods_budget ON budget_periods(budget_id, start_date)"),
+            ],
+        },
+        Migration {
+            version: 8,
+            description: "budget threshold notifications",
+            // Set once `budget_threshold_crossed` fired for the current period
+            steps: vec![MigrationStep::Sql(
+                "ALTER TABLE budgets ADD COLUMN threshold_notified BOOLEAN NOT NULL DEFAULT FALSE"
+            )],
+        },
+        Migration {
+            version: 9,
+            description: "claude api usage",
+            // Tokens and cost per calendar month ("YYYY-MM"), for the monthly cost cap
+            steps: vec![MigrationStep::Sql(r#"
+                CREATE TABLE ai_usage (
+                    month TEXT PRIMARY KEY,
+                    input_tokens INTEGER NOT NULL DEFAULT 0,
+                    output_tokens INTEGER NOT NULL DEFAULT 0,
+                    cost_eur DECIMAL(15,6) NOT NULL DEFAULT 0,
+                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+                )
+                "#)],
+        },
+        Migration {
+            version: 10,
+            description: "claude api usage per request",
+            // One row per request, so the month's spend is derived from request
+            // times rather than kept in a counter. Earlier monthly totals are
+            // carried over as one request at the start of their month.
+            steps: vec![
+                MigrationStep::Sql(r#"
+                CREATE TABLE claude_usage (
+                    id TEXT PRIMARY KEY,
+                    requested_at DATETIME NOT NULL,
+                    input_tokens INTEGER NOT NULL,
+                    output_tokens INTEGER NOT NULL,
+                    cost_eur DECIMAL(15,6) NOT NULL
+                )
+                "#),
+                MigrationStep::Sql("CREATE INDEX idx_claude_usage_requested_at ON claude_usage(requested_at)"),
+                MigrationStep::Sql(r#"
+                INSERT INTO claude_usage (id, requested_at, input_tokens, output_tokens, cost_eur)
+                SELECT lower(hex(randomblob(16))), month || '-01T00:00:00+00:00', input_tokens, output_tokens, cost_eur
+                FROM ai_usage
+                "#),
+                MigrationStep::Sql("DROP TABLE ai_usage"),
+            ],
+        },
+        Migration {
+            version: 11,
+            description: "settings.audit_retention_days",
+            steps: vec![MigrationStep::Sql("ALTER TABLE settings ADD COLUMN audit_retention_days INTEGER NOT NULL DEFAULT 365")],
+        },
+        Migration {
+            version: 12,
+            description: "account bank and currency, accounts for imported account numbers",
+            steps: vec![
+                MigrationStep::Sql("ALTER TABLE accounts ADD COLUMN bank TEXT"),
+                MigrationStep::Sql("ALTER TABLE accounts ADD COLUMN currency TEXT NOT NULL DEFAULT 'EUR'"),
+                MigrationStep::Sql(r#"
+                INSERT OR IGNORE INTO accounts (id, iban, is_default, created_at, updated_at)
+                SELECT lower(hex(randomblob(16))), UPPER(REPLACE(account_number, ' ', '')), FALSE, MIN(created_at), MIN(created_at)
+                FROM transactions
+                WHERE account_number IS NOT NULL AND TRIM(account_number) != ''
+                GROUP BY UPPER(REPLACE(account_number, ' ', ''))
+                "#),
+            ],
+        },
+        Migration {
+            version: 13,
+            description: "transaction splits",
+            steps: vec![
+                MigrationStep::Sql(r#"
+                CREATE TABLE transaction_splits (
+                    id TEXT PRIMARY KEY,
+                    transaction_id TEXT NOT NULL,
+                    category_id TEXT,
+                    amount DECIMAL(15,2) NOT NULL,
+                    note TEXT,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (transaction_id) REFERENCES transactions (id) ON DELETE CASCADE,
+                    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL
+                )
+                "#),
+                MigrationStep::Sql("CREATE INDEX idx_transaction_splits_transaction ON transaction_splits(transaction_id)"),
+                MigrationStep::Sql("CREATE INDEX idx_transaction_splits_category ON transaction_splits(category_id)"),
+                // What spending calculations read instead of `transactions`: one row
+                // per split for split transactions, the transaction itself otherwise
+                MigrationStep::Sql(r#"
+                CREATE VIEW transaction_portions AS
+                SELECT
+                    t.id, t.description, COALESCE(s.amount, t.amount) AS amount, t.date,
+                    CASE WHEN s.id IS NULL THEN t.category_id ELSE s.category_id END AS category_id,
+                    t.account_number, t.account_holder, t.counterparty_bank, t.transaction_type,
+                    t.balance_after, t.notes, t.tags, t.is_recurring, t.recurring_frequency,
+                    t.created_at, t.updated_at
+                FROM transactions t
+                LEFT JOIN transaction_splits s ON s.transaction_id = t.id
+                "#),
+            ],
+        },
+        Migration {
+            version: 14,
+            description: "category review queue",
+            steps: vec![
+                MigrationStep::Sql("ALTER TABLE settings ADD COLUMN category_review_threshold REAL NOT NULL DEFAULT 0.7"),
+                MigrationStep::Sql("ALTER TABLE transactions ADD COLUMN needs_review BOOLEAN NOT NULL DEFAULT FALSE"),
+                MigrationStep::Sql("ALTER TABLE transactions ADD COLUMN suggested_category_id TEXT"),
+                MigrationStep::Sql("ALTER TABLE transactions ADD COLUMN category_confidence REAL"),
+                MigrationStep::Sql("CREATE INDEX idx_transactions_needs_review ON transactions(needs_review)"),
+            ],
+        },
+        Migration {
+            version: 15,
+            description: "settings.seasonal_anomaly_sigma",
+            steps: vec![MigrationStep::Sql("ALTER TABLE settings ADD COLUMN seasonal_anomaly_sigma REAL NOT NULL DEFAULT 2.0")],
+        },
+        Migration {
+            version: 16,
+            description: "settings.cashflow_spend_estimate",
+            steps: vec![MigrationStep::Sql("ALTER TABLE settings ADD COLUMN cashflow_spend_estimate TEXT NOT NULL DEFAULT 'mean'")],
+        },
+        Migration {
+            version: 17,
+            description: "transaction tags table",
+            // One row per tag of a transaction, kept in sync with the JSON
+            // `tags` column by triggers so tags can be queried and counted.
+            // Some rows hold the array encoded a second time as a JSON string.
+            steps: vec![
+                MigrationStep::Sql(r#"
+                CREATE TABLE transaction_tags (
+                    transaction_id TEXT NOT NULL REFERENCES transactions(id) ON DELETE CASCADE ON UPDATE CASCADE,
+                    tag TEXT NOT NULL COLLATE NOCASE,
+                    PRIMARY KEY (transaction_id, tag)
+                )
+                "#),
+                MigrationStep::Sql("CREATE INDEX idx_transaction_tags_tag ON transaction_tags(tag)"),
+                MigrationStep::Sql(r#"
+                CREATE TRIGGER transaction_tags_insert AFTER INSERT ON transactions BEGIN
+                    INSERT OR IGNORE INTO transaction_tags (transaction_id, tag)
+                    SELECT new.id, TRIM(value) FROM json_each(CASE
+                        WHEN NOT json_valid(new.tags) THEN '[]'
+                        WHEN json_type(new.tags) = 'text' AND json_valid(json_extract(new.tags, '$')) THEN json_extract(new.tags, '$')
+                        ELSE new.tags
+                    END)
+                    WHERE type = 'text' AND TRIM(value) <> '';
+                END
+                "#),
+                MigrationStep::Sql(r#"
+                CREATE TRIGGER transaction_tags_update AFTER UPDATE OF id, tags ON transactions BEGIN
+                    DELETE FROM transaction_tags WHERE transaction_id IN (old.id, new.id);
+                    INSERT OR IGNORE INTO transaction_tags (transaction_id, tag)
+                    SELECT new.id, TRIM(value) FROM json_each(CASE
+                        WHEN NOT json_valid(new.tags) THEN '[]'
+                        WHEN json_type(new.tags) = 'text' AND json_valid(json_extract(new.tags, '$')) THEN json_extract(new.tags, '$')
+                        ELSE new.tags
+                    END)
+                    WHERE type = 'text' AND TRIM(value) <> '';
+                END
+                "#),
+                MigrationStep::Sql(r#"
+                INSERT OR IGNORE INTO transaction_tags (transaction_id, tag)
+                SELECT t.id, TRIM(j.value) FROM transactions t, json_each(CASE
+                        WHEN NOT json_valid(t.tags) THEN '[]'
+                        WHEN json_type(t.tags) = 'text' AND json_valid(json_extract(t.tags, '$')) THEN json_extract(t.tags, '$')
+                        ELSE t.tags
+                    END) j
+                WHERE j.type = 'text' AND TRIM(j.value) <> ''
+                "#),
+            ],
+        },
+        Migration {
+            version: 18,
+            description: "import hashes for stored transactions",
+            // Transactions imported before hashing, or added by hand, can then
+            // be recognized when a statement containing them is imported
+            steps: vec![MigrationStep::BackfillImportHashes],
+        },
+        Migration {
+            version: 19,
+            description: "settings.budget_month_start_day",
+            steps: vec![MigrationStep::Sql("ALTER TABLE settings ADD COLUMN budget_month_start_day INTEGER NOT NULL DEFAULT 1")],
+        },
+        Migration {
+            version: 20,
+            description: "settings.encrypt_notes",
+            steps: vec![MigrationStep::Sql("ALTER TABLE settings ADD COLUMN encrypt_notes BOOLEAN NOT NULL DEFAULT TRUE")],
+        },
+        Migration {
+            version: 21,
+            description: "transaction tags that are not JSON",
+            // A `tags` value that is not JSON is one bare tag, as `parse_tags`
+            // reads it, instead of no tags at all
+            steps: vec![
+                MigrationStep::Sql("DROP TRIGGER transaction_tags_insert"),
+                MigrationStep::Sql("DROP TRIGGER transaction_tags_update"),
+                MigrationStep::Sql(r#"
+                CREATE TRIGGER transaction_tags_insert AFTER INSERT ON transactions BEGIN
+                    INSERT OR IGNORE INTO transaction_tags (transaction_id, tag)
+                    SELECT new.id, TRIM(value) FROM json_each(CASE
+                        WHEN NOT json_valid(new.tags) THEN json_array(new.tags)
+                        WHEN json_type(new.tags) = 'text' AND json_valid(json_extract(new.tags, '$')) THEN json_extract(new.tags, '$')
+                        ELSE new.tags
+                    END)
+                    WHERE type = 'text' AND TRIM(value) <> '';
+                END
+                "#),
+                MigrationStep::Sql(r#"
+                CREATE TRIGGER transaction_tags_update AFTER UPDATE OF id, tags ON transactions BEGIN
+                    DELETE FROM transaction_tags WHERE transaction_id IN (old.id, new.id);
+                    INSERT OR IGNORE INTO transaction_tags (transaction_id, tag)
+                    SELECT new.id, TRIM(value) FROM json_each(CASE
+                        WHEN NOT json_valid(new.tags) THEN json_array(new.tags)
+                        WHEN json_type(new.tags) = 'text' AND json_valid(json_extract(new.tags, '$')) THEN json_extract(new.tags, '$')
+                        ELSE new.tags
+                    END)
+                    WHERE type = 'text' AND TRIM(value) <> '';
+                END
+                "#),
+                MigrationStep::Sql(r#"
+                INSERT OR IGNORE INTO transaction_tags (transaction_id, tag)
+                SELECT id, TRIM(tags) FROM transactions
+                WHERE NOT json_valid(tags) AND TRIM(tags) <> ''
+                "#),
+            ],
+        },
+    ]
+}
+
+/// Default category rules, the keywords auto-categorization used to have built
+/// in, for the default categories. Short merchant codes like "ah" and "ns" are
+/// regexes on word boundaries so they don't match inside other words.
+const SEED_CATEGORY_RULES: &str = r#"
+    INSERT INTO category_rules (id, match_text, match_type, category_id, priority, created_at)
+    SELECT lower(hex(randomblob(16))), d.column1, d.column2, c.id, 0, CURRENT_TIMESTAMP
+    FROM (VALUES
+        ('albert heijn', 'contains', 'Boodschappen'),
+        ('\bah\b', 'regex', 'Boodschappen'),
+        ('jumbo', 'contains', 'Boodschappen'),
+        ('\bplus\b', 'regex', 'Boodschappen'),
+        ('dirk', 'contains', 'Boodschappen'),
+        ('c1000', 'contains', 'Boodschappen'),
+        ('vomar', 'contains', 'Boodschappen'),
+        ('dekamarkt', 'contains', 'Boodschappen'),
+        ('ekoplaza', 'contains', 'Boodschappen'),
+        ('picnic', 'contains', 'Boodschappen'),
+        ('gorillas', 'contains', 'Boodschappen'),
+        ('\bflink\b', 'regex', 'Boodschappen'),
+        ('crisp', 'contains', 'Boodschappen'),
+        ('restaurant', 'contains', 'Eten & Drinken'),
+        ('cafe', 'contains', 'Eten & Drinken'),
+        ('\bbar\b', 'regex', 'Eten & Drinken'),
+        ('lunch', 'contains', 'Eten & Drinken'),
+        ('diner', 'contains', 'Eten & Drinken'),
+        ('mcdonald', 'contains', 'Eten & Drinken'),
+        ('burger king', 'contains', 'Eten & Drinken'),
+        ('\bbk\b', 'regex', 'Eten & Drinken'),
+        ('kfc', 'contains', 'Eten & Drinken'),
+        ('subway', 'contains', 'Eten & Drinken'),
+        ('dominos', 'contains', 'Eten & Drinken'),
+        ('huur', 'contains', 'Huur'),
+        ('hypotheek', 'contains', 'Huur'),
+        ('\bvve\b', 'regex', 'Huur'),
+        ('energie', 'contains', 'Utilities'),
+        ('\bgas\b', 'regex', 'Utilities'),
+        ('elektra', 'contains', 'Utilities'),
+        ('water', 'contains', 'Utilities'),
+        ('kpn', 'contains', 'Utilities'),
+        ('vodafone', 'contains', 'Utilities'),
+        ('t-mobile', 'contains', 'Utilities'),
+        ('ziggo', 'contains', 'Utilities'),
+        ('tele2', 'contains', 'Utilities'),
+        ('\bns\b', 'regex', 'Vervoer'),
+        ('\bov\b', 'regex', 'Vervoer'),
+        ('trein', 'contains', 'Vervoer'),
+        ('\bbus\b', 'regex', 'Vervoer'),
+        ('tram', 'contains', 'Vervoer'),
+        ('metro', 'contains', 'Vervoer'),
+        ('benzine', 'contains', 'Vervoer'),
+        ('shell', 'contains', 'Vervoer'),
+        ('\bbp\b', 'regex', 'Vervoer'),
+        ('\btotal\b', 'regex', 'Vervoer'),
+        ('netflix', 'contains', 'Entertainment'),
+        ('spotify', 'contains', 'Entertainment'),
+        ('videoland', 'contains', 'Entertainment'),
+        ('bol.com', 'contains', 'Entertainment'),
+        ('amazon', 'contains', 'Entertainment'),
+        ('coolblue', 'contains', 'Entertainment'),
+        ('h&m', 'contains', 'Kleding'),
+        ('zara', 'contains', 'Kleding'),
+        ('c&a', 'contains', 'Kleding'),
+        ('we fashion', 'contains', 'Kleding'),
+        ('bijenkorf', 'contains', 'Kleding'),
+        ('apotheek', 'contains', 'Gezondheid'),
+        ('huisarts', 'contains', 'Gezondheid'),
+        ('ziekenhuis', 'contains', 'Gezondheid'),
+        ('tandarts', 'contains', 'Gezondheid'),
+        ('\bcz\b', 'regex', 'Gezondheid'),
+        ('menzis', 'contains', 'Gezondheid'),
+        ('sportschool', 'contains', 'Gezondheid'),
+        ('fitness', 'contains', 'Gezondheid'),
+        ('\bgym\b', 'regex', 'Gezondheid'),
+        ('basic-fit', 'contains', 'Gezondheid'),
+        ('salaris', 'contains', 'Inkomen'),
+        ('\bloon\b', 'regex', 'Inkomen'),
+        ('inkomen', 'contains', 'Inkomen')
+    ) AS d
+    JOIN categories c ON c.name = d.column3
+    WHERE NOT EXISTS (
+        SELECT 1 FROM category_rules r WHERE r.match_text = d.column1 AND r.match_type = d.column2
+    )
+"#;
+
+/// `value` as a quoted SQL string, for PRAGMA values that cannot be bound.
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Connection options shared by the app database and snapshots of it, opened
+/// with the SQLCipher `key`.
+fn connect_options(path: &str, key: &str) -> AppResult<SqliteConnectOptions> {
+    // Use the path directly for Windows - SQLX will handle it properly
+    // For SQLX compile-time verification, use unencrypted connection
+    // For runtime, apply encryption if enabled
+    let is_compile_time = std::env::var("SQLX_OFFLINE").is_ok() && std::env::var("SQLX_OFFLINE").unwrap() == "true";
+
+    let mut connect_options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path))?
+        .busy_timeout(std::time::Duration::from_secs(30));
+
+    // Only apply encryption pragmas at runtime, not during compile-time verification
+    if !is_compile_time {
+        // SQLCipher pragmas for encryption
+        connect_options = connect_options
+            .pragma("key", sql_string(key))
+            .pragma("cipher_page_size", "4096")
+            .pragma("kdf_iter", "256000")
+            .pragma("cipher_hmac_algorithm", "HMAC_SHA512")
+            .pragma("cipher_kdf_algorithm", "PBKDF2_HMAC_SHA512");
+    }
+
+    Ok(connect_options)
+}
+
 pub struct Database {
     pool: Arc<Mutex<Option<SqlitePool>>>,
     path: String,
+    /// SQLCipher key the database is opened with
+    key: String,
 }
 
 impl Database {
+    /// Open the database with the legacy key (see
+    /// [`crate::encryption::legacy_database_key`]), as tests and installs
+    /// without a passphrase do.
     pub async fn new(database_path: &str) -> AppResult<Self> {
-        let db = Self {
+        let mut db = Self::locked(database_path);
+        db.unlock(crate::encryption::legacy_database_key()).await?;
+        Ok(db)
+    }
+
+    /// The database at `database_path`, not opened yet. Until [`Database::unlock`]
+    /// succeeds every use of the pool is refused.
+    pub fn locked(database_path: &str) -> Self {
+        Self {
             pool: Arc::new(Mutex::new(None)),
             path: database_path.to_string(),
-        };
+            key: String::new(),
+        }
+    }
 
-        // Initialize database
-        db.initialize().await?;
+    /// Open the database with `key`, creating it and bringing its schema up to
+    /// date as needed. On failure the database stays locked.
+    pub async fn unlock(&mut self, key: String) -> AppResult<()> {
+        self.key = key;
 
-        Ok(db)
+        if let Err(e) = self.initialize().await {
+            if let Some(pool) = self.pool.lock().await.take() {
+                pool.close().await;
+            }
+            return Err(e);
+        }
+        Ok(())
     }
 
-    async fn initialize(&self) -> AppResult<()> {
-        // Use the path directly for Windows - SQLX will handle it properly
-        // For SQLX compile-time verification, use unencrypted connection
-        // For runtime, apply encryption if enabled
-        let is_compile_time = std::env::var("SQLX_OFFLINE").is_ok() && std::env::var("SQLX_OFFLINE").unwrap() == "true";
+    pub async fn is_locked(&self) -> bool {
+        self.pool.lock().await.is_none()
+    }
 
-        let mut connect_options = SqliteConnectOptions::from_str(&format!("sqlite:{}", self.path))?
+    async fn initialize(&self) -> AppResult<()> {
+        let connect_options = connect_options(&self.path, &self.key)?
             .create_if_missing(true)
             .journal_mode(SqliteJournalMode::Wal)
-            .synchronous(SqliteSynchronous::Normal)
-            .busy_timeout(std::time::Duration::from_secs(30));
-
-        // Only apply encryption pragmas at runtime, not during compile-time verification
-        if !is_compile_time {
-            // Configure SQLite connection with encryption
-            // Note: SQLCipher encryption key would be set here in production
-            // For now, we'll use a default key or get it from environment/config
-            let encryption_key = std::env::var("DB_ENCRYPTION_KEY").unwrap_or_else(|_| "spaarapp_default_key".to_string());
-
-            // SQLCipher pragmas for encryption
-            connect_options = connect_options
-                .pragma("key", encryption_key.clone())
-                .pragma("cipher_page_size", "4096")
-                .pragma("kdf_iter", "256000")
-                .pragma("cipher_hmac_algorithm", "HMAC_SHA512")
-                .pragma("cipher_kdf_algorithm", "PBKDF2_HMAC_SHA512");
-        }
+            .synchronous(SqliteSynchronous::Normal);
 
         // Create connection pool
         let pool = SqlitePoolOptions::new()
@@ -72,134 +768,31 @@ impl Database {
         Ok(())
     }
 
+    /// Apply the migrations this database has not had yet, in one transaction.
     async fn migrate(&self) -> AppResult<()> {
         let pool = self.pool.lock().await;
         let pool = pool.as_ref().ok_or("Database not initialized")?;
 
-        // Create tables
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS settings (
-                id TEXT PRIMARY KEY,
-                currency TEXT NOT NULL DEFAULT 'EUR',
-                date_format TEXT NOT NULL DEFAULT 'DD-MM-YYYY',
-                theme TEXT NOT NULL DEFAULT 'light',
-                language TEXT NOT NULL DEFAULT 'nl',
-                notifications_enabled BOOLEAN NOT NULL DEFAULT TRUE,
-                auto_categorization_enabled BOOLEAN NOT NULL DEFAULT TRUE,
-                ai_insights_enabled BOOLEAN NOT NULL DEFAULT TRUE,
-                budget_alerts_enabled BOOLEAN NOT NULL DEFAULT TRUE,
-                data_retention_days INTEGER NOT NULL DEFAULT 365,
-                export_format TEXT NOT NULL DEFAULT 'csv',
-                encryption_enabled BOOLEAN NOT NULL DEFAULT TRUE,
-                last_backup TEXT,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
-
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS categories (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL UNIQUE,
-                description TEXT,
-                color TEXT NOT NULL DEFAULT '#2196F3',
-                icon TEXT NOT NULL DEFAULT 'category',
-                parent_id TEXT,
-                is_system BOOLEAN NOT NULL DEFAULT FALSE,
-                budget_percentage REAL,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (parent_id) REFERENCES categories(id)
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
+        sqlx::query(CREATE_SCHEMA_MIGRATIONS).execute(pool).await?;
 
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS transactions (
-                id TEXT PRIMARY KEY,
-                description TEXT NOT NULL,
-                amount DECIMAL(15,2) NOT NULL,
-                date DATETIME NOT NULL,
-                category_id TEXT,
-                account_number TEXT,
-                account_holder TEXT,
-                transaction_type TEXT NOT NULL DEFAULT 'debit',
-                balance_after DECIMAL(15,2),
-                notes TEXT,
-                tags TEXT DEFAULT '[]',
-                is_recurring BOOLEAN NOT NULL DEFAULT FALSE,
-                recurring_frequency TEXT,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (category_id) REFERENCES categories(id)
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
-
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS budgets (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                category_id TEXT,
-                amount DECIMAL(15,2) NOT NULL,
-                period TEXT NOT NULL DEFAULT 'monthly',
-                spent DECIMAL(15,2) NOT NULL DEFAULT 0,
-                remaining DECIMAL(15,2) GENERATED ALWAYS AS (amount - spent) STORED,
-                is_active BOOLEAN NOT NULL DEFAULT TRUE,
-                notification_threshold DECIMAL(15,2),
-                start_date DATETIME NOT NULL,
-                end_date DATETIME,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (category_id) REFERENCES categories(id)
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
+        let current = applied_version(&mut *pool.acquire().await?).await?;
+        if current > SCHEMA_VERSION {
+            tracing::warn!("Database schema version {} is newer than this app ({})", current, SCHEMA_VERSION);
+        }
 
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS financial_insights (
-                id TEXT PRIMARY KEY,
-                insight_type TEXT NOT NULL,
-                title TEXT NOT NULL,
-                description TEXT NOT NULL,
-                impact TEXT NOT NULL,
-                actionable BOOLEAN NOT NULL DEFAULT TRUE,
-                action_suggestions TEXT DEFAULT '[]',
-                confidence_score REAL NOT NULL,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(pool)
-        .await?;
+        let pending: Vec<Migration> = migrations().into_iter().filter(|m| m.version > current).collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
 
-        // Create indexes for better performance
-        let indexes = vec![
-            "CREATE INDEX IF NOT EXISTS idx_transactions_date ON transactions(date)",
-            "CREATE INDEX IF NOT EXISTS idx_transactions_category ON transactions(category_id)",
-            "CREATE INDEX IF NOT EXISTS idx_transactions_type ON transactions(transaction_type)",
-            "CREATE INDEX IF NOT EXISTS idx_transactions_recurring ON transactions(is_recurring)",
-            "CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id)",
-            "CREATE INDEX IF NOT EXISTS idx_budgets_active ON budgets(is_active)",
-            "CREATE INDEX IF NOT EXISTS idx_budgets_category ON budgets(category_id)",
-        ];
+        let mut tx = pool.begin().await?;
+        for migration in &pending {
+            migration.apply(&mut tx).await?;
+        }
+        tx.commit().await?;
 
-        for index in indexes {
-            sqlx::query(index).execute(pool).await?;
+        for migration in &pending {
+            tracing::info!("Applied schema migration {}: {}", migration.version, migration.description);
         }
 
         Ok(())
@@ -251,9 +844,10 @@ impl Database {
             ("Inkomen", "#8BC34A", "account_balance"),
         ];
 
+        let mut created_categories = 0;
         for (name, color, icon) in default_categories {
             let id = Uuid::new_v4().to_string();
-            sqlx::query(
+            created_categories += sqlx::query(
                 r#"
                 INSERT OR IGNORE INTO categories (id, name, color, icon, is_system, created_at, updated_at)
                 VALUES (?, ?, ?, ?, ?, ?, ?)
@@ -267,7 +861,14 @@ impl Database {
             .bind(Utc::now())
             .bind(Utc::now())
             .execute(pool)
-            .await?;
+            .await?
+            .rows_affected();
+        }
+
+        // A new database gets its categories only now, after the migration
+        // that seeds the default rules for existing ones
+        if created_categories > 0 {
+            sqlx::query(SEED_CATEGORY_RULES).execute(pool).await?;
         }
 
         Ok(())
@@ -276,12 +877,190 @@ impl Database {
     pub async fn get_pool(&self) -> AppResult<SqlitePool> {
         let pool = self.pool.lock().await;
         pool.as_ref()
-            .ok_or(AppError::Database(sqlx::Error::Configuration("Database not initialized".into())))
+            .ok_or_else(|| AppError::PermissionDenied("Database is vergrendeld; ontgrendel eerst met uw wachtwoord".to_string()))
             .cloned()
     }
+
+    /// Path of the SQLite database file
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// SQLCipher key the database is opened with
+    pub(crate) fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Write a consistent copy of the database to `target`, which must not exist yet.
+    pub async fn snapshot_to(&self, target: &Path) -> AppResult<()> {
+        let pool = self.get_pool().await?;
+        sqlx::query("VACUUM INTO ?")
+            .bind(target.to_string_lossy().to_string())
+            .execute(&pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Replace the database file with `source`, which opens with `key`, and
+    /// reopen it, bringing its schema up to date. `source` should be on the
+    /// same file system so the swap is a single rename.
+    pub async fn replace_with(&mut self, source: &Path, key: &str) -> AppResult<()> {
+        if let Some(pool) = self.pool.lock().await.take() {
+            pool.close().await;
+        }
+
+        for suffix in ["-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path, suffix));
+        }
+        std::fs::rename(source, &self.path)?;
+        self.key = key.to_string();
+
+        self.initialize().await
+    }
+}
+
+/// Open a single connection to the database file at `path` with `key`, e.g.
+/// to work on a snapshot before it replaces the database.
+pub async fn connect_file(path: &Path, key: &str) -> AppResult<SqliteConnection> {
+    Ok(SqliteConnection::connect_with(&connect_options(&path.to_string_lossy(), key)?).await?)
+}
+
+/// Re-encrypt the database of `conn` with `new_key` (SQLCipher `PRAGMA rekey`).
+/// SQLCipher rewrites every page in one transaction, so a failure leaves the
+/// file readable with the old key.
+pub async fn rekey(conn: &mut SqliteConnection, new_key: &str) -> AppResult<()> {
+    sqlx::query(&format!("PRAGMA rekey = {}", sql_string(new_key)))
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Schema version of the database file at `path`, after checking that the file
+/// is a readable, intact database. `path` should be a copy, such as a staged
+/// restore, as the check opens it for writing.
+pub async fn file_schema_version(path: &Path, key: &str) -> AppResult<i64> {
+    // Not read-only: the check of the full-text search index needs to write.
+    // Callers pass a staged copy, never the live database.
+    let options = connect_options(&path.to_string_lossy(), key)?;
+    let mut conn = SqliteConnection::connect_with(&options).await?;
+
+    let check: String = sqlx::query_scalar("PRAGMA quick_check").fetch_one(&mut conn).await?;
+    let version = applied_version(&mut conn).await?;
+    conn.close().await?;
+
+    if check != "ok" {
+        return Err(AppError::Validation(format!("Databasebestand is beschadigd: {}", check)));
+    }
+    Ok(version)
 }
 
 // Database helper functions
+
+/// Read a DECIMAL column as `Decimal`.
+///
+/// The columns are written as text, but SQLite's NUMERIC affinity hands them
+/// back as INTEGER or REAL, so every representation is accepted here.
+pub fn get_decimal(row: &SqliteRow, column: &str) -> Decimal {
+    get_optional_decimal(row, column).unwrap_or_default()
+}
+
+/// Read a nullable DECIMAL column as `Option<Decimal>`.
+pub fn get_optional_decimal(row: &SqliteRow, column: &str) -> Option<Decimal> {
+    if let Ok(value) = row.try_get::<Option<String>, _>(column) {
+        return value.and_then(|s| s.parse().ok());
+    }
+    if let Ok(value) = row.try_get::<Option<i64>, _>(column) {
+        return value.map(Decimal::from);
+    }
+    row.try_get::<Option<f64>, _>(column)
+        .ok()
+        .flatten()
+        .and_then(Decimal::from_f64)
+}
+
+/// Store the import hash of every transaction that has none. Of transactions
+/// with the same content only the oldest gets it, as the hash is unique.
+async fn backfill_import_hashes(conn: &mut SqliteConnection) -> AppResult<()> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, date, amount, description, account_number
+        FROM transactions
+        WHERE import_hash IS NULL
+        ORDER BY created_at, id
+        "#
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for row in &rows {
+        let hash = import_hash_v18(
+            row.get("date"),
+            get_decimal(row, "amount"),
+            row.get("description"),
+            row.get::<Option<&str>, _>("account_number"),
+        );
+        sqlx::query("UPDATE OR IGNORE transactions SET import_hash = ? WHERE id = ?")
+            .bind(hash)
+            .bind(row.get::<String, _>("id"))
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// The import hash as migration 18 stored it: a copy of
+/// `csv_import::import_hash` at that version, so the migration keeps writing
+/// the same hashes whatever that function becomes.
+fn import_hash_v18(date: DateTime<Utc>, amount: Decimal, description: &str, account_number: Option<&str>) -> String {
+    let description = description.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let account = account_number.unwrap_or("").replace(' ', "").to_uppercase();
+    let content = format!("{}|{}|{}|{}", date.format("%Y-%m-%d"), amount.round_dp(2).normalize(), description, account);
+
+    ring::digest::digest(&ring::digest::SHA256, content.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Add a column to an existing table unless it is already present.
+async fn add_column_if_missing(
+    conn: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> AppResult<()> {
+    let columns = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    if !columns.iter().any(|c| c.get::<String, _>("name") == column) {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Highest migration version recorded in the database, 0 when none are.
+async fn applied_version(conn: &mut SqliteConnection) -> AppResult<i64> {
+    let has_table: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations')"
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if !has_table {
+        return Ok(0);
+    }
+
+    let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_migrations")
+        .fetch_one(&mut *conn)
+        .await?;
+    Ok(version.unwrap_or(0))
+}
+
 pub async fn execute_query<T>(
     pool: &SqlitePool,
     query: &str,
@@ -372,4 +1151,183 @@ impl Database {
             "encrypted": self.verify_encryption().await.unwrap_or(false)
         }))
     }
-}
\ No newline at end of file
+}
+
+/// Database in a temporary directory of its own for tests. The directory,
+/// with the database and anything else a test puts in it, is removed on drop.
+#[cfg(test)]
+pub(crate) struct TestDatabase {
+    pub db: Database,
+    pub dir: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl TestDatabase {
+    /// Opened and migrated.
+    pub async fn new() -> Self {
+        let mut test_db = Self::locked();
+        test_db.db.unlock(crate::encryption::legacy_database_key()).await.unwrap();
+        test_db
+    }
+
+    /// Not opened yet, as before the passphrase is entered.
+    pub fn locked() -> Self {
+        let dir = std::env::temp_dir().join(format!("spaarapp-test-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::locked(dir.join("spaarapp.db").to_str().unwrap());
+        Self { db, dir }
+    }
+
+    pub async fn pool(&self) -> SqlitePool {
+        self.db.get_pool().await.unwrap()
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TestDatabase {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        &self.db
+    }
+}
+
+#[cfg(test)]
+impl std::ops::DerefMut for TestDatabase {
+    fn deref_mut(&mut self) -> &mut Database {
+        &mut self.db
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database with only the migrations before `version` applied, and an
+    /// open connection to fill it with data for that migration to work on.
+    /// Unlocking it applies the rest.
+    async fn database_before(version: i64) -> (TestDatabase, SqliteConnection) {
+        let db = TestDatabase::locked();
+        let options = connect_options(db.path(), &crate::encryption::legacy_database_key()).unwrap();
+        let mut conn = SqliteConnection::connect_with(&options.create_if_missing(true)).await.unwrap();
+
+        sqlx::query(CREATE_SCHEMA_MIGRATIONS).execute(&mut conn).await.unwrap();
+        for migration in migrations().iter().take_while(|m| m.version < version) {
+            migration.apply(&mut conn).await.unwrap();
+        }
+        (db, conn)
+    }
+
+    #[tokio::test]
+    async fn test_migrations_apply_once() {
+        let versions: Vec<i64> = migrations().iter().map(|m| m.version).collect();
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(versions.last(), Some(&SCHEMA_VERSION));
+
+        let db = TestDatabase::new().await;
+        db.migrate().await.unwrap();
+
+        let pool = db.pool().await;
+        let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM schema_migrations ORDER BY version")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(applied, versions);
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_transaction_tags_backfill() {
+
+        // The schema before the tags table, with tags in every stored shape
+        let (mut db, mut conn) = database_before(17).await;
+        let now = Utc::now();
+        for (id, tags) in [
+            ("array", r#"["iDEAL", " online ", "ideal", ""]"#),
+            ("double", r#""[\"pinbetaling\"]""#),
+            ("empty", "[]"),
+            ("broken", "iDEAL"),
+        ] {
+            sqlx::query(
+                "INSERT INTO transactions (id, description, amount, date, transaction_type, tags, created_at, updated_at) VALUES (?, 'Test', '1.00', ?, 'debit', ?, ?, ?)"
+            )
+            .bind(id)
+            .bind(now)
+            .bind(tags)
+            .bind(now)
+            .bind(now)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        }
+        conn.close().await.unwrap();
+
+        db.unlock(crate::encryption::legacy_database_key()).await.unwrap();
+        let pool = db.pool().await;
+
+        let tags: Vec<(String, String)> =
+            sqlx::query_as("SELECT transaction_id, tag FROM transaction_tags ORDER BY transaction_id, tag")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        let expected = [("array", "iDEAL"), ("array", "online"), ("broken", "iDEAL"), ("double", "pinbetaling")];
+        assert_eq!(tags, expected.map(|(id, tag)| (id.to_string(), tag.to_string())));
+
+        // The triggers are back too
+        sqlx::query("UPDATE transactions SET tags = '[\"huur\"]' WHERE id = 'empty'").execute(&pool).await.unwrap();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transaction_tags WHERE tag = 'HUUR'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_import_hash_backfill() {
+        let (mut db, mut conn) = database_before(18).await;
+        let date = chrono::TimeZone::with_ymd_and_hms(&Utc, 2024, 3, 1, 0, 0, 0).unwrap();
+        for (id, description, created) in [("first", "Albert Heijn", 1), ("copy", "Albert  Heijn", 2), ("other", "Jumbo", 3)] {
+            let created_at = date + chrono::Duration::hours(created);
+            sqlx::query(
+                "INSERT INTO transactions (id, description, amount, date, account_number, transaction_type, created_at, updated_at) VALUES (?, ?, '12.50', ?, 'NL01RABO0123456789', 'debit', ?, ?)"
+            )
+            .bind(id)
+            .bind(description)
+            .bind(date)
+            .bind(created_at)
+            .bind(created_at)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        }
+        conn.close().await.unwrap();
+
+        db.unlock(crate::encryption::legacy_database_key()).await.unwrap();
+        let pool = db.pool().await;
+
+        let hashed: Vec<(String, Option<String>)> = sqlx::query_as("SELECT id, import_hash FROM transactions ORDER BY created_at")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        let first = crate::commands::transactions::fetch_transaction(&mut pool.acquire().await.unwrap(), "first")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(hashed[0].1.as_deref(), Some(crate::commands::csv_import::import_hash(&first).as_str()));
+        // Same content as the first, so it stays without a hash
+        assert_eq!(hashed[1].1, None);
+        assert!(hashed[2].1.is_some());
+
+        pool.close().await;
+    }
+}
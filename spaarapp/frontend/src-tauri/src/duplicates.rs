@@ -0,0 +1,217 @@
+use crate::models::Transaction;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Pairs whose descriptions are less alike than this are not reported
+const MIN_DESCRIPTION_SIMILARITY: f64 = 0.3;
+
+/// Two transactions that look like the same purchase recorded twice.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateCandidate {
+    pub first: Transaction,
+    pub second: Transaction,
+    pub days_apart: i64,
+    pub amount_difference: Decimal,
+    /// Similarity of the normalized descriptions, 0.0 - 1.0
+    pub description_similarity: f64,
+    /// Combined ranking score, 0.0 - 1.0; higher is more likely a duplicate
+    pub score: f64,
+}
+
+/// Lowercase and collapse whitespace so formatting differences between banks don't count
+fn normalize(description: &str) -> String {
+    description
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Edit-distance similarity of two descriptions, 1.0 for identical text.
+pub fn description_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = normalize(a).chars().collect();
+    let b: Vec<char> = normalize(b).chars().collect();
+    let longest = a.len().max(b.len());
+
+    if longest == 0 {
+        return 1.0;
+    }
+
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+/// Find pairs of transactions of the same type with amounts within `amount_tolerance`,
+/// at most `window_days` apart and with differing descriptions, ranked most likely first.
+pub fn find_near_duplicates(
+    transactions: &[Transaction],
+    window_days: u32,
+    amount_tolerance: Decimal,
+) -> Vec<DuplicateCandidate> {
+    let mut sorted: Vec<&Transaction> = transactions.iter().collect();
+    sorted.sort_by_key(|t| t.date);
+
+    let window = i64::from(window_days);
+    let mut candidates = Vec::new();
+
+    for (i, first) in sorted.iter().enumerate() {
+        for second in &sorted[i + 1..] {
+            let days_apart = (second.date - first.date).num_days();
+            if days_apart > window {
+                break;
+            }
+
+            if first.transaction_type != second.transaction_type {
+                continue;
+            }
+
+            let amount_difference = (first.amount - second.amount).abs();
+            if amount_difference > amount_tolerance {
+                continue;
+            }
+
+            if normalize(&first.description) == normalize(&second.description) {
+                continue;
+            }
+
+            let description_similarity = description_similarity(&first.description, &second.description);
+            if description_similarity < MIN_DESCRIPTION_SIMILARITY {
+                continue;
+            }
+
+            let date_closeness = if window == 0 {
+                1.0
+            } else {
+                1.0 - days_apart as f64 / (window + 1) as f64
+            };
+
+            candidates.push(DuplicateCandidate {
+                first: (*first).clone(),
+                second: (*second).clone(),
+                days_apart,
+                amount_difference,
+                description_similarity,
+                score: description_similarity * 0.7 + date_closeness * 0.3,
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
+
+/// Two separate charges by the same merchant for the same amount shortly after
+/// each other, which the user may want to dispute.
+#[derive(Debug, Clone, Serialize)]
+pub struct DoubleCharge {
+    pub first: Transaction,
+    pub second: Transaction,
+    pub hours_apart: i64,
+}
+
+/// Find debits with the same merchant and amount at most `window_hours` apart,
+/// most recent first. Unlike [`find_near_duplicates`] these are stored as two
+/// genuine transactions, so the descriptions must match.
+pub fn find_double_charges(transactions: &[Transaction], window_hours: u32) -> Vec<DoubleCharge> {
+    let mut debits: Vec<&Transaction> = transactions.iter().filter(|t| t.transaction_type == "debit").collect();
+    debits.sort_by_key(|t| t.date);
+
+    let window_minutes = i64::from(window_hours) * 60;
+    let mut charges = Vec::new();
+
+    for (i, first) in debits.iter().enumerate() {
+        for second in &debits[i + 1..] {
+            let minutes_apart = (second.date - first.date).num_minutes();
+            if minutes_apart > window_minutes {
+                break;
+            }
+
+            if first.amount == second.amount && normalize(&first.description) == normalize(&second.description) {
+                charges.push(DoubleCharge {
+                    first: (*first).clone(),
+                    second: (*second).clone(),
+                    hours_apart: minutes_apart / 60,
+                });
+                // A third identical charge pairs with the second one
+                break;
+            }
+        }
+    }
+
+    charges.sort_by_key(|c| std::cmp::Reverse(c.second.date));
+    charges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn transaction(id: &str, description: &str, amount: i64, day: u32) -> Transaction {
+        Transaction {
+            id: id.to_string(),
+            description: description.to_string(),
+            ..Transaction::test("debit", Decimal::new(amount, 2), Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap())
+        }
+    }
+
+    #[test]
+    fn test_similar_descriptions_are_paired() {
+        let transactions = vec![
+            transaction("a", "Albert Heijn 1234 Utrecht", 2345, 4),
+            transaction("b", "ALBERT HEIJN 1234", 2345, 5),
+            transaction("c", "NS Reizigers", 2345, 5),
+            transaction("d", "Albert Heijn 1234", 2345, 20),
+        ];
+
+        let candidates = find_near_duplicates(&transactions, 3, Decimal::ZERO);
+
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].first.id, "a");
+        assert_eq!(candidates[0].second.id, "b");
+    }
+
+    #[test]
+    fn test_double_charge_within_window() {
+        let mut transactions = vec![
+            transaction("a", "Bol.com", 4999, 4),
+            transaction("b", "BOL.COM", 4999, 5),
+            transaction("c", "Bol.com", 4999, 12),
+            transaction("d", "Bol.com", 1999, 12),
+        ];
+        transactions[1].date -= chrono::Duration::hours(2);
+
+        let charges = find_double_charges(&transactions, 24);
+
+        assert_eq!(charges.len(), 1);
+        assert_eq!((charges[0].first.id.as_str(), charges[0].second.id.as_str()), ("a", "b"));
+        assert_eq!(charges[0].hours_apart, 22);
+        assert!(find_double_charges(&transactions, 12).is_empty());
+    }
+
+    #[test]
+    fn test_amount_tolerance() {
+        let transactions = vec![
+            transaction("a", "Jumbo Amsterdam", 1000, 4),
+            transaction("b", "Jumbo Amsterdam Zuid", 1005, 4),
+        ];
+
+        assert!(find_near_duplicates(&transactions, 2, Decimal::ZERO).is_empty());
+        assert_eq!(find_near_duplicates(&transactions, 2, Decimal::new(10, 2)).len(), 1);
+    }
+}
@@ -3,9 +3,19 @@ use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use argon2::password_hash::{SaltString, rand_core::OsRng};
 use ring::aead::{AES_256_GCM, Aad, LessSafeKey, Nonce, UnboundKey};
 use ring::rand::{SecureRandom, SystemRandom};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+/// Prefix marking a database field value as encrypted by [`EncryptionManager::encrypt_field`].
+/// Values without it are legacy plaintext and are returned unchanged.
+pub const ENCRYPTED_FIELD_PREFIX: &str = "enc:v1:";
+
+/// File holding the [`EncryptionConfig`]
+pub const ENCRYPTION_CONFIG_FILE: &str = "encryption.json";
 
 pub struct EncryptionManager<'a> {
     key_derivation: Argon2<'a>,
@@ -23,6 +33,12 @@ impl<'a> EncryptionManager<'a> {
     }
 
     pub fn set_master_key(&mut self, password: &str, salt: &[u8]) -> AppResult<()> {
+        self.master_key = Some(self.derive_key(password, salt)?);
+        Ok(())
+    }
+
+    /// 256-bit key derived from `password` and `salt` with Argon2.
+    pub fn derive_key(&self, password: &str, salt: &[u8]) -> AppResult<[u8; 32]> {
         let salt_string = SaltString::encode_b64(salt)
             .map_err(|e| AppError::Encryption(format!("Failed to encode salt: {}", e)))?;
 
@@ -30,20 +46,46 @@ impl<'a> EncryptionManager<'a> {
             .hash_password(password.as_bytes(), &salt_string)
             .map_err(|e| AppError::Encryption(format!("Failed to derive key: {}", e)))?;
 
-        // Extract the hash bytes as our master key
-        let hash = password_hash.hash.unwrap();
+        // Extract the hash bytes as our key
+        let hash = password_hash.hash
+            .ok_or_else(|| AppError::Encryption("Key derivation produced no hash".to_string()))?;
         let key_bytes = hash.as_bytes();
 
         if key_bytes.len() >= 32 {
-            let mut master_key = [0u8; 32];
-            master_key.copy_from_slice(&key_bytes[..32]);
-            self.master_key = Some(master_key);
-            Ok(())
+            let mut key = [0u8; 32];
+            key.copy_from_slice(&key_bytes[..32]);
+            Ok(key)
         } else {
             Err(AppError::Encryption("Derived key too short".to_string()))
         }
     }
 
+    /// SQLCipher key for `passphrase`: a raw key derived with Argon2 and
+    /// `salt`, so SQLCipher does not run its own key derivation on top.
+    pub fn database_key(&self, passphrase: &str, salt: &[u8]) -> AppResult<String> {
+        let key = self.derive_key(passphrase, salt)?;
+        let hex: String = key.iter().map(|b| format!("{:02X}", b)).collect();
+        Ok(format!("x'{}'", hex))
+    }
+
+    /// Argon2 hash of `passphrase` with a salt of its own, to check the
+    /// passphrase later without storing it or anything a key is derived from.
+    pub fn passphrase_verifier(&self, passphrase: &str) -> AppResult<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        Ok(self.key_derivation
+            .hash_password(passphrase.as_bytes(), &salt)
+            .map_err(|e| AppError::Encryption(format!("Failed to hash passphrase: {}", e)))?
+            .to_string())
+    }
+
+    /// Whether `passphrase` is the one `verifier` was made from.
+    pub fn verify_passphrase(&self, passphrase: &str, verifier: &str) -> AppResult<bool> {
+        let parsed_hash = PasswordHash::new(verifier)
+            .map_err(|e| AppError::Encryption(format!("Invalid passphrase verifier: {}", e)))?;
+
+        Ok(self.key_derivation.verify_password(passphrase.as_bytes(), &parsed_hash).is_ok())
+    }
+
     pub fn generate_salt() -> AppResult<[u8; 16]> {
         let rng = SystemRandom::new();
         let mut salt = [0u8; 16];
@@ -115,6 +157,17 @@ impl<'a> EncryptionManager<'a> {
             .map_err(|e| AppError::Encryption(format!("UTF-8 decode failed: {}", e)))
     }
 
+    pub fn encrypt_field(&self, value: &str) -> AppResult<String> {
+        Ok(format!("{}{}", ENCRYPTED_FIELD_PREFIX, self.encrypt_string(value)?))
+    }
+
+    pub fn decrypt_field(&self, value: &str) -> AppResult<String> {
+        match value.strip_prefix(ENCRYPTED_FIELD_PREFIX) {
+            Some(encrypted) => self.decrypt_string(encrypted),
+            None => Ok(value.to_string()),
+        }
+    }
+
     pub fn verify_password(&self, password: &str, salt: &[u8]) -> AppResult<bool> {
         let salt_string = SaltString::encode_b64(salt)
             .map_err(|e| AppError::Encryption(format!("Failed to encode salt: {}", e)))?;
@@ -144,6 +197,25 @@ pub struct EncryptionConfig {
     pub iterations: u32,
     pub memory_cost: u32,
     pub parallelism: u32,
+    /// Days after which the key should be rotated; 0 turns the reminder off
+    #[serde(default = "default_key_rotation_days")]
+    pub key_rotation_days: u32,
+    /// When the key was last rotated or first set up; `None` in configs from
+    /// before key rotation existed
+    #[serde(default)]
+    pub last_key_rotation: Option<DateTime<Utc>>,
+    /// Salt the database key is derived from the passphrase with; `None`
+    /// until a passphrase is set
+    #[serde(default)]
+    pub database_salt: Option<String>,
+    /// Argon2 hash to check the passphrase against before unlocking; `None`
+    /// until a passphrase is set
+    #[serde(default)]
+    pub passphrase_verifier: Option<String>,
+}
+
+fn default_key_rotation_days() -> u32 {
+    90
 }
 
 impl Default for EncryptionConfig {
@@ -154,16 +226,40 @@ impl Default for EncryptionConfig {
             iterations: 100000,
             memory_cost: 65536,
             parallelism: 4,
+            key_rotation_days: default_key_rotation_days(),
+            last_key_rotation: Some(Utc::now()),
+            database_salt: None,
+            passphrase_verifier: None,
+        }
+    }
+}
+
+impl EncryptionConfig {
+    /// Whether the key is older than `key_rotation_days` at `now`. A key of
+    /// unknown age counts as due.
+    pub fn rotation_due(&self, now: DateTime<Utc>) -> bool {
+        if self.key_rotation_days == 0 {
+            return false;
+        }
+        match self.last_key_rotation {
+            Some(rotated) => now - rotated > Duration::days(self.key_rotation_days as i64),
+            None => true,
         }
     }
 }
 
+/// Write `config` to `path`. The file is replaced in one rename, so a failed
+/// write leaves the previous config (and with it the salt) intact.
 pub fn save_encryption_config<P: AsRef<Path>>(config: &EncryptionConfig, path: P) -> AppResult<()> {
     let config_json = serde_json::to_string_pretty(config)
         .map_err(|e| AppError::Serialization(e))?;
 
-    fs::write(path, config_json)
-        .map_err(|e| AppError::Io(e))?;
+    let mut partial = path.as_ref().as_os_str().to_os_string();
+    partial.push(".tmp");
+    fs::write(&partial, config_json)
+        .map_err(AppError::Io)?;
+    fs::rename(&partial, path)
+        .map_err(AppError::Io)?;
 
     Ok(())
 }
@@ -176,6 +272,39 @@ pub fn load_encryption_config<P: AsRef<Path>>(path: P) -> AppResult<EncryptionCo
         .map_err(|e| AppError::Serialization(e))
 }
 
+/// Key of installs from before passphrases, from `DB_ENCRYPTION_KEY` or the
+/// built-in default. Only used to open such a database once, to move it to a
+/// passphrase.
+pub fn legacy_database_key() -> String {
+    std::env::var("DB_ENCRYPTION_KEY").unwrap_or_else(|_| "spaarapp_default_key".to_string())
+}
+
+/// The encryption config at `config_path`, written with a fresh salt on first run.
+pub fn load_or_create_encryption_config<P: AsRef<Path>>(config_path: P) -> AppResult<EncryptionConfig> {
+    match load_encryption_config(&config_path) {
+        Ok(config) => Ok(config),
+        Err(_) => {
+            let config = EncryptionConfig::default();
+            save_encryption_config(&config, &config_path)?;
+            Ok(config)
+        }
+    }
+}
+
+/// Decode a base64 salt from the encryption config.
+pub fn decode_salt(salt: &str) -> AppResult<Vec<u8>> {
+    BASE64.decode(salt)
+        .map_err(|e| AppError::Encryption(format!("Invalid salt in encryption config: {}", e)))
+}
+
+/// Field encryption for an install still on the legacy key: the legacy key
+/// with the salt from the config.
+pub fn legacy_field_encryption(config: &EncryptionConfig) -> AppResult<EncryptionManager<'static>> {
+    let mut manager = EncryptionManager::new();
+    manager.set_master_key(&legacy_database_key(), &decode_salt(&config.salt)?)?;
+    Ok(manager)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,6 +323,53 @@ mod tests {
         assert_ne!(original, encrypted);
     }
 
+    #[test]
+    fn test_field_encryption_keeps_plaintext_readable() {
+        let mut manager = EncryptionManager::new();
+        let salt = EncryptionManager::generate_salt().unwrap();
+        manager.set_master_key("test_password", &salt).unwrap();
+
+        let encrypted = manager.encrypt_field("Tandarts rekening").unwrap();
+        assert!(encrypted.starts_with(ENCRYPTED_FIELD_PREFIX));
+        assert_eq!(manager.decrypt_field(&encrypted).unwrap(), "Tandarts rekening");
+        assert_eq!(manager.decrypt_field("Oude notitie").unwrap(), "Oude notitie");
+    }
+
+    #[test]
+    fn test_rotation_due_after_configured_days() {
+        let now = Utc::now();
+        let mut config = EncryptionConfig { last_key_rotation: Some(now - Duration::days(30)), ..Default::default() };
+        assert!(!config.rotation_due(now));
+
+        config.last_key_rotation = Some(now - Duration::days(91));
+        assert!(config.rotation_due(now));
+        config.key_rotation_days = 0;
+        assert!(!config.rotation_due(now));
+
+        // Configs written before rotation existed have no date and are due
+        let legacy: EncryptionConfig = serde_json::from_str(
+            r#"{"salt":"c2FsdA==","algorithm":"argon2id","iterations":100000,"memory_cost":65536,"parallelism":4}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.key_rotation_days, 90);
+        assert!(legacy.rotation_due(now));
+    }
+
+    #[test]
+    fn test_passphrase_verifier_and_database_key() {
+        let manager = EncryptionManager::new();
+        let salt = EncryptionManager::generate_salt().unwrap();
+
+        let verifier = manager.passphrase_verifier("correct horse battery").unwrap();
+        assert!(manager.verify_passphrase("correct horse battery", &verifier).unwrap());
+        assert!(!manager.verify_passphrase("correct horse", &verifier).unwrap());
+
+        let key = manager.database_key("correct horse battery", &salt).unwrap();
+        assert_eq!(key, manager.database_key("correct horse battery", &salt).unwrap());
+        assert!(key.starts_with("x'") && key.len() == 67);
+        assert_ne!(key, manager.database_key("correct horse battery", &EncryptionManager::generate_salt().unwrap()).unwrap());
+    }
+
     #[test]
     fn test_password_verification() {
         let mut manager = EncryptionManager::new();
@@ -20,9 +20,6 @@ pub enum AppError {
     #[error("HTTP request error: {0}")]
     Http(#[from] reqwest::Error),
 
-    #[error("Invalid input: {0}")]
-    InvalidInput(String),
-
     #[error("Not found: {0}")]
     NotFound(String),
 
@@ -42,12 +39,63 @@ pub enum AppError {
     Internal(String),
 }
 
+impl AppError {
+    /// Stable machine-readable code for the variant, so the frontend can
+    /// handle errors without parsing the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Database(_) => "DATABASE",
+            AppError::Serialization(_) => "SERIALIZATION",
+            AppError::Io(_) => "IO",
+            AppError::Encryption(_) => "ENCRYPTION",
+            AppError::Csv(_) => "CSV",
+            AppError::Http(_) => "HTTP",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::PermissionDenied(_) => "PERMISSION_DENIED",
+            AppError::Configuration(_) => "CONFIGURATION",
+            AppError::AiService(_) => "AI_SERVICE",
+            AppError::Validation(_) => "VALIDATION",
+            AppError::Internal(_) => "INTERNAL",
+        }
+    }
+
+    /// Friendly Dutch message for the user. Unlike `Display`, which is meant
+    /// for the logs, it never includes details of database, file or crypto
+    /// failures; only messages the commands write for the user themselves
+    /// (validation, not found, access) are passed through.
+    pub fn user_message(&self) -> String {
+        match self {
+            AppError::Database(_) => "Er is een probleem met de database opgetreden".to_string(),
+            AppError::Serialization(_) => "De gegevens konden niet worden verwerkt".to_string(),
+            AppError::Io(_) => "Een bestand kon niet worden gelezen of opgeslagen".to_string(),
+            AppError::Encryption(_) => "Er is een probleem met de beveiliging van uw gegevens opgetreden".to_string(),
+            AppError::Csv(_) => "Het CSV-bestand kon niet worden gelezen".to_string(),
+            AppError::Http(_) => "Er kon geen verbinding worden gemaakt met de externe dienst".to_string(),
+            AppError::Validation(msg) => format!("Invoerfout: {}", msg),
+            AppError::NotFound(msg) => format!("Niet gevonden: {}", msg),
+            AppError::PermissionDenied(msg) => format!("Geen toegang: {}", msg),
+            AppError::Configuration(_) => "De instellingen van de applicatie zijn ongeldig".to_string(),
+            AppError::AiService(_) => "De AI-dienst is tijdelijk niet beschikbaar. Probeer het later opnieuw".to_string(),
+            AppError::Internal(_) => "Er is een onverwachte fout opgetreden".to_string(),
+        }
+    }
+}
+
 impl serde::Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::ser::Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        use serde::ser::SerializeStruct;
+
+        // Errors are serialized when a command returns them to the frontend;
+        // the technical detail goes to the log, the user gets the friendly text
+        tracing::warn!("Command failed: {}", self);
+
+        let mut error = serializer.serialize_struct("AppError", 2)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.user_message())?;
+        error.end()
     }
 }
 
@@ -70,4 +118,47 @@ impl From<anyhow::Error> for AppError {
     }
 }
 
-pub type AppResult<T> = Result<T, AppError>;
\ No newline at end of file
+pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_serialize_with_code_and_message() {
+        let io = || std::io::Error::other("schijf vol");
+        let cases = vec![
+            (AppError::Database(sqlx::Error::RowNotFound), "DATABASE"),
+            (AppError::Serialization(serde_json::from_str::<u32>("x").unwrap_err()), "SERIALIZATION"),
+            (AppError::Io(io()), "IO"),
+            (AppError::Encryption("sleutel".to_string()), "ENCRYPTION"),
+            (AppError::Csv(csv::Error::from(io())), "CSV"),
+            (AppError::Http(reqwest::Client::new().get("geen url").build().unwrap_err()), "HTTP"),
+            (AppError::NotFound("budget".to_string()), "NOT_FOUND"),
+            (AppError::PermissionDenied("vergrendeld".to_string()), "PERMISSION_DENIED"),
+            (AppError::Configuration("instelling".to_string()), "CONFIGURATION"),
+            (AppError::AiService("model".to_string()), "AI_SERVICE"),
+            (AppError::Validation("bedrag".to_string()), "VALIDATION"),
+            (AppError::Internal("fout".to_string()), "INTERNAL"),
+        ];
+
+        for (error, code) in cases {
+            let json = serde_json::to_value(&error).unwrap();
+            assert_eq!(json["code"], code);
+            assert_eq!(json["message"], error.user_message());
+        }
+    }
+
+    #[test]
+    fn test_user_message_hides_technical_detail() {
+        let error = AppError::Database(sqlx::Error::Protocol("SELECT * FROM transactions WHERE notes = 'x'".to_string()));
+        assert!(error.to_string().contains("SELECT"));
+        assert_eq!(error.user_message(), "Er is een probleem met de database opgetreden");
+
+        let error = AppError::Encryption("Failed to derive key: salt too short".to_string());
+        assert!(!error.user_message().contains("salt"));
+
+        let error = AppError::Validation("Bedrag moet groter dan 0 zijn".to_string());
+        assert_eq!(error.user_message(), "Invoerfout: Bedrag moet groter dan 0 zijn");
+    }
+}
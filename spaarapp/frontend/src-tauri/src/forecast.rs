@@ -0,0 +1,391 @@
+use crate::ai_insights::insight_message;
+use crate::models::{FinancialInsight, Settings, Transaction};
+use crate::recurring::{median, split_off_recurring, upcoming_occurrences};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Projected net savings for the current calendar year.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavingsProjection {
+    pub year: i32,
+    pub as_of: DateTime<Utc>,
+    /// Income minus spending from January 1 until now
+    pub year_to_date_savings: Decimal,
+    /// Expected net savings on December 31 at the current pace
+    pub projected_savings: Decimal,
+    pub low_estimate: Decimal,
+    pub high_estimate: Decimal,
+    /// Net monthly effect of recurring income and expenses
+    pub recurring_monthly_net: Decimal,
+    /// Average daily net of everything that is not recurring
+    pub variable_daily_net: Decimal,
+    /// Net savings over the whole previous year, when there is data for it
+    pub last_year_savings: Option<Decimal>,
+}
+
+/// Amount with its effect on savings: income positive, spending negative.
+pub fn signed_amount(transaction: &Transaction) -> Decimal {
+    if transaction.transaction_type == "credit" {
+        transaction.amount.abs()
+    } else {
+        -transaction.amount.abs()
+    }
+}
+
+/// How many times per month a recurring frequency occurs.
+pub fn occurrences_per_month(frequency: &str) -> Option<Decimal> {
+    let per_year = match frequency.to_lowercase().as_str() {
+        "daily" | "dagelijks" => 365,
+        "weekly" | "wekelijks" => 52,
+        "biweekly" | "tweewekelijks" => 26,
+        "monthly" | "maandelijks" => 12,
+        "quarterly" | "per kwartaal" => 4,
+        "yearly" | "jaarlijks" => 1,
+        _ => return None,
+    };
+
+    Some(Decimal::from(per_year) / Decimal::from(12))
+}
+
+/// Project this year's net savings from year-to-date results, the recurring
+/// income/expense pattern and the pace of everything else.
+///
+/// `transactions` should cover at least the previous and the current year.
+pub fn project_year_end_savings(transactions: &[Transaction], now: DateTime<Utc>) -> SavingsProjection {
+    let year = now.year();
+    let year_start = Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap();
+    let year_end = Utc.with_ymd_and_hms(year + 1, 1, 1, 0, 0, 0).unwrap();
+
+    let this_year: Vec<&Transaction> = transactions
+        .iter()
+        .filter(|t| t.date >= year_start && t.date <= now)
+        .collect();
+    let last_year: Vec<&Transaction> = transactions
+        .iter()
+        .filter(|t| t.date.year() == year - 1)
+        .collect();
+
+    let year_to_date_savings: Decimal = this_year.iter().map(|t| signed_amount(t)).sum();
+
+    // Latest amount of each recurring stream seen in the past year, scaled to a month
+    let mut streams: HashMap<(String, String), &Transaction> = HashMap::new();
+    for transaction in transactions.iter().filter(|t| t.is_recurring && t.date <= now) {
+        if (now - transaction.date).num_days() > 366 {
+            continue;
+        }
+        let key = (transaction.description.to_lowercase(), transaction.transaction_type.clone());
+        let latest = streams.entry(key).or_insert(transaction);
+        if transaction.date > latest.date {
+            *latest = transaction;
+        }
+    }
+
+    let recurring_monthly_net: Decimal = streams
+        .values()
+        .filter_map(|t| {
+            let per_month = occurrences_per_month(t.recurring_frequency.as_deref()?)?;
+            Some(signed_amount(t) * per_month)
+        })
+        .sum();
+
+    // Everything that is not a known recurring stream runs at its year-to-date pace
+    let is_variable = |t: &&&Transaction| {
+        !(t.is_recurring && t.recurring_frequency.as_deref().and_then(occurrences_per_month).is_some())
+    };
+    let variable_ytd: Decimal = this_year.iter().filter(is_variable).map(|t| signed_amount(t)).sum();
+
+    let days_elapsed = ((now - year_start).num_seconds() as f64 / 86_400.0).max(1.0);
+    let days_remaining = ((year_end - now).num_seconds() as f64 / 86_400.0).max(0.0);
+    let months_remaining = days_remaining / (365.25 / 12.0);
+
+    let variable_daily_net = variable_ytd / decimal(days_elapsed);
+    let remaining = recurring_monthly_net * decimal(months_remaining) + variable_daily_net * decimal(days_remaining);
+    let projected_savings = year_to_date_savings + remaining;
+
+    // Spread of the variable part across completed months widens the band
+    let mut monthly_variable: HashMap<u32, Decimal> = HashMap::new();
+    for transaction in this_year.iter().filter(is_variable) {
+        if transaction.date.month() < now.month() {
+            *monthly_variable.entry(transaction.date.month()).or_insert(Decimal::ZERO) += signed_amount(transaction);
+        }
+    }
+
+    let half_width = if monthly_variable.len() >= 2 {
+        let values: Vec<f64> = monthly_variable.values().map(|v| v.to_f64().unwrap_or(0.0)).collect();
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+        decimal(variance.sqrt() * months_remaining.sqrt())
+    } else {
+        // Too little history to measure spread; stay wide
+        remaining.abs() / Decimal::from(2)
+    };
+
+    let last_year_savings = if last_year.is_empty() {
+        None
+    } else {
+        Some(last_year.iter().map(|t| signed_amount(t)).sum())
+    };
+
+    SavingsProjection {
+        year,
+        as_of: now,
+        year_to_date_savings: year_to_date_savings.round_dp(2),
+        projected_savings: projected_savings.round_dp(2),
+        low_estimate: (projected_savings - half_width).round_dp(2),
+        high_estimate: (projected_savings + half_width).round_dp(2),
+        recurring_monthly_net: recurring_monthly_net.round_dp(2),
+        variable_daily_net: variable_daily_net.round_dp(2),
+        last_year_savings: last_year_savings.map(|s: Decimal| s.round_dp(2)),
+    }
+}
+
+fn decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or(Decimal::ZERO)
+}
+
+/// Celebratory insight when the projection beats last year, cautionary when it falls short.
+pub fn savings_projection_insight(projection: &SavingsProjection, settings: &Settings) -> Option<FinancialInsight> {
+    let last_year = projection.last_year_savings?;
+    let projected = projection.projected_savings;
+    let beats_last_year = projected >= last_year;
+
+    let values = [
+        ("projected", settings.format_money(projected)),
+        ("year", projection.year.to_string()),
+        ("difference", settings.format_money((projected - last_year).abs())),
+        ("last_year", settings.format_money(last_year)),
+    ];
+    let text = |key: &str| insight_message(key, &settings.language, &values);
+
+    let (description, impact, suggestions) = if beats_last_year {
+        (text("savings_projection_ahead"), "low", vec![text("savings_set_aside")])
+    } else {
+        (
+            text("savings_projection_behind"),
+            if projected < Decimal::ZERO { "high" } else { "medium" },
+            vec![text("savings_review_categories"), text("savings_monthly_goal")],
+        )
+    };
+
+    Some(FinancialInsight {
+        id: uuid::Uuid::new_v4().to_string(),
+        insight_type: "savings_opportunity".to_string(),
+        title: text("savings_projection_title"),
+        description,
+        impact: impact.to_string(),
+        actionable: !beats_last_year,
+        action_suggestions: serde_json::to_string(&suggestions).unwrap_or_default(),
+        confidence_score: if projection.high_estimate - projection.low_estimate < projected.abs() { 0.8 } else { 0.5 },
+        is_read: false,
+        is_dismissed: false,
+        created_at: Utc::now(),
+    })
+}
+
+/// Days of recent history the day-to-day spending estimate is based on
+const DISCRETIONARY_HISTORY_DAYS: i64 = 90;
+
+/// Expected balance at the end of one day of a cashflow forecast.
+#[derive(Debug, Clone, Serialize)]
+pub struct CashflowDay {
+    pub date: NaiveDate,
+    /// Net of the recurring income and charges expected that day
+    pub recurring_net: Decimal,
+    pub balance: Decimal,
+}
+
+/// Day-by-day balance projection for the coming days.
+#[derive(Debug, Clone, Serialize)]
+pub struct CashflowForecast {
+    pub starting_balance: Decimal,
+    /// "mean" or "median" of the daily spending totals
+    pub spend_estimate: String,
+    /// Expected day-to-day spending per day, on top of the recurring charges
+    pub daily_discretionary_spend: Decimal,
+    pub days: Vec<CashflowDay>,
+    pub lowest_balance: Decimal,
+    pub lowest_balance_date: NaiveDate,
+    /// Set when the balance is expected to drop below zero
+    pub warning: Option<String>,
+}
+
+/// Project the balance for `days_ahead` days from `starting_balance`: the
+/// recurring series found in `transactions` are booked on their expected
+/// dates, and every day the usual day-to-day spending of the last
+/// [`DISCRETIONARY_HISTORY_DAYS`] days is taken off. The settings pick the
+/// mean or the median daily total; the median ignores the odd big purchase.
+pub fn forecast_cashflow(
+    transactions: &[Transaction],
+    starting_balance: Decimal,
+    days_ahead: u32,
+    settings: &Settings,
+    now: DateTime<Utc>,
+) -> CashflowForecast {
+    let today = now.date_naive();
+    let (series, rest) = split_off_recurring(transactions);
+
+    // Daily totals of the other debits, days without spending counting as zero
+    let history_start = transactions
+        .iter()
+        .map(|t| t.date.date_naive())
+        .min()
+        .unwrap_or(today)
+        .max(today - Duration::days(DISCRETIONARY_HISTORY_DAYS - 1));
+    let history_days = (today - history_start).num_days().max(0) + 1;
+    let mut daily_totals: HashMap<NaiveDate, Decimal> = HashMap::new();
+    for transaction in rest.iter().filter(|t| t.transaction_type == "debit") {
+        let date = transaction.date.date_naive();
+        if date >= history_start && date <= today {
+            *daily_totals.entry(date).or_default() += transaction.amount.abs();
+        }
+    }
+    let mut totals: Vec<Decimal> = (0..history_days)
+        .map(|day| daily_totals.get(&(history_start + Duration::days(day))).copied().unwrap_or_default())
+        .collect();
+    totals.sort();
+    let daily_discretionary_spend = if settings.cashflow_spend_estimate == "median" {
+        median(&totals)
+    } else {
+        totals.iter().sum::<Decimal>() / Decimal::from(history_days)
+    }
+    .round_dp(2);
+
+    // Charges still expected later today count on the first projected day
+    let mut recurring_by_day: HashMap<NaiveDate, Decimal> = HashMap::new();
+    for upcoming in upcoming_occurrences(&series, now, days_ahead) {
+        let date = upcoming.date.date_naive().max(today + Duration::days(1));
+        let signed = if upcoming.transaction_type == "credit" { upcoming.amount } else { -upcoming.amount };
+        *recurring_by_day.entry(date).or_default() += signed;
+    }
+
+    let mut balance = starting_balance;
+    let (mut lowest_balance, mut lowest_balance_date) = (starting_balance, today);
+    let mut first_negative = None;
+    let days: Vec<CashflowDay> = (1..=days_ahead as i64)
+        .map(|offset| {
+            let date = today + Duration::days(offset);
+            let recurring_net = recurring_by_day.get(&date).copied().unwrap_or_default();
+            balance += recurring_net - daily_discretionary_spend;
+
+            if balance < lowest_balance {
+                (lowest_balance, lowest_balance_date) = (balance, date);
+            }
+            if balance < Decimal::ZERO && first_negative.is_none() {
+                first_negative = Some(date);
+            }
+            CashflowDay { date, recurring_net, balance }
+        })
+        .collect();
+
+    let warning = first_negative.map(|date| {
+        format!(
+            "Je saldo komt naar verwachting op {} onder nul en zakt tot {} op {}.",
+            date.format("%d-%m-%Y"),
+            settings.format_money(lowest_balance),
+            lowest_balance_date.format("%d-%m-%Y")
+        )
+    });
+
+    CashflowForecast {
+        starting_balance,
+        spend_estimate: settings.cashflow_spend_estimate.clone(),
+        daily_discretionary_spend,
+        days,
+        lowest_balance,
+        lowest_balance_date,
+        warning,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(kind: &str, amount: i64, date: DateTime<Utc>, frequency: Option<&str>) -> Transaction {
+        Transaction {
+            description: if frequency.is_some() { "Salaris".to_string() } else { "Boodschappen".to_string() },
+            is_recurring: frequency.is_some(),
+            recurring_frequency: frequency.map(|f| f.to_string()),
+            ..Transaction::test(kind, amount, date)
+        }
+    }
+
+    #[test]
+    fn test_recurring_income_is_projected_forward() {
+        let now = Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap();
+        let mut transactions = Vec::new();
+        for month in 1..=6 {
+            let date = Utc.with_ymd_and_hms(2024, month, 25, 12, 0, 0).unwrap();
+            transactions.push(transaction("credit", 1000, date, Some("monthly")));
+        }
+
+        let projection = project_year_end_savings(&transactions, now);
+
+        assert_eq!(projection.year_to_date_savings, Decimal::from(6000));
+        assert_eq!(projection.recurring_monthly_net, Decimal::from(1000));
+        assert!(projection.projected_savings > Decimal::from(11_900));
+        assert!(projection.projected_savings < Decimal::from(12_100));
+        assert!(projection.last_year_savings.is_none());
+    }
+
+    #[test]
+    fn test_insight_compares_with_last_year() {
+        let now = Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap();
+        let transactions = vec![
+            transaction("credit", 5000, Utc.with_ymd_and_hms(2023, 3, 1, 12, 0, 0).unwrap(), None),
+            transaction("debit", 600, Utc.with_ymd_and_hms(2024, 2, 1, 12, 0, 0).unwrap(), None),
+        ];
+
+        let projection = project_year_end_savings(&transactions, now);
+        let insight = savings_projection_insight(&projection, &Settings::default()).unwrap();
+
+        assert!(projection.projected_savings < Decimal::ZERO);
+        assert_eq!(insight.impact, "high");
+        assert!(insight.description.ends_with("vorig jaar (€ 5.000,00). Er is nog tijd om bij te sturen."));
+
+        let english = Settings { language: "en".to_string(), ..Settings::default() };
+        let insight = savings_projection_insight(&projection, &english).unwrap();
+        assert!(insight.title.starts_with("At this rate you will save -€"));
+        assert!(insight.description.ends_with("less than last year (€5,000.00). There is still time to adjust."));
+    }
+
+    #[test]
+    fn test_cashflow_forecast_books_recurring_and_warns_below_zero() {
+        let now = Utc.with_ymd_and_hms(2024, 7, 10, 9, 0, 0).unwrap();
+        let mut transactions = Vec::new();
+        for month in 3..=7 {
+            let mut rent = transaction("debit", 900, Utc.with_ymd_and_hms(2024, month, 1, 8, 0, 0).unwrap(), None);
+            rent.description = "Huur".to_string();
+            transactions.push(rent);
+        }
+        // Groceries of 10 every day, plus one big purchase
+        for day in 0..90 {
+            transactions.push(transaction("debit", 10, now - Duration::days(day), None));
+        }
+        transactions.push(transaction("debit", 600, now - Duration::days(3), None));
+
+        let mut settings = Settings::default();
+        let mean = forecast_cashflow(&transactions, Decimal::from(1000), 30, &settings, now);
+        settings.cashflow_spend_estimate = "median".to_string();
+        let median = forecast_cashflow(&transactions, Decimal::from(1000), 30, &settings, now);
+
+        // Rent is recurring, so only the groceries and the one-off count as day-to-day spending
+        assert_eq!(median.daily_discretionary_spend, Decimal::from(10));
+        assert!(mean.daily_discretionary_spend > median.daily_discretionary_spend);
+
+        let rent_day = NaiveDate::from_ymd_opt(2024, 8, 1).unwrap();
+        let day = median.days.iter().find(|d| d.date == rent_day).unwrap();
+        assert_eq!(day.recurring_net, Decimal::from(-900));
+        assert_eq!(day.balance, Decimal::from(1000 - 900 - 22 * 10));
+        assert_eq!(median.days.len(), 30);
+        assert_eq!(median.lowest_balance, Decimal::from(1000 - 900 - 30 * 10));
+        assert_eq!(median.lowest_balance_date, NaiveDate::from_ymd_opt(2024, 8, 9).unwrap());
+        assert!(median.warning.as_deref().unwrap().contains("01-08-2024"));
+
+        let comfortable = forecast_cashflow(&transactions, Decimal::from(5000), 30, &settings, now);
+        assert!(comfortable.warning.is_none());
+    }
+}
@@ -0,0 +1,402 @@
+use crate::ai_insights::BudgetRecommendation;
+use crate::forecast::signed_amount;
+use crate::models::{SavingsGoal, Transaction};
+use chrono::{DateTime, Datelike, Duration, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Days of history the savings rate is measured over
+pub const SAVINGS_RATE_DAYS: i64 = 90;
+
+/// Progress percentages that earn a goal progress insight, lowest first
+pub const GOAL_MILESTONES: [u32; 4] = [25, 50, 75, 100];
+
+/// How far a savings goal is and when it will be reached at the current pace.
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalProgress {
+    pub goal_id: String,
+    /// Share of the target saved so far, 0-100
+    pub percentage: f64,
+    pub remaining: Decimal,
+    /// Average saved per month over the last [`SAVINGS_RATE_DAYS`] days
+    pub monthly_savings_rate: Decimal,
+    /// `None` when nothing is being saved, so the goal would never be reached
+    pub projected_completion: Option<DateTime<Utc>>,
+    /// Reached, or projected to be reached by the target date
+    pub on_track: bool,
+}
+
+/// Share of the target saved so far, capped at 100.
+pub fn goal_percentage(goal: &SavingsGoal) -> f64 {
+    if goal.target_amount <= Decimal::ZERO {
+        return 0.0;
+    }
+    (goal.current_amount / goal.target_amount * Decimal::from(100))
+        .to_f64()
+        .unwrap_or(0.0)
+        .clamp(0.0, 100.0)
+}
+
+/// Highest milestone of [`GOAL_MILESTONES`] the goal has reached, 0 for none.
+pub fn reached_milestone(goal: &SavingsGoal) -> u32 {
+    let percentage = goal_percentage(goal);
+    GOAL_MILESTONES
+        .iter()
+        .rev()
+        .find(|&&milestone| percentage >= milestone as f64)
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Progress of `goal` with a completion date projected from the recent savings
+/// rate. With a linked category, money moved into that category counts as
+/// saved (and money taken out of it as withdrawn); without one, net savings of
+/// all transactions do.
+pub fn goal_progress(goal: &SavingsGoal, transactions: &[Transaction], now: DateTime<Utc>) -> GoalProgress {
+    let since = now - Duration::days(SAVINGS_RATE_DAYS);
+    let recent = transactions.iter().filter(|t| t.date > since && t.date <= now);
+
+    let saved: Decimal = match goal.linked_category_id.as_deref() {
+        Some(category_id) => recent
+            .filter(|t| t.category_id.as_deref() == Some(category_id))
+            .map(|t| -signed_amount(t))
+            .sum(),
+        None => recent.map(signed_amount).sum(),
+    };
+
+    let months = Decimal::from(SAVINGS_RATE_DAYS) / Decimal::new(30_4375, 4);
+    let monthly_savings_rate = (saved / months).round_dp(2);
+    let remaining = (goal.target_amount - goal.current_amount).max(Decimal::ZERO);
+
+    let projected_completion = if remaining.is_zero() {
+        Some(now)
+    } else if monthly_savings_rate > Decimal::ZERO {
+        let days = (remaining / monthly_savings_rate * Decimal::new(30_4375, 4)).ceil();
+        days.to_i64().map(|days| now + Duration::days(days))
+    } else {
+        None
+    };
+
+    GoalProgress {
+        goal_id: goal.id.clone(),
+        percentage: goal_percentage(goal),
+        remaining,
+        monthly_savings_rate,
+        on_track: projected_completion.is_some_and(|date| date <= goal.target_date),
+        projected_completion,
+    }
+}
+
+/// Average days in a month, for turning day counts into months
+fn days_per_month() -> Decimal {
+    Decimal::new(30_4375, 4)
+}
+
+/// A category spent above its usual level, and what bringing it back saves.
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryTrim {
+    pub category_id: String,
+    pub category_name: String,
+    /// Average spent per month over the last [`SAVINGS_RATE_DAYS`] days
+    pub monthly_spending: Decimal,
+    /// The budget recommendation for the category, its usual monthly spending
+    pub suggested_monthly: Decimal,
+    /// Monthly saving from trimming the category to `suggested_monthly`
+    pub monthly_saving: Decimal,
+}
+
+/// What a savings goal takes per month to be reached on time, against what is
+/// being saved now.
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalFundingPlan {
+    pub goal_id: String,
+    /// Remaining amount spread over the months until the target date; all of
+    /// it once the target date has passed
+    pub required_monthly: Decimal,
+    /// Average saved per month over the last [`SAVINGS_RATE_DAYS`] days
+    pub monthly_savings_rate: Decimal,
+    /// How much more has to be saved each month, zero when on track
+    pub gap: Decimal,
+    /// The current savings rate reaches the goal by the target date
+    pub feasible: bool,
+    /// Overspent categories to trim, biggest saving first, up to what closes
+    /// the gap; empty when there is no gap
+    pub trims: Vec<CategoryTrim>,
+}
+
+/// Monthly contribution that reaches `goal` by its target date.
+pub fn required_monthly_contribution(goal: &SavingsGoal, now: DateTime<Utc>) -> Decimal {
+    let remaining = (goal.target_amount - goal.current_amount).max(Decimal::ZERO);
+    let months = Decimal::from((goal.target_date - now).num_days()) / days_per_month();
+    if months < Decimal::ONE {
+        return remaining;
+    }
+    (remaining / months).round_dp(2)
+}
+
+/// Funding plan for `goal`: the required monthly contribution against the
+/// recent savings rate, and when that falls short, which categories spent
+/// above their budget recommendation to trim to close the gap.
+pub fn goal_funding_plan(
+    goal: &SavingsGoal,
+    transactions: &[Transaction],
+    recommendations: &[BudgetRecommendation],
+    now: DateTime<Utc>,
+) -> GoalFundingPlan {
+    let progress = goal_progress(goal, transactions, now);
+    let required_monthly = required_monthly_contribution(goal, now);
+    let gap = (required_monthly - progress.monthly_savings_rate).max(Decimal::ZERO);
+
+    let since = now - Duration::days(SAVINGS_RATE_DAYS);
+    let months = Decimal::from(SAVINGS_RATE_DAYS) / days_per_month();
+    let mut candidates: Vec<CategoryTrim> = recommendations
+        .iter()
+        .filter_map(|recommendation| {
+            let spent: Decimal = transactions
+                .iter()
+                .filter(|t| t.transaction_type == "debit" && t.date > since && t.date <= now)
+                .filter(|t| t.category_id.as_deref() == Some(recommendation.category_id.as_str()))
+                .map(|t| t.amount.abs())
+                .sum();
+            let monthly_spending = (spent / months).round_dp(2);
+            // Seasonal categories are held to what is usual for this month
+            let suggested_monthly = recommendation
+                .monthly_suggestions
+                .as_ref()
+                .map_or(recommendation.suggested_monthly, |months| months[now.month0() as usize]);
+            let monthly_saving = monthly_spending - suggested_monthly;
+
+            (monthly_saving > Decimal::ZERO).then(|| CategoryTrim {
+                category_id: recommendation.category_id.clone(),
+                category_name: recommendation.category_name.clone(),
+                monthly_spending,
+                suggested_monthly,
+                monthly_saving,
+            })
+        })
+        .collect();
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.monthly_saving));
+
+    let mut trims = Vec::new();
+    let mut covered = Decimal::ZERO;
+    for trim in candidates {
+        if covered >= gap {
+            break;
+        }
+        covered += trim.monthly_saving;
+        trims.push(trim);
+    }
+
+    GoalFundingPlan {
+        goal_id: goal.id.clone(),
+        required_monthly,
+        monthly_savings_rate: progress.monthly_savings_rate,
+        gap,
+        feasible: gap.is_zero(),
+        trims,
+    }
+}
+
+/// Amounts purchases can be rounded up to, in whole euros
+pub const ROUNDUP_AMOUNTS: [i64; 2] = [1, 5];
+
+/// What rounding up purchases in one month would have saved.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundupMonth {
+    /// Month as `YYYY-MM`
+    pub month: String,
+    pub saved: Decimal,
+    pub transaction_count: u32,
+}
+
+/// What rounding every purchase up to `round_to` would have saved.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundupSimulation {
+    pub round_to: Decimal,
+    pub total_saved: Decimal,
+    pub transaction_count: u32,
+    /// Every month from the start to the end of the range, oldest first
+    pub months: Vec<RoundupMonth>,
+}
+
+/// The difference between `amount` and the next multiple of `round_to`;
+/// amounts that already are a multiple add nothing.
+pub fn roundup_delta(amount: Decimal, round_to: Decimal) -> Decimal {
+    let amount = amount.abs();
+    (amount / round_to).ceil() * round_to - amount
+}
+
+/// Sum the round-up of every debit dated from `from` through `to`, per month.
+/// Months without purchases are included with zeros.
+pub fn simulate_roundup(
+    transactions: &[Transaction],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    round_to: Decimal,
+) -> RoundupSimulation {
+    let month_index = |date: DateTime<Utc>| date.year() * 12 + date.month0() as i32;
+    let first = month_index(from);
+    let mut months: Vec<RoundupMonth> = (first..=month_index(to))
+        .map(|index| RoundupMonth {
+            month: format!("{}-{:02}", index.div_euclid(12), index.rem_euclid(12) + 1),
+            saved: Decimal::ZERO,
+            transaction_count: 0,
+        })
+        .collect();
+
+    for transaction in transactions
+        .iter()
+        .filter(|t| t.transaction_type == "debit" && t.date >= from && t.date <= to)
+    {
+        let month = &mut months[(month_index(transaction.date) - first) as usize];
+        month.saved += roundup_delta(transaction.amount, round_to);
+        month.transaction_count += 1;
+    }
+
+    RoundupSimulation {
+        round_to,
+        total_saved: months.iter().map(|m| m.saved).sum(),
+        transaction_count: months.iter().map(|m| m.transaction_count).sum(),
+        months,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn transaction(kind: &str, amount: i64, date: DateTime<Utc>, category_id: Option<&str>) -> Transaction {
+        Transaction { category_id: category_id.map(str::to_string), ..Transaction::test(kind, amount, date) }
+    }
+
+    #[test]
+    fn test_goal_progress_and_projection() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let mut goal = SavingsGoal {
+            id: "vakantie".to_string(),
+            name: "Vakantie".to_string(),
+            target_amount: Decimal::from(2000),
+            current_amount: Decimal::from(500),
+            target_date: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+            linked_category_id: Some("sparen".to_string()),
+            created_at: now,
+            updated_at: now,
+        };
+
+        // 300 a month into savings for three months, one withdrawal of 150
+        let transactions: Vec<Transaction> = (0..3)
+            .map(|month| transaction("debit", 300, now - Duration::days(10 + 30 * month), Some("sparen")))
+            .chain([
+                transaction("credit", 150, now - Duration::days(5), Some("sparen")),
+                transaction("debit", 80, now - Duration::days(3), Some("boodschappen")),
+                transaction("debit", 300, now - Duration::days(120), Some("sparen")),
+            ])
+            .collect();
+
+        let progress = goal_progress(&goal, &transactions, now);
+        assert_eq!(progress.percentage, 25.0);
+        assert_eq!(reached_milestone(&goal), 25);
+        assert_eq!(progress.remaining, Decimal::from(1500));
+        assert_eq!(progress.monthly_savings_rate, Decimal::new(25365, 2));
+        let projected = progress.projected_completion.unwrap();
+        assert!(projected > now + Duration::days(170) && projected < now + Duration::days(185));
+        assert!(progress.on_track);
+
+        goal.target_date = now + Duration::days(60);
+        assert!(!goal_progress(&goal, &transactions, now).on_track);
+        assert_eq!(goal_progress(&goal, &[], now).projected_completion, None);
+
+        goal.current_amount = Decimal::from(2100);
+        assert_eq!(reached_milestone(&goal), 100);
+        assert_eq!(goal_progress(&goal, &[], now).projected_completion, Some(now));
+    }
+
+    #[test]
+    fn test_goal_funding_plan_trims_overspent_categories() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let goal = SavingsGoal {
+            id: "auto".to_string(),
+            name: "Auto".to_string(),
+            target_amount: Decimal::from(6000),
+            current_amount: Decimal::ZERO,
+            // 487 days, exactly 16 months
+            target_date: now + Duration::days(487),
+            linked_category_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let recommendation = |category_id: &str, suggested: i64| BudgetRecommendation {
+            category_id: category_id.to_string(),
+            category_name: category_id.to_string(),
+            suggested_monthly: Decimal::from(suggested),
+            is_seasonal: false,
+            monthly_suggestions: None,
+            months_of_data: 12,
+        };
+        let recommendations = [recommendation("uit_eten", 100), recommendation("kleding", 50), recommendation("boodschappen", 400)];
+
+        // 90 days is 2.9569 months: 2000 in, 1200 out in total
+        let transactions = vec![
+            transaction("credit", 2000, now - Duration::days(20), None),
+            transaction("debit", 600, now - Duration::days(10), Some("uit_eten")),
+            transaction("debit", 300, now - Duration::days(11), Some("kleding")),
+            transaction("debit", 300, now - Duration::days(12), Some("boodschappen")),
+        ];
+
+        let plan = goal_funding_plan(&goal, &transactions, &recommendations, now);
+        assert_eq!(plan.required_monthly, Decimal::from(375));
+        assert_eq!(plan.monthly_savings_rate, Decimal::new(27056, 2));
+        assert_eq!(plan.gap, Decimal::new(10444, 2));
+        assert!(!plan.feasible);
+        // Eating out alone, 102.92 over its usual 100, nearly closes the gap
+        let trimmed: Vec<&str> = plan.trims.iter().map(|t| t.category_id.as_str()).collect();
+        assert_eq!(trimmed, ["uit_eten", "kleding"]);
+        assert_eq!(plan.trims[0].monthly_saving, Decimal::new(10292, 2));
+
+        let rich = vec![transaction("credit", 3000, now - Duration::days(20), None)];
+        let plan = goal_funding_plan(&goal, &rich, &recommendations, now);
+        assert!(plan.feasible);
+        assert_eq!(plan.gap, Decimal::ZERO);
+        assert!(plan.trims.is_empty());
+
+        let overdue = SavingsGoal { target_date: now - Duration::days(1), ..goal };
+        assert_eq!(required_monthly_contribution(&overdue, now), Decimal::from(6000));
+    }
+
+    #[test]
+    fn test_roundup_simulation() {
+        let cents = |amount: i64| Decimal::new(amount, 2);
+        assert_eq!(roundup_delta(cents(1234), Decimal::ONE), cents(66));
+        assert_eq!(roundup_delta(cents(-1234), Decimal::from(5)), cents(266));
+        assert_eq!(roundup_delta(cents(2000), Decimal::from(5)), Decimal::ZERO);
+
+        let at = |month: u32, day: u32| Utc.with_ymd_and_hms(2024, month, day, 12, 0, 0).unwrap();
+        let with_cents = |kind: &str, amount: i64, date: DateTime<Utc>| Transaction {
+            amount: cents(amount),
+            ..transaction(kind, 0, date, None)
+        };
+        let transactions = vec![
+            with_cents("debit", 399, at(1, 5)),
+            with_cents("debit", 1001, at(1, 20)),
+            with_cents("credit", 250050, at(1, 25)),
+            with_cents("debit", 4250, at(3, 2)),
+            // Outside the range
+            with_cents("debit", 1, at(4, 1)),
+        ];
+
+        let simulation = simulate_roundup(&transactions, at(1, 1), at(3, 31), Decimal::ONE);
+        assert_eq!(simulation.total_saved, cents(1 + 99 + 50));
+        assert_eq!(simulation.transaction_count, 3);
+        let months: Vec<(&str, Decimal, u32)> = simulation
+            .months
+            .iter()
+            .map(|m| (m.month.as_str(), m.saved, m.transaction_count))
+            .collect();
+        assert_eq!(months, vec![("2024-01", cents(100), 2), ("2024-02", Decimal::ZERO, 0), ("2024-03", cents(50), 1)]);
+
+        let simulation = simulate_roundup(&transactions, at(1, 1), at(3, 31), Decimal::from(5));
+        assert_eq!(simulation.total_saved, cents(101 + 499 + 250));
+    }
+}
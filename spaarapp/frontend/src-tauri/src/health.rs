@@ -0,0 +1,187 @@
+use crate::ai_insights::mean_and_std_dev;
+use crate::models::{Budget, Transaction};
+use chrono::{DateTime, Datelike, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Each of the four components contributes at most this many points to the 0-100 score.
+const COMPONENT_MAX: f64 = 25.0;
+
+/// Saving this share of income earns the full savings-rate points
+const TARGET_SAVINGS_RATE: f64 = 0.20;
+
+/// Months of expenses in the bank that earn the full emergency-fund points
+const TARGET_BUFFER_MONTHS: f64 = 6.0;
+
+/// Monthly expenses varying this much (coefficient of variation) or more earn no stability points
+const MAX_EXPENSE_VARIATION: f64 = 0.5;
+
+/// Complete calendar months the score looks back over
+pub const HEALTH_MONTHS: u32 = 6;
+
+/// One part of the financial health score.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthComponent {
+    /// "savings_rate", "budget_adherence", "emergency_fund" or "spending_stability"
+    pub name: &'static str,
+    pub label: &'static str,
+    /// Points earned, 0 - `max_score`
+    pub score: f64,
+    pub max_score: f64,
+    /// The measured value: a fraction for savings rate, budget adherence and
+    /// variation, a number of months for the emergency fund
+    pub value: f64,
+    pub tip: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthScore {
+    /// Sum of the component scores, 0 - 100
+    pub score: u32,
+    pub components: Vec<HealthComponent>,
+    /// Tip for the component with the lowest score
+    pub tip: String,
+}
+
+/// Linear score from 0 at `value <= 0` to the full component at `value >= target`.
+fn scaled(value: f64, target: f64) -> f64 {
+    (value / target).clamp(0.0, 1.0) * COMPONENT_MAX
+}
+
+/// Income and expenses per complete calendar month in the last [`HEALTH_MONTHS`] months.
+fn monthly_totals(transactions: &[Transaction], now: DateTime<Utc>) -> Vec<(Decimal, Decimal)> {
+    let current = now.year() * 12 + now.month0() as i32;
+    let mut months: HashMap<i32, (Decimal, Decimal)> =
+        (1..=HEALTH_MONTHS as i32).map(|offset| (current - offset, Default::default())).collect();
+
+    for transaction in transactions {
+        let index = transaction.date.year() * 12 + transaction.date.month0() as i32;
+        if let Some((income, expenses)) = months.get_mut(&index) {
+            if transaction.transaction_type == "credit" {
+                *income += transaction.amount.abs();
+            } else {
+                *expenses += transaction.amount.abs();
+            }
+        }
+    }
+
+    months.into_values().collect()
+}
+
+/// Score financial health from 0 to 100 as the sum of four components worth 25 points each:
+///
+/// - **Savings rate**: share of income not spent over the last six complete months;
+///   20% or more earns all points.
+/// - **Budget adherence**: share of active budgets within their amount; without
+///   budgets this counts as half.
+/// - **Emergency fund**: `balance` divided by average monthly expenses; six months
+///   or more earns all points.
+/// - **Spending stability**: how much monthly expenses vary (standard deviation
+///   over mean); no variation earns all points, 50% or more none.
+pub fn calculate_health_score(
+    transactions: &[Transaction],
+    budgets: &[Budget],
+    balance: Decimal,
+    now: DateTime<Utc>,
+) -> HealthScore {
+    let months = monthly_totals(transactions, now);
+    let income: Decimal = months.iter().map(|(income, _)| *income).sum();
+    let expenses: Decimal = months.iter().map(|(_, expenses)| *expenses).sum();
+
+    let savings_rate = if income > Decimal::ZERO {
+        ((income - expenses) / income).to_f64().unwrap_or(0.0)
+    } else {
+        0.0
+    };
+
+    let active: Vec<&Budget> = budgets.iter().filter(|b| b.is_active).collect();
+    let adherence = if active.is_empty() {
+        0.5
+    } else {
+        active.iter().filter(|b| b.spent <= b.amount).count() as f64 / active.len() as f64
+    };
+
+    let monthly_expenses = (expenses / Decimal::from(HEALTH_MONTHS)).to_f64().unwrap_or(0.0);
+    let buffer_months = if monthly_expenses > 0.0 {
+        balance.to_f64().unwrap_or(0.0) / monthly_expenses
+    } else {
+        0.0
+    };
+
+    let expense_values: Vec<f64> = months.iter().map(|(_, e)| e.to_f64().unwrap_or(0.0)).collect();
+    let variation = match mean_and_std_dev(&expense_values) {
+        Some((mean, std_dev)) if mean > 0.0 => std_dev / mean,
+        // No spread (or no spending at all) counts as fully stable
+        _ => 0.0,
+    };
+
+    let components = vec![
+        HealthComponent {
+            name: "savings_rate",
+            label: "Spaarquote",
+            score: scaled(savings_rate, TARGET_SAVINGS_RATE),
+            max_score: COMPONENT_MAX,
+            value: savings_rate,
+            tip: "Zet direct na je salaris een vast bedrag opzij, bijvoorbeeld 10% van je inkomen.",
+        },
+        HealthComponent {
+            name: "budget_adherence",
+            label: "Budgetdiscipline",
+            score: adherence * COMPONENT_MAX,
+            max_score: COMPONENT_MAX,
+            value: adherence,
+            tip: "Bekijk de budgetten die je overschrijdt en pas je uitgaven of het budget aan.",
+        },
+        HealthComponent {
+            name: "emergency_fund",
+            label: "Buffer",
+            score: scaled(buffer_months, TARGET_BUFFER_MONTHS),
+            max_score: COMPONENT_MAX,
+            value: buffer_months,
+            tip: "Bouw een buffer op van drie tot zes maanden aan uitgaven voor onverwachte kosten.",
+        },
+        HealthComponent {
+            name: "spending_stability",
+            label: "Stabiliteit van uitgaven",
+            score: (1.0 - variation / MAX_EXPENSE_VARIATION).clamp(0.0, 1.0) * COMPONENT_MAX,
+            max_score: COMPONENT_MAX,
+            value: variation,
+            tip: "Spreid grote uitgaven door er maandelijks een vast bedrag voor te reserveren.",
+        },
+    ];
+
+    let score = components.iter().map(|c| c.score).sum::<f64>().round() as u32;
+    let tip = components
+        .iter()
+        .min_by(|a, b| a.score.total_cmp(&b.score))
+        .map(|c| c.tip.to_string())
+        .unwrap_or_default();
+
+    HealthScore { score, components, tip }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_steady_saver_scores_high_and_gets_buffer_tip() {
+        let now = Utc.with_ymd_and_hms(2024, 7, 15, 12, 0, 0).unwrap();
+        let mut transactions = Vec::new();
+        for month in 1..=6 {
+            let date = Utc.with_ymd_and_hms(2024, month, 10, 12, 0, 0).unwrap();
+            transactions.push(Transaction::test("credit", 3000, date));
+            transactions.push(Transaction::test("debit", 2000, date));
+        }
+
+        let health = calculate_health_score(&transactions, &[], Decimal::from(4000), now);
+
+        // Savings 25 + budgets 12.5 (none set) + buffer 2 of 6 months 8.3 + stability 25
+        assert_eq!(health.score, 71);
+        assert_eq!(health.components[2].value, 2.0);
+        assert!(health.tip.contains("buffer"));
+    }
+}
@@ -7,61 +7,127 @@ mod commands;
 mod encryption;
 mod csv_import;
 mod ai_insights;
+mod alerts;
+mod claude;
+mod category_tree;
+mod forecast;
+mod goals;
+mod health;
+mod recurring;
+mod duplicates;
+mod paths;
+mod audit;
+mod pii;
+mod rate_limit;
+mod user_export;
+mod monthly_report;
 mod error;
 
+use alerts::AlertMonitor;
+use commands::csv_import::RecentImports;
 use database::Database;
+use encryption::EncryptionManager;
 use error::AppError;
+use rate_limit::RateLimiter;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 pub type AppDatabase = Arc<Mutex<Database>>;
+pub type AppEncryption = Arc<Mutex<EncryptionManager<'static>>>;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: AppDatabase,
+    pub encryption: AppEncryption,
+    pub recent_imports: Arc<Mutex<RecentImports>>,
+    /// Set to stop a running re-categorization after its current chunk
+    pub recategorize_cancel: Arc<AtomicBool>,
+    /// Limits how often expensive commands (AI insights, CSV parsing, exports) run
+    pub rate_limiter: Arc<Mutex<RateLimiter>>,
+    /// Watches for activity over the alert thresholds in the security config
+    pub alert_monitor: Arc<Mutex<AlertMonitor>>,
+    /// Files the user chose in the file dialog, readable wherever they are
+    pub picked_files: Arc<Mutex<HashSet<PathBuf>>>,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Optional configuration such as ANTHROPIC_API_KEY from a .env file
+    let _ = dotenvy::dotenv();
+
     // Initialize tracing
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::INFO)
         .init();
 
-    // Initialize the runtime and database
-    let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
-
-    let db = rt.block_on(async {
-        match Database::new("spaarapp.db").await {
-            Ok(db) => {
-                tracing::info!("Database initialized successfully");
-                Arc::new(Mutex::new(db))
-            }
-            Err(e) => {
-                tracing::error!("Failed to initialize database: {}", e);
-                panic!("Database initialization failed: {}", e);
-            }
-        }
-    });
+    // The database stays locked until the user unlocks it with their passphrase
+    let db = Arc::new(Mutex::new(Database::locked("spaarapp.db")));
 
-    let state = AppState { db };
+    let encryption = match encryption::load_or_create_encryption_config(encryption::ENCRYPTION_CONFIG_FILE) {
+        Ok(_) => Arc::new(Mutex::new(EncryptionManager::new())),
+        Err(e) => {
+            tracing::error!("Failed to initialize field encryption: {}", e);
+            panic!("Field encryption initialization failed: {}", e);
+        }
+    };
+
+    let state = AppState {
+        db,
+        encryption,
+        recent_imports: Arc::new(Mutex::new(RecentImports::default())),
+        recategorize_cancel: Arc::new(AtomicBool::new(false)),
+        rate_limiter: Arc::new(Mutex::new(RateLimiter::default())),
+        alert_monitor: Arc::new(Mutex::new(AlertMonitor::new(alerts::SECURITY_CONFIG_FILE))),
+        picked_files: Arc::new(Mutex::new(HashSet::new())),
+    };
+    let cleanup_db = state.db.clone();
 
     tauri::Builder::default()
         .manage(state)
+        .plugin(tauri_plugin_dialog::init())
+        .setup(move |_app| {
+            tauri::async_runtime::spawn(commands::app::run_daily_cleanup(cleanup_db));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Transaction commands
             commands::transactions::get_transactions,
             commands::transactions::add_transaction,
             commands::transactions::update_transaction,
+            commands::transactions::get_transaction_stats,
             commands::transactions::delete_transaction,
+            commands::splits::split_transaction,
+            commands::splits::get_transaction_splits,
             commands::transactions::get_transaction_by_id,
+            commands::transactions::search_transactions,
+            commands::transactions::set_transaction_notes,
+            commands::transactions::set_transaction_category,
+            commands::transactions::find_near_duplicates,
+            commands::transactions::detect_double_charges,
+            commands::transactions::get_transactions_missing_fields,
 
             // Category commands
             commands::categories::get_categories,
             commands::categories::add_category,
             commands::categories::update_category,
+            commands::categories::set_category_parent,
             commands::categories::delete_category,
+            commands::categories::merge_categories,
             commands::categories::get_category_by_id,
+            commands::categories::get_category_transaction_stats,
+            commands::categories::get_hierarchical_spending,
+            commands::categories::get_category_month_over_month,
+            commands::categories::suggest_category_icon,
+            commands::category_rules::get_category_rules,
+            commands::category_rules::add_category_rule,
+            commands::category_rules::delete_category_rule,
+            commands::category_rules::recategorize_transactions,
+            commands::category_rules::cancel_recategorization,
+            commands::category_rules::get_transactions_needing_review,
+            commands::category_rules::approve_category,
 
             // Budget commands
             commands::budgets::get_budgets,
@@ -70,18 +136,85 @@ pub fn run() {
             commands::budgets::delete_budget,
             commands::budgets::get_budget_by_id,
             commands::budgets::get_budget_summary,
-            commands::budgets::update_budget_spending,
+            commands::budgets::get_spending_pace,
+            commands::budgets::recompute_budget,
+            commands::budgets::set_budget_amount,
+            commands::budgets::get_budget_transactions,
+            commands::budgets::get_budget_history,
+            commands::budgets::get_budget_alert_settings,
+            commands::budgets::set_all_budget_thresholds,
+
+            // Savings goal commands
+            commands::savings_goals::get_savings_goals,
+            commands::savings_goals::get_savings_goal_by_id,
+            commands::savings_goals::add_savings_goal,
+            commands::savings_goals::update_savings_goal,
+            commands::savings_goals::delete_savings_goal,
+            commands::savings_goals::get_goal_progress,
+            commands::savings_goals::get_goal_funding_plan,
+            commands::savings_goals::simulate_roundup_savings,
+
+            // Tag commands
+            commands::tags::get_tags,
+            commands::tags::add_tag,
+            commands::tags::delete_tag,
+            commands::tags::set_transaction_tags,
+            commands::tags::merge_tags,
+            commands::tags::get_all_tags,
+            commands::tags::add_transaction_tag,
+            commands::tags::remove_transaction_tag,
+
+            // Account commands
+            commands::accounts::get_accounts,
+            commands::accounts::add_account,
+            commands::accounts::set_default_account,
+            commands::accounts::set_account_default_category,
+            commands::accounts::get_balance_at_date,
+            commands::accounts::reconstruct_balances,
 
             // CSV import commands
             commands::csv_import::import_csv,
             commands::csv_import::parse_csv,
             commands::csv_import::preview_csv,
+            commands::csv_import::import_mt940,
+            commands::csv_import::import_camt053,
+            commands::csv_import::dry_run_import,
+            commands::csv_import::import_multiple,
             commands::csv_import::validate_csv_structure,
+            commands::csv_import::import_transactions,
+
+            // Export commands
+            commands::export::export_transactions_csv,
+            commands::export::export_transactions_rabobank_csv,
+            commands::export::export_user_data,
+            commands::export::generate_monthly_report_pdf,
+            commands::backup::backup_database,
+            commands::backup::restore_database,
+            commands::key_rotation::rotate_encryption_key,
+            commands::unlock::get_lock_status,
+            commands::unlock::unlock_database,
+            commands::unlock::set_passphrase,
 
             // AI insights commands
             commands::ai_insights::get_financial_insights,
+            commands::ai_insights::get_claude_usage,
             commands::ai_insights::analyze_spending_patterns,
             commands::ai_insights::get_budget_recommendations,
+            commands::ai_insights::get_category_budget_recommendations,
+            commands::ai_insights::get_category_insights,
+            commands::ai_insights::mark_all_insights_read,
+            commands::ai_insights::dismiss_all_insights,
+            commands::ai_insights::get_weekday_spending_matrix,
+            commands::ai_insights::get_weekly_summary,
+            commands::ai_insights::get_monthly_spending,
+            commands::ai_insights::project_year_end_savings,
+            commands::ai_insights::calculate_health_score,
+            commands::ai_insights::get_unusual_transactions,
+            commands::ai_insights::get_recurring_income,
+            commands::ai_insights::get_upcoming_recurring,
+            commands::ai_insights::forecast_cashflow,
+            commands::ai_insights::detect_subscription_price_increases,
+            commands::ai_insights::analyze_subscriptions,
 
             // Settings commands
             commands::settings::get_settings,
@@ -97,6 +230,9 @@ pub fn run() {
             commands::app::get_app_info,
             commands::app::get_version,
             commands::app::get_platform,
+            commands::app::get_storage_usage,
+            commands::app::cleanup_derived_data,
+            commands::app::get_audit_log,
             commands::app::test_database,
         ])
         .run(tauri::generate_context!())
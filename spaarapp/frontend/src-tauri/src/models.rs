@@ -1,4 +1,5 @@
-use chrono::{DateTime, Utc};
+use crate::error::AppError;
+use chrono::{DateTime, Duration, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
@@ -14,17 +15,101 @@ pub struct Transaction {
     pub category_id: Option<String>,
     pub account_number: Option<String>,
     pub account_holder: Option<String>,
+    /// Bank of the counterparty account, derived from its IBAN on import
+    #[serde(default)]
+    pub counterparty_bank: Option<String>,
     pub transaction_type: String, // Store as string to avoid enum complications
     // SQLX mapping: try from "0.0"
     pub balance_after: Option<rust_decimal::Decimal>,
     pub notes: Option<String>,
-    pub tags: String, // Store as JSON string instead of Vec<String>
+    /// Stored as a JSON array in `transactions.tags`; triggers mirror it into
+    /// `transaction_tags` for filtering and counting
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub is_recurring: bool,
     pub recurring_frequency: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Longest description a transaction may have
+pub const MAX_DESCRIPTION_LENGTH: usize = 255;
+
+/// How far ahead a transaction may be dated, for planned payments
+pub const MAX_FUTURE_DAYS: i64 = 366;
+
+impl Transaction {
+    /// Check a transaction entered by the user before it is stored: a positive
+    /// amount (the type says which way the money went), a description, a known
+    /// type and a date no more than [`MAX_FUTURE_DAYS`] ahead.
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.amount <= Decimal::ZERO {
+            return Err(AppError::Validation("Bedrag moet groter dan 0 zijn".to_string()));
+        }
+        if self.description.trim().is_empty() {
+            return Err(AppError::Validation("Omschrijving is verplicht".to_string()));
+        }
+        if self.description.chars().count() > MAX_DESCRIPTION_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Omschrijving mag hoogstens {} tekens lang zijn",
+                MAX_DESCRIPTION_LENGTH
+            )));
+        }
+        if !matches!(self.transaction_type.as_str(), "credit" | "debit") {
+            return Err(AppError::Validation(format!(
+                "Soort transactie moet \"credit\" of \"debit\" zijn, niet {}",
+                self.transaction_type
+            )));
+        }
+        if self.date > Utc::now() + Duration::days(MAX_FUTURE_DAYS) {
+            return Err(AppError::Validation(format!(
+                "Datum {} ligt te ver in de toekomst",
+                self.date.format("%d-%m-%Y")
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl Transaction {
+    /// Transaction for tests: `amount` of `transaction_type` ("debit" or
+    /// "credit") on `date`, described as "Test" and with nothing else set.
+    /// Set other fields with struct update syntax.
+    pub(crate) fn test(transaction_type: &str, amount: impl Into<Decimal>, date: DateTime<Utc>) -> Self {
+        Transaction {
+            id: Uuid::new_v4().to_string(),
+            description: "Test".to_string(),
+            amount: amount.into(),
+            date,
+            category_id: None,
+            account_number: None,
+            account_holder: None,
+            counterparty_bank: None,
+            transaction_type: transaction_type.to_string(),
+            balance_after: None,
+            notes: None,
+            tags: Vec::new(),
+            is_recurring: false,
+            recurring_frequency: None,
+            created_at: date,
+            updated_at: date,
+        }
+    }
+}
+
+/// Part of a transaction booked on its own category, e.g. the household
+/// items in a supermarket purchase. The splits of a transaction add up to its amount.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionSplit {
+    pub id: String,
+    pub transaction_id: String,
+    pub category_id: Option<String>,
+    pub amount: rust_decimal::Decimal,
+    pub note: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum TransactionType {
     Credit,
@@ -73,6 +158,22 @@ pub struct Budget {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Amount the user is saving up for, e.g. a holiday or an emergency fund.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavingsGoal {
+    pub id: String,
+    pub name: String,
+    // SQLX mapping: try from "0.0"
+    pub target_amount: rust_decimal::Decimal,
+    // SQLX mapping: try from "0.0"
+    pub current_amount: rust_decimal::Decimal,
+    pub target_date: chrono::DateTime<chrono::Utc>,
+    /// Category whose transactions are the money put aside for this goal, e.g. "Sparen"
+    pub linked_category_id: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BudgetPeriod {
     Weekly,
@@ -81,6 +182,31 @@ pub enum BudgetPeriod {
     Yearly,
 }
 
+impl BudgetPeriod {
+    /// Parse the `period` string stored on a budget.
+    pub fn parse(period: &str) -> Option<Self> {
+        match period {
+            "weekly" => Some(BudgetPeriod::Weekly),
+            "monthly" => Some(BudgetPeriod::Monthly),
+            "quarterly" => Some(BudgetPeriod::Quarterly),
+            "yearly" => Some(BudgetPeriod::Yearly),
+            _ => None,
+        }
+    }
+
+    /// `date` moved one period ahead. Months that are too short for the day
+    /// end up on their last day, e.g. 31 January becomes 29 February.
+    pub fn advance(&self, date: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+        let months = match self {
+            BudgetPeriod::Weekly => return date + chrono::Duration::weeks(1),
+            BudgetPeriod::Monthly => 1,
+            BudgetPeriod::Quarterly => 3,
+            BudgetPeriod::Yearly => 12,
+        };
+        date.checked_add_months(chrono::Months::new(months)).unwrap_or(date)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FinancialInsight {
     pub id: String,
@@ -91,6 +217,10 @@ pub struct FinancialInsight {
     pub actionable: bool,
     pub action_suggestions: String, // Store as JSON string instead of Vec<String>
     pub confidence_score: f64,
+    #[serde(default)]
+    pub is_read: bool,
+    #[serde(default)]
+    pub is_dismissed: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -121,6 +251,8 @@ pub struct SpendingAnalysis {
     pub spending_trend: TrendDirection,
     pub period_start: chrono::DateTime<chrono::Utc>,
     pub period_end: chrono::DateTime<chrono::Utc>,
+    /// Spending in the period per category, nested by parent category
+    pub category_tree: Vec<crate::category_tree::CategorySpendingNode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -155,10 +287,106 @@ pub struct Settings {
     pub encryption_enabled: bool,
     // Skip SQLX mapping
     pub last_backup: Option<chrono::DateTime<chrono::Utc>>,
+    /// Fraction of the monthly budget that, when spent ahead of the same fraction of the month, triggers a spending velocity alert
+    #[serde(default = "default_spending_velocity_alert_fraction")]
+    pub spending_velocity_alert_fraction: f64,
+    /// Only allow tags from the curated vocabulary in the tags table
+    #[serde(default = "default_restrict_tags_to_vocabulary")]
+    pub restrict_tags_to_vocabulary: bool,
+    /// Insights older than this many days are removed by the daily cleanup
+    #[serde(default = "default_insight_retention_days")]
+    pub insight_retention_days: u32,
+    /// Seconds in which an identical import is refused as an accidental repeat; 0 turns the check off
+    #[serde(default = "default_import_repeat_window_seconds")]
+    pub import_repeat_window_seconds: u32,
+    /// Whether the user may export all their data (GDPR art. 20)
+    #[serde(default = "default_allow_data_export")]
+    pub allow_data_export: bool,
+    /// Days audit log entries are kept
+    #[serde(default = "default_audit_retention_days")]
+    pub audit_retention_days: u32,
+    /// Confidence (0 to 1) a category rule needs to assign its category;
+    /// less confident matches go to the review queue
+    #[serde(default = "default_category_review_threshold")]
+    pub category_review_threshold: f64,
+    /// Standard deviations above its usual level for the time of year before
+    /// a category's spending this month is flagged as unusual
+    #[serde(default = "default_seasonal_anomaly_sigma")]
+    pub seasonal_anomaly_sigma: f64,
+    /// How the cashflow forecast estimates day-to-day spending: "mean" or "median" of the daily totals
+    #[serde(default = "default_cashflow_spend_estimate")]
+    pub cashflow_spend_estimate: String,
+    /// Day of the month budget months start on, e.g. 25 for a payday on the 25th; months too short for it start on their last day
+    #[serde(default = "default_budget_month_start_day")]
+    pub budget_month_start_day: u8,
+    /// Store transaction notes encrypted; encrypted notes are left out of search
+    #[serde(default = "default_encrypt_notes")]
+    pub encrypt_notes: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+fn default_spending_velocity_alert_fraction() -> f64 {
+    0.5
+}
+
+fn default_restrict_tags_to_vocabulary() -> bool {
+    false
+}
+
+impl Settings {
+    /// How amounts in the configured currency are written and read.
+    pub fn currency(&self) -> spaarapp_shared::Currency {
+        spaarapp_shared::Currency::for_code(&self.currency)
+    }
+
+    /// Amount formatted for the configured currency and language, e.g. "€ 1.234,50".
+    pub fn format_money(&self, amount: Decimal) -> String {
+        spaarapp_shared::format_money(amount, &self.currency, &self.language)
+    }
+
+    /// Percentage with one decimal for the configured language, e.g. "14,3".
+    pub fn format_percentage(&self, percentage: Decimal) -> String {
+        spaarapp_shared::format_percentage(percentage, &self.language)
+    }
+}
+
+fn default_insight_retention_days() -> u32 {
+    90
+}
+
+fn default_import_repeat_window_seconds() -> u32 {
+    5
+}
+
+fn default_allow_data_export() -> bool {
+    true
+}
+
+fn default_audit_retention_days() -> u32 {
+    365
+}
+
+fn default_category_review_threshold() -> f64 {
+    0.7
+}
+
+fn default_seasonal_anomaly_sigma() -> f64 {
+    2.0
+}
+
+fn default_cashflow_spend_estimate() -> String {
+    "mean".to_string()
+}
+
+fn default_budget_month_start_day() -> u8 {
+    1
+}
+
+fn default_encrypt_notes() -> bool {
+    true
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
@@ -175,6 +403,17 @@ impl Default for Settings {
             export_format: "csv".to_string(),
             encryption_enabled: true,
             last_backup: None,
+            spending_velocity_alert_fraction: default_spending_velocity_alert_fraction(),
+            restrict_tags_to_vocabulary: default_restrict_tags_to_vocabulary(),
+            insight_retention_days: default_insight_retention_days(),
+            import_repeat_window_seconds: default_import_repeat_window_seconds(),
+            allow_data_export: default_allow_data_export(),
+            audit_retention_days: default_audit_retention_days(),
+            category_review_threshold: default_category_review_threshold(),
+            seasonal_anomaly_sigma: default_seasonal_anomaly_sigma(),
+            cashflow_spend_estimate: default_cashflow_spend_estimate(),
+            budget_month_start_day: default_budget_month_start_day(),
+            encrypt_notes: default_encrypt_notes(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -189,6 +428,12 @@ pub struct CsvImportConfig {
     pub encoding: String,
     pub has_header_row: bool,
     pub column_mapping: ColumnMapping,
+    /// Columns to ignore entirely, even when a field is mapped to them
+    #[serde(default)]
+    pub skip_columns: Vec<usize>,
+    /// Append the values of columns that are neither mapped nor skipped to the notes
+    #[serde(default)]
+    pub unmapped_columns_to_notes: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -202,6 +447,24 @@ pub struct ColumnMapping {
     pub balance_after: Option<usize>,
 }
 
+impl ColumnMapping {
+    /// Column indexes that map to a transaction field.
+    pub fn mapped_columns(&self) -> Vec<usize> {
+        [
+            self.date,
+            self.description,
+            self.amount,
+            self.account_number,
+            self.account_holder,
+            self.transaction_type,
+            self.balance_after,
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
 impl Default for CsvImportConfig {
     fn default() -> Self {
         Self {
@@ -219,6 +482,43 @@ impl Default for CsvImportConfig {
                 transaction_type: Some(5),
                 balance_after: Some(6),
             },
+            skip_columns: Vec::new(),
+            unmapped_columns_to_notes: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction() -> Transaction {
+        Transaction {
+            id: "t1".to_string(),
+            description: "Albert Heijn".to_string(),
+            ..Transaction::test("debit", Decimal::new(2350, 2), Utc::now())
         }
     }
-}
\ No newline at end of file
+
+    fn rejected(change: impl FnOnce(&mut Transaction)) -> bool {
+        let mut transaction = transaction();
+        change(&mut transaction);
+        matches!(transaction.validate(), Err(AppError::Validation(_)))
+    }
+
+    #[test]
+    fn test_transaction_validation() {
+        assert!(transaction().validate().is_ok());
+        assert!(!rejected(|t| t.date = Utc::now() + Duration::days(30)));
+        assert!(!rejected(|t| t.description = "é".repeat(MAX_DESCRIPTION_LENGTH)));
+
+        assert!(rejected(|t| t.amount = Decimal::ZERO));
+        assert!(rejected(|t| t.amount = Decimal::new(-2350, 2)));
+        assert!(rejected(|t| t.description = String::new()));
+        assert!(rejected(|t| t.description = "   ".to_string()));
+        assert!(rejected(|t| t.description = "a".repeat(MAX_DESCRIPTION_LENGTH + 1)));
+        assert!(rejected(|t| t.transaction_type = "transfer".to_string()));
+        assert!(rejected(|t| t.transaction_type = "Debit".to_string()));
+        assert!(rejected(|t| t.date = Utc::now() + Duration::days(MAX_FUTURE_DAYS + 1)));
+    }
+}
@@ -0,0 +1,350 @@
+use crate::ai_insights::AIInsightEngine;
+use crate::category_tree::UNCATEGORIZED_ID;
+use crate::commands::budgets::monthly_equivalent;
+use crate::error::{AppError, AppResult};
+use crate::models::{Budget, Category, FinancialInsight, Settings, Transaction};
+use crate::user_export::simple_pdf;
+use chrono::{DateTime, TimeZone, Utc};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+
+/// Insights shown at the bottom of the report
+const REPORT_INSIGHTS: usize = 3;
+
+/// Report texts with their Dutch and English wording.
+const REPORT_LABELS: &[(&str, &str, &str)] = &[
+    ("title", "SpaarApp - maandoverzicht", "SpaarApp - monthly report"),
+    ("generated", "Gemaakt op", "Generated on"),
+    ("summary", "Samenvatting", "Summary"),
+    ("transactions", "Transacties", "Transactions"),
+    ("income", "Inkomsten", "Income"),
+    ("expenses", "Uitgaven", "Expenses"),
+    ("net_savings", "Netto gespaard", "Net savings"),
+    ("no_transactions", "Er zijn geen transacties in deze maand.", "There were no transactions this month."),
+    ("categories", "Uitgaven per categorie", "Spending by category"),
+    ("category", "Categorie", "Category"),
+    ("amount", "Bedrag", "Amount"),
+    ("share", "Aandeel", "Share"),
+    ("uncategorized", "Zonder categorie", "Uncategorized"),
+    ("budgets", "Budgetten", "Budgets"),
+    ("budget", "Budget", "Budget"),
+    ("spent", "Uitgegeven", "Spent"),
+    ("limit", "Limiet", "Limit"),
+    ("status", "Status", "Status"),
+    ("within_budget", "binnen budget", "within budget"),
+    ("over_budget", "over budget", "over budget"),
+    ("insights", "Inzichten", "Insights"),
+];
+
+const MONTH_NAMES: [(&str, &str); 12] = [
+    ("januari", "January"),
+    ("februari", "February"),
+    ("maart", "March"),
+    ("april", "April"),
+    ("mei", "May"),
+    ("juni", "June"),
+    ("juli", "July"),
+    ("augustus", "August"),
+    ("september", "September"),
+    ("oktober", "October"),
+    ("november", "November"),
+    ("december", "December"),
+];
+
+fn label(key: &str, language: &str) -> &'static str {
+    REPORT_LABELS
+        .iter()
+        .find(|(name, _, _)| *name == key)
+        .map(|(_, nl, en)| if language == "en" { *en } else { *nl })
+        .unwrap_or_default()
+}
+
+/// Start of the month and start of the next one.
+pub fn report_month_bounds(year: i32, month: u32) -> AppResult<(DateTime<Utc>, DateTime<Utc>)> {
+    let invalid = || AppError::Validation(format!("Ongeldige maand: {}-{}", year, month));
+    if !(1..=12).contains(&month) {
+        return Err(invalid());
+    }
+
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    let start = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single().ok_or_else(invalid)?;
+    let end = Utc.with_ymd_and_hms(next_year, next_month, 1, 0, 0, 0).single().ok_or_else(invalid)?;
+    Ok((start, end))
+}
+
+/// Spending in one budget's category during the report month.
+#[derive(Debug, Clone)]
+pub struct BudgetStatus {
+    pub name: String,
+    pub spent: Decimal,
+    /// The budget amount for one month
+    pub limit: Decimal,
+}
+
+/// Printable overview of one month: totals, spending per category, budget
+/// status and a few insights.
+#[derive(Debug, Clone)]
+pub struct MonthlyReport {
+    pub year: i32,
+    pub month: u32,
+    pub settings: Settings,
+    pub transaction_count: usize,
+    pub income: Decimal,
+    pub expenses: Decimal,
+    /// Category name and amount spent, largest first
+    pub categories: Vec<(String, Decimal)>,
+    pub budgets: Vec<BudgetStatus>,
+    pub insights: Vec<FinancialInsight>,
+}
+
+impl MonthlyReport {
+    /// Report for the month from the transaction portions (split transactions
+    /// as one row per split) dated within it.
+    pub async fn build(
+        year: i32,
+        month: u32,
+        settings: &Settings,
+        portions: &[Transaction],
+        categories: &[Category],
+        budgets: &[Budget],
+    ) -> AppResult<Self> {
+        let (start, end) = report_month_bounds(year, month)?;
+        let portions: Vec<Transaction> = portions
+            .iter()
+            .filter(|t| t.date >= start && t.date < end)
+            .cloned()
+            .collect();
+
+        let total = |transaction_type: &str| -> Decimal {
+            portions
+                .iter()
+                .filter(|t| t.transaction_type == transaction_type)
+                .map(|t| t.amount.abs())
+                .sum()
+        };
+        let debits = || portions.iter().filter(|t| t.transaction_type == "debit");
+
+        let names: HashMap<&str, &str> = categories.iter().map(|c| (c.id.as_str(), c.name.as_str())).collect();
+        let mut by_category: HashMap<String, Decimal> = HashMap::new();
+        for transaction in debits() {
+            let name = transaction
+                .category_id
+                .as_deref()
+                .filter(|id| *id != UNCATEGORIZED_ID)
+                .and_then(|id| names.get(id))
+                .map_or_else(|| label("uncategorized", &settings.language).to_string(), |name| name.to_string());
+            *by_category.entry(name).or_default() += transaction.amount.abs();
+        }
+        let mut category_spending: Vec<(String, Decimal)> = by_category.into_iter().collect();
+        category_spending.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let budget_status = budgets
+            .iter()
+            .filter(|b| b.is_active && b.category_id.is_some())
+            .filter(|b| b.start_date < end && b.end_date.is_none_or(|budget_end| budget_end >= start))
+            .map(|budget| BudgetStatus {
+                name: budget.name.clone(),
+                spent: debits()
+                    .filter(|t| t.category_id == budget.category_id)
+                    .map(|t| t.amount.abs())
+                    .sum(),
+                limit: monthly_equivalent(budget.amount, &budget.period).round_dp(2),
+            })
+            .collect();
+
+        let mut insights = if portions.is_empty() {
+            Vec::new()
+        } else {
+            AIInsightEngine::with_settings(settings)
+                .generate_spending_insights(&portions, categories, budgets)
+                .await?
+        };
+        insights.sort_by(|a, b| b.confidence_score.total_cmp(&a.confidence_score));
+        insights.truncate(REPORT_INSIGHTS);
+
+        Ok(Self {
+            year,
+            month,
+            settings: settings.clone(),
+            transaction_count: portions.iter().map(|t| t.id.as_str()).collect::<HashSet<_>>().len(),
+            income: total("credit"),
+            expenses: total("debit"),
+            categories: category_spending,
+            budgets: budget_status,
+            insights,
+        })
+    }
+
+    /// The report as lines of text in the settings' language; tabs separate
+    /// table columns.
+    pub fn lines(&self, generated_at: DateTime<Utc>) -> Vec<String> {
+        let language = self.settings.language.as_str();
+        let money = |amount: Decimal| self.settings.format_money(amount);
+        let (nl, en) = MONTH_NAMES[self.month as usize - 1];
+        let month_name = if language == "en" { en } else { nl };
+
+        let mut lines = vec![
+            format!("{} {} {}", label("title", language), month_name, self.year),
+            format!("{} {}", label("generated", language), generated_at.format("%d-%m-%Y %H:%M UTC")),
+            String::new(),
+        ];
+
+        if self.transaction_count == 0 {
+            lines.push(label("no_transactions", language).to_string());
+        } else {
+            lines.push(label("summary", language).to_string());
+            lines.push(format!("  {}\t{}", label("transactions", language), self.transaction_count));
+            lines.push(format!("  {}\t{}", label("income", language), money(self.income)));
+            lines.push(format!("  {}\t{}", label("expenses", language), money(self.expenses)));
+            lines.push(format!("  {}\t{}", label("net_savings", language), money(self.income - self.expenses)));
+        }
+        lines.push(String::new());
+
+        if !self.categories.is_empty() {
+            lines.push(label("categories", language).to_string());
+            lines.push(format!(
+                "  {}\t{}\t{}",
+                label("category", language),
+                label("amount", language),
+                label("share", language)
+            ));
+            for (name, amount) in &self.categories {
+                let share = if self.expenses.is_zero() {
+                    Decimal::ZERO
+                } else {
+                    (*amount / self.expenses * Decimal::from(100)).round_dp(0)
+                };
+                lines.push(format!("  {}\t{}\t{}%", name, money(*amount), share));
+            }
+            lines.push(String::new());
+        }
+
+        if !self.budgets.is_empty() {
+            lines.push(label("budgets", language).to_string());
+            lines.push(format!(
+                "  {}\t{}\t{}\t{}",
+                label("budget", language),
+                label("spent", language),
+                label("limit", language),
+                label("status", language)
+            ));
+            for budget in &self.budgets {
+                let status = if budget.spent > budget.limit { "over_budget" } else { "within_budget" };
+                lines.push(format!(
+                    "  {}\t{}\t{}\t{}",
+                    budget.name,
+                    money(budget.spent),
+                    money(budget.limit),
+                    label(status, language)
+                ));
+            }
+            lines.push(String::new());
+        }
+
+        if !self.insights.is_empty() {
+            lines.push(label("insights", language).to_string());
+            for insight in &self.insights {
+                lines.push(format!("  - {}", insight.title));
+                lines.push(format!("    {}", insight.description));
+            }
+        }
+
+        lines
+    }
+
+    pub fn to_pdf(&self, generated_at: DateTime<Utc>) -> Vec<u8> {
+        simple_pdf(&self.lines(generated_at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(id: &str, amount: i64, transaction_type: &str, category_id: Option<&str>, day: u32) -> Transaction {
+        Transaction {
+            id: id.to_string(),
+            category_id: category_id.map(str::to_string),
+            ..Transaction::test(transaction_type, amount, Utc.with_ymd_and_hms(2024, 2, day, 12, 0, 0).unwrap())
+        }
+    }
+
+    #[test]
+    fn test_report_month_bounds() {
+        let (start, end) = report_month_bounds(2024, 12).unwrap();
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 12, 1, 0, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+        assert!(matches!(report_month_bounds(2024, 13), Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_month_without_transactions_gives_valid_report() {
+        let settings = Settings { language: "en".to_string(), ..Settings::default() };
+        // Outside the month, so left out
+        let portions = vec![transaction("t1", 20, "debit", None, 1)];
+
+        let report = MonthlyReport::build(2024, 3, &settings, &portions, &[], &[]).await.unwrap();
+        assert_eq!(report.transaction_count, 0);
+
+        let generated_at = Utc.with_ymd_and_hms(2024, 4, 1, 9, 0, 0).unwrap();
+        let lines = report.lines(generated_at);
+        assert_eq!(lines[0], "SpaarApp - monthly report March 2024");
+        assert!(lines.iter().any(|line| line == "There were no transactions this month."));
+        assert!(report.to_pdf(generated_at).starts_with(b"%PDF-1.4"));
+    }
+
+    #[tokio::test]
+    async fn test_report_totals_categories_and_budgets() {
+        let settings = Settings::default();
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let categories = vec![Category {
+            id: "boodschappen".to_string(),
+            name: "Boodschappen".to_string(),
+            description: None,
+            color: "#000000".to_string(),
+            icon: "🛒".to_string(),
+            parent_id: None,
+            is_system: false,
+            budget_percentage: None,
+            created_at: now,
+            updated_at: now,
+        }];
+        let budgets = vec![Budget {
+            id: "b1".to_string(),
+            name: "Boodschappen".to_string(),
+            category_id: Some("boodschappen".to_string()),
+            amount: Decimal::from(100),
+            period: "monthly".to_string(),
+            spent: Decimal::ZERO,
+            remaining: Decimal::ZERO,
+            is_active: true,
+            notification_threshold: None,
+            start_date: now,
+            end_date: None,
+            created_at: now,
+            updated_at: now,
+        }];
+        // t2 is split over two portions
+        let portions = vec![
+            transaction("t1", 2000, "credit", None, 1),
+            transaction("t2", 90, "debit", Some("boodschappen"), 5),
+            transaction("t2", 30, "debit", Some("boodschappen"), 5),
+            transaction("t3", 40, "debit", None, 10),
+        ];
+
+        let report = MonthlyReport::build(2024, 2, &settings, &portions, &categories, &budgets).await.unwrap();
+        assert_eq!(report.transaction_count, 3);
+        assert_eq!(report.income, Decimal::from(2000));
+        assert_eq!(report.expenses, Decimal::from(160));
+        assert_eq!(
+            report.categories,
+            vec![("Boodschappen".to_string(), Decimal::from(120)), ("Zonder categorie".to_string(), Decimal::from(40))]
+        );
+        assert_eq!(report.budgets[0].spent, Decimal::from(120));
+
+        let lines = report.lines(now);
+        assert_eq!(lines[0], "SpaarApp - maandoverzicht februari 2024");
+        assert!(lines.iter().any(|line| line.starts_with("  Boodschappen\t") && line.ends_with("\tover budget")));
+        assert!(lines.iter().any(|line| line.starts_with("  Boodschappen\t") && line.ends_with("\t75%")));
+    }
+}
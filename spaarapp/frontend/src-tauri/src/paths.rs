@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+
+/// Folder for files attached to transactions
+pub const ATTACHMENTS_DIR: &str = "attachments";
+/// Folder for database backups
+pub const BACKUPS_DIR: &str = "backups";
+/// Folder for application log files
+pub const LOGS_DIR: &str = "logs";
+
+/// Directory holding the database and the app's other data folders.
+pub fn data_dir(database_path: &str) -> PathBuf {
+    Path::new(database_path)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Path of one of the data folders next to the database.
+pub fn data_subdir(database_path: &str, name: &str) -> PathBuf {
+    data_dir(database_path).join(name)
+}
+
+/// Whether `path` lies inside one of `roots`, after resolving `..` and symlinks.
+/// Paths that do not exist are never inside.
+pub fn is_within(path: &Path, roots: &[PathBuf]) -> bool {
+    let Ok(path) = path.canonicalize() else {
+        return false;
+    };
+
+    roots
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| path.starts_with(root))
+}
+
+/// Whether a file may be written at `path` inside one of `roots`: an existing
+/// file must be inside (see [`is_within`]), a new one needs its folder inside.
+pub fn is_writable_within(path: &Path, roots: &[PathBuf]) -> bool {
+    if path.exists() {
+        return is_within(path, roots);
+    }
+
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(_)) if !parent.as_os_str().is_empty() => is_within(parent, roots),
+        _ => false,
+    }
+}
+
+/// Absolute form of `path` with `..` and symlinks resolved. A file that does
+/// not exist yet is resolved through its folder, which must exist.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Some(path);
+    }
+
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    Some(parent.canonicalize().ok()?.join(path.file_name()?))
+}
+
+/// Whether `path` is one of `files`, however either is spelled.
+pub fn is_one_of(path: &Path, files: &[PathBuf]) -> bool {
+    let Some(path) = resolve(path) else {
+        return false;
+    };
+
+    files.iter().filter_map(|file| resolve(file)).any(|file| file == path)
+}
+
+/// Size of a file in bytes, or 0 when it does not exist.
+pub fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Total size of all files below `path`, or 0 when it does not exist.
+pub fn dir_size(path: &Path) -> u64 {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(t) if t.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_size_counts_nested_files() {
+        let root = std::env::temp_dir().join(format!("spaarapp-paths-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        std::fs::write(root.join("a.txt"), b"12345").unwrap();
+        std::fs::write(root.join("nested").join("b.txt"), b"123").unwrap();
+
+        assert_eq!(dir_size(&root), 8);
+        assert_eq!(dir_size(&root.join("missing")), 0);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_is_within_resolves_parent_components() {
+        let root = std::env::temp_dir().join(format!("spaarapp-paths-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("data").join("export.csv"), b"").unwrap();
+        std::fs::write(root.join("secret.txt"), b"").unwrap();
+        let roots = vec![root.join("data")];
+
+        assert!(is_within(&root.join("data").join("export.csv"), &roots));
+        assert!(!is_within(&root.join("data").join("..").join("secret.txt"), &roots));
+        assert!(!is_within(&root.join("data").join("missing.csv"), &roots));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_is_writable_within_allows_new_files_in_roots() {
+        let root = std::env::temp_dir().join(format!("spaarapp-paths-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("data").join("export.csv"), b"").unwrap();
+        let roots = vec![root.join("data")];
+
+        assert!(is_writable_within(&root.join("data").join("export.csv"), &roots));
+        assert!(is_writable_within(&root.join("data").join("new.csv"), &roots));
+        assert!(!is_writable_within(&root.join("data").join("..").join("new.csv"), &roots));
+        assert!(!is_writable_within(&root.join("elsewhere").join("new.csv"), &roots));
+        assert!(!is_writable_within(Path::new("new.csv"), &roots));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_is_one_of_matches_other_spellings() {
+        let root = std::env::temp_dir().join(format!("spaarapp-paths-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("spaarapp.db"), b"").unwrap();
+        let files = vec![root.join("spaarapp.db"), root.join("spaarapp.db-wal")];
+
+        assert!(is_one_of(&root.join("data").join("..").join("spaarapp.db"), &files));
+        assert!(is_one_of(&root.join("spaarapp.db-wal"), &files));
+        assert!(!is_one_of(&root.join("data").join("spaarapp.db"), &files));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_data_dir_of_bare_filename() {
+        assert_eq!(data_dir("spaarapp.db"), PathBuf::from("."));
+        assert_eq!(data_subdir("/data/spaarapp.db", BACKUPS_DIR), PathBuf::from("/data/backups"));
+    }
+}
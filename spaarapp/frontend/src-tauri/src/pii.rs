@@ -0,0 +1,133 @@
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Mask replacing a redacted IBAN
+pub const IBAN_MASK: &str = "[iban]";
+/// Mask replacing a redacted name
+pub const NAME_MASK: &str = "[naam]";
+/// Mask replacing a redacted phone number
+pub const PHONE_MASK: &str = "[telefoon]";
+/// Mask replacing a redacted email address
+pub const EMAIL_MASK: &str = "[e-mail]";
+
+/// How much personal data was masked, per kind. Holds counts only, so it is
+/// safe to log.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RedactionReport {
+    pub ibans: usize,
+    pub names: usize,
+    pub phone_numbers: usize,
+    pub emails: usize,
+}
+
+impl RedactionReport {
+    pub fn total(&self) -> usize {
+        self.ibans + self.names + self.phone_numbers + self.emails
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    pub fn add(&mut self, other: RedactionReport) {
+        self.ibans += other.ibans;
+        self.names += other.names;
+        self.phone_numbers += other.phone_numbers;
+        self.emails += other.emails;
+    }
+}
+
+impl fmt::Display for RedactionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} IBANs, {} names, {} phone numbers, {} email addresses",
+            self.ibans, self.names, self.phone_numbers, self.emails
+        )
+    }
+}
+
+struct Patterns {
+    email: Regex,
+    iban: Regex,
+    phone: Regex,
+    name: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        email: Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap(),
+        // Printed in groups of four or as one run, e.g. NL91 ABNA 0417 1643 00
+        iban: Regex::new(r"\b[A-Z]{2}\d{2} ?[A-Z]{4}(?: ?\d{2,4}){2,8}\b").unwrap(),
+        // Mobile and landline numbers, with +31 or 0031 or a leading 0
+        phone: Regex::new(r"(?:\+31|\b0031|\b0)(?:[ -]?\(?0?\)?)?(?:[ -]?\d){9}\b").unwrap(),
+        // A salutation or "t.n.v." followed by initials, a first name,
+        // tussenvoegsels and a surname
+        name: Regex::new(concat!(
+            r"(?P<prefix>\b(?i:dhr\.?|de heer|hr\.?|mevr\.?|mevrouw|mw\.?|fam\.?|familie|t\.?\s?n\.?\s?v\.?|naam:?)\s+)",
+            r"(?P<name>(?:[A-Z]\.\s?)*(?:[A-Z][a-zà-ÿ]+\s+)?",
+            r"(?:(?:van|de|der|den|ter|ten|te|het|'t|la|le)\s+)*[A-Z][a-zà-ÿ'-]+)"
+        ))
+        .unwrap(),
+    })
+}
+
+/// `text` with IBANs, names after a Dutch salutation, phone numbers and email
+/// addresses masked, and a report of what was masked.
+pub fn redact_pii_with_report(text: &str) -> (String, RedactionReport) {
+    let patterns = patterns();
+
+    // Email addresses first, so their digits are not taken for a phone number
+    let emails = patterns.email.find_iter(text).count();
+    let text = patterns.email.replace_all(text, EMAIL_MASK);
+
+    let ibans = patterns.iban.find_iter(&text).count();
+    let text = patterns.iban.replace_all(&text, IBAN_MASK);
+
+    let phone_numbers = patterns.phone.find_iter(&text).count();
+    let text = patterns.phone.replace_all(&text, PHONE_MASK);
+
+    let names = patterns.name.find_iter(&text).count();
+    let text = patterns
+        .name
+        .replace_all(&text, |caps: &Captures| format!("{}{}", &caps["prefix"], NAME_MASK));
+
+    (text.into_owned(), RedactionReport { ibans, names, phone_numbers, emails })
+}
+
+/// `text` with IBANs, names after a Dutch salutation, phone numbers and email
+/// addresses masked. Run on any text that leaves the device.
+pub fn redact_pii(text: &str) -> String {
+    redact_pii_with_report(text).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_iban_email_and_phone() {
+        let (text, report) = redact_pii_with_report(
+            "Overboeking NL91 ABNA 0417 1643 00 t.n.v. J. de Vries, factuur 2024-118 info@schildersbedrijf.nl tel 06-12345678"
+        );
+        assert_eq!(
+            text,
+            "Overboeking [iban] t.n.v. [naam], factuur 2024-118 [e-mail] tel [telefoon]"
+        );
+        assert_eq!(report, RedactionReport { ibans: 1, names: 1, phone_numbers: 1, emails: 1 });
+
+        let (text, report) = redact_pii_with_report("Tikkie van dhr Pieter van der Berg via NL02RABO0123456789, +31 20 123 4567");
+        assert_eq!(text, "Tikkie van dhr [naam] via [iban], [telefoon]");
+        assert_eq!(report.total(), 3);
+    }
+
+    #[test]
+    fn test_leaves_ordinary_descriptions_alone() {
+        let description = "Albert Heijn 1403 Utrecht pas 123 2024-03-05 12:30";
+        assert_eq!(redact_pii(description), description);
+        assert_eq!(redact_pii("Betaling aan mevrouw Jansen"), "Betaling aan mevrouw [naam]");
+    }
+}
@@ -0,0 +1,145 @@
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Rate limits for expensive commands, mirroring `RateLimitConfig` in the
+/// security configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Requests per minute
+    pub requests_per_minute: u32,
+
+    /// Burst limit
+    pub burst_limit: u32,
+
+    /// Rate limit window in seconds
+    pub window_secs: u64,
+
+    /// Enable exponential backoff
+    pub enable_exponential_backoff: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 60,
+            burst_limit: 10,
+            window_secs: 60,
+            enable_exponential_backoff: true,
+        }
+    }
+}
+
+/// Groups of commands that share one bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimited {
+    AiInsights,
+    CsvParsing,
+    Export,
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+    /// Refused requests in a row, for the backoff
+    violations: u32,
+    blocked_until: Option<Instant>,
+}
+
+/// Token bucket per command group: up to `burst_limit` requests at once,
+/// refilled at `requests_per_minute`. With exponential backoff every refusal
+/// in a row doubles the wait before the next request is let through, up to
+/// ten times `window_secs`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: HashMap<RateLimited, Bucket>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, buckets: HashMap::new() }
+    }
+
+    /// Take a token for a request in `group` made at `now`, or refuse it with
+    /// a hint how long to wait.
+    pub fn check(&mut self, group: RateLimited, now: Instant) -> AppResult<()> {
+        let burst = self.config.burst_limit.max(1) as f64;
+        // Tokens added per second
+        let rate = self.config.requests_per_minute.max(1) as f64 / 60.0;
+        let max_penalty = Duration::from_secs(self.config.window_secs.max(1) * 10);
+
+        let bucket = self.buckets.entry(group).or_insert_with(|| Bucket {
+            tokens: burst,
+            refilled_at: now,
+            violations: 0,
+            blocked_until: None,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.refilled_at = now;
+
+        let blocked = bucket.blocked_until.is_some_and(|until| now < until);
+        if !blocked && bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            bucket.violations = 0;
+            bucket.blocked_until = None;
+            return Ok(());
+        }
+
+        bucket.violations += 1;
+        let wait = if self.config.enable_exponential_backoff {
+            // One token interval, doubled for every refusal in a row
+            let factor = 2u32.saturating_pow(bucket.violations - 1);
+            Duration::from_secs_f64(1.0 / rate).saturating_mul(factor).min(max_penalty)
+        } else {
+            Duration::from_secs_f64((1.0 - bucket.tokens).max(0.0) / rate)
+        };
+        bucket.blocked_until = Some(now + wait);
+
+        Err(AppError::PermissionDenied(format!(
+            "Te veel verzoeken; probeer het over {} s opnieuw",
+            wait.as_secs_f64().ceil().max(1.0) as u64
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_refused_with_growing_backoff() {
+        let config = RateLimitConfig::default();
+        let burst = config.burst_limit;
+        let mut limiter = RateLimiter::new(config);
+        let start = Instant::now();
+
+        for _ in 0..burst {
+            limiter.check(RateLimited::AiInsights, start).unwrap();
+        }
+        let refused = limiter.check(RateLimited::AiInsights, start);
+        assert!(matches!(&refused, Err(AppError::PermissionDenied(msg)) if msg.contains("over 1 s")));
+
+        // Other command groups have their own bucket
+        limiter.check(RateLimited::Export, start).unwrap();
+
+        // Trying again while blocked doubles the wait
+        let refused = limiter.check(RateLimited::AiInsights, start + Duration::from_millis(500));
+        assert!(matches!(&refused, Err(AppError::PermissionDenied(msg)) if msg.contains("over 2 s")));
+        let refused = limiter.check(RateLimited::AiInsights, start + Duration::from_millis(1200));
+        assert!(matches!(&refused, Err(AppError::PermissionDenied(msg)) if msg.contains("over 4 s")));
+
+        // Once the penalty is over, refilled tokens are let through again
+        limiter.check(RateLimited::AiInsights, start + Duration::from_secs(10)).unwrap();
+    }
+}
@@ -0,0 +1,644 @@
+use crate::ai_insights::insight_message;
+use crate::forecast::occurrences_per_month;
+use crate::models::{FinancialInsight, Settings, Transaction};
+use chrono::{DateTime, Duration, Months, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Amounts differing more than this fraction from the typical amount make a stream variable
+const VARIABLE_AMOUNT_SPREAD: Decimal = Decimal::from_parts(10, 0, 0, false, 2);
+
+/// One payment of a recurring stream.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecurringPayment {
+    pub date: DateTime<Utc>,
+    pub amount: Decimal,
+}
+
+/// Payments to or from the same counterparty at a regular interval.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecurringStream {
+    /// Name of the employer, merchant or other counterparty
+    pub counterparty: String,
+    pub transaction_type: String,
+    /// "weekly", "biweekly", "monthly", "quarterly" or "yearly"
+    pub frequency: String,
+    /// Median amount
+    pub typical_amount: Decimal,
+    pub min_amount: Decimal,
+    pub max_amount: Decimal,
+    /// Amounts vary (hourly or freelance income); use the range rather than the typical amount
+    pub is_variable: bool,
+    pub last_date: DateTime<Utc>,
+    pub next_expected: DateTime<Utc>,
+    /// Payments oldest first
+    pub history: Vec<RecurringPayment>,
+}
+
+/// Frequency name and the range of days between payments it allows.
+const FREQUENCIES: &[(&str, i64, i64)] = &[
+    ("weekly", 6, 8),
+    ("biweekly", 13, 16),
+    ("monthly", 26, 35),
+    ("quarterly", 85, 97),
+    ("yearly", 355, 375),
+];
+
+/// Description without digits (dates, invoice numbers), case and extra spaces.
+fn normalized_description(description: &str) -> String {
+    description
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_ascii_digit())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Key grouping payments of one counterparty: the counter account when known,
+/// otherwise the normalized description.
+fn counterparty_key(transaction: &Transaction) -> String {
+    if let Some(account) = transaction.account_holder.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+        return account.replace(' ', "").to_uppercase();
+    }
+
+    normalized_description(&transaction.description)
+}
+
+/// Readable name: the first part of a bank description ("Werkgever BV - Salaris maart").
+fn counterparty_name(transaction: &Transaction) -> String {
+    transaction
+        .description
+        .split(" - ")
+        .next()
+        .unwrap_or(&transaction.description)
+        .trim()
+        .to_string()
+}
+
+/// Middle value of a sorted, non-empty slice.
+pub(crate) fn median(sorted: &[Decimal]) -> Decimal {
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / Decimal::from(2)
+    } else {
+        sorted[middle]
+    }
+}
+
+/// The frequency whose interval range contains most gaps, if (nearly) all of them fit.
+fn classify_interval(gaps_in_days: &[i64]) -> Option<&'static str> {
+    let mut sorted = gaps_in_days.to_vec();
+    sorted.sort_unstable();
+    let typical = sorted[sorted.len() / 2];
+
+    let (name, min, max) = FREQUENCIES.iter().find(|(_, min, max)| (*min..=*max).contains(&typical))?;
+    let fitting = gaps_in_days.iter().filter(|gap| (*min..=*max).contains(*gap)).count();
+
+    // Allow one late or skipped payment in every three
+    (fitting * 3 >= gaps_in_days.len() * 2).then_some(*name)
+}
+
+fn next_date(last: DateTime<Utc>, frequency: &str) -> DateTime<Utc> {
+    let months = match frequency {
+        "weekly" => return last + Duration::days(7),
+        "biweekly" => return last + Duration::days(14),
+        "quarterly" => 3,
+        "yearly" => 12,
+        _ => 1,
+    };
+
+    last.checked_add_months(Months::new(months)).unwrap_or(last + Duration::days(30 * months as i64))
+}
+
+/// Find regular payments of the given type ("credit" or "debit"), soonest expected first.
+///
+/// A stream needs at least three payments, or two for yearly ones.
+pub fn detect_recurring(transactions: &[Transaction], transaction_type: &str) -> Vec<RecurringStream> {
+    let mut groups: HashMap<String, Vec<&Transaction>> = HashMap::new();
+    for transaction in transactions.iter().filter(|t| t.transaction_type == transaction_type) {
+        groups.entry(counterparty_key(transaction)).or_default().push(transaction);
+    }
+
+    let mut streams: Vec<RecurringStream> = groups
+        .into_values()
+        .filter(|group| group.len() >= 2)
+        .filter_map(|mut group| {
+            group.sort_by_key(|t| t.date);
+
+            let gaps: Vec<i64> = group.windows(2).map(|w| (w[1].date - w[0].date).num_days()).collect();
+            let frequency = classify_interval(&gaps)?;
+            if group.len() < 3 && frequency != "yearly" {
+                return None;
+            }
+
+            let mut amounts: Vec<Decimal> = group.iter().map(|t| t.amount.abs()).collect();
+            amounts.sort();
+            let typical_amount = median(&amounts);
+            let min_amount = amounts[0];
+            let max_amount = amounts[amounts.len() - 1];
+
+            let last = group[group.len() - 1];
+
+            Some(RecurringStream {
+                counterparty: counterparty_name(last),
+                transaction_type: transaction_type.to_string(),
+                frequency: frequency.to_string(),
+                typical_amount,
+                min_amount,
+                max_amount,
+                is_variable: typical_amount > Decimal::ZERO
+                    && (max_amount - min_amount) / typical_amount > VARIABLE_AMOUNT_SPREAD,
+                last_date: last.date,
+                next_expected: next_date(last.date, frequency),
+                history: group
+                    .iter()
+                    .map(|t| RecurringPayment { date: t.date, amount: t.amount.abs() })
+                    .collect(),
+            })
+        })
+        .collect();
+
+    streams.sort_by_key(|s| s.next_expected);
+    streams
+}
+
+/// Series with fewer occurrences than this get a lower confidence
+const FULL_CONFIDENCE_OCCURRENCES: usize = 6;
+
+/// The same payment (description and amount) repeating at a regular interval.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecurringSeries {
+    pub description: String,
+    pub amount: Decimal,
+    pub transaction_type: String,
+    /// "weekly", "biweekly", "monthly", "quarterly" or "yearly"
+    pub frequency: String,
+    /// 0.0 - 1.0: the share of intervals that match the frequency, lowered for
+    /// series with only a few occurrences
+    pub confidence: f64,
+    pub occurrences: usize,
+    pub last_date: DateTime<Utc>,
+    pub next_expected: DateTime<Utc>,
+}
+
+/// Group transactions by normalized description, amount and type, and keep the
+/// groups whose dates follow a regular cadence. At least three occurrences must
+/// be spaced within the tolerance of that cadence, so one-off repeats and
+/// irregular purchases are not flagged. Most confident first.
+pub fn detect_recurring_series(transactions: &[Transaction]) -> Vec<RecurringSeries> {
+    split_off_recurring(transactions).0
+}
+
+/// The recurring series as [`detect_recurring_series`] finds them, and the
+/// transactions that are not part of any series, e.g. the day-to-day
+/// spending around the fixed charges.
+pub fn split_off_recurring(transactions: &[Transaction]) -> (Vec<RecurringSeries>, Vec<&Transaction>) {
+    let mut groups: HashMap<(String, Decimal, &str), Vec<&Transaction>> = HashMap::new();
+    for transaction in transactions {
+        let key = (
+            normalized_description(&transaction.description),
+            transaction.amount.abs().round_dp(2).normalize(),
+            transaction.transaction_type.as_str(),
+        );
+        groups.entry(key).or_default().push(transaction);
+    }
+
+    let mut rest = Vec::new();
+    let mut series: Vec<RecurringSeries> = groups
+        .into_iter()
+        .filter_map(|((_, amount, transaction_type), mut group)| {
+            let found = as_series(amount, transaction_type, &mut group);
+            if found.is_none() {
+                rest.extend(group);
+            }
+            found
+        })
+        .collect();
+
+    series.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then(a.next_expected.cmp(&b.next_expected)));
+    rest.sort_by_key(|t| t.date);
+    (series, rest)
+}
+
+/// The series formed by one group of identical payments, if their dates follow a cadence.
+fn as_series(amount: Decimal, transaction_type: &str, group: &mut [&Transaction]) -> Option<RecurringSeries> {
+    if group.len() < 3 {
+        return None;
+    }
+    group.sort_by_key(|t| t.date);
+
+    let gaps: Vec<i64> = group.windows(2).map(|w| (w[1].date - w[0].date).num_days()).collect();
+    let frequency = classify_interval(&gaps)?;
+    let (_, min, max) = FREQUENCIES.iter().find(|(name, _, _)| *name == frequency)?;
+    let fitting = gaps.iter().filter(|gap| (*min..=*max).contains(*gap)).count();
+
+    // Two aligned intervals means three occurrences on the cadence
+    if fitting < 2 {
+        return None;
+    }
+
+    let occurrence_weight = group.len().min(FULL_CONFIDENCE_OCCURRENCES) as f64 / FULL_CONFIDENCE_OCCURRENCES as f64;
+    let last = group[group.len() - 1];
+
+    Some(RecurringSeries {
+        description: counterparty_name(last),
+        amount,
+        transaction_type: transaction_type.to_string(),
+        frequency: frequency.to_string(),
+        confidence: fitting as f64 / gaps.len() as f64 * (0.5 + 0.5 * occurrence_weight),
+        occurrences: group.len(),
+        last_date: last.date,
+        next_expected: next_date(last.date, frequency),
+    })
+}
+
+/// An expected future occurrence of a recurring series.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpcomingRecurring {
+    pub date: DateTime<Utc>,
+    pub description: String,
+    pub amount: Decimal,
+    pub transaction_type: String,
+    pub frequency: String,
+    pub confidence: f64,
+}
+
+/// Expected occurrences between `now` and `days_ahead` days later, soonest first.
+/// Series that missed two expected occurrences are considered stopped.
+pub fn upcoming_occurrences(series: &[RecurringSeries], now: DateTime<Utc>, days_ahead: u32) -> Vec<UpcomingRecurring> {
+    let horizon = now + Duration::days(days_ahead as i64);
+    let mut upcoming = Vec::new();
+
+    for s in series {
+        let mut date = s.next_expected;
+        let mut missed = 0;
+        while date < now {
+            date = next_date(date, &s.frequency);
+            missed += 1;
+        }
+        if missed >= 2 {
+            continue;
+        }
+
+        while date <= horizon {
+            upcoming.push(UpcomingRecurring {
+                date,
+                description: s.description.clone(),
+                amount: s.amount,
+                transaction_type: s.transaction_type.clone(),
+                frequency: s.frequency.clone(),
+                confidence: s.confidence,
+            });
+            date = next_date(date, &s.frequency);
+        }
+    }
+
+    upcoming.sort_by_key(|u| u.date);
+    upcoming
+}
+
+/// A recurring charge whose latest amount is above what it used to be.
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceIncrease {
+    pub counterparty: String,
+    pub frequency: String,
+    /// Median of the charges before the latest one
+    pub previous_amount: Decimal,
+    pub current_amount: Decimal,
+    pub increase_percentage: Decimal,
+    /// Extra cost per year at the new price
+    pub annual_extra_cost: Decimal,
+    pub changed_on: DateTime<Utc>,
+}
+
+/// Rise above the earlier baseline that counts as a price increase when no
+/// other threshold is given (5%)
+pub const PRICE_INCREASE_THRESHOLD: Decimal = Decimal::from_parts(5, 0, 0, false, 2);
+
+/// Streams whose latest charge exceeds the earlier baseline by more than
+/// `threshold` (0.05 = 5%). Needs at least two earlier charges for a baseline.
+pub fn find_price_increases(streams: &[RecurringStream], threshold: Decimal) -> Vec<PriceIncrease> {
+    let mut increases: Vec<PriceIncrease> = streams
+        .iter()
+        .filter(|s| s.transaction_type == "debit" && s.history.len() >= 3)
+        .filter_map(|stream| {
+            let (latest, earlier) = stream.history.split_last()?;
+            let mut amounts: Vec<Decimal> = earlier.iter().map(|p| p.amount).collect();
+            amounts.sort();
+            let baseline = median(&amounts);
+
+            if baseline <= Decimal::ZERO || latest.amount <= baseline * (Decimal::ONE + threshold) {
+                return None;
+            }
+
+            let per_year = occurrences_per_month(&stream.frequency)? * Decimal::from(12);
+            let change = latest.amount - baseline;
+
+            Some(PriceIncrease {
+                counterparty: stream.counterparty.clone(),
+                frequency: stream.frequency.clone(),
+                previous_amount: baseline,
+                current_amount: latest.amount,
+                increase_percentage: (change / baseline * Decimal::from(100)).round_dp(1),
+                annual_extra_cost: (change * per_year).round_dp(2),
+                changed_on: latest.date,
+            })
+        })
+        .collect();
+
+    increases.sort_by_key(|i| std::cmp::Reverse(i.annual_extra_cost));
+    increases
+}
+
+/// "Je Netflix-abonnement is gestegen van € 13,99 naar € 15,99" insight, in
+/// the configured language.
+pub fn price_increase_insight(increase: &PriceIncrease, settings: &Settings) -> FinancialInsight {
+    let values = [
+        ("merchant", increase.counterparty.clone()),
+        ("previous", settings.format_money(increase.previous_amount)),
+        ("current", settings.format_money(increase.current_amount)),
+        ("percentage", settings.format_percentage(increase.increase_percentage)),
+        ("annual", settings.format_money(increase.annual_extra_cost)),
+    ];
+    let text = |key: &str| insight_message(key, &settings.language, &values);
+
+    FinancialInsight {
+        id: uuid::Uuid::new_v4().to_string(),
+        insight_type: "recurring_expense".to_string(),
+        title: text("price_increase_title"),
+        description: text("price_increase_description"),
+        impact: if increase.annual_extra_cost >= Decimal::from(50) { "medium" } else { "low" }.to_string(),
+        actionable: true,
+        action_suggestions: serde_json::to_string(&vec![text("subscription_review"), text("subscription_cheaper")])
+            .unwrap_or_default(),
+        confidence_score: 0.85,
+        is_read: false,
+        is_dismissed: false,
+        created_at: Utc::now(),
+    }
+}
+
+/// A gap between two monthly charges longer than this suggests the
+/// subscription was paused or not used for a while
+const UNUSED_GAP_DAYS: i64 = 45;
+
+/// A monthly subscription with what stands out about it.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionReview {
+    pub merchant: String,
+    /// Latest charge
+    pub current_amount: Decimal,
+    pub last_charged: DateTime<Utc>,
+    /// Longest time between two charges
+    pub longest_gap_days: i64,
+    /// Latest charge rose more than [`PRICE_INCREASE_THRESHOLD`] above the
+    /// earlier baseline, as found by [`find_price_increases`]
+    pub price_increase: Option<PriceIncrease>,
+    /// A charge came more than [`UNUSED_GAP_DAYS`] after the one before
+    pub possibly_unused: bool,
+    /// What to do about it in the configured language; `None` when nothing stands out
+    pub suggestion: Option<String>,
+}
+
+/// Monthly debit streams still being charged at `now`, with price changes and
+/// long gaps between charges flagged. Increased and possibly unused ones first.
+pub fn analyze_subscriptions(streams: &[RecurringStream], now: DateTime<Utc>, settings: &Settings) -> Vec<SubscriptionReview> {
+    let increases = find_price_increases(streams, PRICE_INCREASE_THRESHOLD);
+
+    let mut reviews: Vec<SubscriptionReview> = streams
+        .iter()
+        .filter(|s| s.transaction_type == "debit" && s.frequency == "monthly")
+        .filter(|s| (now - s.last_date).num_days() <= UNUSED_GAP_DAYS)
+        .filter_map(|stream| {
+            let latest = stream.history.last()?;
+            let longest_gap_days = stream
+                .history
+                .windows(2)
+                .map(|w| (w[1].date - w[0].date).num_days())
+                .max()
+                .unwrap_or(0);
+
+            let price_increase = increases.iter().find(|i| i.counterparty == stream.counterparty).cloned();
+            let possibly_unused = longest_gap_days > UNUSED_GAP_DAYS;
+            let suggestion = if possibly_unused {
+                Some("subscription_cancel")
+            } else if price_increase.is_some() {
+                Some("subscription_cheaper")
+            } else {
+                None
+            };
+
+            Some(SubscriptionReview {
+                merchant: stream.counterparty.clone(),
+                current_amount: latest.amount,
+                last_charged: latest.date,
+                longest_gap_days,
+                price_increase,
+                possibly_unused,
+                suggestion: suggestion.map(|key| insight_message(key, &settings.language, &[])),
+            })
+        })
+        .collect();
+
+    reviews.sort_by(|a, b| {
+        (b.price_increase.is_some() || b.possibly_unused)
+            .cmp(&(a.price_increase.is_some() || a.possibly_unused))
+            .then(b.current_amount.cmp(&a.current_amount))
+    });
+    reviews
+}
+
+/// `recurring_expense` insights for a subscription that got more expensive or
+/// seems unused; none when nothing stands out. Increases get the same insight
+/// as [`find_price_increases`] results.
+pub fn subscription_insights(review: &SubscriptionReview, settings: &Settings) -> Vec<FinancialInsight> {
+    let values = [
+        ("merchant", review.merchant.clone()),
+        ("days", review.longest_gap_days.to_string()),
+    ];
+    let text = |key: &str| insight_message(key, &settings.language, &values);
+    let insight = |title: &str, description: &str, impact: &str, suggestions: &[&str]| FinancialInsight {
+        id: uuid::Uuid::new_v4().to_string(),
+        insight_type: "recurring_expense".to_string(),
+        title: text(title),
+        description: text(description),
+        impact: impact.to_string(),
+        actionable: true,
+        action_suggestions: serde_json::to_string(&suggestions.iter().map(|key| text(key)).collect::<Vec<_>>())
+            .unwrap_or_default(),
+        confidence_score: 0.8,
+        is_read: false,
+        is_dismissed: false,
+        created_at: Utc::now(),
+    };
+
+    let mut insights = Vec::new();
+    if let Some(increase) = &review.price_increase {
+        insights.push(price_increase_insight(increase, settings));
+    }
+    if review.possibly_unused {
+        insights.push(insight(
+            "subscription_gap_title",
+            "subscription_gap_description",
+            "low",
+            &["subscription_review", "subscription_cancel"],
+        ));
+    }
+    insights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn credit(description: &str, amount: Decimal, date: DateTime<Utc>) -> Transaction {
+        Transaction { description: description.to_string(), ..Transaction::test("credit", amount, date) }
+    }
+
+    #[test]
+    fn test_monthly_salary_and_variable_freelance_income() {
+        let mut transactions = Vec::new();
+        for (month, hours) in [(1, 40), (2, 52), (3, 31), (4, 45)] {
+            transactions.push(credit(
+                &format!("Werkgever BV - Salaris {}", month),
+                Decimal::from(2500),
+                Utc.with_ymd_and_hms(2024, month, 25, 12, 0, 0).unwrap(),
+            ));
+            transactions.push(credit(
+                "Klant X - Factuur",
+                Decimal::from(hours * 60),
+                Utc.with_ymd_and_hms(2024, month, 3, 12, 0, 0).unwrap(),
+            ));
+        }
+        transactions.push(credit("Tikkie", Decimal::from(12), Utc.with_ymd_and_hms(2024, 2, 9, 12, 0, 0).unwrap()));
+
+        let streams = detect_recurring(&transactions, "credit");
+
+        assert_eq!(streams.len(), 2);
+        let freelance = &streams[0];
+        assert_eq!(freelance.counterparty, "Klant X");
+        assert!(freelance.is_variable);
+        assert_eq!(freelance.min_amount, Decimal::from(1860));
+
+        let salary = &streams[1];
+        assert_eq!(salary.counterparty, "Werkgever BV");
+        assert_eq!(salary.frequency, "monthly");
+        assert!(!salary.is_variable);
+        assert_eq!(salary.next_expected, Utc.with_ymd_and_hms(2024, 5, 25, 12, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_subscription_price_increase() {
+        let mut transactions = Vec::new();
+        for (month, cents) in [(1, 1399), (2, 1399), (3, 1399), (4, 1599)] {
+            let mut charge = credit("Netflix", Decimal::new(cents, 2), Utc.with_ymd_and_hms(2024, month, 5, 12, 0, 0).unwrap());
+            charge.transaction_type = "debit".to_string();
+            transactions.push(charge);
+        }
+
+        let streams = detect_recurring(&transactions, "debit");
+        let increases = find_price_increases(&streams, Decimal::new(5, 2));
+
+        assert_eq!(increases.len(), 1);
+        assert_eq!(increases[0].annual_extra_cost, Decimal::from(24));
+        assert_eq!(
+            price_increase_insight(&increases[0], &Settings::default()).title,
+            "Je Netflix-abonnement is gestegen van € 13,99 naar € 15,99"
+        );
+        let english = Settings { language: "en".to_string(), ..Settings::default() };
+        let insight = price_increase_insight(&increases[0], &english);
+        assert_eq!(insight.title, "Your Netflix subscription went up from €13.99 to €15.99");
+        assert_eq!(insight.description, "An increase of 14.3%. That costs you €24.00 more per year.");
+        assert!(find_price_increases(&streams, Decimal::new(20, 2)).is_empty());
+    }
+
+    #[test]
+    fn test_subscription_review_flags_increase_and_gap() {
+        let mut transactions = Vec::new();
+        let mut charge = |description: &str, cents: i64, month: u32| {
+            let mut charge = credit(description, Decimal::new(cents, 2), Utc.with_ymd_and_hms(2024, month, 5, 12, 0, 0).unwrap());
+            charge.transaction_type = "debit".to_string();
+            transactions.push(charge);
+        };
+        for (month, cents) in [(1, 1399), (2, 1399), (3, 1599), (4, 1599), (5, 1599)] {
+            charge("Netflix", cents, month);
+        }
+        // Nothing charged in March and April
+        for month in [1, 2, 5, 6] {
+            charge("Sportschool", 2999, month);
+        }
+        for month in 1..=6 {
+            charge("Spotify", 1099, month);
+        }
+        // Stopped in February
+        for month in 1..=2 {
+            charge("Videoland", 899, month);
+        }
+
+        let streams = detect_recurring(&transactions, "debit");
+        let now = Utc.with_ymd_and_hms(2024, 6, 10, 12, 0, 0).unwrap();
+        let reviews = analyze_subscriptions(&streams, now, &Settings::default());
+        let merchants: Vec<&str> = reviews.iter().map(|r| r.merchant.as_str()).collect();
+        assert_eq!(merchants, ["Sportschool", "Netflix", "Spotify"]);
+
+        let netflix = &reviews[1];
+        assert!(netflix.price_increase.is_some() && !netflix.possibly_unused);
+        // Baseline is the median of the earlier charges
+        let increase = netflix.price_increase.as_ref().unwrap();
+        assert_eq!((increase.previous_amount, increase.current_amount), (Decimal::new(1499, 2), Decimal::new(1599, 2)));
+        assert!(reviews[0].possibly_unused);
+        assert!(reviews[2].price_increase.is_none());
+        assert_eq!(reviews[2].suggestion, None);
+
+        let insights = subscription_insights(netflix, &Settings::default());
+        assert_eq!(insights.len(), 1);
+        assert_eq!(insights[0].insight_type, "recurring_expense");
+        assert_eq!(insights[0].title, price_increase_insight(increase, &Settings::default()).title);
+        assert!(subscription_insights(&reviews[2], &Settings::default()).is_empty());
+    }
+
+    #[test]
+    fn test_series_needs_three_aligned_occurrences() {
+        let debit = |description: &str, cents: i64, date: DateTime<Utc>| {
+            let mut transaction = credit(description, Decimal::new(cents, 2), date);
+            transaction.transaction_type = "debit".to_string();
+            transaction
+        };
+
+        let mut transactions = Vec::new();
+        for month in 1..=4 {
+            transactions.push(debit("Spotify P1234", 1099, Utc.with_ymd_and_hms(2024, month, 12, 12, 0, 0).unwrap()));
+        }
+        // Same shop and amount, but at irregular intervals
+        for (month, day) in [(1, 2), (1, 20), (3, 28)] {
+            transactions.push(debit("Bakker Bart", 450, Utc.with_ymd_and_hms(2024, month, day, 12, 0, 0).unwrap()));
+        }
+        // Only two occurrences
+        for month in [1, 2] {
+            transactions.push(debit("Sportschool", 2500, Utc.with_ymd_and_hms(2024, month, 1, 12, 0, 0).unwrap()));
+        }
+
+        let series = detect_recurring_series(&transactions);
+
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].description, "Spotify P1234");
+        assert_eq!(series[0].frequency, "monthly");
+        assert!((series[0].confidence - (0.5 + 0.5 * 4.0 / 6.0)).abs() < 1e-9);
+
+        let now = Utc.with_ymd_and_hms(2024, 4, 20, 12, 0, 0).unwrap();
+        let upcoming = upcoming_occurrences(&series, now, 60);
+        let dates: Vec<_> = upcoming.iter().map(|u| u.date).collect();
+        assert_eq!(
+            dates,
+            vec![
+                Utc.with_ymd_and_hms(2024, 5, 12, 12, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap(),
+            ]
+        );
+        assert!(upcoming_occurrences(&series, now + Duration::days(90), 30).is_empty());
+    }
+}
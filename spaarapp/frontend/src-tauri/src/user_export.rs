@@ -0,0 +1,445 @@
+use crate::audit::AuditEntry;
+use crate::commands::accounts::Account;
+use crate::commands::budgets::ClosedBudgetPeriod;
+use crate::commands::category_rules::CategoryRule;
+use crate::error::{AppError, AppResult};
+use crate::models::{Budget, Category, FinancialInsight, SavingsGoal, Settings, Transaction};
+use crate::pii::{redact_pii_with_report, RedactionReport};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use csv::WriterBuilder;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Everything SpaarApp stores about the user, as handed over on a data export
+/// request (GDPR art. 20).
+#[derive(Debug, Clone, Serialize)]
+pub struct UserDataExport {
+    pub exported_at: DateTime<Utc>,
+    pub app_version: String,
+    pub settings: Settings,
+    pub accounts: Vec<Account>,
+    pub categories: Vec<Category>,
+    pub budgets: Vec<Budget>,
+    /// Closed periods of the budgets
+    pub budget_periods: Vec<ClosedBudgetPeriod>,
+    pub category_rules: Vec<CategoryRule>,
+    pub savings_goals: Vec<SavingsGoal>,
+    pub transactions: Vec<Transaction>,
+    pub insights: Vec<FinancialInsight>,
+    /// Newest first
+    pub audit_log: Vec<AuditEntry>,
+}
+
+/// File formats a data export can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One structured document
+    Json,
+    /// A zip archive with one CSV file per table
+    Csv,
+    /// A readable summary report
+    Pdf,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> AppResult<Self> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "pdf" => Ok(ExportFormat::Pdf),
+            _ => Err(AppError::Validation(format!("Onbekend exportformaat: {} (kies JSON, CSV of PDF)", format))),
+        }
+    }
+}
+
+impl UserDataExport {
+    /// Mask personal data in transaction descriptions and notes with
+    /// [`redact_pii`], and drop counterparty account numbers and holders, for
+    /// an export that is shared with someone else.
+    pub fn redact_pii(&mut self) -> RedactionReport {
+        let mut report = RedactionReport::default();
+
+        for transaction in &mut self.transactions {
+            let (description, found) = redact_pii_with_report(&transaction.description);
+            transaction.description = description;
+            report.add(found);
+
+            if let Some(notes) = &transaction.notes {
+                let (notes, found) = redact_pii_with_report(notes);
+                transaction.notes = Some(notes);
+                report.add(found);
+            }
+
+            if transaction.account_number.take().is_some() {
+                report.ibans += 1;
+            }
+            if transaction.account_holder.take().is_some() {
+                report.names += 1;
+            }
+        }
+
+        report
+    }
+
+    pub fn render(&self, format: ExportFormat) -> AppResult<Vec<u8>> {
+        match format {
+            ExportFormat::Json => Ok(serde_json::to_vec_pretty(self)?),
+            ExportFormat::Csv => self.to_csv_zip(),
+            ExportFormat::Pdf => Ok(self.to_pdf()),
+        }
+    }
+
+    fn to_csv_zip(&self) -> AppResult<Vec<u8>> {
+        let files = vec![
+            ("settings.csv", table_csv(std::slice::from_ref(&self.settings))?),
+            ("accounts.csv", table_csv(&self.accounts)?),
+            ("categories.csv", table_csv(&self.categories)?),
+            ("budgets.csv", table_csv(&self.budgets)?),
+            ("budget_periods.csv", table_csv(&self.budget_periods)?),
+            ("category_rules.csv", table_csv(&self.category_rules)?),
+            ("savings_goals.csv", table_csv(&self.savings_goals)?),
+            ("transactions.csv", table_csv(&self.transactions.iter().map(TransactionCsvRow::from).collect::<Vec<_>>())?),
+            ("insights.csv", table_csv(&self.insights)?),
+            ("audit_log.csv", table_csv(&self.audit_log)?),
+        ];
+
+        Ok(zip_stored(&files, self.exported_at))
+    }
+
+    fn summary_lines(&self) -> Vec<String> {
+        let money = |amount: Decimal| self.settings.format_money(amount);
+        let total = |transaction_type: &str| -> Decimal {
+            self.transactions
+                .iter()
+                .filter(|t| t.transaction_type == transaction_type)
+                .map(|t| t.amount.abs())
+                .sum()
+        };
+
+        let mut lines = vec![
+            "SpaarApp - overzicht van je gegevens".to_string(),
+            format!("Geëxporteerd op {} (SpaarApp {})", self.exported_at.format("%d-%m-%Y %H:%M UTC"), self.app_version),
+            String::new(),
+            "Opgeslagen gegevens".to_string(),
+            format!("  Transacties: {}", self.transactions.len()),
+            format!("  Categorieën: {}", self.categories.len()),
+            format!("  Budgetten: {}", self.budgets.len()),
+            format!("  Afgesloten budgetperiodes: {}", self.budget_periods.len()),
+            format!("  Categorieregels: {}", self.category_rules.len()),
+            format!("  Spaardoelen: {}", self.savings_goals.len()),
+            format!("  Rekeningen: {}", self.accounts.len()),
+            format!("  Inzichten: {}", self.insights.len()),
+            format!("  Regels in het auditlogboek: {}", self.audit_log.len()),
+            String::new(),
+        ];
+
+        if let (Some(first), Some(last)) = (
+            self.transactions.iter().map(|t| t.date).min(),
+            self.transactions.iter().map(|t| t.date).max(),
+        ) {
+            lines.push(format!("Transacties van {} t/m {}", first.format("%d-%m-%Y"), last.format("%d-%m-%Y")));
+            lines.push(format!("  Totaal inkomsten: {}", money(total("credit"))));
+            lines.push(format!("  Totaal uitgaven: {}", money(total("debit"))));
+            lines.push(String::new());
+        }
+
+        if !self.accounts.is_empty() {
+            lines.push("Rekeningen".to_string());
+            for account in &self.accounts {
+                lines.push(format!("  {} {}", account.iban, account.name.as_deref().unwrap_or("")));
+            }
+            lines.push(String::new());
+        }
+
+        if !self.budgets.is_empty() {
+            lines.push("Budgetten".to_string());
+            for budget in &self.budgets {
+                lines.push(format!(
+                    "  {}: {} van {} ({})",
+                    budget.name,
+                    money(budget.spent),
+                    money(budget.amount),
+                    budget.period
+                ));
+            }
+            lines.push(String::new());
+        }
+
+        lines.push("Instellingen".to_string());
+        lines.push(format!("  Valuta: {}, taal: {}", self.settings.currency, self.settings.language));
+        lines.push(format!("  Bewaartermijn: {} dagen", self.settings.data_retention_days));
+        lines.push(format!(
+            "  Laatste back-up: {}",
+            self.settings.last_backup.map_or("nooit".to_string(), |d| d.format("%d-%m-%Y").to_string())
+        ));
+        lines.push(String::new());
+        lines.push("De volledige gegevens zijn beschikbaar als JSON- of CSV-export.".to_string());
+
+        lines
+    }
+
+    fn to_pdf(&self) -> Vec<u8> {
+        simple_pdf(&self.summary_lines())
+    }
+}
+
+/// A transaction as a CSV row; CSV has no lists, so the tags are joined.
+#[derive(Serialize)]
+struct TransactionCsvRow<'a> {
+    id: &'a str,
+    description: &'a str,
+    amount: Decimal,
+    date: DateTime<Utc>,
+    category_id: Option<&'a str>,
+    account_number: Option<&'a str>,
+    account_holder: Option<&'a str>,
+    counterparty_bank: Option<&'a str>,
+    transaction_type: &'a str,
+    balance_after: Option<Decimal>,
+    notes: Option<&'a str>,
+    tags: String,
+    is_recurring: bool,
+    recurring_frequency: Option<&'a str>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl<'a> From<&'a Transaction> for TransactionCsvRow<'a> {
+    fn from(t: &'a Transaction) -> Self {
+        Self {
+            id: &t.id,
+            description: &t.description,
+            amount: t.amount,
+            date: t.date,
+            category_id: t.category_id.as_deref(),
+            account_number: t.account_number.as_deref(),
+            account_holder: t.account_holder.as_deref(),
+            counterparty_bank: t.counterparty_bank.as_deref(),
+            transaction_type: &t.transaction_type,
+            balance_after: t.balance_after,
+            notes: t.notes.as_deref(),
+            tags: t.tags.join(", "),
+            is_recurring: t.is_recurring,
+            recurring_frequency: t.recurring_frequency.as_deref(),
+            created_at: t.created_at,
+            updated_at: t.updated_at,
+        }
+    }
+}
+
+/// Semicolon-separated CSV with a header row from the field names.
+fn table_csv<T: Serialize>(rows: &[T]) -> AppResult<Vec<u8>> {
+    let mut writer = WriterBuilder::new().delimiter(b';').from_writer(Vec::new());
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.into_inner().map_err(|e| AppError::Io(e.into_error()))
+}
+
+/// CRC-32 (IEEE) as used by zip archives.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Zip archive of `files` without compression, which every unzip tool reads.
+fn zip_stored(files: &[(&str, Vec<u8>)], modified: DateTime<Utc>) -> Vec<u8> {
+    let dos_time = ((modified.hour() << 11) | (modified.minute() << 5) | (modified.second() / 2)) as u16;
+    let dos_date = (((modified.year() - 1980).max(0) as u32) << 9 | (modified.month() << 5) | modified.day()) as u16;
+
+    let mut archive = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, data) in files {
+        let offset = archive.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+
+        // Fields shared by the local header and the central directory entry
+        let mut common = Vec::new();
+        common.extend(20u16.to_le_bytes()); // version needed
+        common.extend(0x0800u16.to_le_bytes()); // UTF-8 names
+        common.extend(0u16.to_le_bytes()); // stored
+        common.extend(dos_time.to_le_bytes());
+        common.extend(dos_date.to_le_bytes());
+        common.extend(crc.to_le_bytes());
+        common.extend(size.to_le_bytes());
+        common.extend(size.to_le_bytes());
+        common.extend((name.len() as u16).to_le_bytes());
+        common.extend(0u16.to_le_bytes()); // extra field length
+
+        archive.extend(0x0403_4b50u32.to_le_bytes());
+        archive.extend(&common);
+        archive.extend(name.as_bytes());
+        archive.extend(data);
+
+        central.extend(0x0201_4b50u32.to_le_bytes());
+        central.extend(20u16.to_le_bytes()); // version made by
+        central.extend(&common);
+        central.extend(0u16.to_le_bytes()); // comment length
+        central.extend(0u16.to_le_bytes()); // disk number
+        central.extend(0u16.to_le_bytes()); // internal attributes
+        central.extend(0u32.to_le_bytes()); // external attributes
+        central.extend(offset.to_le_bytes());
+        central.extend(name.as_bytes());
+    }
+
+    let central_offset = archive.len() as u32;
+    archive.extend(&central);
+
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend((central.len() as u32).to_le_bytes());
+    archive.extend(central_offset.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+
+    archive
+}
+
+/// Lines of text per A4 page at 10 pt
+const PDF_LINES_PER_PAGE: usize = 60;
+/// Width of the first table column, in points; the other columns are narrower
+const PDF_FIRST_COLUMN_WIDTH: i32 = 200;
+const PDF_COLUMN_WIDTH: i32 = 95;
+
+/// A PDF string literal in WinAnsi encoding, so "ë" and "€" print with the standard fonts.
+fn pdf_text(line: &str) -> Vec<u8> {
+    let mut text = vec![b'('];
+    for c in line.chars() {
+        match c {
+            '(' | ')' | '\\' => text.extend([b'\\', c as u8]),
+            '€' => text.push(0x80),
+            ' '..='~' => text.push(c as u8),
+            '\u{a0}'..='\u{ff}' => text.push(c as u32 as u8),
+            _ => text.push(b'?'),
+        }
+    }
+    text.push(b')');
+    text
+}
+
+/// Minimal A4 PDF printing `lines` in Helvetica, paginated.
+/// Single-font A4 PDF with one line of text per entry; tabs in a line start
+/// the next table column.
+pub(crate) fn simple_pdf(lines: &[String]) -> Vec<u8> {
+    let pages: Vec<&[String]> = if lines.is_empty() {
+        vec![&[]]
+    } else {
+        lines.chunks(PDF_LINES_PER_PAGE).collect()
+    };
+
+    // Objects 1 and 2 are the catalog and page tree, 3 the font, then a page
+    // and its content stream for every page
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", 4 + i * 2)).collect();
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes());
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+
+    for (i, page) in pages.iter().enumerate() {
+        let mut content = b"BT /F1 10 Tf 14 TL 50 800 Td\n".to_vec();
+        for line in page.iter() {
+            let mut column_x = 0;
+            for (column, cell) in line.split('\t').enumerate() {
+                if column > 0 {
+                    let width = if column == 1 { PDF_FIRST_COLUMN_WIDTH } else { PDF_COLUMN_WIDTH };
+                    content.extend(format!("{} 0 Td ", width).into_bytes());
+                    column_x += width;
+                }
+                content.extend(pdf_text(cell));
+                content.extend(b" Tj ");
+            }
+            // Back to the left margin for the next line
+            if column_x > 0 {
+                content.extend(format!("{} 0 Td ", -column_x).into_bytes());
+            }
+            content.extend(b"T*\n");
+        }
+        content.extend(b"ET");
+
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                5 + i * 2
+            )
+            .into_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    pdf.extend(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).into_bytes());
+
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_zip_and_pdf_structure() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let modified = Utc.with_ymd_and_hms(2024, 3, 5, 12, 0, 0).unwrap();
+        let zip = zip_stored(&[("a.csv", b"id\n1\n".to_vec()), ("b.csv", Vec::new())], modified);
+        assert!(zip.starts_with(b"PK\x03\x04"));
+        let end = &zip[zip.len() - 22..];
+        assert!(end.starts_with(b"PK\x05\x06"));
+        assert_eq!(u16::from_le_bytes([end[10], end[11]]), 2);
+
+        let lines: Vec<String> = (0..70).map(|i| format!("Regel {} (€ 1,00)", i)).collect();
+        let pdf = simple_pdf(&lines);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.contains("/Count 2"));
+
+        let xref: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        assert!(pdf[xref..].starts_with(b"xref"));
+        assert!(pdf.windows(3).any(|w| w == b"\\(\x80"));
+
+        let pdf = simple_pdf(&["  Boodschappen\t€ 1,00\t50%".to_string()]);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("(  Boodschappen) Tj 200 0 Td (\u{fffd} 1,00) Tj 95 0 Td (50%) Tj -295 0 Td T*"));
+    }
+
+    #[test]
+    fn test_transaction_csv_joins_tags() {
+        let date = Utc.with_ymd_and_hms(2024, 3, 5, 12, 0, 0).unwrap();
+        let transaction = Transaction {
+            description: "Bol.com".to_string(),
+            tags: vec!["online".to_string(), "webshop".to_string()],
+            ..Transaction::test("debit", Decimal::new(2499, 2), date)
+        };
+
+        let csv = String::from_utf8(table_csv(&[TransactionCsvRow::from(&transaction)]).unwrap()).unwrap();
+        assert!(csv.starts_with("id;description;amount;date;"));
+        assert!(csv.contains(";online, webshop;"));
+    }
+}
@@ -1,10 +1,10 @@
 import React, { useState, useCallback } from 'react'
 import { FileText, Upload, AlertCircle, CheckCircle, Loader2, Eye, X } from 'lucide-react'
 import { appApi, csvApi, filesApi } from '../services/apiWithFallback'
-import { CsvImportResult, Transaction } from '../types'
+import { CsvImportResult, ImportOutcome, Transaction } from '../types'
 
 interface CsvImportProps {
-  onImportComplete?: (transactions: Transaction[]) => void
+  onImportComplete?: (transactions: Transaction[], outcome: ImportOutcome) => void
   onCancel?: () => void
 }
 
@@ -107,6 +107,13 @@ export const CsvImport: React.FC<CsvImportProps> = ({ onImportComplete, onCancel
     try {
       const content = await file.text()
       const result = await csvApi.parseContent(content)
+      if (result.errors.length > 0) {
+        setError(`${result.errors.length} transacties konden niet worden geïmporteerd`)
+        setIsLoading(false)
+        return
+      }
+
+      const outcome = await csvApi.importTransactions(result.transactions)
 
       // Simulate progress
       const interval = setInterval(() => {
@@ -124,11 +131,7 @@ export const CsvImport: React.FC<CsvImportProps> = ({ onImportComplete, onCancel
         setProgress(100)
         setIsLoading(false)
 
-        if (result.errors.length === 0) {
-          onImportComplete?.(result.transactions)
-        } else {
-          setError(`${result.errors.length} transacties konden niet worden geïmporteerd`)
-        }
+        onImportComplete?.(result.transactions, outcome)
       }, 1500)
 
     } catch (err) {
@@ -175,7 +178,9 @@ export const CsvImport: React.FC<CsvImportProps> = ({ onImportComplete, onCancel
                 </summary>
                 <ul className="mt-1 text-xs text-red-600 list-disc list-inside">
                   {preview.errors.slice(0, 5).map((err, idx) => (
-                    <li key={idx}>{err}</li>
+                    <li key={idx} title={err.raw}>
+                      {err.line > 0 && <>Regel {err.line}{err.field ? ` (${err.field})` : ''}: </>}{err.reason}
+                    </li>
                   ))}
                   {preview.errors.length > 5 && (
                     <li>...en nog {preview.errors.length - 5} fouten</li>
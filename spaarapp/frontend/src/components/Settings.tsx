@@ -531,6 +531,24 @@ const Settings: React.FC = () => {
                   </Box>
                 </Grid>
 
+                <Grid item xs={12}>
+                  <Box sx={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between' }}>
+                    <Box>
+                      <Typography variant="subtitle2" sx={{ fontWeight: 600 }}>
+                        Notities Versleutelen
+                      </Typography>
+                      <Typography variant="caption" color="text.secondary">
+                        Versleutelde notities worden niet doorzocht bij het zoeken naar transacties
+                      </Typography>
+                    </Box>
+                    <Switch
+                      checked={settings.encrypt_notes}
+                      disabled={!settings.encryption_enabled}
+                      onChange={(e) => updateSetting('encrypt_notes', e.target.checked)}
+                    />
+                  </Box>
+                </Grid>
+
                 {settings.last_backup && (
                   <Grid item xs={12}>
                     <Box sx={{ display: 'flex', alignItems: 'center', p: 2, backgroundColor: alpha(theme.palette.info.main, 0.1), borderRadius: 1 }}>
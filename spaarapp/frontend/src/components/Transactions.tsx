@@ -11,7 +11,7 @@ import {
   ChevronDown,
   X
 } from 'lucide-react'
-import { Transaction, Category, Notification } from '../types'
+import { Transaction, Category, Notification, ImportOutcome } from '../types'
 import { transactionsApi, categoriesApi } from '../services/apiWithFallback'
 import { CsvImport } from './CsvImport'
 import { Notifications } from './Notifications'
@@ -60,12 +60,12 @@ const Transactions: React.FC = () => {
     setNotifications(prev => prev.filter(n => n.id !== id))
   }
 
-  const handleImportComplete = async (importedTransactions: Transaction[]) => {
+  const handleImportComplete = async (_importedTransactions: Transaction[], outcome: ImportOutcome) => {
     addNotification({
       id: Date.now().toString(),
       type: 'success',
       title: 'Import succesvol',
-      message: `${importedTransactions.length} transacties geïmporteerd`
+      message: `${outcome.imported} transacties geïmporteerd, ${outcome.skipped_duplicates} al aanwezig`
     })
 
     setShowImport(false)
@@ -40,7 +40,7 @@ import {
 import { styled } from '@mui/material/styles'
 import toast from 'react-hot-toast'
 
-import { Transaction, Category } from '../types'
+import { Transaction, Category, ImportOutcome } from '../types'
 import { transactionsApi, categoriesApi } from '../services/apiWithFallback'
 import { CsvImport } from './CsvImport'
 
@@ -104,10 +104,10 @@ const Transactions: React.FC = () => {
     }
   }
 
-  const handleImportComplete = (importedTransactions: Transaction[]) => {
-    setTransactions(prev => [...importedTransactions, ...prev])
+  const handleImportComplete = async (_importedTransactions: Transaction[], outcome: ImportOutcome) => {
     setShowImport(false)
-    toast.success(`${importedTransactions.length} transacties geïmporteerd`)
+    toast.success(`${outcome.imported} transacties geïmporteerd, ${outcome.skipped_duplicates} al aanwezig`)
+    setTransactions(await transactionsApi.getAll())
   }
 
   const formatCurrency = (amount: number) => {
@@ -23,25 +23,28 @@ const safeInvoke = async <T>(command: string, args?: any): Promise<T> => {
 const getMockData = (command: string) => {
   switch (command) {
     case 'get_transactions':
-      return [
-        {
-          id: '1',
-          description: 'Albert Heijn boodschappen',
-          amount: -75.50,
-          date: '2025-11-14',
-          category_id: '1',
-          account_number: 'NL123456789',
-          account_holder: 'John Doe',
-          transaction_type: 'debit',
-          balance_after: 2500.00,
-          notes: 'Wekelijkse boodschappen',
-          tags: ['boodschappen', 'supermarkt'],
-          is_recurring: true,
-          recurring_frequency: 'weekly',
-          created_at: '2025-11-14T10:00:00Z',
-          updated_at: '2025-11-14T10:00:00Z'
-        }
-      ]
+      return {
+        transactions: [
+          {
+            id: '1',
+            description: 'Albert Heijn boodschappen',
+            amount: -75.50,
+            date: '2025-11-14',
+            category_id: '1',
+            account_number: 'NL123456789',
+            account_holder: 'John Doe',
+            transaction_type: 'debit',
+            balance_after: 2500.00,
+            notes: 'Wekelijkse boodschappen',
+            tags: ['boodschappen', 'supermarkt'],
+            is_recurring: true,
+            recurring_frequency: 'weekly',
+            created_at: '2025-11-14T10:00:00Z',
+            updated_at: '2025-11-14T10:00:00Z'
+          }
+        ],
+        total_count: 1
+      }
     case 'get_categories':
       return [
         {
@@ -102,6 +105,17 @@ const getMockData = (command: string) => {
         export_format: 'json',
         encryption_enabled: false,
         last_backup: null,
+        spending_velocity_alert_fraction: 0.5,
+        restrict_tags_to_vocabulary: false,
+        insight_retention_days: 90,
+        import_repeat_window_seconds: 5,
+        allow_data_export: true,
+        audit_retention_days: 365,
+        category_review_threshold: 0.7,
+        seasonal_anomaly_sigma: 2.0,
+        cashflow_spend_estimate: 'mean',
+        budget_month_start_day: 1,
+        encrypt_notes: true,
         created_at: '2025-11-14T10:00:00Z',
         updated_at: '2025-11-14T10:00:00Z'
       }
@@ -143,7 +157,8 @@ const getMockData = (command: string) => {
         average_daily_spending: 40.00,
         spending_trend: 'increasing',
         period_start: '2025-10-14',
-        period_end: '2025-11-14'
+        period_end: '2025-11-14',
+        category_tree: []
       }
     case 'get_budget_recommendations':
       return [
@@ -157,26 +172,94 @@ const getMockData = (command: string) => {
 }
 import {
   Transaction,
+  TransactionFilter,
+  TransactionPage,
+  TransactionSearch,
+  TransactionSplit,
+  NewSplit,
   Category,
+  CategoryRule,
+  RecategorizeOutcome,
+  TransactionForReview,
   Budget,
+  BudgetPeriodSummary,
+  SavingsGoal,
+  GoalProgress,
+  GoalFundingPlan,
+  RoundupSimulation,
   Settings,
+  LockStatus,
+  AuditEntry,
   FinancialInsight,
   SpendingAnalysis,
   DashboardStats,
   ApiResponse,
-  CsvImportResult
+  CsvImportResult,
+  ImportOutcome,
+  DryRunReport,
+  MultiImportOutcome,
+  RecurringStream,
+  UpcomingRecurring,
+  CashflowForecast,
+  PriceIncrease,
+  SubscriptionReview,
+  BudgetAlertSetting,
+  BulkThresholdOutcome,
+  CsvImportConfig,
+  SpendingPace,
+  Account,
+  AccountBalance,
+  TagUsage,
+  TransactionStats,
+  BalanceReconstruction,
+  WeekSummary,
+  MonthSummary,
+  ClaudeUsage,
+  DoubleCharge,
+  HealthScore,
+  AppError,
+  AppErrorCode
 } from '../types'
 
+// Commands reject with an AppError: a machine-readable code plus a message
+export const isAppError = (error: unknown): error is AppError =>
+  typeof error === 'object' && error !== null && 'code' in error && 'message' in error
+
+// Error thrown by the API wrappers, keeping the backend error code so the UI
+// can pick the right toast style
+export class CommandError extends Error {
+  constructor(message: string, public readonly code?: AppErrorCode) {
+    super(message)
+    this.name = 'CommandError'
+  }
+}
+
+export const commandError = (context: string, error: unknown): CommandError =>
+  isAppError(error)
+    ? new CommandError(`${context}: ${error.message}`, error.code)
+    : new CommandError(`${context}: ${error}`)
+
 // Transaction API
 export const transactionsApi = {
   // Get all transactions
   getAll: async (): Promise<Transaction[]> => {
     try {
-      const result = await safeInvoke<Transaction[]>('get_transactions')
-      return result
+      const result = await safeInvoke<TransactionPage>('get_transactions')
+      return result.transactions
     } catch (error) {
       console.error('Failed to fetch transactions:', error)
-      throw new Error(`Transacties ophalen mislukt: ${error}`)
+      throw commandError('Transacties ophalen mislukt', error)
+    }
+  },
+
+  // Get one filtered page of transactions plus the total number of matches
+  getPage: async (filter: TransactionFilter): Promise<TransactionPage> => {
+    try {
+      const result = await invoke<TransactionPage>('get_transactions', { filter })
+      return result
+    } catch (error) {
+      console.error('Failed to fetch transactions page:', error)
+      throw commandError('Transacties ophalen mislukt', error)
     }
   },
 
@@ -187,7 +270,7 @@ export const transactionsApi = {
       return result
     } catch (error) {
       console.error(`Failed to fetch transaction ${id}:`, error)
-      throw new Error(`Transactie ophalen mislukt: ${error}`)
+      throw commandError('Transactie ophalen mislukt', error)
     }
   },
 
@@ -198,7 +281,7 @@ export const transactionsApi = {
       return result
     } catch (error) {
       console.error('Failed to create transaction:', error)
-      throw new Error(`Transactie aanmaken mislukt: ${error}`)
+      throw commandError('Transactie aanmaken mislukt', error)
     }
   },
 
@@ -209,7 +292,7 @@ export const transactionsApi = {
       return result
     } catch (error) {
       console.error(`Failed to update transaction ${id}:`, error)
-      throw new Error(`Transactie bijwerken mislukt: ${error}`)
+      throw commandError('Transactie bijwerken mislukt', error)
     }
   },
 
@@ -220,7 +303,106 @@ export const transactionsApi = {
       return result
     } catch (error) {
       console.error(`Failed to delete transaction ${id}:`, error)
-      throw new Error(`Transactie verwijderen mislukt: ${error}`)
+      throw commandError('Transactie verwijderen mislukt', error)
+    }
+  },
+
+  // Spread a transaction over several categories; an empty list undoes the split
+  split: async (transactionId: string, splits: NewSplit[]): Promise<TransactionSplit[]> => {
+    try {
+      return await invoke<TransactionSplit[]>('split_transaction', { transactionId, splits })
+    } catch (error) {
+      console.error(`Failed to split transaction ${transactionId}:`, error)
+      throw commandError('Transactie splitsen mislukt', error)
+    }
+  },
+
+  // Get the splits of a transaction, empty when it is not split
+  getSplits: async (transactionId: string): Promise<TransactionSplit[]> => {
+    try {
+      return await invoke<TransactionSplit[]>('get_transaction_splits', { transactionId })
+    } catch (error) {
+      console.error(`Failed to fetch splits of transaction ${transactionId}:`, error)
+      throw commandError('Splitsing ophalen mislukt', error)
+    }
+  },
+
+  // Change only the category; pass nothing to uncategorize
+  setCategory: async (id: string, categoryId?: string): Promise<Transaction> => {
+    try {
+      const result = await invoke<Transaction>('set_transaction_category', { id, categoryId: categoryId ?? null })
+      return result
+    } catch (error) {
+      console.error(`Failed to set category of transaction ${id}:`, error)
+      throw commandError('Categorie instellen mislukt', error)
+    }
+  },
+
+  // Full-text search on description, notes and account holder, best match first
+  search: async (query: string, limit: number = 50): Promise<TransactionSearch> => {
+    try {
+      const result = await invoke<TransactionSearch>('search_transactions', { query, limit })
+      return result
+    } catch (error) {
+      console.error('Failed to search transactions:', error)
+      throw commandError('Transacties zoeken mislukt', error)
+    }
+  },
+
+  // Same merchant and amount charged twice within the window (24 hours by default)
+  detectDoubleCharges: async (windowHours?: number): Promise<DoubleCharge[]> => {
+    try {
+      const result = await invoke<DoubleCharge[]>('detect_double_charges', { windowHours: windowHours ?? null })
+      return result
+    } catch (error) {
+      console.error('Failed to detect double charges:', error)
+      throw commandError('Dubbele afschrijvingen zoeken mislukt', error)
+    }
+  },
+
+  // Add one tag to a transaction; returns its tags afterwards
+  addTag: async (id: string, tag: string): Promise<string[]> => {
+    try {
+      return await invoke<string[]>('add_transaction_tag', { id, tag })
+    } catch (error) {
+      console.error(`Failed to add tag to transaction ${id}:`, error)
+      throw commandError('Tag toevoegen mislukt', error)
+    }
+  },
+
+  // Remove one tag from a transaction; returns its tags afterwards
+  removeTag: async (id: string, tag: string): Promise<string[]> => {
+    try {
+      return await invoke<string[]>('remove_transaction_tag', { id, tag })
+    } catch (error) {
+      console.error(`Failed to remove tag from transaction ${id}:`, error)
+      throw commandError('Tag verwijderen mislukt', error)
+    }
+  },
+
+  // Income, expenses and counts for the dashboard; all-time when no range is given
+  getStats: async (dateFrom?: string, dateTo?: string): Promise<TransactionStats> => {
+    try {
+      return await invoke<TransactionStats>('get_transaction_stats', {
+        dateFrom: dateFrom ?? null,
+        dateTo: dateTo ?? null,
+      })
+    } catch (error) {
+      console.error('Failed to get transaction stats:', error)
+      throw commandError('Transactiestatistieken ophalen mislukt', error)
+    }
+  },
+}
+
+// Tags API
+export const tagsApi = {
+  // Every tag in use with the number of transactions carrying it, plus unused vocabulary tags
+  getAll: async (): Promise<TagUsage[]> => {
+    try {
+      return await invoke<TagUsage[]>('get_all_tags')
+    } catch (error) {
+      console.error('Failed to fetch tags:', error)
+      throw commandError('Tags ophalen mislukt', error)
     }
   },
 }
@@ -234,7 +416,7 @@ export const categoriesApi = {
       return result
     } catch (error) {
       console.error('Failed to fetch categories:', error)
-      throw new Error(`Categorieën ophalen mislukt: ${error}`)
+      throw commandError('Categorieën ophalen mislukt', error)
     }
   },
 
@@ -245,7 +427,7 @@ export const categoriesApi = {
       return result
     } catch (error) {
       console.error(`Failed to fetch category ${id}:`, error)
-      throw new Error(`Categorie ophalen mislukt: ${error}`)
+      throw commandError('Categorie ophalen mislukt', error)
     }
   },
 
@@ -256,7 +438,7 @@ export const categoriesApi = {
       return result
     } catch (error) {
       console.error('Failed to create category:', error)
-      throw new Error(`Categorie aanmaken mislukt: ${error}`)
+      throw commandError('Categorie aanmaken mislukt', error)
     }
   },
 
@@ -267,7 +449,18 @@ export const categoriesApi = {
       return result
     } catch (error) {
       console.error(`Failed to update category ${id}:`, error)
-      throw new Error(`Categorie bijwerken mislukt: ${error}`)
+      throw commandError('Categorie bijwerken mislukt', error)
+    }
+  },
+
+  // Move a category under another one, or to the top level without a parent
+  setParent: async (id: string, parentId?: string): Promise<Category> => {
+    try {
+      const result = await invoke<Category>('set_category_parent', { id, parentId: parentId ?? null })
+      return result
+    } catch (error) {
+      console.error(`Failed to move category ${id}:`, error)
+      throw commandError('Categorie verplaatsen mislukt', error)
     }
   },
 
@@ -278,7 +471,93 @@ export const categoriesApi = {
       return result
     } catch (error) {
       console.error(`Failed to delete category ${id}:`, error)
-      throw new Error(`Categorie verwijderen mislukt: ${error}`)
+      throw commandError('Categorie verwijderen mislukt', error)
+    }
+  },
+
+  // Merge a duplicate category into another; returns the number of transactions moved
+  merge: async (sourceId: string, targetId: string, allowSystem = false): Promise<number> => {
+    try {
+      return await invoke<number>('merge_categories', { sourceId, targetId, allowSystem })
+    } catch (error) {
+      console.error(`Failed to merge category ${sourceId} into ${targetId}:`, error)
+      throw commandError('Categorieën samenvoegen mislukt', error)
+    }
+  },
+
+  // Get the category rules in the order they are applied
+  getRules: async (): Promise<CategoryRule[]> => {
+    try {
+      const result = await invoke<CategoryRule[]>('get_category_rules')
+      return result
+    } catch (error) {
+      console.error('Failed to fetch category rules:', error)
+      throw commandError('Categorieregels ophalen mislukt', error)
+    }
+  },
+
+  // Add a rule, e.g. { match_text: 'tinq', match_type: 'contains', category_id, priority: 10 }
+  addRule: async (rule: Omit<CategoryRule, 'id' | 'created_at'>): Promise<CategoryRule> => {
+    try {
+      const result = await invoke<CategoryRule>('add_category_rule', {
+        rule: { ...rule, id: '', created_at: new Date().toISOString() },
+      })
+      return result
+    } catch (error) {
+      console.error('Failed to add category rule:', error)
+      throw commandError('Categorieregel toevoegen mislukt', error)
+    }
+  },
+
+  // Delete a category rule
+  deleteRule: async (id: string): Promise<boolean> => {
+    try {
+      const result = await invoke<boolean>('delete_category_rule', { id })
+      return result
+    } catch (error) {
+      console.error(`Failed to delete category rule ${id}:`, error)
+      throw commandError('Categorieregel verwijderen mislukt', error)
+    }
+  },
+
+  // Apply the rules to stored transactions; progress arrives as 'recategorize_progress' events
+  recategorize: async (onlyUncategorized: boolean): Promise<RecategorizeOutcome> => {
+    try {
+      const result = await invoke<RecategorizeOutcome>('recategorize_transactions', { onlyUncategorized })
+      return result
+    } catch (error) {
+      console.error('Failed to recategorize transactions:', error)
+      throw commandError('Opnieuw categoriseren mislukt', error)
+    }
+  },
+
+  // Stop a running recategorization; it undoes its changes
+  cancelRecategorize: async (): Promise<void> => {
+    try {
+      await invoke('cancel_recategorization')
+    } catch (error) {
+      console.error('Failed to cancel recategorization:', error)
+      throw commandError('Annuleren mislukt', error)
+    }
+  },
+
+  // Get the transactions the category rules were unsure about
+  getReviewQueue: async (): Promise<TransactionForReview[]> => {
+    try {
+      return await invoke<TransactionForReview[]>('get_transactions_needing_review')
+    } catch (error) {
+      console.error('Failed to fetch review queue:', error)
+      throw commandError('Te controleren transacties ophalen mislukt', error)
+    }
+  },
+
+  // Confirm a category from the review queue; returns the rule learned from it, if new
+  approveCategory: async (transactionId: string, categoryId: string): Promise<CategoryRule | null> => {
+    try {
+      return await invoke<CategoryRule | null>('approve_category', { transactionId, categoryId })
+    } catch (error) {
+      console.error(`Failed to approve category of transaction ${transactionId}:`, error)
+      throw commandError('Categorie bevestigen mislukt', error)
     }
   },
 }
@@ -292,7 +571,7 @@ export const budgetsApi = {
       return result
     } catch (error) {
       console.error('Failed to fetch budgets:', error)
-      throw new Error(`Budgetten ophalen mislukt: ${error}`)
+      throw commandError('Budgetten ophalen mislukt', error)
     }
   },
 
@@ -303,7 +582,7 @@ export const budgetsApi = {
       return result
     } catch (error) {
       console.error(`Failed to fetch budget ${id}:`, error)
-      throw new Error(`Budget ophalen mislukt: ${error}`)
+      throw commandError('Budget ophalen mislukt', error)
     }
   },
 
@@ -314,7 +593,7 @@ export const budgetsApi = {
       return result
     } catch (error) {
       console.error('Failed to create budget:', error)
-      throw new Error(`Budget aanmaken mislukt: ${error}`)
+      throw commandError('Budget aanmaken mislukt', error)
     }
   },
 
@@ -325,7 +604,7 @@ export const budgetsApi = {
       return result
     } catch (error) {
       console.error(`Failed to update budget ${id}:`, error)
-      throw new Error(`Budget bijwerken mislukt: ${error}`)
+      throw commandError('Budget bijwerken mislukt', error)
     }
   },
 
@@ -336,36 +615,248 @@ export const budgetsApi = {
       return result
     } catch (error) {
       console.error(`Failed to delete budget ${id}:`, error)
-      throw new Error(`Budget verwijderen mislukt: ${error}`)
+      throw commandError('Budget verwijderen mislukt', error)
     }
   },
 
-  // Update budget spending
-  updateSpending: async (id: string, additionalSpent: number): Promise<Budget> => {
+  // Recalculate spent from the budget's transactions
+  recompute: async (id: string): Promise<Budget> => {
     try {
-      const result = await invoke<Budget>('update_budget_spending', {
-        id,
-        additionalSpent
-      })
+      const result = await invoke<Budget>('recompute_budget', { id })
       return result
     } catch (error) {
-      console.error(`Failed to update budget spending ${id}:`, error)
-      throw new Error(`Budget uitgaven bijwerken mislukt: ${error}`)
+      console.error(`Failed to recompute budget ${id}:`, error)
+      throw commandError('Budget uitgaven herberekenen mislukt', error)
+    }
+  },
+
+  // Change only a budget's amount
+  setAmount: async (id: string, amount: number): Promise<Budget> => {
+    try {
+      const result = await invoke<Budget>('set_budget_amount', { id, amount })
+      return result
+    } catch (error) {
+      console.error(`Failed to set budget amount ${id}:`, error)
+      throw commandError('Budgetbedrag aanpassen mislukt', error)
     }
   },
 
   // Get budget summary
-  getSummary: async () => {
+  getSummary: async (accountId?: string) => {
     try {
-      const result = await safeInvoke('get_budget_summary')
+      const result = await safeInvoke('get_budget_summary', { accountId: accountId ?? null })
       return result
     } catch (error) {
       console.error('Failed to fetch budget summary:', error)
-      throw new Error(`Budget overzicht ophalen mislukt: ${error}`)
+      throw commandError('Budget overzicht ophalen mislukt', error)
+    }
+  },
+
+  // Get month-to-date spending pace against the combined budget
+  getSpendingPace: async (): Promise<SpendingPace> => {
+    try {
+      const result = await invoke<SpendingPace>('get_spending_pace')
+      return result
+    } catch (error) {
+      console.error('Failed to fetch spending pace:', error)
+      throw commandError('Bestedingstempo ophalen mislukt', error)
+    }
+  },
+
+  // Transactions that count toward a budget's spent amount
+  getTransactions: async (budgetId: string): Promise<Transaction[]> => {
+    try {
+      const result = await invoke<Transaction[]>('get_budget_transactions', { budgetId })
+      return result
+    } catch (error) {
+      console.error('Failed to fetch budget transactions:', error)
+      throw commandError('Budgettransacties ophalen mislukt', error)
+    }
+  },
+
+  // Past periods of a budget, newest first
+  getHistory: async (budgetId: string): Promise<BudgetPeriodSummary[]> => {
+    try {
+      return await invoke('get_budget_history', { budgetId })
+    } catch (error) {
+      console.error('Failed to fetch budget history:', error)
+      throw commandError('Budgetgeschiedenis ophalen mislukt', error)
+    }
+  },
+
+  // Alert thresholds of all active budgets
+  getAlertSettings: async (): Promise<BudgetAlertSetting[]> => {
+    try {
+      const result = await invoke<BudgetAlertSetting[]>('get_budget_alert_settings')
+      return result
+    } catch (error) {
+      console.error('Failed to fetch budget alert settings:', error)
+      throw commandError('Budgetmeldingen ophalen mislukt', error)
+    }
+  },
+
+  // Apply one alert threshold to every active budget
+  setAllThresholds: async (thresholdType: 'percent' | 'amount', value: number): Promise<BulkThresholdOutcome> => {
+    try {
+      const result = await invoke<BulkThresholdOutcome>('set_all_budget_thresholds', { thresholdType, value })
+      return result
+    } catch (error) {
+      console.error('Failed to set budget thresholds:', error)
+      throw commandError('Drempels instellen mislukt', error)
     }
   },
 }
 
+// Savings goals API
+export const goalsApi = {
+  // Get all savings goals
+  getAll: async (): Promise<SavingsGoal[]> => {
+    try {
+      const result = await invoke<SavingsGoal[]>('get_savings_goals')
+      return result
+    } catch (error) {
+      console.error('Failed to fetch savings goals:', error)
+      throw commandError('Spaardoelen ophalen mislukt', error)
+    }
+  },
+
+  // Get single savings goal by ID
+  getById: async (id: string): Promise<SavingsGoal | null> => {
+    try {
+      const result = await invoke<SavingsGoal | null>('get_savings_goal_by_id', { id })
+      return result
+    } catch (error) {
+      console.error(`Failed to fetch savings goal ${id}:`, error)
+      throw commandError('Spaardoel ophalen mislukt', error)
+    }
+  },
+
+  // Create new savings goal
+  create: async (goal: Omit<SavingsGoal, 'id' | 'created_at' | 'updated_at'>): Promise<SavingsGoal> => {
+    try {
+      const result = await invoke<SavingsGoal>('add_savings_goal', { goal })
+      return result
+    } catch (error) {
+      console.error('Failed to create savings goal:', error)
+      throw commandError('Spaardoel aanmaken mislukt', error)
+    }
+  },
+
+  // Update existing savings goal
+  update: async (id: string, goal: Omit<SavingsGoal, 'id' | 'created_at' | 'updated_at'>): Promise<SavingsGoal> => {
+    try {
+      const result = await invoke<SavingsGoal>('update_savings_goal', { id, goal })
+      return result
+    } catch (error) {
+      console.error(`Failed to update savings goal ${id}:`, error)
+      throw commandError('Spaardoel bijwerken mislukt', error)
+    }
+  },
+
+  // Delete savings goal
+  delete: async (id: string): Promise<boolean> => {
+    try {
+      const result = await invoke<boolean>('delete_savings_goal', { id })
+      return result
+    } catch (error) {
+      console.error(`Failed to delete savings goal ${id}:`, error)
+      throw commandError('Spaardoel verwijderen mislukt', error)
+    }
+  },
+
+  // Percentage saved and projected completion at the recent savings rate
+  getProgress: async (id: string): Promise<GoalProgress> => {
+    try {
+      const result = await invoke<GoalProgress>('get_goal_progress', { id })
+      return result
+    } catch (error) {
+      console.error(`Failed to fetch progress of savings goal ${id}:`, error)
+      throw commandError('Voortgang spaardoel ophalen mislukt', error)
+    }
+  },
+
+  // Monthly contribution needed to reach the goal on time and categories to trim for it
+  getFundingPlan: async (goalId: string): Promise<GoalFundingPlan> => {
+    try {
+      const result = await invoke<GoalFundingPlan>('get_goal_funding_plan', { goalId })
+      return result
+    } catch (error) {
+      console.error(`Failed to fetch funding plan of savings goal ${goalId}:`, error)
+      throw commandError('Spaarplan ophalen mislukt', error)
+    }
+  },
+
+  // What rounding every purchase up to the next 1 or 5 euro would have saved
+  simulateRoundup: async (dateFrom: string, dateTo: string, roundTo: 1 | 5): Promise<RoundupSimulation> => {
+    try {
+      return await invoke<RoundupSimulation>('simulate_roundup_savings', { dateFrom, dateTo, roundTo })
+    } catch (error) {
+      console.error('Failed to simulate round-up savings:', error)
+      throw commandError('Afrondsparen berekenen mislukt', error)
+    }
+  },
+}
+
+// Accounts API
+export const accountsApi = {
+  // Get all own accounts, including those added by imports
+  getAll: async (): Promise<Account[]> => {
+    try {
+      return await invoke<Account[]>('get_accounts')
+    } catch (error) {
+      console.error('Failed to fetch accounts:', error)
+      throw commandError('Rekeningen ophalen mislukt', error)
+    }
+  },
+
+  // Add an own account before its first import
+  add: async (iban: string, name?: string, currency?: string): Promise<Account> => {
+    try {
+      return await invoke<Account>('add_account', { iban, name: name ?? null, currency: currency ?? null })
+    } catch (error) {
+      console.error('Failed to add account:', error)
+      throw commandError('Rekening toevoegen mislukt', error)
+    }
+  },
+
+  // Make this the account for imported transactions without an account number
+  setDefault: async (iban: string): Promise<Account> => {
+    try {
+      const result = await invoke<Account>('set_default_account', { iban })
+      return result
+    } catch (error) {
+      console.error('Failed to set default account:', error)
+      throw commandError('Standaardrekening instellen mislukt', error)
+    }
+  },
+
+  // Category for imports from this account that could not be categorized
+  setDefaultCategory: async (iban: string, categoryId?: string): Promise<Account> => {
+    try {
+      const result = await invoke<Account>('set_account_default_category', { iban, categoryId: categoryId ?? null })
+      return result
+    } catch (error) {
+      console.error('Failed to set account default category:', error)
+      throw commandError('Standaardcategorie instellen mislukt', error)
+    }
+  },
+
+  // Balance at the end of a day (YYYY-MM-DD), reported by the bank or computed
+  getBalanceAtDate: async (accountNumber: string, date: string, startingBalance?: number): Promise<AccountBalance> => {
+    try {
+      const result = await invoke<AccountBalance>('get_balance_at_date', {
+        accountNumber,
+        date,
+        startingBalance: startingBalance ?? null
+      })
+      return result
+    } catch (error) {
+      console.error('Failed to get balance at date:', error)
+      throw commandError('Saldo ophalen mislukt', error)
+    }
+  }
+}
+
 // Settings API
 export const settingsApi = {
   // Get current settings
@@ -375,7 +866,17 @@ export const settingsApi = {
       return result
     } catch (error) {
       console.error('Failed to fetch settings:', error)
-      throw new Error(`Instellingen ophalen mislukt: ${error}`)
+      throw commandError('Instellingen ophalen mislukt', error)
+    }
+  },
+
+  // Running balance over time from a starting balance; fills in missing balances and reports mismatches
+  reconstructBalances: async (startingBalance: number, accountNumber: string): Promise<BalanceReconstruction> => {
+    try {
+      return await invoke<BalanceReconstruction>('reconstruct_balances', { startingBalance, accountNumber })
+    } catch (error) {
+      console.error('Failed to reconstruct balances:', error)
+      throw commandError('Saldoverloop berekenen mislukt', error)
     }
   },
 
@@ -386,7 +887,7 @@ export const settingsApi = {
       return result
     } catch (error) {
       console.error('Failed to update settings:', error)
-      throw new Error(`Instellingen bijwerken mislukt: ${error}`)
+      throw commandError('Instellingen bijwerken mislukt', error)
     }
   },
 
@@ -397,7 +898,7 @@ export const settingsApi = {
       return result
     } catch (error) {
       console.error('Failed to reset settings:', error)
-      throw new Error(`Instellingen resetten mislukt: ${error}`)
+      throw commandError('Instellingen resetten mislukt', error)
     }
   },
 
@@ -408,7 +909,7 @@ export const settingsApi = {
       return result
     } catch (error) {
       console.error('Failed to export settings:', error)
-      throw new Error(`Instellingen exporteren mislukt: ${error}`)
+      throw commandError('Instellingen exporteren mislukt', error)
     }
   },
 
@@ -421,7 +922,79 @@ export const settingsApi = {
       return result
     } catch (error) {
       console.error('Failed to import settings:', error)
-      throw new Error(`Instellingen importeren mislukt: ${error}`)
+      throw commandError('Instellingen importeren mislukt', error)
+    }
+  },
+
+  // Write an encrypted database backup; returns the new last_backup time
+  backupDatabase: async (targetPath: string): Promise<string> => {
+    try {
+      const result = await invoke<string>('backup_database', { targetPath })
+      return result
+    } catch (error) {
+      console.error('Failed to back up database:', error)
+      throw commandError('Back-up maken mislukt', error)
+    }
+  },
+
+  // Replace all data with a backup made by backupDatabase; pass the old
+  // passphrase for a backup made before the key was rotated
+  restoreDatabase: async (sourcePath: string, oldPassphrase?: string): Promise<void> => {
+    try {
+      await invoke('restore_database', { sourcePath, oldPassphrase: oldPassphrase ?? null })
+    } catch (error) {
+      console.error('Failed to restore database:', error)
+      throw commandError('Back-up terugzetten mislukt', error)
+    }
+  },
+
+  // Export all stored data (GDPR) as 'json', 'csv' (zip) or 'pdf'
+  exportUserData: async (format: 'json' | 'csv' | 'pdf', targetPath: string, redactPii = false): Promise<void> => {
+    try {
+      await invoke('export_user_data', { format, targetPath, redactPii })
+    } catch (error) {
+      console.error('Failed to export user data:', error)
+      throw commandError('Gegevens exporteren mislukt', error)
+    }
+  },
+
+  // Re-encrypt the database and encrypted fields with a new password
+  rotateEncryptionKey: async (oldPassword: string, newPassword: string): Promise<void> => {
+    try {
+      await invoke('rotate_encryption_key', { oldPassword, newPassword })
+    } catch (error) {
+      console.error('Failed to rotate encryption key:', error)
+      throw commandError('Sleutel vervangen mislukt', error)
+    }
+  },
+
+  // Whether the database is unlocked and a password has been set
+  getLockStatus: async (): Promise<LockStatus> => {
+    try {
+      return await invoke('get_lock_status')
+    } catch (error) {
+      console.error('Failed to get lock status:', error)
+      throw commandError('Vergrendelstatus ophalen mislukt', error)
+    }
+  },
+
+  // Unlock the database with the user's password
+  unlockDatabase: async (passphrase: string): Promise<void> => {
+    try {
+      await invoke('unlock_database', { passphrase })
+    } catch (error) {
+      console.error('Failed to unlock database:', error)
+      throw commandError('Ontgrendelen mislukt', error)
+    }
+  },
+
+  // Set the password on first run
+  setPassphrase: async (passphrase: string): Promise<void> => {
+    try {
+      await invoke('set_passphrase', { passphrase })
+    } catch (error) {
+      console.error('Failed to set passphrase:', error)
+      throw commandError('Wachtwoord instellen mislukt', error)
     }
   },
 }
@@ -435,18 +1008,37 @@ export const insightsApi = {
       return result
     } catch (error) {
       console.error('Failed to fetch financial insights:', error)
-      throw new Error(`Financiële inzichten ophalen mislukt: ${error}`)
+      throw commandError('Financiële inzichten ophalen mislukt', error)
+    }
+  },
+
+  // Insights for a single category's detail page
+  getCategoryInsights: async (categoryId: string): Promise<FinancialInsight[]> => {
+    try {
+      const result = await invoke<FinancialInsight[]>('get_category_insights', { categoryId })
+      return result
+    } catch (error) {
+      console.error(`Failed to fetch insights for category ${categoryId}:`, error)
+      throw commandError('Categorie-inzichten ophalen mislukt', error)
     }
   },
 
   // Analyze spending patterns
-  analyzeSpendingPatterns: async (): Promise<SpendingAnalysis> => {
+  analyzeSpendingPatterns: async (
+    periodDays?: number,
+    accountId?: string,
+    rollUpToParent = false
+  ): Promise<SpendingAnalysis> => {
     try {
-      const result = await invoke<SpendingAnalysis>('analyze_spending_patterns')
+      const result = await invoke<SpendingAnalysis>('analyze_spending_patterns', {
+        periodDays: periodDays ?? null,
+        accountId: accountId ?? null,
+        rollUpToParent,
+      })
       return result
     } catch (error) {
       console.error('Failed to analyze spending patterns:', error)
-      throw new Error(`Uitgavenpatronen analyseren mislukt: ${error}`)
+      throw commandError('Uitgavenpatronen analyseren mislukt', error)
     }
   },
 
@@ -457,7 +1049,103 @@ export const insightsApi = {
       return result
     } catch (error) {
       console.error('Failed to get budget recommendations:', error)
-      throw new Error(`Budget aanbevelingen ophalen mislukt: ${error}`)
+      throw commandError('Budget aanbevelingen ophalen mislukt', error)
+    }
+  },
+
+  // Regular income streams with the next expected payment date
+  getRecurringIncome: async (): Promise<RecurringStream[]> => {
+    try {
+      const result = await invoke<RecurringStream[]>('get_recurring_income')
+      return result
+    } catch (error) {
+      console.error('Failed to get recurring income:', error)
+      throw commandError('Vaste inkomsten ophalen mislukt', error)
+    }
+  },
+
+  // Expected recurring income and charges in the coming days
+  getUpcomingRecurring: async (daysAhead: number): Promise<UpcomingRecurring[]> => {
+    try {
+      const result = await invoke<UpcomingRecurring[]>('get_upcoming_recurring', { daysAhead })
+      return result
+    } catch (error) {
+      console.error('Failed to get upcoming recurring transactions:', error)
+      throw commandError('Verwachte vaste lasten ophalen mislukt', error)
+    }
+  },
+
+  // Expected daily balance for the coming days, starting from the current balance
+  forecastCashflow: async (daysAhead: number, startingBalance: number): Promise<CashflowForecast> => {
+    try {
+      return await invoke<CashflowForecast>('forecast_cashflow', { daysAhead, startingBalance })
+    } catch (error) {
+      console.error('Failed to forecast cashflow:', error)
+      throw commandError('Kasstroomprognose mislukt', error)
+    }
+  },
+
+  // Subscriptions that became more expensive; threshold is a fraction (0.05 = 5%)
+  detectSubscriptionPriceIncreases: async (threshold?: number): Promise<PriceIncrease[]> => {
+    try {
+      const result = await invoke<PriceIncrease[]>('detect_subscription_price_increases', { threshold: threshold ?? null })
+      return result
+    } catch (error) {
+      console.error('Failed to detect subscription price increases:', error)
+      throw commandError('Prijsstijgingen controleren mislukt', error)
+    }
+  },
+
+  // Monthly subscriptions with price increases and long gaps between charges flagged
+  analyzeSubscriptions: async (): Promise<SubscriptionReview[]> => {
+    try {
+      return await invoke<SubscriptionReview[]>('analyze_subscriptions')
+    } catch (error) {
+      console.error('Failed to analyze subscriptions:', error)
+      throw commandError('Abonnementen analyseren mislukt', error)
+    }
+  },
+
+  // Financial health from 0 to 100 with a breakdown per component
+  getHealthScore: async (): Promise<HealthScore> => {
+    try {
+      const result = await invoke<HealthScore>('calculate_health_score')
+      return result
+    } catch (error) {
+      console.error('Failed to calculate health score:', error)
+      throw commandError('Financiële gezondheid berekenen mislukt', error)
+    }
+  },
+
+  // Income, expenses and net per ISO week, oldest first
+  getWeeklySummary: async (weeks: number): Promise<WeekSummary[]> => {
+    try {
+      const result = await invoke<WeekSummary[]>('get_weekly_summary', { weeks })
+      return result
+    } catch (error) {
+      console.error('Failed to fetch weekly summary:', error)
+      throw commandError('Weekoverzicht ophalen mislukt', error)
+    }
+  },
+
+  // Income, expenses and net savings per calendar month, oldest first
+  getMonthlySpending: async (monthsBack: number): Promise<MonthSummary[]> => {
+    try {
+      const result = await invoke<MonthSummary[]>('get_monthly_spending', { monthsBack })
+      return result
+    } catch (error) {
+      console.error('Failed to fetch monthly spending:', error)
+      throw commandError('Maandoverzicht ophalen mislukt', error)
+    }
+  },
+
+  // Claude API spend this month and what is left of the monthly limit
+  getClaudeUsage: async (): Promise<ClaudeUsage> => {
+    try {
+      return await invoke('get_claude_usage')
+    } catch (error) {
+      console.error('Failed to fetch Claude usage:', error)
+      throw commandError('AI-verbruik ophalen mislukt', error)
     }
   },
 }
@@ -471,7 +1159,7 @@ export const appApi = {
       return result
     } catch (error) {
       console.error('Failed to get app info:', error)
-      throw new Error(`App informatie ophalen mislukt: ${error}`)
+      throw commandError('App informatie ophalen mislukt', error)
     }
   },
 
@@ -482,7 +1170,7 @@ export const appApi = {
       return result
     } catch (error) {
       console.error('Failed to get app version:', error)
-      throw new Error(`App versie ophalen mislukt: ${error}`)
+      throw commandError('App versie ophalen mislukt', error)
     }
   },
 
@@ -493,7 +1181,28 @@ export const appApi = {
       return result
     } catch (error) {
       console.error('Failed to get platform:', error)
-      throw new Error(`Platform informatie ophalen mislukt: ${error}`)
+      throw commandError('Platform informatie ophalen mislukt', error)
+    }
+  },
+
+  // Remove old insights and alert records now instead of waiting for the daily run
+  cleanupDerivedData: async (): Promise<{ insights_deleted: number; alerts_deleted: number; audit_entries_deleted: number }> => {
+    try {
+      const result = await invoke<{ insights_deleted: number; alerts_deleted: number; audit_entries_deleted: number }>('cleanup_derived_data')
+      return result
+    } catch (error) {
+      console.error('Failed to clean up derived data:', error)
+      throw commandError('Opschonen mislukt', error)
+    }
+  },
+
+  // Review sensitive actions such as exports and resets, newest first
+  getAuditLog: async (limit?: number, offset?: number): Promise<AuditEntry[]> => {
+    try {
+      return await invoke<AuditEntry[]>('get_audit_log', { limit, offset })
+    } catch (error) {
+      console.error('Failed to get audit log:', error)
+      throw commandError('Auditlogboek ophalen mislukt', error)
     }
   },
 }
@@ -507,7 +1216,7 @@ export const filesApi = {
       return result
     } catch (error) {
       console.error('Failed to select file:', error)
-      throw new Error(`Bestand selecteren mislukt: ${error}`)
+      throw commandError('Bestand selecteren mislukt', error)
     }
   },
 
@@ -532,7 +1241,7 @@ export const filesApi = {
       return null
     } catch (error) {
       console.error('Failed to select CSV file:', error)
-      throw new Error(`CSV-bestand selecteren mislukt: ${error}`)
+      throw commandError('CSV-bestand selecteren mislukt', error)
     }
   },
 
@@ -543,7 +1252,7 @@ export const filesApi = {
       return result
     } catch (error) {
       console.error(`Failed to read file ${path}:`, error)
-      throw new Error(`Bestand lezen mislukt: ${error}`)
+      throw commandError('Bestand lezen mislukt', error)
     }
   },
 
@@ -554,7 +1263,7 @@ export const filesApi = {
       return result
     } catch (error) {
       console.error(`Failed to write file ${path}:`, error)
-      throw new Error(`Bestand schrijven mislukt: ${error}`)
+      throw commandError('Bestand schrijven mislukt', error)
     }
   },
 }
@@ -562,13 +1271,13 @@ export const filesApi = {
 // CSV Import API
 export const csvApi = {
   // Import CSV from file
-  importFromFile: async (filePath: string): Promise<CsvImportResult> => {
+  importFromFile: async (filePath: string, config?: CsvImportConfig): Promise<CsvImportResult> => {
     try {
-      const result = await invoke<CsvImportResult>('import_csv', { filePath })
+      const result = await invoke<CsvImportResult>('import_csv', { filePath, config: config ?? null })
       return result
     } catch (error) {
       console.error(`Failed to import CSV from ${filePath}:`, error)
-      throw new Error(`CSV importeren mislukt: ${error}`)
+      throw commandError('CSV importeren mislukt', error)
     }
   },
 
@@ -579,7 +1288,7 @@ export const csvApi = {
       return result
     } catch (error) {
       console.error('Failed to parse CSV content:', error)
-      throw new Error(`CSV parsen mislukt: ${error}`)
+      throw commandError('CSV parsen mislukt', error)
     }
   },
 
@@ -590,7 +1299,60 @@ export const csvApi = {
       return result
     } catch (error) {
       console.error('Failed to preview CSV content:', error)
-      throw new Error(`CSV voorbeeld mislukt: ${error}`)
+      throw commandError('CSV voorbeeld mislukt', error)
+    }
+  },
+
+  // Parse an MT940 (SWIFT) bank statement into the same result shape as a CSV import
+  parseMt940: async (content: string): Promise<CsvImportResult> => {
+    try {
+      return await invoke<CsvImportResult>('import_mt940', { content })
+    } catch (error) {
+      console.error('Failed to parse MT940 statement:', error)
+      throw commandError('MT940 inlezen mislukt', error)
+    }
+  },
+
+  // Parse a CAMT.053 XML bank statement; entries in other currencies than EUR come back as warnings
+  parseCamt053: async (content: string): Promise<CsvImportResult> => {
+    try {
+      return await invoke<CsvImportResult>('import_camt053', { content })
+    } catch (error) {
+      console.error('Failed to parse CAMT.053 statement:', error)
+      throw commandError('CAMT.053 inlezen mislukt', error)
+    }
+  },
+
+  // Store parsed transactions; rows imported before are reported and, unless told otherwise, skipped
+  importTransactions: async (transactions: Transaction[], skipDuplicates: boolean = true): Promise<ImportOutcome> => {
+    try {
+      const result = await invoke<ImportOutcome>('import_transactions', { transactions, skipDuplicates })
+      return result
+    } catch (error) {
+      console.error('Failed to import transactions:', error)
+      throw commandError('Transacties importeren mislukt', error)
+    }
+  },
+
+  // Import several files at once; progress arrives as `import_progress` events
+  importMultiple: async (paths: string[]): Promise<MultiImportOutcome> => {
+    try {
+      const result = await invoke<MultiImportOutcome>('import_multiple', { paths })
+      return result
+    } catch (error) {
+      console.error('Failed to import files:', error)
+      throw commandError('Bestanden importeren mislukt', error)
+    }
+  },
+
+  // Everything an import would do, without storing anything
+  dryRunImport: async (content: string, config?: CsvImportConfig): Promise<DryRunReport> => {
+    try {
+      const result = await invoke<DryRunReport>('dry_run_import', { content, config: config ?? null })
+      return result
+    } catch (error) {
+      console.error('Failed to dry-run import:', error)
+      throw commandError('Importcontrole mislukt', error)
     }
   },
 
@@ -601,7 +1363,42 @@ export const csvApi = {
       return result
     } catch (error) {
       console.error('Failed to validate CSV structure:', error)
-      throw new Error(`CSV validatie mislukt: ${error}`)
+      throw commandError('CSV validatie mislukt', error)
+    }
+  },
+
+  // Export transactions; `columns` picks the fields and their order, all when omitted
+  exportTransactions: async (columns?: string[]): Promise<string> => {
+    try {
+      const result = await invoke<string>('export_transactions_csv', { columns: columns ?? null })
+      return result
+    } catch (error) {
+      console.error('Failed to export transactions:', error)
+      throw commandError('Transacties exporteren mislukt', error)
+    }
+  },
+
+  // Write a date range as a Rabobank CSV that can be imported again; returns the number of transactions
+  exportTransactionsRabobank: async (targetPath: string, dateFrom?: string, dateTo?: string): Promise<number> => {
+    try {
+      return await invoke<number>('export_transactions_rabobank_csv', {
+        dateFrom: dateFrom ?? null,
+        dateTo: dateTo ?? null,
+        targetPath,
+      })
+    } catch (error) {
+      console.error('Failed to export Rabobank CSV:', error)
+      throw commandError('Rabobank-export mislukt', error)
+    }
+  },
+
+  // Printable PDF overview of one month (month 1-12), in the language from the settings
+  generateMonthlyReport: async (year: number, month: number, targetPath: string): Promise<void> => {
+    try {
+      await invoke('generate_monthly_report_pdf', { year, month, targetPath })
+    } catch (error) {
+      console.error('Failed to generate monthly report:', error)
+      throw commandError('Maandoverzicht maken mislukt', error)
     }
   },
 }
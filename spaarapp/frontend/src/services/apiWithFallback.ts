@@ -2,6 +2,7 @@ import { invoke } from '@tauri-apps/api/core'
 import { open } from '@tauri-apps/plugin-dialog'
 import {
   Transaction,
+  TransactionPage,
   Category,
   Budget,
   Settings,
@@ -9,7 +10,8 @@ import {
   SpendingAnalysis,
   DashboardStats,
   ApiResponse,
-  CsvImportResult
+  CsvImportResult,
+  ImportOutcome
 } from '../types'
 import {
   mockTransactions,
@@ -21,6 +23,7 @@ import {
   mockSpendingAnalysis,
   shouldUseMockData
 } from './mockData'
+import { commandError } from './api'
 
 // Helper function to check if we should use mock data
 const useMockData = (): boolean => {
@@ -37,8 +40,8 @@ export const transactionsApi = {
     }
 
     try {
-      const result = await invoke<Transaction[]>('get_transactions')
-      return result
+      const result = await invoke<TransactionPage>('get_transactions')
+      return result.transactions
     } catch (error) {
       console.warn('Failed to fetch transactions, falling back to mock data:', error)
       return mockTransactions
@@ -434,6 +437,23 @@ export const csvApi = {
       duplicate_count: 2,
       total_processed: 17
     }
+  },
+
+  // Store parsed transactions in one database transaction; rows imported before are skipped
+  importTransactions: async (transactions: Transaction[]): Promise<ImportOutcome> => {
+    if (useMockData()) {
+      console.log('Mock: Importing transactions')
+      mockTransactions.push(...transactions)
+      return { imported: transactions.length, skipped_duplicates: 0 }
+    }
+
+    try {
+      const result = await invoke<ImportOutcome>('import_transactions', { transactions })
+      return result
+    } catch (error) {
+      console.error('Failed to import transactions:', error)
+      throw commandError('Transacties importeren mislukt', error)
+    }
   }
 }
 
@@ -346,6 +346,17 @@ export const mockSettings: Settings = {
   data_retention_days: 365,
   export_format: 'csv',
   encryption_enabled: true,
+  spending_velocity_alert_fraction: 0.5,
+  restrict_tags_to_vocabulary: false,
+  insight_retention_days: 90,
+  import_repeat_window_seconds: 5,
+  allow_data_export: true,
+  audit_retention_days: 365,
+  category_review_threshold: 0.7,
+  seasonal_anomaly_sigma: 2.0,
+  cashflow_spend_estimate: 'mean',
+  budget_month_start_day: 1,
+  encrypt_notes: true,
   last_backup: '2024-01-14T23:00:00Z',
   created_at: '2024-01-01T00:00:00Z',
   updated_at: '2024-01-14T23:00:00Z'
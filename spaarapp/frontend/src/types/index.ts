@@ -9,6 +9,7 @@ export interface Transaction {
   category_id?: string
   account_number?: string
   account_holder?: string
+  counterparty_bank?: string
   transaction_type: 'credit' | 'debit'
   balance_after?: number
   notes?: string
@@ -19,6 +20,60 @@ export interface Transaction {
   updated_at: string
 }
 
+// Part of a transaction booked on its own category; the splits add up to the transaction's amount
+export interface TransactionSplit {
+  id: string
+  transaction_id: string
+  category_id?: string
+  amount: number
+  note?: string
+  created_at: string
+}
+
+export interface NewSplit {
+  category_id?: string
+  amount: number
+  note?: string
+}
+
+export interface TransactionFilter {
+  limit?: number
+  offset?: number
+  category_id?: string
+  transaction_type?: 'credit' | 'debit'
+  date_from?: string
+  date_to?: string
+  search?: string
+  account_id?: string
+  tags?: string[] // transactions with any of these tags
+}
+
+export interface TagUsage {
+  name: string
+  usage_count: number
+}
+
+// Dashboard totals from get_transaction_stats
+export interface TransactionStats {
+  total_income: number
+  total_expenses: number
+  net: number
+  transaction_count: number
+  average_amount: number
+  largest_expense: number | null
+}
+
+export interface TransactionPage {
+  transactions: Transaction[]
+  total_count: number
+}
+
+export interface TransactionSearch {
+  transactions: Transaction[]
+  // False while notes are stored encrypted, which search can't look into
+  notes_searchable: boolean
+}
+
 export interface Category {
   id: string
   name: string
@@ -32,6 +87,32 @@ export interface Category {
   updated_at: string
 }
 
+// Puts transactions whose description matches in a category; higher priority is tried first
+export interface CategoryRule {
+  id: string
+  match_text: string
+  match_type: 'contains' | 'starts_with' | 'regex'
+  category_id: string
+  priority: number
+  created_at: string
+}
+
+// Transaction a category rule matched too uncertainly to categorize
+export interface TransactionForReview {
+  transaction: Transaction
+  suggested_category_id?: string
+  confidence?: number
+}
+
+export interface RecategorizeOutcome {
+  examined: number
+  changed: number
+  flagged_for_review: number
+  by_category: { category_id: string; category_name: string; count: number }[]
+  // Cancelled runs change nothing
+  cancelled: boolean
+}
+
 export interface Budget {
   id: string
   name: string
@@ -48,6 +129,80 @@ export interface Budget {
   updated_at: string
 }
 
+// A closed budget period with what was spent in it
+export interface BudgetPeriodSummary {
+  budget_id: string
+  start_date: string
+  end_date: string
+  amount: number
+  spent: number
+  remaining: number
+}
+
+export interface SavingsGoal {
+  id: string
+  name: string
+  target_amount: number
+  current_amount: number
+  target_date: string
+  linked_category_id?: string
+  created_at: string
+  updated_at: string
+}
+
+export interface GoalProgress {
+  goal_id: string
+  percentage: number
+  remaining: number
+  monthly_savings_rate: number
+  projected_completion?: string
+  on_track: boolean
+}
+
+export interface CategoryTrim {
+  category_id: string
+  category_name: string
+  monthly_spending: number
+  suggested_monthly: number
+  monthly_saving: number
+}
+
+export interface GoalFundingPlan {
+  goal_id: string
+  required_monthly: number
+  monthly_savings_rate: number
+  gap: number
+  feasible: boolean
+  trims: CategoryTrim[]
+}
+
+export interface RoundupMonth {
+  month: string // YYYY-MM
+  saved: number
+  transaction_count: number
+}
+
+export interface RoundupSimulation {
+  round_to: number
+  total_saved: number
+  transaction_count: number
+  months: RoundupMonth[]
+}
+
+export interface LockStatus {
+  unlocked: boolean
+  passphrase_set: boolean
+}
+
+export interface AuditEntry {
+  id: string
+  action: string
+  outcome: string
+  detail?: string
+  success: boolean
+  created_at: string
+}
+
 export interface Settings {
   id: string
   currency: string
@@ -62,10 +217,54 @@ export interface Settings {
   export_format: string
   encryption_enabled: boolean
   last_backup?: string
+  spending_velocity_alert_fraction: number
+  restrict_tags_to_vocabulary: boolean
+  insight_retention_days: number
+  import_repeat_window_seconds: number
+  allow_data_export: boolean
+  audit_retention_days: number
+  category_review_threshold: number
+  seasonal_anomaly_sigma: number
+  cashflow_spend_estimate: 'mean' | 'median'
+  budget_month_start_day: number
+  encrypt_notes: boolean
+  created_at: string
+  updated_at: string
+}
+
+export interface Account {
+  id: string
+  iban: string
+  name?: string
+  default_category_id?: string
+  is_default: boolean
+  bank?: string
+  currency: string
   created_at: string
   updated_at: string
 }
 
+export interface AccountBalance {
+  account_number: string
+  date: string
+  balance: number
+  source: 'authoritative' | 'derived'
+}
+
+export interface BalancePoint {
+  transaction_id: string
+  date: string
+  balance: number
+  reported_balance?: number
+}
+
+export interface BalanceReconstruction {
+  account_number: string
+  points: BalancePoint[]
+  filled_in: number
+  warnings: string[]
+}
+
 export interface FinancialInsight {
   id: string
   insight_type: 'spending_pattern' | 'budget_optimization' | 'savings_opportunity' | 'unusual_activity'
@@ -78,6 +277,15 @@ export interface FinancialInsight {
   created_at: string
 }
 
+export interface CategorySpendingNode {
+  category_id: string
+  category_name: string
+  parent_id?: string
+  own_amount: number
+  total_amount: number
+  children: CategorySpendingNode[]
+}
+
 export interface SpendingAnalysis {
   total_spending: number
   total_income: number
@@ -87,9 +295,167 @@ export interface SpendingAnalysis {
   spending_trend: 'increasing' | 'decreasing' | 'stable'
   period_start: string
   period_end: string
+  category_tree: CategorySpendingNode[]
   current_month_spending: number
 }
 
+export interface RecurringPayment {
+  date: string
+  amount: number
+}
+
+export interface RecurringStream {
+  counterparty: string
+  transaction_type: string
+  frequency: 'weekly' | 'biweekly' | 'monthly' | 'quarterly' | 'yearly'
+  typical_amount: number
+  min_amount: number
+  max_amount: number
+  is_variable: boolean
+  last_date: string
+  next_expected: string
+  history: RecurringPayment[]
+}
+
+export interface UpcomingRecurring {
+  date: string
+  description: string
+  amount: number
+  transaction_type: string
+  frequency: 'weekly' | 'biweekly' | 'monthly' | 'quarterly' | 'yearly'
+  confidence: number
+}
+
+export interface CashflowDay {
+  date: string
+  recurring_net: number
+  balance: number
+}
+
+export interface CashflowForecast {
+  starting_balance: number
+  spend_estimate: 'mean' | 'median'
+  daily_discretionary_spend: number
+  days: CashflowDay[]
+  lowest_balance: number
+  lowest_balance_date: string
+  warning?: string
+}
+
+export interface BudgetAlertSetting {
+  budget_id: string
+  name: string
+  amount: number
+  warning_at: number
+  warning_percentage: number
+  uses_default: boolean
+}
+
+export interface BulkThresholdOutcome {
+  updated: number
+  skipped: { budget_id: string; name: string; reason: string }[]
+}
+
+export interface PriceIncrease {
+  counterparty: string
+  frequency: string
+  previous_amount: number
+  current_amount: number
+  increase_percentage: number
+  annual_extra_cost: number
+  changed_on: string
+}
+
+// Monthly subscription from analyze_subscriptions
+export interface SubscriptionReview {
+  merchant: string
+  current_amount: number
+  last_charged: string
+  longest_gap_days: number
+  // Same as detect_subscription_price_increases reports for this merchant
+  price_increase: PriceIncrease | null
+  possibly_unused: boolean
+  suggestion: string | null
+}
+
+export interface HealthComponent {
+  name: 'savings_rate' | 'budget_adherence' | 'emergency_fund' | 'spending_stability'
+  label: string
+  score: number
+  max_score: number
+  value: number
+  tip: string
+}
+
+export interface HealthScore {
+  score: number
+  components: HealthComponent[]
+  tip: string
+}
+
+export interface DoubleCharge {
+  first: Transaction
+  second: Transaction
+  hours_apart: number
+}
+
+export interface WeekSummary {
+  week: string
+  start: string
+  income: number
+  expenses: number
+  net: number
+  transaction_count: number
+}
+
+export interface MonthSummary {
+  month: string
+  total_income: number
+  total_expenses: number
+  net_savings: number
+  transaction_count: number
+}
+
+// Claude API spend in the current calendar month
+export interface ClaudeUsage {
+  month: string
+  request_count: number
+  input_tokens: number
+  output_tokens: number
+  spent_eur: number
+  limit_eur: number
+  remaining_eur: number
+}
+
+export interface SpendingPace {
+  period_start: string
+  period_end: string
+  budget_total: number
+  spent_to_date: number
+  spent_fraction: number
+  elapsed_fraction: number
+}
+
+export interface SpendingVelocityAlert extends SpendingPace {
+  threshold: number
+}
+
+// Payload of the budget_threshold_crossed event
+export interface BudgetThresholdCrossed {
+  budget_id: string
+  percentage: number
+  threshold: number
+}
+
+export type SecurityAlertKind = 'data_export_attempts' | 'api_calls' | 'unusual_transaction'
+
+// Payload of the security_alert event
+export interface SecurityAlert {
+  kind: SecurityAlertKind
+  message: string
+  transaction_id?: string
+}
+
 export interface CategorySpending {
   category_id: string
   category_name: string
@@ -181,6 +547,8 @@ export interface CsvImportConfig {
     transaction_type?: number
     balance_after?: number
   }
+  skip_columns?: number[]
+  unmapped_columns_to_notes?: boolean
 }
 
 export interface ImportResult {
@@ -191,10 +559,72 @@ export interface ImportResult {
   transactions: Transaction[]
 }
 
+export interface ImportError {
+  line: number
+  // Absent for errors about the whole file, or a record that could not be read
+  raw?: string
+  field?: string
+  reason: string
+}
+
+export interface ImportDuplicate {
+  index: number
+  description: string
+  date: string
+  amount: number
+  existing_id: string
+}
+
+export interface ImportOutcome {
+  imported: number
+  skipped_duplicates: number
+  duplicates: ImportDuplicate[]
+}
+
+export interface FileImportSummary {
+  path: string
+  bank?: 'rabobank' | 'abn_amro'
+  imported: number
+  skipped_duplicates: number
+  errors: ImportError[]
+  warnings: string[]
+}
+
+export interface MultiImportOutcome {
+  files: FileImportSummary[]
+  imported: number
+  skipped_duplicates: number
+}
+
+export interface ImportProgress {
+  phase: 'parsing' | 'saving'
+  files_done: number
+  total_files: number
+  path: string
+}
+
+export interface DryRunRow {
+  transaction: Transaction
+  category_source: 'suggested' | 'rule' | 'needs_review' | 'account_default' | 'uncategorized'
+  duplicate: 'new' | 'in_file' | 'already_imported'
+  // Content hash; rows sharing one are the same transaction
+  import_hash: string
+}
+
+export interface DryRunReport {
+  rows: DryRunRow[]
+  errors: ImportError[]
+  warnings: string[]
+  total_rows: number
+  skipped_footer_rows: number
+  would_import: number
+  would_skip: number
+}
+
 export interface CsvImportResult {
   success: boolean
   transactions: Transaction[]
-  errors: string[]
+  errors: ImportError[]
   warnings: string[]
   total_rows: number
   imported_rows: number
@@ -262,9 +692,24 @@ export type SortField = 'date' | 'amount' | 'description' | 'category'
 export type FilterOperator = 'equals' | 'contains' | 'greater_than' | 'less_than' | 'between'
 
 // Error types
+export type AppErrorCode =
+  | 'DATABASE'
+  | 'SERIALIZATION'
+  | 'IO'
+  | 'ENCRYPTION'
+  | 'CSV'
+  | 'HTTP'
+  | 'NOT_FOUND'
+  | 'PERMISSION_DENIED'
+  | 'CONFIGURATION'
+  | 'AI_SERVICE'
+  | 'VALIDATION'
+  | 'INTERNAL'
+
+// Error returned by a failed Tauri command
 export interface AppError {
-  code: string
+  code: AppErrorCode
   message: string
   details?: any
-  timestamp: string
+  timestamp?: string
 }
\ No newline at end of file
@@ -1,9 +1,152 @@
 // Shared utility functions between frontend and backend
 use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
 
-/// Format a monetary amount in Dutch format
-pub fn format_currency(amount: f64) -> String {
-    format!("€{:.2}", amount.abs())
+/// How amounts in a currency are written: its symbol and the separators
+/// used by the banks that hold accounts in it
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Currency {
+    /// ISO 4217 code, e.g. "EUR"
+    pub code: String,
+    pub symbol: String,
+    pub decimal_separator: char,
+    pub thousands_separator: char,
+    /// Decimals shown, see [`minor_units`]
+    pub minor_units: u32,
+}
+
+impl Currency {
+    /// The conventions for an ISO 4217 code: Dutch "1.234,50" for the euro,
+    /// "1,234.50" for currencies mostly written that way, "1'234.50" for the franc
+    pub fn for_code(code: &str) -> Self {
+        let code = code.trim().to_uppercase();
+        let (decimal_separator, thousands_separator) = match code.as_str() {
+            "USD" | "GBP" | "JPY" | "AUD" | "CAD" | "NZD" => ('.', ','),
+            "CHF" => ('.', '\''),
+            _ => (',', '.'),
+        };
+
+        Self {
+            symbol: currency_symbol(&code),
+            minor_units: minor_units(&code),
+            decimal_separator,
+            thousands_separator,
+            code,
+        }
+    }
+
+    /// Parse an amount as written in a bank export for this currency, e.g.
+    /// "€ -1.234,50" or "-1234,5". The symbol, code and spaces are ignored.
+    pub fn parse_amount(&self, text: &str) -> Option<rust_decimal::Decimal> {
+        let cleaned: String = text
+            .replace(self.symbol.as_str(), "")
+            .replace(self.code.as_str(), "")
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != self.thousands_separator)
+            .map(|c| if c == self.decimal_separator { '.' } else { c })
+            .collect();
+
+        if cleaned.is_empty() {
+            return None;
+        }
+        cleaned.parse().ok()
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self::for_code("EUR")
+    }
+}
+
+/// Digits of `whole` with `separator` between every group of three
+fn group_thousands(whole: &str, separator: char) -> String {
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Format a monetary amount the way it is written in `currency`, without its
+/// sign: "€1.234,50" for the euro, "$1,234.50" for the dollar. Amounts are
+/// stored unsigned with a debit/credit type, so lists show them like this.
+pub fn format_currency(amount: Decimal, currency: &Currency) -> String {
+    format_currency_signed(amount.abs(), currency)
+}
+
+/// Format a monetary amount like [`format_currency`] but keep a minus sign,
+/// for balances that can be negative: "-€1.234,50"
+pub fn format_currency_signed(amount: Decimal, currency: &Currency) -> String {
+    let rounded = amount.round_dp_with_strategy(currency.minor_units, RoundingStrategy::MidpointAwayFromZero);
+    let digits = format!("{:.*}", currency.minor_units as usize, rounded.abs());
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits.as_str(), ""));
+
+    let sign = if rounded.is_sign_negative() && !rounded.is_zero() { "-" } else { "" };
+    let grouped = group_thousands(whole, currency.thousands_separator);
+
+    if fraction.is_empty() {
+        format!("{}{}{}", sign, currency.symbol, grouped)
+    } else {
+        format!("{}{}{}{}{}", sign, currency.symbol, grouped, currency.decimal_separator, fraction)
+    }
+}
+
+/// Symbol for an ISO 4217 currency code, or the code itself when there is no common symbol
+pub fn currency_symbol(currency: &str) -> String {
+    match currency.to_uppercase().as_str() {
+        "EUR" => "€".to_string(),
+        "USD" => "$".to_string(),
+        "GBP" => "£".to_string(),
+        "CHF" => "CHF".to_string(),
+        "JPY" => "¥".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Number of decimals (minor units) of an ISO 4217 currency: 2 for euro cents
+pub fn minor_units(currency: &str) -> u32 {
+    match currency.to_uppercase().as_str() {
+        "JPY" | "KRW" | "ISK" => 0,
+        "BHD" | "KWD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+/// Format a monetary amount for the user's currency and language:
+/// "€ 1.234,50" for Dutch, "€1,234.50" for English
+pub fn format_money(amount: Decimal, currency: &str, language: &str) -> String {
+    let rounded = amount.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+    let digits = format!("{:.2}", rounded.abs());
+    let (whole, cents) = digits.split_once('.').unwrap_or((digits.as_str(), "00"));
+
+    let english = language.to_lowercase().starts_with("en");
+    let (group_separator, decimal_separator) = if english { (',', '.') } else { ('.', ',') };
+
+    let grouped = group_thousands(whole, group_separator);
+
+    let sign = if rounded.is_sign_negative() && !rounded.is_zero() { "-" } else { "" };
+    let symbol = currency_symbol(currency);
+
+    if english {
+        format!("{}{}{}{}{}", sign, symbol, grouped, decimal_separator, cents)
+    } else {
+        format!("{} {}{}{}{}", symbol, sign, grouped, decimal_separator, cents)
+    }
+}
+
+/// Format a percentage with one decimal for the user's language, without the
+/// percent sign: "12,5" for Dutch, "12.5" for English
+pub fn format_percentage(percentage: Decimal, language: &str) -> String {
+    let text = format!("{:.1}", percentage.round_dp_with_strategy(1, RoundingStrategy::MidpointAwayFromZero));
+    if language.to_lowercase().starts_with("en") {
+        text
+    } else {
+        text.replace('.', ",")
+    }
 }
 
 /// Format a date in Dutch format
@@ -21,9 +164,269 @@ pub fn calculate_budget_percentage(spent: f64, budget: f64) -> f64 {
     }
 }
 
-/// Validate IBAN (basic implementation)
-pub fn validate_iban(iban: &str) -> bool {
-    // Simple length check - full IBAN validation would be more complex
+/// Outcome of checking an IBAN, specific enough to tell the user what is wrong
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IbanValidation {
+    Valid,
+    /// The check digits do not match the rest of the IBAN (typo or swapped digits)
+    BadChecksum,
+    /// Too short or too long for the country
+    BadLength,
+    /// Characters that cannot appear in an IBAN, or no country code and check digits
+    BadFormat,
+}
+
+impl IbanValidation {
+    pub fn is_valid(&self) -> bool {
+        *self == IbanValidation::Valid
+    }
+}
+
+/// IBAN lengths of the countries SpaarApp users bank with most
+const IBAN_LENGTHS: &[(&str, usize)] = &[
+    ("AT", 20),
+    ("BE", 16),
+    ("CH", 21),
+    ("DE", 22),
+    ("ES", 24),
+    ("FR", 27),
+    ("GB", 22),
+    ("IE", 22),
+    ("IT", 27),
+    ("LU", 20),
+    ("NL", 18),
+    ("PT", 25),
+];
+
+/// Validate an IBAN (ISO 13616): format, country-specific length and the
+/// mod-97 check digits. Spaces and lower case are accepted.
+pub fn validate_iban(iban: &str) -> IbanValidation {
+    let cleaned = iban.replace(" ", "").to_uppercase();
+
+    let well_formed = cleaned.len() >= 4
+        && cleaned.chars().all(|c| c.is_ascii_alphanumeric())
+        && cleaned[..2].chars().all(|c| c.is_ascii_alphabetic())
+        && cleaned[2..4].chars().all(|c| c.is_ascii_digit());
+    if !well_formed {
+        return IbanValidation::BadFormat;
+    }
+
+    let length_ok = match IBAN_LENGTHS.iter().find(|(country, _)| *country == &cleaned[..2]) {
+        Some((_, length)) => cleaned.len() == *length,
+        None => (15..=34).contains(&cleaned.len()),
+    };
+    if !length_ok {
+        return IbanValidation::BadLength;
+    }
+
+    // Country code and check digits move to the end, letters count as 10..35;
+    // the remainder is computed digit by digit to stay within u32
+    let remainder = cleaned[4..]
+        .chars()
+        .chain(cleaned[..4].chars())
+        .fold(0u32, |remainder, c| {
+            let value = c.to_digit(36).unwrap_or_default();
+            if value >= 10 {
+                (remainder * 100 + value) % 97
+            } else {
+                (remainder * 10 + value) % 97
+            }
+        });
+
+    if remainder == 1 {
+        IbanValidation::Valid
+    } else {
+        IbanValidation::BadChecksum
+    }
+}
+
+/// Validate a BIC (ISO 9362): 4-letter bank code, 2-letter country code,
+/// 2-character location code and an optional 3-character branch code
+pub fn validate_bic(bic: &str) -> bool {
+    let cleaned = bic.replace(" ", "").to_uppercase();
+    if cleaned.len() != 8 && cleaned.len() != 11 {
+        return false;
+    }
+
+    let (bank, rest) = cleaned.split_at(4);
+    let (country, rest) = rest.split_at(2);
+
+    bank.chars().all(|c| c.is_ascii_alphabetic())
+        && country.chars().all(|c| c.is_ascii_alphabetic())
+        && rest.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// BICs of Dutch banks, keyed by the bank code in positions 5-8 of a Dutch IBAN
+const DUTCH_BANK_BICS: &[(&str, &str)] = &[
+    ("ABNA", "ABNANL2A"),
+    ("ASNB", "ASNBNL21"),
+    ("BUNQ", "BUNQNL2A"),
+    ("FVLB", "FVLBNL22"),
+    ("HAND", "HANDNL2A"),
+    ("INGB", "INGBNL2A"),
+    ("KNAB", "KNABNL2H"),
+    ("NWAB", "NWABNL2G"),
+    ("RABO", "RABONL2U"),
+    ("RBRB", "RBRBNL21"),
+    ("SNSB", "SNSBNL2A"),
+    ("TRIO", "TRIONL2U"),
+];
+
+/// Derive the BIC for a Dutch IBAN from its bank code
+pub fn bic_for_dutch_iban(iban: &str) -> Option<&'static str> {
     let cleaned = iban.replace(" ", "").to_uppercase();
-    cleaned.len() >= 15 && cleaned.len() <= 34
-}
\ No newline at end of file
+    if !cleaned.starts_with("NL") || cleaned.len() != 18 {
+        return None;
+    }
+
+    let bank_code = &cleaned[4..8];
+    DUTCH_BANK_BICS
+        .iter()
+        .find(|(code, _)| *code == bank_code)
+        .map(|(_, bic)| *bic)
+}
+
+/// Names of the supported Dutch banks (as in `DutchBankingConfig::supported_banks`),
+/// keyed by the bank code in positions 5-8 of a Dutch IBAN
+const DUTCH_BANK_NAMES: &[(&str, &str)] = &[
+    ("ABNA", "ABN AMRO"),
+    ("ASNB", "ASN Bank"),
+    ("FVLB", "Van Lanschot"),
+    ("INGB", "ING"),
+    ("RABO", "Rabobank"),
+    ("RBRB", "RegioBank"),
+    ("TRIO", "Triodos Bank"),
+];
+
+/// Name of the bank a Dutch IBAN belongs to, or `None` for other countries
+/// and unknown bank codes
+pub fn bank_name_for_dutch_iban(iban: &str) -> Option<&'static str> {
+    let cleaned = iban.replace(" ", "").to_uppercase();
+    if !cleaned.starts_with("NL") || cleaned.len() != 18 {
+        return None;
+    }
+
+    let bank_code = &cleaned[4..8];
+    DUTCH_BANK_NAMES
+        .iter()
+        .find(|(code, _)| *code == bank_code)
+        .map(|(_, name)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_money() {
+        assert_eq!(format_money(Decimal::new(12345, 1), "EUR", "nl"), "€ 1.234,50");
+        assert_eq!(format_money(Decimal::new(-125, 1), "EUR", "nl"), "€ -12,50");
+        assert_eq!(format_money(Decimal::new(1234567, 0), "USD", "en"), "$1,234,567.00");
+        assert_eq!(format_money(Decimal::new(-1, 3), "EUR", "nl"), "€ 0,00");
+        assert_eq!(format_money(Decimal::new(5, 3), "SEK", "nl"), "SEK 0,01");
+    }
+
+    #[test]
+    fn test_format_percentage() {
+        assert_eq!(format_percentage(Decimal::new(143, 1), "nl"), "14,3");
+        assert_eq!(format_percentage(Decimal::new(143, 1), "en"), "14.3");
+        assert_eq!(format_percentage(Decimal::new(5, 0), "nl"), "5,0");
+    }
+
+    #[test]
+    fn test_format_currency() {
+        assert_eq!(format_currency(Decimal::new(123450, 2), &Currency::default()), "€1.234,50");
+        assert_eq!(format_currency(Decimal::new(-123450, 2), &Currency::for_code("usd")), "$1,234.50");
+        assert_eq!(format_currency(Decimal::new(12345, 1), &Currency::for_code("JPY")), "¥1,235");
+        assert_eq!(format_currency(Decimal::new(100000050, 2), &Currency::for_code("CHF")), "CHF1'000'000.50");
+    }
+
+    #[test]
+    fn test_format_currency_uses_euro_sign() {
+        let formatted = format_currency(Decimal::new(1250, 2), &Currency::default());
+        // U+20AC in UTF-8, not the "â‚¬" a Latin-1 round trip turns it into
+        assert!(formatted.as_bytes().starts_with(&[0xE2, 0x82, 0xAC]));
+        assert_eq!(formatted, "\u{20AC}12,50");
+    }
+
+    #[test]
+    fn test_format_currency_signed_keeps_sign() {
+        let euro = Currency::default();
+        assert_eq!(format_currency_signed(Decimal::new(-123450, 2), &euro), "-€1.234,50");
+        assert_eq!(format_currency_signed(Decimal::new(123450, 2), &euro), "€1.234,50");
+        assert_eq!(format_currency_signed(Decimal::new(-1, 3), &euro), "€0,00");
+        assert_eq!(format_currency(Decimal::new(-123450, 2), &euro), "€1.234,50");
+    }
+
+    #[test]
+    fn test_parse_amount_per_currency() {
+        let euro = Currency::default();
+        assert_eq!(euro.parse_amount("€ -1.234,50"), Some(Decimal::new(-123450, 2)));
+        assert_eq!(euro.parse_amount("12,5"), Some(Decimal::new(125, 1)));
+        assert_eq!(euro.parse_amount("€"), None);
+
+        let dollar = Currency::for_code("USD");
+        assert_eq!(dollar.parse_amount("$1,234.50"), Some(Decimal::new(123450, 2)));
+        assert_eq!(dollar.parse_amount("+12.50 USD"), Some(Decimal::new(1250, 2)));
+        assert_eq!(dollar.parse_amount("twelve"), None);
+    }
+
+    #[test]
+    fn test_valid_bics() {
+        assert!(validate_bic("RABONL2U"));
+        assert!(validate_bic("ingbnl2a"));
+        assert!(validate_bic("DEUTDEFF500"));
+    }
+
+    #[test]
+    fn test_malformed_bics() {
+        assert!(!validate_bic(""));
+        assert!(!validate_bic("RABONL2"));
+        assert!(!validate_bic("RABONL2U12"));
+        assert!(!validate_bic("RAB0NL2U"));
+        assert!(!validate_bic("RABON12U"));
+        assert!(!validate_bic("RABONL2U-01"));
+    }
+
+    #[test]
+    fn test_valid_ibans() {
+        assert_eq!(validate_iban("NL91ABNA0417164300"), IbanValidation::Valid);
+        assert_eq!(validate_iban("nl91 abna 0417 1643 00"), IbanValidation::Valid);
+        assert_eq!(validate_iban("NL44RABO0123456789"), IbanValidation::Valid);
+        assert_eq!(validate_iban("DE89370400440532013000"), IbanValidation::Valid);
+        assert!(validate_iban("GB29 NWBK 6016 1331 9268 19").is_valid());
+    }
+
+    #[test]
+    fn test_invalid_ibans() {
+        // Changed and swapped digits
+        assert_eq!(validate_iban("NL91ABNA0417164301"), IbanValidation::BadChecksum);
+        assert_eq!(validate_iban("NL91ABNA0417163400"), IbanValidation::BadChecksum);
+        assert_eq!(validate_iban("NL01RABO0123456789"), IbanValidation::BadChecksum);
+        // One digit missing or extra for a Dutch IBAN
+        assert_eq!(validate_iban("NL91ABNA041716430"), IbanValidation::BadLength);
+        assert_eq!(validate_iban("NL91ABNA04171643000"), IbanValidation::BadLength);
+        assert_eq!(validate_iban(""), IbanValidation::BadFormat);
+        assert_eq!(validate_iban("9191ABNA0417164300"), IbanValidation::BadFormat);
+        assert_eq!(validate_iban("NL91-ABNA-0417-1643-00"), IbanValidation::BadFormat);
+        assert!(!validate_iban("NLXXABNA0417164300").is_valid());
+    }
+
+    #[test]
+    fn test_bic_from_dutch_iban() {
+        assert_eq!(bic_for_dutch_iban("NL91 ABNA 0417 1643 00"), Some("ABNANL2A"));
+        assert_eq!(bic_for_dutch_iban("NL02RABO0123456789"), Some("RABONL2U"));
+        assert_eq!(bic_for_dutch_iban("NL02XXXX0123456789"), None);
+        assert_eq!(bic_for_dutch_iban("DE89370400440532013000"), None);
+    }
+
+    #[test]
+    fn test_bank_name_from_dutch_iban() {
+        assert_eq!(bank_name_for_dutch_iban("NL91 ABNA 0417 1643 00"), Some("ABN AMRO"));
+        assert_eq!(bank_name_for_dutch_iban("nl44rabo0123456789"), Some("Rabobank"));
+        assert_eq!(bank_name_for_dutch_iban("NL02BUNQ0123456789"), None);
+        assert_eq!(bank_name_for_dutch_iban("DE89370400440532013000"), None);
+        assert_eq!(bank_name_for_dutch_iban(""), None);
+    }
+}